@@ -3,13 +3,14 @@
 //! This example demonstrates how to:
 //! 1. Scan for newsletters with high confidence scores
 //! 2. Automatically clean them without user interaction
-//! 3. Report results
+//! 3. Report what happened to each subscription: unsubscribed, unsubscribe
+//!    failed, no unsubscribe method, or skipped
 //!
 //! **WARNING**: This example will actually delete emails!
 //! Only use with a test Gmail account, not your primary account.
 //!
 //! Usage:
-//!   cargo run --example batch_cleanup <email@gmail.com> [--dry-run]
+//!   cargo run --example batch_cleanup <email@gmail.com> [--dry-run] [--details]
 //!
 //! Prerequisites:
 //!   - Set GOOGLE_CLIENT_ID and GOOGLE_CLIENT_SECRET environment variables
@@ -17,9 +18,16 @@
 
 use anyhow::Result;
 use unsubmail::application::workflow;
-use unsubmail::domain::{analysis, models::ActionType, planner};
+use unsubmail::domain::{
+    email::EmailAddress,
+    models::{self, ActionType, CleanupResult, UnsubscribeOutcome, UnsubscribeReport},
+    planner,
+};
 use unsubmail::infrastructure::{imap, network, storage};
 
+/// Senders scoring above this are cleaned even without one-click unsubscribe
+const MIN_SCORE: f32 = 1.0;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables
@@ -30,13 +38,18 @@ async fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: {} <email@gmail.com> [--dry-run]", args[0]);
+        eprintln!(
+            "Usage: {} <email@gmail.com> [--dry-run] [--details]",
+            args[0]
+        );
         eprintln!("\nWARNING: This will DELETE emails! Use a test account only.");
         std::process::exit(1);
     }
 
-    let email = &args[1];
-    let dry_run = args.get(2).map(|s| s == "--dry-run").unwrap_or(false);
+    let email = EmailAddress::parse(&args[1])?.to_string();
+    let email = email.as_str();
+    let dry_run = args.iter().skip(2).any(|s| s == "--dry-run");
+    let details = args.iter().skip(2).any(|s| s == "--details");
 
     if dry_run {
         println!("DRY RUN MODE - No actual changes will be made\n");
@@ -59,49 +72,39 @@ async fn main() -> Result<()> {
         }
         _ => {
             println!("Authenticating with Google...");
-            let account = workflow::add_account_for_email(email).await?;
+            let account = workflow::add_account_for_email(
+                email,
+                models::AccessScope::Full,
+                workflow::SignInMethod::detect(),
+            )
+            .await?;
             let token = storage::keyring::get_token(&account.email)?
                 .ok_or_else(|| anyhow::anyhow!("Token not found after auth"))?;
             token.access_token
         }
     };
 
-    // Connect and scan
-    println!("\nConnecting to Gmail IMAP...");
-    let mut session = imap::connection::connect_and_auth(email, &access_token).await?;
-
-    println!("Fetching message headers (max 200)...");
-    let headers = imap::fetch::fetch_all_headers(&mut session, 200).await?;
-    println!("Fetched {} messages", headers.len());
-
-    // Group and analyze
-    let grouped = imap::fetch::group_by_sender(headers);
-    let senders: Vec<_> = grouped
-        .into_iter()
-        .map(|(email, messages)| {
-            let message_count = messages.len();
-            let message_uids: Vec<u32> = messages.iter().map(|m| m.uid).collect();
-            let first = &messages[0];
-            let display_name = extract_display_name(&first.from);
-            let sample_subjects: Vec<String> =
-                messages.iter().take(3).map(|m| m.subject.clone()).collect();
-
-            analysis::analyze_sender(
-                email,
-                display_name,
-                message_count,
-                message_uids,
-                first.list_unsubscribe.clone(),
-                first.list_unsubscribe_post.clone(),
-                sample_subjects,
-            )
-        })
-        .collect();
-
-    // Filter: Only high-confidence newsletters (score > 1.0 OR one-click available)
+    // Scan and analyze
+    println!("\nScanning the 200 most recent messages...");
+    let senders = workflow::scan_inbox(
+        email,
+        &access_token,
+        &workflow::ScanOptions {
+            fetch: imap::fetch::FetchOptions {
+                batch_size: 200,
+                max_messages: Some(200),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await?;
+    println!("Found {} unique senders", senders.len());
+
+    // Filter: Only high-confidence newsletters (score > MIN_SCORE OR one-click available)
     let candidates: Vec<_> = senders
         .into_iter()
-        .filter(|s| s.heuristic_score > 1.0 || s.unsubscribe_method.is_one_click())
+        .filter(|s| s.heuristic_score > MIN_SCORE || s.unsubscribe_method.is_one_click())
         .collect();
 
     println!(
@@ -111,12 +114,11 @@ async fn main() -> Result<()> {
 
     if candidates.is_empty() {
         println!("No newsletters to clean!");
-        session.logout().await?;
         return Ok(());
     }
 
     // Plan actions
-    let actions = planner::plan_actions(candidates);
+    let actions = planner::plan_actions(candidates.clone());
 
     println!("\nPlanned Actions:");
     for action in &actions {
@@ -138,14 +140,18 @@ async fn main() -> Result<()> {
 
     if dry_run {
         println!("\nDRY RUN - Skipping actual execution");
-        session.logout().await?;
         return Ok(());
     }
 
+    let imap_config = storage::config::load_config()?.imap_config()?;
+    let (mut session, imap_config) =
+        imap::connection::connect_and_resolve(&imap_config, email, &access_token).await?;
+
     println!("\nExecuting cleanup...\n");
 
-    let mut total_deleted = 0;
-    let mut total_unsubscribed = 0;
+    let http = network::http_client::HttpClient::new(Default::default())?;
+
+    let mut results = Vec::with_capacity(actions.len());
 
     for action in actions {
         let name = action
@@ -156,49 +162,87 @@ async fn main() -> Result<()> {
         println!("Processing: {}", name);
 
         // Try to unsubscribe if one-click available
-        if action.sender.unsubscribe_method.is_one_click() {
-            if let unsubmail::domain::models::UnsubscribeMethod::OneClick { url } =
-                &action.sender.unsubscribe_method
+        let unsubscribed = if action.sender.unsubscribe_method.is_one_click() {
+            match http
+                .unsubscribe_one_click(&action.sender.one_click_urls())
+                .await
             {
-                match network::http_client::unsubscribe_one_click(url).await {
-                    Ok(true) => {
-                        println!("  ✓ Unsubscribed");
-                        total_unsubscribed += 1;
-                    }
-                    Ok(false) => println!("  ✗ Unsubscribe failed"),
-                    Err(e) => println!("  ✗ Unsubscribe error: {}", e),
+                Ok(Some(_)) => {
+                    println!("  ✓ Unsubscribed");
+                    Some(true)
+                }
+                Ok(None) => {
+                    println!("  ✗ Unsubscribe failed");
+                    Some(false)
+                }
+                Err(e) => {
+                    println!("  ✗ Unsubscribe error: {}", e);
+                    Some(false)
                 }
             }
-        }
+        } else {
+            println!("  ! No one-click unsubscribe, deleting only");
+            None
+        };
 
         // Delete messages
-        match imap::actions::delete_messages(&mut session, &action.sender.message_uids).await {
+        match imap::actions::delete_messages(
+            &mut session,
+            &imap_config,
+            &imap_config.mailbox,
+            &action.sender.message_uids,
+            imap_config.flag_only,
+        )
+        .await
+        {
             Ok(count) => {
                 println!("  ✓ Deleted {} messages", count);
-                total_deleted += count;
+                results.push(CleanupResult::success(
+                    action.sender.email.clone(),
+                    action.action_type,
+                    count,
+                    unsubscribed,
+                ));
             }
             Err(e) => {
                 println!("  ✗ Delete error: {}", e);
+                let mut result = CleanupResult::failure(
+                    action.sender.email.clone(),
+                    action.action_type,
+                    e.to_string(),
+                );
+                result.unsubscribe_success = unsubscribed;
+                results.push(result);
             }
         }
     }
 
+    let report = UnsubscribeReport::from_results(&results, &candidates);
+
     println!("\n{}", "=".repeat(80));
     println!("Cleanup Complete!");
-    println!("  Total unsubscribed: {}", total_unsubscribed);
-    println!("  Total messages deleted: {}", total_deleted);
+    println!("  {}", models::CleanupSummary::from_results(&results));
+    for outcome in UnsubscribeOutcome::ALL {
+        println!(
+            "  {:<22} {}",
+            format!("{}:", outcome),
+            report.count(outcome)
+        );
+    }
+
+    if details {
+        for outcome in UnsubscribeOutcome::ALL {
+            if report.count(outcome) == 0 {
+                continue;
+            }
+            println!("\n{}:", outcome);
+            for sender in report.senders_with(outcome) {
+                println!("  - {}", sender);
+            }
+        }
+    }
 
     session.logout().await?;
 
     Ok(())
 }
-
-fn extract_display_name(from: &str) -> Option<String> {
-    if let Some(pos) = from.find('<') {
-        let name = from[..pos].trim().trim_matches('"');
-        if !name.is_empty() {
-            return Some(name.to_string());
-        }
-    }
-    None
-}
@@ -56,7 +56,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("\n=== Next Steps ===");
     println!("1. Verify IMAP is enabled: https://mail.google.com/mail/u/0/#settings/fwdandpop");
-    println!("2. Delete old token from Credential Manager if exists");
+    println!("2. Delete the old token: unsubmail token clear --email <address>");
     println!("3. Run the main program to get a fresh token");
 
     Ok(())
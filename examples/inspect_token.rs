@@ -59,7 +59,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     Utc::now() - token.expires_at
                 );
                 println!("\n🔧 Action Required:");
-                println!("   1. Delete this token from Windows Credential Manager");
+                println!("   1. Run `unsubmail token clear --email {}`", email);
                 println!("   2. Run the main program to get a new token");
             } else {
                 let time_left = token.expires_at - Utc::now();
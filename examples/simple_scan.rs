@@ -14,7 +14,8 @@
 
 use anyhow::Result;
 use unsubmail::application::workflow;
-use unsubmail::domain::analysis;
+use unsubmail::domain::email::EmailAddress;
+use unsubmail::domain::models::AccessScope;
 use unsubmail::infrastructure::{imap, storage};
 
 #[tokio::main]
@@ -32,6 +33,7 @@ async fn main() -> Result<()> {
         println!("Usage: cargo run --example simple_scan <email@gmail.com>");
         std::process::exit(1);
     });
+    let email = EmailAddress::parse(&email)?.to_string();
 
     println!("Email: {}\n", email);
 
@@ -43,48 +45,34 @@ async fn main() -> Result<()> {
         }
         _ => {
             println!("Authenticating with Google...");
-            let account = workflow::add_account_for_email(&email).await?;
+            let account = workflow::add_account_for_email(
+                &email,
+                AccessScope::Full,
+                workflow::SignInMethod::detect(),
+            )
+            .await?;
             let token = storage::keyring::get_token(&account.email)?
                 .ok_or_else(|| anyhow::anyhow!("Token not found after auth"))?;
             token.access_token
         }
     };
 
-    // Connect to IMAP
-    println!("\nConnecting to Gmail IMAP...");
-    let mut session = imap::connection::connect_and_auth(&email, &access_token).await?;
-
-    // Fetch message headers (limit to 100 for this example)
-    println!("Fetching message headers (max 100)...");
-    let headers = imap::fetch::fetch_all_headers(&mut session, 100).await?;
-    println!("Fetched {} messages\n", headers.len());
-
-    // Group by sender
-    let grouped = imap::fetch::group_by_sender(headers);
-    println!("Found {} unique senders\n", grouped.len());
-
-    // Analyze each sender
-    let mut senders: Vec<_> = grouped
-        .into_iter()
-        .map(|(email, messages)| {
-            let message_count = messages.len();
-            let message_uids: Vec<u32> = messages.iter().map(|m| m.uid).collect();
-            let first = &messages[0];
-            let display_name = extract_display_name(&first.from);
-            let sample_subjects: Vec<String> =
-                messages.iter().take(3).map(|m| m.subject.clone()).collect();
-
-            analysis::analyze_sender(
-                email,
-                display_name,
-                message_count,
-                message_uids,
-                first.list_unsubscribe.clone(),
-                first.list_unsubscribe_post.clone(),
-                sample_subjects,
-            )
-        })
-        .collect();
+    // Connect, fetch message headers (limit to 100 for this example) and analyze
+    println!("\nScanning the 100 most recent messages...");
+    let mut senders = workflow::scan_inbox(
+        &email,
+        &access_token,
+        &workflow::ScanOptions {
+            fetch: imap::fetch::FetchOptions {
+                batch_size: 100,
+                max_messages: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await?;
+    println!("Found {} unique senders\n", senders.len());
 
     // Sort by heuristic score (highest first)
     senders.sort_by(|a, b| {
@@ -120,9 +108,6 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Disconnect
-    session.logout().await?;
-
     println!("\n{}", "=".repeat(80));
     println!("Scan complete!");
     println!("\nNote: This example only scans and displays results.");
@@ -131,13 +116,3 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
-
-fn extract_display_name(from: &str) -> Option<String> {
-    if let Some(pos) = from.find('<') {
-        let name = from[..pos].trim().trim_matches('"');
-        if !name.is_empty() {
-            return Some(name.to_string());
-        }
-    }
-    None
-}
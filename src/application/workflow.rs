@@ -1,38 +1,618 @@
 //! Main workflow orchestration
 
+use crate::domain::email::{EmailAddress, Mailbox};
 use crate::domain::models::*;
-use crate::infrastructure::storage;
+use crate::domain::{analysis, planner};
+use crate::infrastructure::browser::{BrowserOpener, SystemBrowser};
+use crate::infrastructure::imap::connection::ImapConfig;
+use crate::infrastructure::imap::fetch::{self, FetchOptions};
+use crate::infrastructure::network::gmail_api::GmailFetchConfig;
+use crate::infrastructure::network::http_client::{HttpClient, UnsubscribeOptions};
+use crate::infrastructure::{imap, network, storage};
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
 use oauth2::{
-    basic::BasicClient, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken,
-    PkceCodeChallenge, RedirectUrl, Scope, TokenResponse, TokenUrl,
+    basic::{BasicClient, BasicTokenResponse},
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl,
+    Scope, TokenResponse, TokenUrl,
 };
+use std::collections::HashMap;
 use std::env;
-use std::io::{BufRead, BufReader, Write};
 use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
 use url::Url;
 
 const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
-const GMAIL_SCOPE: &str = "https://mail.google.com/";
 
-/// Add account for specific email (OAuth2 flow with browser)
-pub async fn add_account_for_email(email: &str) -> Result<EmailAccount> {
-    // Get OAuth2 credentials from environment
-    let client_id = env::var("GOOGLE_CLIENT_ID").context("GOOGLE_CLIENT_ID not set")?;
-    let client_secret = env::var("GOOGLE_CLIENT_SECRET").context("GOOGLE_CLIENT_SECRET not set")?;
-    let redirect_uri = env::var("GOOGLE_REDIRECT_URI")
-        .unwrap_or_else(|_| "http://localhost:9090/callback".to_string());
+const GOOGLE_DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+const DEFAULT_REDIRECT_URI: &str = "http://localhost:9090/callback";
+const OAUTH_CALLBACK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Polling interval for the device flow when Google doesn't name one
+const DEFAULT_DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Added to the polling interval on every `slow_down` answer (RFC 8628)
+const DEVICE_SLOW_DOWN_STEP: Duration = Duration::from_secs(5);
+
+/// OAuth `prompt` for the first sign-in attempt
+const CONSENT_PROMPT: &str = "consent";
+/// OAuth `prompt` for the retry when no refresh token was returned
+const RETRY_CONSENT_PROMPT: &str = "select_account consent";
+
+/// OAuth2 client credentials used for the browser flow and token refresh
+#[derive(Debug, Clone, PartialEq)]
+struct OAuthCredentials {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+/// Shape of the `client_secret.json` file downloaded from Google Cloud Console
+#[derive(Debug, serde::Deserialize)]
+struct ClientSecretFile {
+    installed: Option<ClientSecretEntry>,
+    web: Option<ClientSecretEntry>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ClientSecretEntry {
+    client_id: String,
+    client_secret: String,
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+}
+
+/// Load OAuth2 credentials
+///
+/// `GOOGLE_CLIENT_ID`/`GOOGLE_CLIENT_SECRET` take precedence. When they are
+/// unset, the credentials file named by `UNSUBMAIL_CREDENTIALS` (or
+/// `client_secret.json` in the config directory) is used instead.
+/// `GOOGLE_REDIRECT_URI` always overrides the redirect URI.
+fn load_oauth_credentials() -> Result<OAuthCredentials> {
+    let mut credentials = match (
+        env::var("GOOGLE_CLIENT_ID"),
+        env::var("GOOGLE_CLIENT_SECRET"),
+    ) {
+        (Ok(client_id), Ok(client_secret)) => OAuthCredentials {
+            client_id,
+            client_secret,
+            redirect_uri: DEFAULT_REDIRECT_URI.to_string(),
+        },
+        _ => {
+            let path = credentials_path()?;
+            let json = std::fs::read_to_string(&path).with_context(|| {
+                format!(
+                    "GOOGLE_CLIENT_ID/GOOGLE_CLIENT_SECRET not set and no credentials file at {}",
+                    path.display()
+                )
+            })?;
+            parse_client_secret(&json)
+                .with_context(|| format!("Invalid credentials file {}", path.display()))?
+        }
+    };
+
+    if let Ok(redirect_uri) = env::var("GOOGLE_REDIRECT_URI") {
+        credentials.redirect_uri = redirect_uri;
+    }
+
+    Ok(credentials)
+}
+
+/// Path of the OAuth2 credentials file
+fn credentials_path() -> Result<std::path::PathBuf> {
+    if let Ok(path) = env::var("UNSUBMAIL_CREDENTIALS") {
+        return Ok(path.into());
+    }
+
+    let proj_dirs = directories::ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    Ok(proj_dirs.config_dir().join("client_secret.json"))
+}
+
+/// Parse a Google `client_secret.json` (either `installed` or `web` client)
+///
+/// Only a redirect URI matching the local callback server is kept; anything
+/// else falls back to the default.
+fn parse_client_secret(json: &str) -> Result<OAuthCredentials> {
+    let file: ClientSecretFile =
+        serde_json::from_str(json).context("Failed to parse client secret JSON")?;
+
+    let entry = file
+        .installed
+        .or(file.web)
+        .context("Client secret JSON has no \"installed\" or \"web\" section")?;
+
+    let redirect_uri = entry
+        .redirect_uris
+        .into_iter()
+        .find(|uri| uri.starts_with("http://localhost:9090"))
+        .unwrap_or_else(|| DEFAULT_REDIRECT_URI.to_string());
+
+    Ok(OAuthCredentials {
+        client_id: entry.client_id,
+        client_secret: entry.client_secret,
+        redirect_uri,
+    })
+}
+
+/// Candidate ports for the OAuth2 callback server
+///
+/// The port from the redirect URI is tried first, then any ports listed in
+/// `UNSUBMAIL_OAUTH_PORTS` (comma-separated), then an OS-assigned port.
+fn callback_ports(redirect_uri: &Url) -> Vec<u16> {
+    let extra = env::var("UNSUBMAIL_OAUTH_PORTS").unwrap_or_default();
+    candidate_ports(redirect_uri.port_or_known_default().unwrap_or(9090), &extra)
+}
+
+fn candidate_ports(configured: u16, extra: &str) -> Vec<u16> {
+    let mut ports = vec![configured];
+
+    for port in extra
+        .split(',')
+        .filter_map(|p| p.trim().parse::<u16>().ok())
+    {
+        if !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+
+    if !ports.contains(&0) {
+        ports.push(0);
+    }
+
+    ports
+}
+
+/// Bind the OAuth2 callback listener on the first free candidate port
+///
+/// Returns the listener and the redirect URI rewritten to the bound port.
+fn bind_callback_listener(redirect_uri: &str) -> Result<(TcpListener, Url)> {
+    let mut redirect_url = Url::parse(redirect_uri).context("Invalid OAuth2 redirect URI")?;
+    let ports = callback_ports(&redirect_url);
+
+    for port in &ports {
+        match TcpListener::bind(("127.0.0.1", *port)) {
+            Ok(listener) => {
+                let bound = listener
+                    .local_addr()
+                    .context("Failed to read callback server address")?
+                    .port();
+
+                if bound != ports[0] {
+                    tracing::debug!(
+                        busy = ports[0],
+                        port = bound,
+                        "OAuth2 callback bound to fallback port"
+                    );
+                }
+
+                redirect_url
+                    .set_port(Some(bound))
+                    .map_err(|_| anyhow::anyhow!("Cannot set port on redirect URI"))?;
+
+                return Ok((listener, redirect_url));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to bind to localhost:{}", port))
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "No free port for the OAuth2 callback server (tried {:?}). \
+         Free one of them or set UNSUBMAIL_OAUTH_PORTS",
+        ports
+    )
+}
+
+/// Receive the OAuth2 redirect on the callback listener
+///
+/// Gives up after `timeout` or when the user presses Ctrl-C; the listener is
+/// closed either way.
+async fn wait_for_callback(
+    listener: TcpListener,
+    redirect_uri: &Url,
+    timeout: Duration,
+) -> Result<Url> {
+    listener
+        .set_nonblocking(true)
+        .context("Failed to configure callback server")?;
+    let listener =
+        tokio::net::TcpListener::from_std(listener).context("Failed to start callback server")?;
+
+    let accept = async {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("Failed to accept connection")?;
+
+        let mut reader = tokio::io::BufReader::new(stream);
+        let mut request_line = String::new();
+        reader
+            .read_line(&mut request_line)
+            .await
+            .context("Failed to read request")?;
+
+        // Parse callback URL
+        let path = request_line
+            .split_whitespace()
+            .nth(1)
+            .context("Invalid request line")?;
+        let url = redirect_uri
+            .join(path)
+            .context("Failed to parse callback URL")?;
+
+        // Send success response to browser
+        let response = "HTTP/1.1 200 OK\r\n\r\n<html><body><h1>Authentication successful!</h1><p>You can close this window.</p></body></html>";
+        reader.get_mut().write_all(response.as_bytes()).await.ok();
+
+        Ok::<_, anyhow::Error>(url)
+    };
+
+    tokio::select! {
+        result = tokio::time::timeout(timeout, accept) => result.map_err(|_| {
+            anyhow::anyhow!(
+                "Authentication timed out after {}s waiting for the browser",
+                timeout.as_secs()
+            )
+        })?,
+        _ = tokio::signal::ctrl_c() => anyhow::bail!("Authentication cancelled"),
+    }
+}
+
+/// How the user signs in to Google
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignInMethod {
+    /// Open a browser and receive the redirect on a localhost callback
+    Browser,
+    /// Show a URL and code to enter on another device (RFC 8628 device grant)
+    DeviceCode,
+}
+
+impl SignInMethod {
+    /// The method to use when none was asked for, e.g. with a CLI flag
+    ///
+    /// `UNSUBMAIL_SIGN_IN=browser|device` wins. Otherwise the device flow is
+    /// picked when no display is available, such as in an SSH session.
+    pub fn detect() -> Self {
+        match env::var("UNSUBMAIL_SIGN_IN").as_deref() {
+            Ok("browser") => return Self::Browser,
+            Ok("device") => return Self::DeviceCode,
+            Ok(other) => tracing::warn!(value = ?other, "ignoring unknown UNSUBMAIL_SIGN_IN value"),
+            Err(_) => {}
+        }
+
+        if is_headless(
+            |name| env::var_os(name).is_some(),
+            cfg!(target_os = "linux"),
+        ) {
+            Self::DeviceCode
+        } else {
+            Self::Browser
+        }
+    }
+}
+
+/// Whether no browser can be opened on this machine
+///
+/// `is_set` reports whether an environment variable is set. SSH sessions
+/// without X forwarding count as headless, as do Linux sessions without
+/// an X11 or Wayland display.
+fn is_headless(is_set: impl Fn(&str) -> bool, linux: bool) -> bool {
+    let has_display = is_set("DISPLAY") || is_set("WAYLAND_DISPLAY");
+    let over_ssh = is_set("SSH_CONNECTION") || is_set("SSH_TTY");
+
+    !has_display && (over_ssh || linux)
+}
+
+/// Add account for specific email (OAuth2 flow with browser or device code)
+///
+/// `scope` decides what the account can do: [`AccessScope::ReadOnly`] only
+/// allows [`scan_inbox_readonly`], [`AccessScope::Full`] is needed for IMAP
+/// scans, cleanup and undo. Signing in again replaces the stored scope.
+/// `method` picks the sign-in flow; see [`SignInMethod::detect`] for a default.
+pub async fn add_account_for_email(
+    email: &str,
+    scope: AccessScope,
+    method: SignInMethod,
+) -> Result<EmailAccount> {
+    add_account_with_browser(email, scope, method, &SystemBrowser).await
+}
+
+/// Add account for specific email, opening the sign-in page with `browser`
+///
+/// See [`add_account_for_email`].
+pub async fn add_account_with_browser(
+    email: &str,
+    scope: AccessScope,
+    method: SignInMethod,
+    browser: &dyn BrowserOpener,
+) -> Result<EmailAccount> {
+    let email = EmailAddress::parse(email)?;
+    if !email.is_gmail() {
+        tracing::warn!(email = %email, "not a Gmail address, sign-in may fail");
+    }
+    let email = email.as_str();
+
+    // Get OAuth2 credentials from environment or client_secret.json
+    let credentials = load_oauth_credentials()?;
+
+    let oauth_token = match method {
+        SignInMethod::Browser => sign_in_with_browser(&credentials, email, scope, browser).await?,
+        SignInMethod::DeviceCode => sign_in_with_device_code(&credentials, scope).await?,
+    };
+
+    storage::keyring::store_token(email, oauth_token)?;
+
+    // Create and save account, keeping the original record when signing in again
+    let account = match storage::json_store::load_account(email)? {
+        Some(existing) => existing,
+        None => EmailAccount {
+            email: email.to_string(),
+            added_at: Utc::now(),
+            protected: false,
+        },
+    };
+
+    storage::json_store::save_account(&account)?;
+
+    Ok(account)
+}
+
+/// Error shown when Google grants access without a refresh token
+const MISSING_REFRESH_TOKEN: &str = "No refresh token received from Google. Remove UnsubMail's \
+    access at https://myaccount.google.com/permissions and add the account again";
+
+/// Sign in through the browser flow
+async fn sign_in_with_browser(
+    credentials: &OAuthCredentials,
+    email: &str,
+    scope: AccessScope,
+    browser: &dyn BrowserOpener,
+) -> Result<OAuth2Token> {
+    // Google omits the refresh token when it considers the grant already
+    // given; retry once with the account chooser forced before giving up
+    let mut token =
+        authorize_in_browser(credentials, email, CONSENT_PROMPT, scope, browser).await?;
+    if token.refresh_token().is_some() {
+        tracing::info!("refresh token received on first sign-in");
+    } else {
+        tracing::warn!("no refresh token received, retrying sign-in with forced consent");
+        println!("Google did not return a refresh token; please sign in once more.");
+        token =
+            authorize_in_browser(credentials, email, RETRY_CONSENT_PROMPT, scope, browser).await?;
+        if token.refresh_token().is_some() {
+            tracing::info!("refresh token received after retrying sign-in");
+        }
+    }
+
+    let refresh_token = token
+        .refresh_token()
+        .context(MISSING_REFRESH_TOKEN)?
+        .secret()
+        .clone();
+
+    Ok(OAuth2Token {
+        access_token: token.access_token().secret().clone(),
+        refresh_token,
+        expires_at: Utc::now() + chrono::Duration::seconds(3600),
+        scope,
+    })
+}
+
+/// Device and user codes returned by Google's device authorization endpoint
+#[derive(Debug, serde::Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    /// Google names this `verification_url`; RFC 8628 uses `verification_uri`
+    #[serde(alias = "verification_uri")]
+    verification_url: String,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// Tokens granted at the end of the device flow
+#[derive(Debug, serde::Deserialize)]
+struct DeviceToken {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// OAuth2 error body from the device and token endpoints
+#[derive(Debug, serde::Deserialize)]
+struct OAuthErrorBody {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Answer to one poll of the token endpoint during the device flow
+#[derive(Debug)]
+enum DevicePoll {
+    Granted(DeviceToken),
+    /// The user hasn't finished signing in yet
+    Pending,
+    /// Polling too fast; wait longer between polls
+    SlowDown,
+}
+
+/// Interpret a token endpoint response body during the device flow
+///
+/// Errors other than `authorization_pending` and `slow_down` end the flow.
+fn parse_device_poll(body: &str) -> Result<DevicePoll> {
+    if let Ok(token) = serde_json::from_str::<DeviceToken>(body) {
+        return Ok(DevicePoll::Granted(token));
+    }
+
+    let error: OAuthErrorBody =
+        serde_json::from_str(body).context("Unexpected response from Google's token endpoint")?;
+
+    match error.error.as_str() {
+        "authorization_pending" => Ok(DevicePoll::Pending),
+        "slow_down" => Ok(DevicePoll::SlowDown),
+        _ => Err(device_flow_error(error)),
+    }
+}
+
+/// Explain an error that ends the device flow
+fn device_flow_error(error: OAuthErrorBody) -> anyhow::Error {
+    match error.error.as_str() {
+        "access_denied" => anyhow::anyhow!("Sign-in was denied on the verification page"),
+        "expired_token" => {
+            anyhow::anyhow!("The sign-in code expired before it was entered; please try again")
+        }
+        "invalid_scope" => anyhow::anyhow!(
+            "Google does not allow the Gmail scope with the device flow for this client. \
+            Sign in with the browser flow (UNSUBMAIL_SIGN_IN=browser), e.g. over SSH \
+            with X forwarding"
+        ),
+        "invalid_client" | "unauthorized_client" => anyhow::anyhow!(
+            "This OAuth client can't use the device flow; create a \"TVs and Limited Input \
+            devices\" client in Google Cloud Console"
+        ),
+        _ => match error.error_description {
+            Some(description) => {
+                anyhow::anyhow!("Sign-in failed: {} ({})", description, error.error)
+            }
+            None => anyhow::anyhow!("Sign-in failed: {}", error.error),
+        },
+    }
+}
+
+/// Sign in with the device authorization grant
+///
+/// Prints a URL and a code to enter on any device with a browser, then polls
+/// until the user finishes, the code expires or Ctrl-C is pressed.
+async fn sign_in_with_device_code(
+    credentials: &OAuthCredentials,
+    scope: AccessScope,
+) -> Result<OAuth2Token> {
+    let http = reqwest::Client::new();
+
+    let response = http
+        .post(GOOGLE_DEVICE_CODE_URL)
+        .form(&[
+            ("client_id", credentials.client_id.as_str()),
+            ("scope", scope.url()),
+        ])
+        .send()
+        .await
+        .context("Failed to request a sign-in code from Google")?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .context("Failed to read the sign-in code response")?;
+
+    if !status.is_success() {
+        return Err(match serde_json::from_str(&body) {
+            Ok(error) => device_flow_error(error),
+            Err(_) => anyhow::anyhow!("Google refused the sign-in code request ({})", status),
+        });
+    }
+
+    let device: DeviceAuthorization =
+        serde_json::from_str(&body).context("Failed to parse the sign-in code response")?;
+
+    println!(
+        "\nOn any device with a browser, visit: {}",
+        device.verification_url
+    );
+    println!("and enter the code: {}\n", device.user_code);
+    println!("Waiting for authorization...\n");
+
+    let token = tokio::select! {
+        token = poll_device_token(&http, credentials, &device) => token?,
+        _ = tokio::signal::ctrl_c() => anyhow::bail!("Authentication cancelled"),
+    };
+
+    Ok(OAuth2Token {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token.context(MISSING_REFRESH_TOKEN)?,
+        expires_at: Utc::now() + chrono::Duration::seconds(token.expires_in.unwrap_or(3600)),
+        scope,
+    })
+}
+
+/// Poll the token endpoint until the device code is granted or expires
+async fn poll_device_token(
+    http: &reqwest::Client,
+    credentials: &OAuthCredentials,
+    device: &DeviceAuthorization,
+) -> Result<DeviceToken> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = device
+        .interval
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_DEVICE_POLL_INTERVAL);
+
+    loop {
+        tokio::time::sleep(interval).await;
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("The sign-in code expired before it was entered; please try again");
+        }
+
+        let body = http
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[
+                ("client_id", credentials.client_id.as_str()),
+                ("client_secret", credentials.client_secret.as_str()),
+                ("device_code", device.device_code.as_str()),
+                ("grant_type", DEVICE_CODE_GRANT_TYPE),
+            ])
+            .send()
+            .await
+            .context("Failed to poll Google's token endpoint")?
+            .text()
+            .await
+            .context("Failed to read Google's token response")?;
+
+        match parse_device_poll(&body)? {
+            DevicePoll::Granted(token) => return Ok(token),
+            DevicePoll::Pending => {}
+            DevicePoll::SlowDown => {
+                interval += DEVICE_SLOW_DOWN_STEP;
+                tracing::debug!(interval = ?interval, "device flow asked to slow down");
+            }
+        }
+    }
+}
+
+/// Run the browser authorization flow once and exchange the code for a token
+///
+/// `prompt` is sent as the OAuth `prompt` parameter. The authorization URL is
+/// also printed, for when `browser` fails to open it.
+async fn authorize_in_browser(
+    credentials: &OAuthCredentials,
+    email: &str,
+    prompt: &str,
+    scope: AccessScope,
+    browser: &dyn BrowserOpener,
+) -> Result<BasicTokenResponse> {
+    // Start local server to receive callback; the redirect URI follows the bound port
+    let (listener, redirect_uri) = bind_callback_listener(&credentials.redirect_uri)?;
 
     // Create OAuth2 client
     let client = BasicClient::new(
-        ClientId::new(client_id),
-        Some(ClientSecret::new(client_secret)),
+        ClientId::new(credentials.client_id.clone()),
+        Some(ClientSecret::new(credentials.client_secret.clone())),
         AuthUrl::new(GOOGLE_AUTH_URL.to_string())?,
         Some(TokenUrl::new(GOOGLE_TOKEN_URL.to_string())?),
     )
-    .set_redirect_uri(RedirectUrl::new(redirect_uri.clone())?);
+    .set_redirect_uri(RedirectUrl::new(redirect_uri.to_string())?);
 
     // Generate PKCE challenge
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -40,9 +620,9 @@ pub async fn add_account_for_email(email: &str) -> Result<EmailAccount> {
     // Generate authorization URL
     let (auth_url, csrf_token) = client
         .authorize_url(CsrfToken::new_random)
-        .add_scope(Scope::new(GMAIL_SCOPE.to_string()))
+        .add_scope(Scope::new(scope.url().to_string()))
         .add_extra_param("access_type", "offline")
-        .add_extra_param("prompt", "consent")
+        .add_extra_param("prompt", prompt)
         .add_extra_param("login_hint", email)
         .set_pkce_challenge(pkce_challenge)
         .url();
@@ -51,36 +631,14 @@ pub async fn add_account_for_email(email: &str) -> Result<EmailAccount> {
     println!("\nOpening browser for authentication...");
     println!("If browser doesn't open, visit: {}\n", auth_url);
 
-    if let Err(e) = open::that(auth_url.as_str()) {
-        eprintln!("Failed to open browser: {}", e);
+    if let Err(e) = browser.open(auth_url.as_str()) {
+        eprintln!("Failed to open browser: {:#}", e);
     }
 
-    // Start local server to receive callback
-    let listener =
-        TcpListener::bind("127.0.0.1:9090").context("Failed to bind to localhost:9090")?;
-
     println!("Waiting for authorization...\n");
 
     // Wait for callback
-    let (mut stream, _) = listener.accept().context("Failed to accept connection")?;
-
-    let mut reader = BufReader::new(&stream);
-    let mut request_line = String::new();
-    reader
-        .read_line(&mut request_line)
-        .context("Failed to read request")?;
-
-    // Parse callback URL
-    let redirect_url = request_line
-        .split_whitespace()
-        .nth(1)
-        .context("Invalid request line")?;
-    let url = Url::parse(&format!("http://localhost:9090{}", redirect_url))
-        .context("Failed to parse callback URL")?;
-
-    // Send success response to browser
-    let response = "HTTP/1.1 200 OK\r\n\r\n<html><body><h1>Authentication successful!</h1><p>You can close this window.</p></body></html>";
-    stream.write_all(response.as_bytes()).ok();
+    let url = wait_for_callback(listener, &redirect_uri, OAUTH_CALLBACK_TIMEOUT).await?;
 
     // Extract code and state
     let code = url
@@ -101,54 +659,29 @@ pub async fn add_account_for_email(email: &str) -> Result<EmailAccount> {
     }
 
     // Exchange code for token
-    let token = client
+    client
         .exchange_code(code)
         .set_pkce_verifier(pkce_verifier)
         .request_async(oauth2::reqwest::async_http_client)
         .await
-        .context("Failed to exchange authorization code for token")?;
-
-    // Store token for provided email
-    let access_token = token.access_token().secret();
-    let oauth_token = OAuth2Token {
-        access_token: access_token.clone(),
-        refresh_token: token
-            .refresh_token()
-            .context("No refresh token received")?
-            .secret()
-            .clone(),
-        expires_at: Utc::now() + chrono::Duration::seconds(3600),
-    };
-
-    storage::keyring::store_token(email, oauth_token)?;
-
-    // Create and save account
-    let account = EmailAccount {
-        email: email.to_string(),
-        added_at: Utc::now(),
-    };
-
-    storage::json_store::save_account(&account)?;
-
-    Ok(account)
+        .context("Failed to exchange authorization code for token")
 }
 
 /// Refresh an expired OAuth2 token
 pub async fn refresh_token_for_email(email: &str) -> Result<OAuth2Token> {
-    tracing::debug!("Refreshing token for {}", email);
+    tracing::debug!(email, "refreshing token");
 
     // Get existing token (which should have refresh_token)
     let old_token =
         storage::keyring::get_token(email)?.context("No existing token found for this email")?;
 
-    // Get OAuth2 credentials from environment
-    let client_id = env::var("GOOGLE_CLIENT_ID").context("GOOGLE_CLIENT_ID not set")?;
-    let client_secret = env::var("GOOGLE_CLIENT_SECRET").context("GOOGLE_CLIENT_SECRET not set")?;
+    // Get OAuth2 credentials from environment or client_secret.json
+    let credentials = load_oauth_credentials()?;
 
     // Create OAuth2 client
     let client = BasicClient::new(
-        ClientId::new(client_id),
-        Some(ClientSecret::new(client_secret)),
+        ClientId::new(credentials.client_id),
+        Some(ClientSecret::new(credentials.client_secret)),
         AuthUrl::new(GOOGLE_AUTH_URL.to_string())?,
         Some(TokenUrl::new(GOOGLE_TOKEN_URL.to_string())?),
     );
@@ -166,6 +699,7 @@ pub async fn refresh_token_for_email(email: &str) -> Result<OAuth2Token> {
     let new_token = OAuth2Token {
         access_token: token_response.access_token().secret().clone(),
         refresh_token: old_token.refresh_token, // Keep the same refresh token
+        scope: old_token.scope,
         expires_at: Utc::now()
             + chrono::Duration::seconds(
                 token_response
@@ -177,7 +711,2219 @@ pub async fn refresh_token_for_email(email: &str) -> Result<OAuth2Token> {
 
     storage::keyring::store_token(email, new_token.clone())?;
 
-    tracing::debug!("Token refreshed successfully for {}", email);
+    tracing::debug!(email, "token refreshed");
 
     Ok(new_token)
 }
+
+/// Refresh the stored token and return the new access token
+pub async fn refreshed_access_token(email: &str) -> Result<String> {
+    Ok(refresh_token_for_email(email).await?.access_token)
+}
+
+/// Run `op` with `access_token`, refreshing the token once if it expired
+///
+/// Long scans and cleanups can outlive the token. When `op` fails with an
+/// expired-token error ([`imap::connection::is_token_expired`]), `refresh` is
+/// called, `access_token` is replaced so later operations use the new token,
+/// and `op` runs again. Other errors, a failed refresh or a second failure
+/// are returned as is.
+pub async fn with_token_refresh<T, Op, OpFut, Refresh, RefreshFut>(
+    access_token: &mut String,
+    mut op: Op,
+    refresh: Refresh,
+) -> Result<T>
+where
+    Op: FnMut(String) -> OpFut,
+    OpFut: std::future::Future<Output = Result<T>>,
+    Refresh: FnOnce() -> RefreshFut,
+    RefreshFut: std::future::Future<Output = Result<String>>,
+{
+    match op(access_token.clone()).await {
+        Err(e) if imap::connection::is_token_expired(&e) => {
+            tracing::info!(
+                error = %format_args!("{:#}", e),
+                "access token expired mid-operation, refreshing"
+            );
+            match refresh().await {
+                Ok(token) => *access_token = token,
+                Err(refresh_error) => {
+                    tracing::warn!(
+                        error = %format_args!("{:#}", refresh_error),
+                        "failed to refresh the access token"
+                    );
+                    return Err(e);
+                }
+            }
+            op(access_token.clone()).await
+        }
+        result => result,
+    }
+}
+
+/// Get a valid access token for an already-added account
+///
+/// Refreshes the stored token if it has expired. Fails if the account has
+/// never been authenticated (use [`add_account_for_email`] first).
+pub async fn access_token_for_email(email: &str) -> Result<String> {
+    access_token_with_scope(email, AccessScope::ReadOnly).await
+}
+
+/// Scope the account was signed in with (None if it never was)
+pub fn account_scope(email: &str) -> Result<Option<AccessScope>> {
+    Ok(storage::keyring::get_token(email)?.map(|token| token.scope))
+}
+
+/// Like [`access_token_for_email`], but fails unless the account's scope
+/// covers `needed`
+///
+/// Use [`AccessScope::Full`] before deleting, moving, labeling or restoring
+/// messages, so scan-only accounts fail with an explanation instead of an
+/// IMAP authentication error.
+pub async fn access_token_with_scope(email: &str, needed: AccessScope) -> Result<String> {
+    let token = storage::keyring::get_token(email)?.with_context(|| {
+        format!(
+            "No stored token for {} - run `unsubmail` interactively to authenticate",
+            email
+        )
+    })?;
+
+    if !token.scope.covers(needed) {
+        anyhow::bail!(
+            "{} was signed in for scan only (gmail.readonly), which can't change messages. \
+            Run `unsubmail` interactively and choose full access to clean",
+            email
+        );
+    }
+
+    if !token.is_expired() {
+        return Ok(token.access_token);
+    }
+
+    Ok(refresh_token_for_email(email).await?.access_token)
+}
+
+/// Options for [`scan_inbox`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    /// How headers are fetched
+    pub fetch: FetchOptions,
+
+    /// Whether senders are grouped by address or by domain
+    pub grouping: SenderGrouping,
+
+    /// Newsletter address patterns added to the built-in ones
+    pub newsletter_patterns: Vec<String>,
+
+    /// Scoring weights and thresholds
+    pub scoring: analysis::ScoringConfig,
+
+    /// Gmail API concurrency and retries, for [`scan_inbox_readonly`]
+    pub gmail_api: GmailFetchConfig,
+}
+
+impl ScanOptions {
+    /// Scoring weights with the extra newsletter patterns merged in
+    pub fn scoring_config(&self) -> analysis::ScoringConfig {
+        self.scoring
+            .clone()
+            .with_extra_patterns(&self.newsletter_patterns)
+    }
+}
+
+/// Scan the inbox and analyze every sender
+///
+/// Connects with the IMAP settings from `config.toml` and the environment
+/// (see [`storage::config::Config::imap_config`]), fetches headers,
+/// groups them by sender and scores each group. Mail sent from the
+/// account's own address is left out. Accounts signed in with
+/// [`AccessScope::ReadOnly`] have no IMAP access and are scanned through
+/// the Gmail API instead (see [`scan_inbox_readonly`]).
+///
+/// ```no_run
+/// use unsubmail::application::workflow::{self, ScanOptions};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let token = workflow::access_token_for_email("me@gmail.com").await?;
+/// let senders = workflow::scan_inbox("me@gmail.com", &token, &ScanOptions::default()).await?;
+/// println!("{} senders", senders.len());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn scan_inbox(
+    email: &str,
+    access_token: &str,
+    options: &ScanOptions,
+) -> Result<Vec<SenderInfo>> {
+    let imap_config = storage::config::load_config()?.imap_config()?;
+    if account_scope(email)?.is_some_and(|scope| !scope.allows_changes()) {
+        return scan_inbox_readonly(
+            email,
+            access_token,
+            &imap_config.mailbox,
+            options,
+            |_, _| {},
+        )
+        .await;
+    }
+
+    scan_inbox_with(&imap_config, email, access_token, options, |_, _| {}).await
+}
+
+/// Scan a mailbox through the Gmail API with a read-only token
+///
+/// Works with [`AccessScope::ReadOnly`], which has no IMAP access. `mailbox`
+/// is the IMAP name the full scan would use (INBOX, a `[Gmail]/` folder or
+/// a label). Doesn't use the header cache. The returned senders carry
+/// placeholder message UIDs, so they can be shown or exported but not
+/// cleaned.
+#[tracing::instrument(name = "scan", skip_all, fields(email = %email, mailbox = %mailbox, via = "gmail_api"))]
+pub async fn scan_inbox_readonly<F>(
+    email: &str,
+    access_token: &str,
+    mailbox: &str,
+    options: &ScanOptions,
+    on_progress: F,
+) -> Result<Vec<SenderInfo>>
+where
+    F: FnMut(usize, usize),
+{
+    let client = network::gmail_api::GmailApiClient::new(access_token)?
+        .with_fetch_config(options.gmail_api.clone());
+    let headers = client
+        .fetch_headers(mailbox, &options.fetch, on_progress)
+        .await?;
+
+    Ok(analyze_headers_with(
+        fetch::exclude_own_messages(headers, email),
+        options.grouping,
+        &options.scoring_config(),
+    ))
+}
+
+/// Addresses the account has sent mail to, sorted
+///
+/// Reads the To, Cc and Bcc headers in the Sent folder (found through its
+/// `\Sent` attribute, falling back to `imap_config.sent_folder`). The
+/// account's own address is left out. Meant to be added to the allowlist so
+/// people the user corresponds with are never offered for cleanup.
+pub async fn sent_correspondents(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+    options: &FetchOptions,
+) -> Result<Vec<String>> {
+    let (mut session, resolved) =
+        imap::connection::connect_and_resolve(imap_config, email, access_token).await?;
+
+    let correspondents =
+        fetch::fetch_correspondents(&mut session, &resolved.sent_folder, options).await?;
+
+    session.logout().await?;
+
+    let own = fetch::normalize_mailbox(email);
+    let mut correspondents: Vec<String> = correspondents
+        .into_iter()
+        .filter(|address| fetch::normalize_mailbox(address) != own)
+        .collect();
+    correspondents.sort();
+
+    Ok(correspondents)
+}
+
+/// Result of [`scan_all_accounts`]
+#[derive(Debug, Default)]
+pub struct MultiAccountScan {
+    /// Accounts that were scanned, in address order
+    pub scans: Vec<AccountScan>,
+
+    /// Accounts that were skipped, with the reason
+    pub skipped: Vec<(String, anyhow::Error)>,
+}
+
+/// Scan every stored account one after the other
+///
+/// Expired tokens are refreshed first. Scan-only accounts are scanned
+/// through the Gmail API, the others over IMAP with `imap_config`. An
+/// account that fails (revoked token, network error...) is logged and
+/// skipped so the remaining accounts are still scanned. `on_account` is
+/// called before each account is scanned.
+#[tracing::instrument(name = "scan_all", skip_all)]
+pub async fn scan_all_accounts<F>(
+    imap_config: &ImapConfig,
+    options: &ScanOptions,
+    mut on_account: F,
+) -> Result<MultiAccountScan>
+where
+    F: FnMut(&str),
+{
+    let mut accounts = storage::json_store::list_accounts()?;
+    accounts.sort_by(|a, b| a.email.cmp(&b.email));
+
+    let mut result = MultiAccountScan::default();
+    for account in accounts {
+        on_account(&account.email);
+        match scan_account(imap_config, &account.email, options).await {
+            Ok(senders) => result.scans.push(AccountScan {
+                email: account.email,
+                senders,
+            }),
+            Err(e) => {
+                tracing::warn!(
+                    email = %account.email,
+                    error = %format_args!("{:#}", e),
+                    "skipping account"
+                );
+                result.skipped.push((account.email, e));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Scan one stored account with whichever access it was signed in with
+async fn scan_account(
+    imap_config: &ImapConfig,
+    email: &str,
+    options: &ScanOptions,
+) -> Result<Vec<SenderInfo>> {
+    let access_token = access_token_for_email(email).await?;
+    if account_scope(email)? == Some(AccessScope::ReadOnly) {
+        scan_inbox_readonly(
+            email,
+            &access_token,
+            &imap_config.mailbox,
+            options,
+            |_, _| {},
+        )
+        .await
+    } else {
+        scan_inbox_with(imap_config, email, &access_token, options, |_, _| {}).await
+    }
+}
+
+/// Scan the inbox with explicit IMAP settings, reporting fetch progress
+///
+/// See [`scan_inbox`] and [`scan_session`].
+pub async fn scan_inbox_with<F>(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+    options: &ScanOptions,
+    on_progress: F,
+) -> Result<Vec<SenderInfo>>
+where
+    F: FnMut(usize, usize),
+{
+    let mut session = imap::connection::connect_and_auth(imap_config, email, access_token).await?;
+
+    let senders = scan_session(
+        &mut session,
+        email,
+        &imap_config.mailbox,
+        options,
+        on_progress,
+    )
+    .await?;
+
+    session.logout().await?;
+
+    Ok(senders)
+}
+
+/// Fetch, group and analyze the headers in `mailbox` on an open session
+///
+/// Only messages new since the last scan are downloaded (see
+/// [`fetch_headers_cached`]).
+#[tracing::instrument(name = "scan", skip_all, fields(email = %email, mailbox = %mailbox))]
+pub async fn scan_session<F>(
+    session: &mut imap::connection::ImapSession,
+    email: &str,
+    mailbox: &str,
+    options: &ScanOptions,
+    on_progress: F,
+) -> Result<Vec<SenderInfo>>
+where
+    F: FnMut(usize, usize),
+{
+    let headers =
+        fetch_headers_cached(session, email, mailbox, &options.fetch, on_progress).await?;
+
+    Ok(analyze_headers_with(
+        fetch::exclude_own_messages(headers, email),
+        options.grouping,
+        &options.scoring_config(),
+    ))
+}
+
+/// Progress of a streaming scan
+#[derive(Debug, Clone)]
+pub struct ScanUpdate {
+    /// Senders whose counts changed with the latest batch, fully re-analyzed
+    pub senders: Vec<SenderInfo>,
+
+    /// Messages fetched from the server so far
+    pub fetched: usize,
+
+    /// Messages that need fetching in total (cached ones are not counted)
+    pub total: usize,
+
+    /// Headers scanned so far, cached ones included
+    pub scanned: usize,
+}
+
+impl ScanUpdate {
+    /// Approximate mailbox size, if this last update comes from a scan its
+    /// time budget stopped early
+    pub fn partial_of(&self) -> Option<usize> {
+        (self.fetched < self.total).then(|| self.scanned + self.total - self.fetched)
+    }
+}
+
+/// Like [`scan_session`], but sends a [`ScanUpdate`] after every batch
+///
+/// Cached headers are reported first, then each fetched batch. The complete
+/// sender list is still returned at the end; updates are dropped if the
+/// receiver has gone away.
+#[tracing::instrument(name = "scan", skip_all, fields(email = %email, mailbox = %mailbox))]
+pub async fn scan_session_streaming(
+    session: &mut imap::connection::ImapSession,
+    email: &str,
+    mailbox: &str,
+    options: &ScanOptions,
+    updates: mpsc::UnboundedSender<ScanUpdate>,
+) -> Result<Vec<SenderInfo>> {
+    let mut accumulator =
+        SenderAccumulator::new(email, options.grouping).with_scoring(options.scoring_config());
+    let mut scanned = 0;
+
+    fetch_headers_cached_batched(
+        session,
+        email,
+        mailbox,
+        &options.fetch,
+        |headers, fetched, total| {
+            let senders = accumulator.add(headers);
+            scanned += headers.len();
+            let _ = updates.send(ScanUpdate {
+                senders,
+                fetched,
+                total,
+                scanned,
+            });
+        },
+    )
+    .await?;
+
+    Ok(accumulator.into_senders())
+}
+
+/// Fetch the headers in `mailbox`, downloading only messages new since the
+/// last scan
+///
+/// The mailbox's header snapshot is loaded from and saved back to the cache;
+/// a missing or unreadable cache means a full scan.
+pub async fn fetch_headers_cached<F>(
+    session: &mut imap::connection::ImapSession,
+    email: &str,
+    mailbox: &str,
+    options: &FetchOptions,
+    mut on_progress: F,
+) -> Result<Vec<fetch::MessageHeader>>
+where
+    F: FnMut(usize, usize),
+{
+    fetch_headers_cached_batched(session, email, mailbox, options, |_, done, total| {
+        on_progress(done, total)
+    })
+    .await
+}
+
+/// Like [`fetch_headers_cached`], passing each batch of headers to `on_batch`
+///
+/// See [`fetch::fetch_headers_incremental_batched`].
+pub async fn fetch_headers_cached_batched<F>(
+    session: &mut imap::connection::ImapSession,
+    email: &str,
+    mailbox: &str,
+    options: &FetchOptions,
+    mut on_batch: F,
+) -> Result<Vec<fetch::MessageHeader>>
+where
+    F: FnMut(&[fetch::MessageHeader], usize, usize),
+{
+    let previous = storage::header_cache::load_snapshot(email, mailbox).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "failed to load header cache");
+        None
+    });
+
+    // The last batch reports fewer fetched than total when the time budget
+    // stopped the scan
+    let mut complete = true;
+    let snapshot = fetch::fetch_headers_incremental_batched(
+        session,
+        mailbox,
+        options,
+        previous,
+        |headers, fetched, total| {
+            complete = fetched >= total;
+            on_batch(headers, fetched, total)
+        },
+    )
+    .await?;
+
+    // A date-limited or partial scan only sees part of the mailbox; saving it
+    // would make later full scans skip the missing messages
+    if options.since.is_none() && complete {
+        if let Err(e) = storage::header_cache::save_snapshot(email, mailbox, &snapshot) {
+            tracing::warn!(error = %e, "failed to save header cache");
+        }
+    }
+
+    Ok(snapshot.headers)
+}
+
+/// Group fetched headers by sender and analyze each sender
+pub fn analyze_headers(headers: Vec<fetch::MessageHeader>) -> Vec<SenderInfo> {
+    analyze_headers_grouped(headers, SenderGrouping::Address)
+}
+
+/// Group fetched headers by address or domain and analyze each group
+///
+/// With [`SenderGrouping::Domain`], `SenderInfo.email` holds the domain and
+/// the UIDs of every subaddress are aggregated into one sender.
+pub fn analyze_headers_grouped(
+    headers: Vec<fetch::MessageHeader>,
+    grouping: SenderGrouping,
+) -> Vec<SenderInfo> {
+    analyze_headers_with(headers, grouping, &analysis::ScoringConfig::default())
+}
+
+/// Like [`analyze_headers_grouped`], scoring with custom weights and patterns
+pub fn analyze_headers_with(
+    headers: Vec<fetch::MessageHeader>,
+    grouping: SenderGrouping,
+    scoring: &analysis::ScoringConfig,
+) -> Vec<SenderInfo> {
+    let grouped = match grouping {
+        SenderGrouping::Address => fetch::group_by_sender(headers),
+        SenderGrouping::Domain => fetch::group_by_domain(headers),
+    };
+
+    grouped
+        .into_iter()
+        .map(|(email, messages)| analyze_group(scoring, email, &messages))
+        .collect()
+}
+
+/// Analyze the messages of one sender group
+fn analyze_group(
+    scoring: &analysis::ScoringConfig,
+    email: String,
+    messages: &[fetch::MessageHeader],
+) -> SenderInfo {
+    let message_count = messages.len();
+    let message_uids: Vec<u32> = messages.iter().map(|m| m.uid).collect();
+    let first = &messages[0];
+    let display_name = extract_display_name(&first.from);
+    let sample_subjects: Vec<String> = messages.iter().take(3).map(|m| m.subject.clone()).collect();
+
+    let has_list_id = messages.iter().any(|m| m.list_id.is_some());
+    let unsubscribe_headers: Vec<analysis::UnsubscribeHeaders> = messages
+        .iter()
+        .map(|m| analysis::UnsubscribeHeaders {
+            date: m.date,
+            list_unsubscribe: m.list_unsubscribe.clone(),
+            list_unsubscribe_post: m.list_unsubscribe_post.clone(),
+        })
+        .collect();
+    let signals = analysis::BulkSignals {
+        is_bulk: messages
+            .iter()
+            .any(|m| analysis::is_bulk_precedence(m.precedence.as_deref())),
+        is_auto: messages
+            .iter()
+            .any(|m| analysis::is_auto_submitted(m.auto_submitted.as_deref())),
+    };
+
+    let mut sender = analysis::analyze_sender_with(
+        scoring,
+        email,
+        display_name,
+        message_count,
+        message_uids,
+        &unsubscribe_headers,
+        has_list_id,
+        signals,
+        sample_subjects,
+    );
+    sender.set_message_dates(messages.iter().map(|m| m.date).collect());
+    sender.unread_count = messages.iter().filter(|m| m.seen == Some(false)).count();
+    sender
+}
+
+/// Running sender analysis for a scan whose headers arrive in batches
+///
+/// Each batch is grouped into the senders seen so far; only the groups it
+/// touched are re-analyzed, so counts and scores update in place.
+#[derive(Debug, Clone)]
+pub struct SenderAccumulator {
+    own_email: String,
+    grouping: SenderGrouping,
+    scoring: analysis::ScoringConfig,
+    groups: HashMap<String, Vec<fetch::MessageHeader>>,
+}
+
+impl SenderAccumulator {
+    pub fn new(own_email: &str, grouping: SenderGrouping) -> Self {
+        Self {
+            own_email: own_email.to_string(),
+            grouping,
+            scoring: analysis::ScoringConfig::default(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Score senders with custom weights and patterns
+    pub fn with_scoring(mut self, scoring: analysis::ScoringConfig) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Add a batch of headers and return the updated senders it touched
+    pub fn add(&mut self, headers: &[fetch::MessageHeader]) -> Vec<SenderInfo> {
+        let headers = fetch::exclude_own_messages(headers.to_vec(), &self.own_email);
+        let batch = match self.grouping {
+            SenderGrouping::Address => fetch::group_by_sender(headers),
+            SenderGrouping::Domain => fetch::group_by_domain(headers),
+        };
+
+        batch
+            .into_iter()
+            .map(|(email, mut messages)| {
+                let group = self.groups.entry(email.clone()).or_default();
+                group.append(&mut messages);
+                analyze_group(&self.scoring, email, group)
+            })
+            .collect()
+    }
+
+    /// Analyze every sender seen so far, in [`fetch::SenderGroups`] order
+    pub fn into_senders(self) -> Vec<SenderInfo> {
+        fetch::sort_groups(self.groups)
+            .into_iter()
+            .map(|(email, messages)| analyze_group(&self.scoring, email, &messages))
+            .collect()
+    }
+}
+
+/// Execute planned cleanup actions without user interaction
+///
+/// Each action is executed independently: a failure for one sender is
+/// recorded in its [`CleanupResult`] and does not stop the others. Once the
+/// next sender would take the run past `max_delete` removed messages, it and
+/// every remaining sender are skipped with an error result. Unsubscribe
+/// requests use `unsubscribe` (see [`storage::config::Config::unsubscribe_options`]).
+pub async fn clean_senders(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+    senders: Vec<SenderInfo>,
+    max_delete: Option<usize>,
+    unsubscribe: &UnsubscribeOptions,
+) -> Result<Vec<CleanupResult>> {
+    let actions = planner::plan_actions(senders);
+    clean_actions(
+        imap_config,
+        email,
+        access_token,
+        actions,
+        max_delete,
+        unsubscribe,
+    )
+    .await
+}
+
+/// Execute already planned cleanup actions without user interaction
+///
+/// See [`clean_senders`].
+#[tracing::instrument(name = "cleanup", skip_all, fields(email = %email, senders = actions.len()))]
+pub async fn clean_actions(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+    actions: Vec<CleanupAction>,
+    max_delete: Option<usize>,
+    unsubscribe: &UnsubscribeOptions,
+) -> Result<Vec<CleanupResult>> {
+    let (mut session, imap_config) =
+        imap::connection::connect_and_resolve(imap_config, email, access_token).await?;
+    let imap_config = &imap_config;
+    let journal = CleanupJournal::new(email);
+    let mut unsubscribes = UnsubscribeTracker::with_options(unsubscribe.clone());
+    let mut cap = planner::DeletionCap::new(max_delete);
+    let mut halted = false;
+    let mut results = Vec::with_capacity(actions.len());
+
+    for action in actions {
+        halted = halted || cap.would_exceed(action.sender.message_uids.len());
+        if halted {
+            results.push(skipped_at_cap(action, &cap));
+            continue;
+        }
+
+        let result = cleanup_sender(
+            &mut session,
+            imap_config,
+            &journal,
+            &mut unsubscribes,
+            action,
+        )
+        .await;
+        cap.record(result.messages_deleted);
+        results.push(result);
+    }
+
+    session.logout().await?;
+
+    Ok(results)
+}
+
+/// Execute one planned cleanup action
+///
+/// Order is unsubscribe → spam → delete, depending on the action type. Errors
+/// are captured in the returned [`CleanupResult`] rather than propagated.
+#[tracing::instrument(name = "clean_sender", skip_all, fields(sender = %action.sender.email))]
+pub async fn cleanup_sender(
+    session: &mut imap::connection::ImapSession,
+    imap_config: &ImapConfig,
+    journal: &CleanupJournal,
+    unsubscribes: &mut UnsubscribeTracker,
+    action: CleanupAction,
+) -> CleanupResult {
+    let sender = action.sender;
+    tracing::info!(action = ?action.action_type, "cleaning");
+
+    let result = match action.action_type {
+        ActionType::UnsubscribeAndDelete => {
+            let unsubscribed = attempt_unsubscribe(unsubscribes, &sender).await;
+
+            journal
+                .delete_messages(session, imap_config, &sender.email, &sender.message_uids)
+                .await
+                .map(|count| (count, unsubscribed))
+        }
+        ActionType::SpamAndDelete => journal
+            .move_to_spam(session, imap_config, &sender.email, &sender.message_uids)
+            .await
+            .map(|count| (count, None)),
+        ActionType::DeleteOnly => journal
+            .delete_messages(session, imap_config, &sender.email, &sender.message_uids)
+            .await
+            .map(|count| (count, None)),
+    };
+
+    let host = unsubscribe_host(&sender);
+    let via = unsubscribes.method_used(&sender.one_click_urls());
+    match result {
+        Ok((count, unsubscribed)) => {
+            CleanupResult::success(sender.email, action.action_type, count, unsubscribed)
+                .with_unsubscribe_host(host)
+                .with_unsubscribe_via(via)
+        }
+        Err(e) => CleanupResult::failure(sender.email, action.action_type, e.to_string()),
+    }
+}
+
+/// Result for a sender skipped because it would take the run past `cap`
+fn skipped_at_cap(action: CleanupAction, cap: &planner::DeletionCap) -> CleanupResult {
+    tracing::warn!(
+        sender = %action.sender.email,
+        cap = cap.limit().unwrap_or_default(),
+        "skipping sender, deletion cap reached"
+    );
+    CleanupResult::failure(
+        action.sender.email,
+        action.action_type,
+        format!(
+            "Skipped: would exceed the cap of {} deleted messages",
+            cap.limit().unwrap_or_default()
+        ),
+    )
+}
+
+/// A sender and the UIDs of its messages, as moved by batched cleanups
+pub type SenderUids = (String, Vec<u32>);
+
+/// Execute planned actions with one batched move per destination folder
+///
+/// Unsubscribes run first, then all trash-bound and all spam-bound messages
+/// are each moved with a single expunge (see
+/// [`imap::actions::delete_messages_batched`]). Results stay per sender and
+/// count the messages actually moved, leaving out those already gone from
+/// the mailbox; if a batch fails, every sender in it gets the error. Once
+/// the next sender would take the run past `cap`, it and every remaining
+/// sender are skipped with an error result.
+#[tracing::instrument(name = "clean_batch", skip_all, fields(senders = actions.len()))]
+pub async fn cleanup_senders_batched(
+    session: &mut imap::connection::ImapSession,
+    imap_config: &ImapConfig,
+    journal: &CleanupJournal,
+    unsubscribes: &mut UnsubscribeTracker,
+    actions: Vec<CleanupAction>,
+    cap: &mut planner::DeletionCap,
+) -> Vec<CleanupResult> {
+    let (actions, skipped) = split_at_cap(actions, cap);
+
+    let mut unsubscribed = Vec::with_capacity(actions.len());
+    for action in &actions {
+        unsubscribed.push(match action.action_type {
+            ActionType::UnsubscribeAndDelete => {
+                attempt_unsubscribe(unsubscribes, &action.sender).await
+            }
+            _ => None,
+        });
+    }
+
+    let (trash_groups, spam_groups) = batch_groups(&actions);
+
+    tracing::info!(
+        trash = trash_groups.len(),
+        spam = spam_groups.len(),
+        "moving senders in batches"
+    );
+
+    let mut moved: HashMap<String, usize> = HashMap::new();
+    let mut record_moved = |groups: Vec<SenderUids>| {
+        for (sender_email, uids) in groups {
+            *moved.entry(sender_email).or_default() += uids.len();
+        }
+    };
+    let trash_error = match journal
+        .delete_messages_batched(session, imap_config, &trash_groups)
+        .await
+    {
+        Ok(groups) => {
+            record_moved(groups);
+            None
+        }
+        Err(e) => Some(format!("{:#}", e)),
+    };
+    let spam_error = match journal
+        .move_to_spam_batched(session, imap_config, &spam_groups)
+        .await
+    {
+        Ok(groups) => {
+            record_moved(groups);
+            None
+        }
+        Err(e) => Some(format!("{:#}", e)),
+    };
+    cap.record(moved.values().sum());
+
+    let skipped: Vec<CleanupResult> = skipped
+        .into_iter()
+        .map(|action| skipped_at_cap(action, cap))
+        .collect();
+    actions
+        .into_iter()
+        .zip(unsubscribed)
+        .map(|(action, unsubscribed)| {
+            let error = if action.action_type == ActionType::SpamAndDelete {
+                &spam_error
+            } else {
+                &trash_error
+            };
+            let sender = action.sender;
+            let host = unsubscribe_host(&sender);
+            let via = unsubscribes.method_used(&sender.one_click_urls());
+
+            match error {
+                None => {
+                    let count = moved.get(&sender.email).copied().unwrap_or_default();
+                    CleanupResult::success(sender.email, action.action_type, count, unsubscribed)
+                }
+                Some(e) => {
+                    let mut result =
+                        CleanupResult::failure(sender.email, action.action_type, e.clone());
+                    result.unsubscribe_success = unsubscribed;
+                    result
+                }
+            }
+            .with_unsubscribe_host(host)
+            .with_unsubscribe_via(via)
+        })
+        .chain(skipped)
+        .collect()
+}
+
+/// Split `actions` into those that fit under `cap` and the rest
+///
+/// Actions are taken in order until the next one would go over the cap.
+fn split_at_cap(
+    actions: Vec<CleanupAction>,
+    cap: &planner::DeletionCap,
+) -> (Vec<CleanupAction>, Vec<CleanupAction>) {
+    let mut planned = 0;
+    let mut halted = false;
+    actions.into_iter().partition(|action| {
+        let count = action.sender.message_uids.len();
+        halted = halted || cap.would_exceed(planned + count);
+        if !halted {
+            planned += count;
+        }
+        !halted
+    })
+}
+
+/// `groups` with only the UIDs found in the sorted `moved`
+fn moved_groups(groups: &[SenderUids], moved: &[u32]) -> Vec<SenderUids> {
+    groups
+        .iter()
+        .map(|(sender_email, uids)| {
+            let uids = uids
+                .iter()
+                .copied()
+                .filter(|uid| moved.binary_search(uid).is_ok())
+                .collect();
+            (sender_email.clone(), uids)
+        })
+        .collect()
+}
+
+/// Split actions into (trash, spam) groups of sender and UIDs
+fn batch_groups(actions: &[CleanupAction]) -> (Vec<SenderUids>, Vec<SenderUids>) {
+    let (spam, trash): (Vec<&CleanupAction>, Vec<&CleanupAction>) = actions
+        .iter()
+        .partition(|a| a.action_type == ActionType::SpamAndDelete);
+
+    let to_groups = |actions: Vec<&CleanupAction>| {
+        actions
+            .into_iter()
+            .map(|a| (a.sender.email.clone(), a.sender.message_uids.clone()))
+            .collect()
+    };
+
+    (to_groups(trash), to_groups(spam))
+}
+
+/// Attempt a one-click unsubscribe for a sender
+///
+/// Returns `None` when the sender has no one-click method, otherwise whether
+/// the request succeeded.
+pub async fn attempt_unsubscribe(
+    unsubscribes: &mut UnsubscribeTracker,
+    sender: &SenderInfo,
+) -> Option<bool> {
+    let urls = sender.one_click_urls();
+    if urls.is_empty() {
+        return None;
+    }
+
+    match unsubscribes.unsubscribe(&urls).await {
+        Ok(success) => Some(success),
+        Err(e) => {
+            tracing::warn!(sender = %sender.email, error = %e, "unsubscribe failed");
+            Some(false)
+        }
+    }
+}
+
+/// Host of a sender's one-click unsubscribe URL, for per-ESP statistics
+pub fn unsubscribe_host(sender: &SenderInfo) -> Option<String> {
+    network::http_client::unsubscribe_host(&sender.one_click_urls())
+}
+
+/// HTTPS unsubscribe page of a sender that needs a manual confirmation
+///
+/// Only [`UnsubscribeMethod::HttpLink`] senders have one; one-click senders
+/// are unsubscribed with a POST instead.
+pub fn manual_unsubscribe_url(sender: &SenderInfo) -> Option<&str> {
+    match &sender.unsubscribe_method {
+        UnsubscribeMethod::HttpLink { url } if network::http_client::is_https_url(url) => Some(url),
+        _ => None,
+    }
+}
+
+/// Unsubscribe page to offer when a one-click unsubscribe is rejected
+///
+/// One-click senders often list a second HTTPS link in List-Unsubscribe;
+/// the first one that differs from the one-click URL is returned.
+pub fn fallback_unsubscribe_url(sender: &SenderInfo) -> Option<&str> {
+    let UnsubscribeMethod::OneClick { url: one_click } = &sender.unsubscribe_method else {
+        return None;
+    };
+
+    sender
+        .unsubscribe_urls
+        .iter()
+        .map(String::as_str)
+        .find(|url| url != one_click && network::http_client::is_https_url(url))
+}
+
+/// Open a manual unsubscribe page with `browser`
+///
+/// Refuses anything but HTTPS, like one-click requests.
+pub fn open_unsubscribe_page(url: &str, browser: &dyn BrowserOpener) -> Result<()> {
+    if !network::http_client::is_https_url(url) {
+        anyhow::bail!("Only HTTPS unsubscribe pages are opened");
+    }
+
+    browser.open(url)
+}
+
+/// Remembers one-click unsubscribe attempts within a single cleanup run
+///
+/// Senders sharing an ESP endpoint (or listed twice) only trigger one request;
+/// later senders reuse its result. Requests are spaced out by the options'
+/// delays ([`network::http_client::Throttle`]) and share one [`HttpClient`],
+/// built on the first request. Nothing is persisted across runs.
+#[derive(Debug, Default)]
+pub struct UnsubscribeTracker {
+    attempted: HashMap<String, bool>,
+    methods: HashMap<String, UnsubscribeVia>,
+    options: UnsubscribeOptions,
+    throttle: network::http_client::Throttle,
+    client: Option<HttpClient>,
+}
+
+impl UnsubscribeTracker {
+    /// Start with no recorded attempts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with no recorded attempts, sending requests with `options`
+    pub fn with_options(options: UnsubscribeOptions) -> Self {
+        Self {
+            attempted: HashMap::new(),
+            methods: HashMap::new(),
+            throttle: network::http_client::Throttle::new(&options),
+            options,
+            client: None,
+        }
+    }
+
+    /// The run's HTTP client, built from the options on first use
+    fn client(&mut self) -> Result<HttpClient> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+
+        let client = HttpClient::new(self.options.clone())?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Unsubscribe via `urls` unless one of them was already tried this run
+    pub async fn unsubscribe(&mut self, urls: &[String]) -> Result<bool> {
+        if let Some(success) = self.previous_result(urls) {
+            tracing::info!("already unsubscribed via this endpoint this session, skipping");
+            return Ok(success);
+        }
+
+        let client = self.client()?;
+        let host = network::http_client::unsubscribe_host(urls).unwrap_or_default();
+        self.throttle.wait(&host).await;
+        let result = client.unsubscribe_one_click(urls).await;
+        self.record_outcome(urls, &result);
+        result.map(|via| via.is_some())
+    }
+
+    /// Unsubscribe from every sender with one-click URLs concurrently
+    ///
+    /// Senders already covered by an earlier attempt reuse its result. At most
+    /// `concurrency` requests run at once. Returns one result per sender that
+    /// has one-click URLs, in input order.
+    pub async fn unsubscribe_all(
+        &mut self,
+        senders: &[SenderInfo],
+        concurrency: usize,
+    ) -> Vec<(String, Result<bool>)> {
+        let mut targets = Vec::new();
+        let mut results = Vec::new();
+
+        for sender in senders {
+            let urls = sender.one_click_urls();
+            if urls.is_empty() {
+                continue;
+            }
+
+            match self.previous_result(&urls) {
+                Some(success) => results.push((sender.email.clone(), Some(Ok(success)))),
+                None => {
+                    results.push((sender.email.clone(), None));
+                    targets.push((sender.email.clone(), urls));
+                }
+            }
+        }
+
+        let sent = match self.client() {
+            Ok(client) => {
+                client
+                    .unsubscribe_all(&targets, concurrency, &self.throttle)
+                    .await
+            }
+            Err(e) => targets
+                .iter()
+                .map(|(key, _)| (key.clone(), Err(anyhow::anyhow!("{:#}", e))))
+                .collect(),
+        };
+        let mut sent = sent.into_iter().zip(&targets);
+
+        results
+            .into_iter()
+            .map(|(email, previous)| match previous {
+                Some(result) => (email, result),
+                None => {
+                    let ((email, result), (_, urls)) =
+                        sent.next().expect("one result per unsubscribe target");
+                    self.record_outcome(urls, &result);
+                    (email, result.map(|via| via.is_some()))
+                }
+            })
+            .collect()
+    }
+
+    /// Result of an earlier attempt covering these URLs
+    ///
+    /// A success through any URL counts; a failure only counts once every
+    /// candidate has been tried.
+    pub fn previous_result(&self, urls: &[String]) -> Option<bool> {
+        let results: Vec<Option<bool>> = urls
+            .iter()
+            .map(|url| self.attempted.get(&normalize_url(url)).copied())
+            .collect();
+
+        if results.contains(&Some(true)) {
+            Some(true)
+        } else if !results.is_empty() && results.iter().all(Option::is_some) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// HTTP method that unsubscribed through one of these URLs, if any
+    pub fn method_used(&self, urls: &[String]) -> Option<UnsubscribeVia> {
+        urls.iter()
+            .find_map(|url| self.methods.get(&normalize_url(url)).copied())
+    }
+
+    /// Record the outcome of an attempt through `urls`
+    pub fn record(&mut self, urls: &[String], success: bool) {
+        for url in urls {
+            let previous = self.attempted.entry(normalize_url(url)).or_insert(false);
+            *previous |= success;
+        }
+    }
+
+    /// Record a request's result, remembering the method that succeeded
+    fn record_outcome(&mut self, urls: &[String], result: &Result<Option<UnsubscribeVia>>) {
+        let via = result.as_ref().ok().copied().flatten();
+        self.record(urls, via.is_some());
+        if let Some(via) = via {
+            for url in urls {
+                self.methods.insert(normalize_url(url), via);
+            }
+        }
+    }
+}
+
+/// Normalize an unsubscribe URL for de-duplication
+///
+/// Scheme and host are lowercased and the fragment is dropped; the query is
+/// kept since it usually identifies the subscriber.
+fn normalize_url(url: &str) -> String {
+    match Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+/// What to do when Ctrl-C is pressed
+///
+/// While a [`CleanupCancellation`] guard is alive the first Ctrl-C only asks
+/// the cleanup loop to stop after the current sender; a second press quits.
+#[derive(Debug, Default)]
+pub struct CancelState {
+    active: AtomicBool,
+    requested: AtomicBool,
+}
+
+impl CancelState {
+    /// Create an idle state
+    pub const fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            requested: AtomicBool::new(false),
+        }
+    }
+
+    /// Start a cancellable run, forgetting any earlier request
+    pub fn activate(&self) {
+        self.requested.store(false, Ordering::SeqCst);
+        self.active.store(true, Ordering::SeqCst);
+    }
+
+    /// End the cancellable run
+    pub fn deactivate(&self) {
+        self.active.store(false, Ordering::SeqCst);
+    }
+
+    /// Record a Ctrl-C press
+    ///
+    /// Returns `true` if a running cleanup will stop gracefully, `false` if
+    /// the process should exit right away.
+    pub fn interrupt(&self) -> bool {
+        self.active.load(Ordering::SeqCst) && !self.requested.swap(true, Ordering::SeqCst)
+    }
+
+    /// Whether Ctrl-C was pressed during the current run
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+static CLEANUP_CANCEL: CancelState = CancelState::new();
+
+/// Stops a cleanup loop between senders when Ctrl-C is pressed
+///
+/// The Ctrl-C watcher lives as long as the guard: a second press while it is
+/// alive exits with status 130, and once it is dropped Ctrl-C is left to
+/// whatever else is listening, such as the cancellable sign-in flows.
+pub struct CleanupCancellation {
+    watcher: tokio::task::JoinHandle<()>,
+}
+
+impl CleanupCancellation {
+    /// Start watching for Ctrl-C until the guard is dropped
+    pub fn install() -> Self {
+        CLEANUP_CANCEL.activate();
+        let watcher = tokio::spawn(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if CLEANUP_CANCEL.interrupt() {
+                    tracing::info!("cleanup cancellation requested");
+                    eprintln!(
+                        "\nStopping after the current sender... (press Ctrl-C again to quit now)"
+                    );
+                } else {
+                    std::process::exit(130);
+                }
+            }
+        });
+
+        Self { watcher }
+    }
+
+    /// Whether the loop should stop before the next sender
+    pub fn is_cancelled(&self) -> bool {
+        CLEANUP_CANCEL.is_requested()
+    }
+}
+
+impl Drop for CleanupCancellation {
+    fn drop(&mut self) {
+        self.watcher.abort();
+        CLEANUP_CANCEL.deactivate();
+    }
+}
+
+/// Records messages moved out of the inbox so a cleanup run can be undone
+///
+/// All operations performed through the same journal belong to one run.
+/// With [`CleanupJournal::with_filters`], senders moved to spam also get a
+/// Gmail filter that trashes their future mail.
+pub struct CleanupJournal {
+    account: String,
+    started_at: DateTime<Utc>,
+    filters: Option<network::gmail_filters::FilterManager>,
+    staged: std::sync::Mutex<imap::actions::StagedUids>,
+}
+
+impl CleanupJournal {
+    /// Start a new cleanup run for an account
+    pub fn new(account: &str) -> Self {
+        Self {
+            account: account.to_string(),
+            started_at: Utc::now(),
+            filters: None,
+            staged: Default::default(),
+        }
+    }
+
+    /// Block the future mail of senders moved to spam with a Gmail filter
+    ///
+    /// `filters` needs a token granted [`AccessScope::FullWithFilters`].
+    pub fn with_filters(mut self, filters: network::gmail_filters::FilterManager) -> Self {
+        self.filters = Some(filters);
+        self
+    }
+
+    /// Make sure a Gmail filter trashes future mail from `sender_email`
+    ///
+    /// Does nothing without [`CleanupJournal::with_filters`]. A failure only
+    /// means future mail isn't filtered, so it is logged and `None` is
+    /// returned; otherwise the filter id.
+    pub async fn block_future_mail(&self, sender_email: &str) -> Option<String> {
+        let filters = self.filters.as_ref()?;
+        match filters.ensure_trash_filter(sender_email).await {
+            Ok(id) => {
+                tracing::info!(sender = %sender_email, filter = %id, "future mail filtered to trash");
+                Some(id)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    sender = %sender_email,
+                    error = %format_args!("{:#}", e),
+                    "failed to create gmail filter"
+                );
+                None
+            }
+        }
+    }
+
+    /// Account this run cleans
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    /// `session` wrapped to log in again if the server drops it mid-action
+    ///
+    /// See [`imap::connection::ReconnectingSession`].
+    pub fn reconnecting<'s>(
+        &'s self,
+        session: &'s mut imap::connection::ImapSession,
+        imap_config: &'s ImapConfig,
+    ) -> imap::connection::ReconnectingSession<
+        's,
+        impl Fn() -> BoxFuture<'s, Result<String>> + Send + Sync + 's,
+    > {
+        imap::connection::ReconnectingSession::new(session, imap_config, &self.account, || {
+            let token: BoxFuture<'s, Result<String>> =
+                Box::pin(access_token_with_scope(&self.account, AccessScope::Full));
+            token
+        })
+    }
+
+    /// Move messages to trash and record them
+    pub async fn delete_messages(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        sender_email: &str,
+        uids: &[u32],
+    ) -> Result<usize> {
+        let message_ids = self.message_ids(session, &imap_config.mailbox, uids).await;
+        let count = imap::actions::delete_messages(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            uids,
+            imap_config.flag_only,
+        )
+        .await?;
+        self.record(
+            sender_email,
+            imap_config,
+            &imap_config.trash_folder,
+            uids,
+            message_ids,
+        );
+        Ok(count)
+    }
+
+    /// Move messages to spam and record them
+    pub async fn move_to_spam(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        sender_email: &str,
+        uids: &[u32],
+    ) -> Result<usize> {
+        let message_ids = self.message_ids(session, &imap_config.mailbox, uids).await;
+        let count = imap::actions::move_to_spam(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            uids,
+            imap_config.flag_only,
+        )
+        .await?;
+        self.record(
+            sender_email,
+            imap_config,
+            &imap_config.spam_folder,
+            uids,
+            message_ids,
+        );
+        self.block_future_mail(sender_email).await;
+        Ok(count)
+    }
+
+    /// Copy messages to trash and flag them without expunging, and record them
+    ///
+    /// See [`imap::actions::stage_delete`].
+    pub async fn stage_delete(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        sender_email: &str,
+        uids: &[u32],
+    ) -> Result<usize> {
+        let message_ids = self.message_ids(session, &imap_config.mailbox, uids).await;
+        let count = imap::actions::stage_delete(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            uids,
+        )
+        .await?;
+        self.staged().trash.extend_from_slice(uids);
+        self.record(
+            sender_email,
+            imap_config,
+            &imap_config.trash_folder,
+            uids,
+            message_ids,
+        );
+        Ok(count)
+    }
+
+    /// Copy messages to spam and flag them without expunging, and record them
+    pub async fn stage_move_to_spam(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        sender_email: &str,
+        uids: &[u32],
+    ) -> Result<usize> {
+        let message_ids = self.message_ids(session, &imap_config.mailbox, uids).await;
+        let count = imap::actions::stage_move_to_spam(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            uids,
+        )
+        .await?;
+        self.staged().spam.extend_from_slice(uids);
+        self.record(
+            sender_email,
+            imap_config,
+            &imap_config.spam_folder,
+            uids,
+            message_ids,
+        );
+        self.block_future_mail(sender_email).await;
+        Ok(count)
+    }
+
+    /// Number of messages staged by this run and not committed yet
+    pub fn staged_count(&self) -> usize {
+        self.staged().len()
+    }
+
+    /// Remove the messages this run staged from the mailbox
+    ///
+    /// See [`imap::actions::commit_delete`]. Returns the number of messages
+    /// committed; they were already recorded when staged.
+    pub async fn commit_staged(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+    ) -> Result<usize> {
+        let staged = self.staged().clone();
+        let count = imap::actions::commit_delete(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            &staged,
+        )
+        .await?;
+        *self.staged() = Default::default();
+        Ok(count)
+    }
+
+    fn staged(&self) -> std::sync::MutexGuard<'_, imap::actions::StagedUids> {
+        self.staged.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Move every group's messages to trash in one batch and record each group
+    ///
+    /// `groups` pairs a sender with its UIDs so the journal keeps per-sender
+    /// entries even though INBOX is expunged only once. Returns each group
+    /// with the UIDs that were still in the mailbox and got moved.
+    pub async fn delete_messages_batched(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        groups: &[SenderUids],
+    ) -> Result<Vec<SenderUids>> {
+        let message_ids = self
+            .group_message_ids(session, &imap_config.mailbox, groups)
+            .await;
+        let uids: Vec<u32> = groups.iter().flat_map(|(_, uids)| uids).copied().collect();
+        let moved = imap::actions::delete_messages_batched(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            &uids,
+            imap_config.flag_only,
+        )
+        .await?;
+        let groups = moved_groups(groups, &moved);
+        self.record_groups(imap_config, &imap_config.trash_folder, &groups, message_ids);
+        Ok(groups)
+    }
+
+    /// Move every group's messages to spam in one batch and record each group
+    ///
+    /// See [`CleanupJournal::delete_messages_batched`].
+    pub async fn move_to_spam_batched(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        imap_config: &ImapConfig,
+        groups: &[SenderUids],
+    ) -> Result<Vec<SenderUids>> {
+        let message_ids = self
+            .group_message_ids(session, &imap_config.mailbox, groups)
+            .await;
+        let uids: Vec<u32> = groups.iter().flat_map(|(_, uids)| uids).copied().collect();
+        let moved = imap::actions::move_to_spam_batched(
+            &mut self.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            &uids,
+            imap_config.flag_only,
+        )
+        .await?;
+        let moved_groups = moved_groups(groups, &moved);
+        self.record_groups(
+            imap_config,
+            &imap_config.spam_folder,
+            &moved_groups,
+            message_ids,
+        );
+        for (sender_email, _) in groups {
+            self.block_future_mail(sender_email).await;
+        }
+        Ok(moved_groups)
+    }
+
+    async fn group_message_ids(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        mailbox: &str,
+        groups: &[SenderUids],
+    ) -> Vec<Vec<String>> {
+        let mut message_ids = Vec::with_capacity(groups.len());
+        for (_, uids) in groups {
+            message_ids.push(self.message_ids(session, mailbox, uids).await);
+        }
+        message_ids
+    }
+
+    fn record_groups(
+        &self,
+        imap_config: &ImapConfig,
+        folder: &str,
+        groups: &[SenderUids],
+        message_ids: Vec<Vec<String>>,
+    ) {
+        for ((sender_email, uids), message_ids) in groups.iter().zip(message_ids) {
+            self.record(sender_email, imap_config, folder, uids, message_ids);
+        }
+    }
+
+    /// Look up Message-IDs before the messages leave `mailbox`
+    ///
+    /// Failures only make the operation non-undoable, so they are logged
+    /// rather than propagated.
+    async fn message_ids(
+        &self,
+        session: &mut imap::connection::ImapSession,
+        mailbox: &str,
+        uids: &[u32],
+    ) -> Vec<String> {
+        let result = match session.select(mailbox).await {
+            Ok(_) => fetch::fetch_message_ids(session, uids).await,
+            Err(e) => Err(e.into()),
+        };
+
+        result.unwrap_or_else(|e| {
+            tracing::warn!(
+                error = %e,
+                "failed to fetch Message-IDs, cleanup won't be undoable"
+            );
+            Vec::new()
+        })
+    }
+
+    fn record(
+        &self,
+        sender_email: &str,
+        imap_config: &ImapConfig,
+        folder: &str,
+        uids: &[u32],
+        message_ids: Vec<String>,
+    ) {
+        let operation = CleanupOperation {
+            sender_email: sender_email.to_string(),
+            source: imap_config.mailbox.clone(),
+            folder: folder.to_string(),
+            uids: uids.to_vec(),
+            message_ids,
+            timestamp: Utc::now(),
+        };
+
+        if let Err(e) =
+            storage::json_store::record_cleanup_operation(&self.account, self.started_at, operation)
+        {
+            tracing::warn!(error = %e, "failed to record cleanup operation");
+        }
+
+        // The inbox changed, so any cached scan is stale
+        if let Err(e) = storage::scan_cache::invalidate(&self.account) {
+            tracing::warn!(error = %e, "failed to invalidate scan cache");
+        }
+    }
+}
+
+/// Restore the messages moved by the most recent cleanup run back to the mailboxes they came from
+///
+/// Returns `None` if there is nothing to undo, otherwise the number of
+/// messages restored. The run is removed from the log once restored.
+#[tracing::instrument(name = "undo", skip_all, fields(email = %email))]
+pub async fn undo_last_cleanup(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+) -> Result<Option<usize>> {
+    let Some(record) = storage::json_store::load_cleanup_log(email)?.pop() else {
+        return Ok(None);
+    };
+
+    let mut session = imap::connection::connect_and_auth(imap_config, email, access_token).await?;
+    let mut restored = 0;
+
+    for operation in record.operations.iter().rev() {
+        tracing::info!(
+            sender = %operation.sender_email,
+            folder = %operation.folder,
+            count = operation.message_ids.len(),
+            "restoring"
+        );
+        restored += imap::actions::restore_messages(
+            &mut session,
+            &operation.folder,
+            &operation.source,
+            &operation.message_ids,
+        )
+        .await?;
+    }
+
+    session.logout().await?;
+
+    storage::json_store::pop_last_cleanup(email)?;
+
+    Ok(Some(restored))
+}
+
+/// Remove an account and its stored token
+pub fn remove_account(email: &str) -> Result<()> {
+    storage::keyring::delete_token(email)?;
+    storage::json_store::remove_account(email)?;
+
+    Ok(())
+}
+
+/// Outcome of revoking an account's token with Google
+#[derive(Debug)]
+pub enum RevocationStatus {
+    /// Google revoked the refresh token
+    Revoked,
+
+    /// No token was stored, so there was nothing to revoke
+    NoToken,
+
+    /// Revocation failed (e.g. offline); local state was still cleared
+    Failed(anyhow::Error),
+}
+
+/// Revoke an account's token with Google and forget it locally
+///
+/// Local state (keyring token, account file, cached scan) is cleared even if
+/// the revocation request fails; the returned status reports that outcome.
+pub async fn revoke_and_remove_account(email: &str) -> Result<RevocationStatus> {
+    let status = match storage::keyring::get_token(email)? {
+        Some(token) => match network::token_revocation::revoke_token(&token.refresh_token).await {
+            Ok(()) => RevocationStatus::Revoked,
+            Err(e) => {
+                tracing::warn!(email, error = %e, "failed to revoke token");
+                RevocationStatus::Failed(e)
+            }
+        },
+        None => RevocationStatus::NoToken,
+    };
+
+    remove_account(email)?;
+    storage::scan_cache::invalidate(email)?;
+    storage::header_cache::invalidate(email)?;
+
+    Ok(status)
+}
+
+/// Extract display name from From header
+///
+/// Example: `"Example News" <news@example.com>` -> `Example News`
+pub fn extract_display_name(from: &str) -> Option<String> {
+    Mailbox::parse_first(from).and_then(|mailbox| mailbox.display_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::browser::mock::RecordingBrowser;
+    use crate::infrastructure::network::gmail_filters::{mock::spawn_filters_api, FilterManager};
+
+    fn urls(list: &[&str]) -> Vec<String> {
+        list.iter().map(|u| u.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_block_future_mail_creates_trash_filter() {
+        let journal = CleanupJournal::new("me@gmail.com");
+        assert_eq!(journal.block_future_mail("news@example.com").await, None);
+
+        let (url, server) = spawn_filters_api(vec![
+            "{}",
+            r#"{"id": "f-1", "criteria": {"from": "news@example.com"}, "action": {}}"#,
+        ])
+        .await;
+        let journal = journal.with_filters(FilterManager::with_url("token", &url));
+
+        assert_eq!(
+            journal.block_future_mail("news@example.com").await,
+            Some("f-1".to_string())
+        );
+        let requests = server.await.unwrap();
+        assert!(requests[1].contains(r#""addLabelIds":["TRASH"]"#));
+
+        // An unreachable filters API doesn't fail the cleanup
+        let (url, _server) = spawn_filters_api(vec![]).await;
+        let journal = CleanupJournal::new("me@gmail.com")
+            .with_filters(FilterManager::with_url("token", &url));
+        assert_eq!(journal.block_future_mail("news@example.com").await, None);
+    }
+
+    #[test]
+    fn test_parse_client_secret_installed() {
+        let json = r#"{
+            "installed": {
+                "client_id": "123.apps.googleusercontent.com",
+                "project_id": "unsubmail",
+                "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+                "token_uri": "https://oauth2.googleapis.com/token",
+                "client_secret": "shh",
+                "redirect_uris": ["http://localhost"]
+            }
+        }"#;
+
+        let credentials = parse_client_secret(json).unwrap();
+        assert_eq!(credentials.client_id, "123.apps.googleusercontent.com");
+        assert_eq!(credentials.client_secret, "shh");
+        assert_eq!(credentials.redirect_uri, DEFAULT_REDIRECT_URI);
+    }
+
+    #[test]
+    fn test_parse_client_secret_web() {
+        let json = r#"{
+            "web": {
+                "client_id": "456.apps.googleusercontent.com",
+                "client_secret": "secret",
+                "redirect_uris": ["https://example.com/cb", "http://localhost:9090/oauth"]
+            }
+        }"#;
+
+        let credentials = parse_client_secret(json).unwrap();
+        assert_eq!(credentials.client_id, "456.apps.googleusercontent.com");
+        assert_eq!(credentials.redirect_uri, "http://localhost:9090/oauth");
+    }
+
+    #[test]
+    fn test_parse_client_secret_rejects_unknown_shape() {
+        assert!(parse_client_secret(r#"{"service_account": {}}"#).is_err());
+        assert!(parse_client_secret("not json").is_err());
+    }
+
+    #[test]
+    fn test_is_headless() {
+        let env = |vars: &'static [&'static str]| move |name: &str| vars.contains(&name);
+
+        assert!(is_headless(env(&["SSH_CONNECTION"]), false));
+        assert!(is_headless(env(&[]), true));
+        assert!(!is_headless(env(&[]), false));
+        assert!(!is_headless(env(&["DISPLAY"]), true));
+        // X forwarding over SSH can still open a browser
+        assert!(!is_headless(env(&["SSH_TTY", "DISPLAY"]), true));
+        assert!(!is_headless(env(&["WAYLAND_DISPLAY"]), true));
+    }
+
+    #[test]
+    fn test_parse_device_authorization() {
+        let google = r#"{
+            "device_code": "AH-1Ng",
+            "user_code": "GQVQ-JKEC",
+            "verification_url": "https://www.google.com/device",
+            "expires_in": 1800,
+            "interval": 5
+        }"#;
+        let device: DeviceAuthorization = serde_json::from_str(google).unwrap();
+        assert_eq!(device.user_code, "GQVQ-JKEC");
+        assert_eq!(device.verification_url, "https://www.google.com/device");
+        assert_eq!(device.interval, Some(5));
+
+        let rfc = r#"{"device_code": "d", "user_code": "u",
+            "verification_uri": "https://example.com/device", "expires_in": 600}"#;
+        let device: DeviceAuthorization = serde_json::from_str(rfc).unwrap();
+        assert_eq!(device.verification_url, "https://example.com/device");
+        assert_eq!(device.interval, None);
+    }
+
+    #[test]
+    fn test_parse_device_poll() {
+        assert!(matches!(
+            parse_device_poll(r#"{"error": "authorization_pending"}"#).unwrap(),
+            DevicePoll::Pending
+        ));
+        assert!(matches!(
+            parse_device_poll(r#"{"error": "slow_down", "error_description": "Forbidden"}"#)
+                .unwrap(),
+            DevicePoll::SlowDown
+        ));
+
+        let granted = parse_device_poll(
+            r#"{"access_token": "ya29.a", "refresh_token": "1//r", "expires_in": 3599,
+                "scope": "https://mail.google.com/", "token_type": "Bearer"}"#,
+        )
+        .unwrap();
+        let DevicePoll::Granted(token) = granted else {
+            panic!("expected a token");
+        };
+        assert_eq!(token.access_token, "ya29.a");
+        assert_eq!(token.refresh_token.as_deref(), Some("1//r"));
+        assert_eq!(token.expires_in, Some(3599));
+
+        let denied = parse_device_poll(r#"{"error": "access_denied"}"#).unwrap_err();
+        assert!(denied.to_string().contains("denied"));
+        let scope = parse_device_poll(r#"{"error": "invalid_scope"}"#).unwrap_err();
+        assert!(scope.to_string().contains("UNSUBMAIL_SIGN_IN=browser"));
+        assert!(parse_device_poll("<html>").is_err());
+    }
+
+    #[test]
+    fn test_candidate_ports_order() {
+        assert_eq!(candidate_ports(9090, ""), vec![9090, 0]);
+        assert_eq!(
+            candidate_ports(9090, "9091, 9090,bogus,9092"),
+            vec![9090, 9091, 9092, 0]
+        );
+    }
+
+    #[test]
+    fn test_bind_callback_listener_falls_back_when_busy() {
+        let busy = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = busy.local_addr().unwrap().port();
+
+        let (listener, redirect) =
+            bind_callback_listener(&format!("http://localhost:{}/callback", port)).unwrap();
+        let bound = listener.local_addr().unwrap().port();
+
+        assert_ne!(bound, port);
+        assert_eq!(redirect.port(), Some(bound));
+        assert_eq!(redirect.path(), "/callback");
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_refreshed_and_retried() {
+        let expired = || {
+            anyhow::Error::new(async_imap::error::Error::No(
+                "[AUTHENTICATIONFAILED] Invalid credentials".into(),
+            ))
+        };
+
+        // Token expires mid-scan: refresh once, retry with the new token
+        let mut token = "old".to_string();
+        let mut used = Vec::new();
+        let result = with_token_refresh(
+            &mut token,
+            |t| {
+                used.push(t.clone());
+                let result = if t == "old" { Err(expired()) } else { Ok(42) };
+                async move { result }
+            },
+            || async { Ok("new".to_string()) },
+        )
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(used, vec!["old", "new"]);
+        assert_eq!(token, "new");
+
+        // Other failures are not retried and keep the token
+        let mut token = "old".to_string();
+        let mut calls = 0;
+        let result: Result<()> = with_token_refresh(
+            &mut token,
+            |_| {
+                calls += 1;
+                async { Err(anyhow::anyhow!("Mailbox not found")) }
+            },
+            || async { panic!("refresh must not be called") },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert_eq!(token, "old");
+
+        // A failed refresh returns the original expiry error
+        let result: Result<()> = with_token_refresh(
+            &mut token,
+            |_| async { Err(expired()) },
+            || async { Err(anyhow::anyhow!("refresh token revoked")) },
+        )
+        .await;
+        assert!(imap::connection::is_token_expired(&result.unwrap_err()));
+        assert_eq!(token, "old");
+    }
+
+    #[tokio::test]
+    async fn test_authorize_in_browser_opens_auth_url() {
+        let credentials = OAuthCredentials {
+            client_id: "123.apps.googleusercontent.com".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_uri: "http://localhost:0/callback".to_string(),
+        };
+        let browser = RecordingBrowser::default();
+
+        // Play the browser: once the URL is opened, redirect back with a
+        // state that doesn't match so the flow stops before the token exchange
+        let redirect_back = async {
+            let auth_url = loop {
+                if let Some(url) = browser.opened().pop() {
+                    break Url::parse(&url).unwrap();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            };
+            let redirect_uri = auth_url
+                .query_pairs()
+                .find(|(key, _)| key == "redirect_uri")
+                .map(|(_, value)| Url::parse(&value).unwrap())
+                .unwrap();
+            let mut stream =
+                tokio::net::TcpStream::connect(("127.0.0.1", redirect_uri.port().unwrap()))
+                    .await
+                    .unwrap();
+            stream
+                .write_all(b"GET /callback?code=abc&state=forged HTTP/1.1\r\n\r\n")
+                .await
+                .unwrap();
+            auth_url
+        };
+
+        let (result, auth_url) = tokio::time::timeout(Duration::from_secs(5), async {
+            tokio::join!(
+                authorize_in_browser(
+                    &credentials,
+                    "me@gmail.com",
+                    CONSENT_PROMPT,
+                    AccessScope::Full,
+                    &browser,
+                ),
+                redirect_back
+            )
+        })
+        .await
+        .expect("sign-in should finish once redirected");
+
+        assert!(result.unwrap_err().to_string().contains("CSRF"));
+        assert_eq!(browser.opened().len(), 1);
+        assert!(auth_url.as_str().starts_with(GOOGLE_AUTH_URL));
+
+        let params: HashMap<_, _> = auth_url.query_pairs().into_owned().collect();
+        assert_eq!(params["client_id"], "123.apps.googleusercontent.com");
+        assert_eq!(params["login_hint"], "me@gmail.com");
+        assert_eq!(params["prompt"], CONSENT_PROMPT);
+        assert_eq!(params["access_type"], "offline");
+        assert_eq!(params["scope"], AccessScope::Full.url());
+        assert!(params.contains_key("code_challenge"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_callback_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let redirect = Url::parse("http://localhost:9090/callback").unwrap();
+
+        let err = wait_for_callback(listener, &redirect, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_callback_parses_redirect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let redirect = Url::parse(&format!("http://localhost:{}/callback", port)).unwrap();
+
+        let client = tokio::spawn(async move {
+            let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .unwrap();
+            stream
+                .write_all(b"GET /callback?code=abc&state=xyz HTTP/1.1\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let url = wait_for_callback(listener, &redirect, Duration::from_secs(5))
+            .await
+            .unwrap();
+        client.await.unwrap();
+
+        assert_eq!(url.path(), "/callback");
+        assert_eq!(url.query(), Some("code=abc&state=xyz"));
+    }
+
+    #[test]
+    fn test_batch_groups_split_by_destination() {
+        let sender = |email: &str, uids: Vec<u32>, unsubscribe: Option<&str>| {
+            analysis::analyze_sender(
+                email.to_string(),
+                None,
+                uids.len(),
+                uids,
+                &[analysis::UnsubscribeHeaders::new(
+                    unsubscribe.map(str::to_string),
+                    unsubscribe.map(|_| "List-Unsubscribe=One-Click".to_string()),
+                )],
+                vec![],
+            )
+        };
+        let actions = planner::plan_actions(vec![
+            sender("news@a.com", vec![1, 2], Some("<https://a.com/u>")),
+            sender("spam@b.com", vec![3], None),
+            sender("news@c.com", vec![4, 5], Some("<https://c.com/u>")),
+        ]);
+
+        let (trash, spam) = batch_groups(&actions);
+
+        assert_eq!(
+            trash,
+            vec![
+                ("news@a.com".to_string(), vec![1, 2]),
+                ("news@c.com".to_string(), vec![4, 5]),
+            ]
+        );
+        assert_eq!(spam, vec![("spam@b.com".to_string(), vec![3])]);
+    }
+
+    #[test]
+    fn test_split_at_cap_keeps_senders_in_order_until_the_cap() {
+        let sender = |email: &str, uids: Vec<u32>| SenderInfo {
+            email: email.to_string(),
+            message_count: uids.len(),
+            message_uids: uids,
+            ..SenderInfo::default()
+        };
+        let actions = planner::plan_deletions(vec![
+            sender("a@x.com", vec![1, 2]),
+            sender("b@x.com", vec![3, 4]),
+            sender("c@x.com", vec![5]),
+        ]);
+        let emails = |actions: &[CleanupAction]| -> Vec<String> {
+            actions.iter().map(|a| a.sender.email.clone()).collect()
+        };
+
+        let (kept, skipped) = split_at_cap(actions.clone(), &planner::DeletionCap::new(Some(3)));
+        assert_eq!(emails(&kept), vec!["a@x.com"]);
+        // Once over the cap, later senders are skipped even if they would fit
+        assert_eq!(emails(&skipped), vec!["b@x.com", "c@x.com"]);
+
+        let (kept, skipped) = split_at_cap(actions, &planner::DeletionCap::new(None));
+        assert_eq!(kept.len(), 3);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_moved_groups_drop_vanished_uids() {
+        let groups = vec![
+            ("a@x.com".to_string(), vec![1, 2]),
+            ("b@x.com".to_string(), vec![3]),
+        ];
+        assert_eq!(
+            moved_groups(&groups, &[2, 3]),
+            vec![
+                ("a@x.com".to_string(), vec![2]),
+                ("b@x.com".to_string(), vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_tracker_reuses_results() {
+        let mut tracker = UnsubscribeTracker::new();
+        assert_eq!(
+            tracker.previous_result(&urls(&["https://esp.example.com/u?id=1"])),
+            None
+        );
+
+        tracker.record(&urls(&["https://ESP.example.com/u?id=1#top"]), true);
+        assert_eq!(
+            tracker.previous_result(&urls(&["https://esp.example.com/u?id=1"])),
+            Some(true)
+        );
+
+        // Different subscriber id is a different endpoint
+        assert_eq!(
+            tracker.previous_result(&urls(&["https://esp.example.com/u?id=2"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_tracker_failure_needs_all_candidates() {
+        let mut tracker = UnsubscribeTracker::new();
+        tracker.record(&urls(&["https://a.example.com/u"]), false);
+
+        assert_eq!(
+            tracker.previous_result(&urls(&[
+                "https://a.example.com/u",
+                "https://b.example.com/u"
+            ])),
+            None
+        );
+        assert_eq!(
+            tracker.previous_result(&urls(&["https://a.example.com/u"])),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_tracker_remembers_method() {
+        let mut tracker = UnsubscribeTracker::new();
+        let get_only = urls(&["https://esp.example.com/u?id=1"]);
+        tracker.record_outcome(&get_only, &Ok(Some(UnsubscribeVia::Get)));
+        tracker.record_outcome(&urls(&["https://other.example.com/u"]), &Ok(None));
+
+        assert_eq!(tracker.previous_result(&get_only), Some(true));
+        assert_eq!(tracker.method_used(&get_only), Some(UnsubscribeVia::Get));
+        assert_eq!(
+            tracker.method_used(&urls(&["https://other.example.com/u"])),
+            None
+        );
+
+        let result = CleanupResult::success(
+            "news@example.com".to_string(),
+            ActionType::UnsubscribeAndDelete,
+            3,
+            Some(true),
+        )
+        .with_unsubscribe_via(tracker.method_used(&get_only));
+        assert_eq!(result.unsubscribe_via, Some(UnsubscribeVia::Get));
+    }
+
+    #[test]
+    fn test_cancel_state_stops_gracefully_once() {
+        let state = CancelState::new();
+
+        // Outside a cleanup run Ctrl-C quits
+        assert!(!state.interrupt());
+        assert!(!state.is_requested());
+
+        state.activate();
+        assert!(state.interrupt());
+        assert!(state.is_requested());
+        // A second press quits
+        assert!(!state.interrupt());
+
+        state.deactivate();
+        state.activate();
+        assert!(!state.is_requested());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_cancellation_stops_watching_when_dropped() {
+        let guard = CleanupCancellation::install();
+        let watcher = guard.watcher.abort_handle();
+        assert!(!watcher.is_finished());
+
+        drop(guard);
+        tokio::task::yield_now().await;
+        assert!(watcher.is_finished());
+    }
+
+    #[test]
+    fn test_manual_unsubscribe_url_requires_https_link() {
+        let sender = |method| SenderInfo {
+            email: "news@shop.com".to_string(),
+            message_count: 1,
+            message_uids: vec![1],
+            unsubscribe_method: method,
+            heuristic_score: 1.0,
+            ..SenderInfo::default()
+        };
+
+        let link = sender(UnsubscribeMethod::HttpLink {
+            url: "https://shop.com/unsubscribe".to_string(),
+        });
+        assert_eq!(
+            manual_unsubscribe_url(&link),
+            Some("https://shop.com/unsubscribe")
+        );
+
+        let plain_http = sender(UnsubscribeMethod::HttpLink {
+            url: "http://shop.com/unsubscribe".to_string(),
+        });
+        assert_eq!(manual_unsubscribe_url(&plain_http), None);
+
+        let one_click = sender(UnsubscribeMethod::OneClick {
+            url: "https://shop.com/unsubscribe".to_string(),
+        });
+        assert_eq!(manual_unsubscribe_url(&one_click), None);
+
+        let browser = RecordingBrowser::default();
+        assert!(open_unsubscribe_page("http://shop.com/unsubscribe", &browser).is_err());
+        open_unsubscribe_page("https://shop.com/unsubscribe", &browser).unwrap();
+        assert_eq!(browser.opened(), vec!["https://shop.com/unsubscribe"]);
+    }
+
+    #[test]
+    fn test_fallback_unsubscribe_url_skips_one_click_url() {
+        let mut sender = SenderInfo {
+            email: "news@shop.com".to_string(),
+            message_count: 1,
+            message_uids: vec![1],
+            unsubscribe_method: UnsubscribeMethod::OneClick {
+                url: "https://esp.com/oc".to_string(),
+            },
+            heuristic_score: 1.0,
+            unsubscribe_urls: vec!["https://esp.com/oc".to_string()],
+            ..SenderInfo::default()
+        };
+        assert_eq!(fallback_unsubscribe_url(&sender), None);
+
+        sender.unsubscribe_urls.extend([
+            "http://shop.com/plain".to_string(),
+            "https://shop.com/preferences".to_string(),
+        ]);
+        assert_eq!(
+            fallback_unsubscribe_url(&sender),
+            Some("https://shop.com/preferences")
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Account management
+
+use crate::application::workflow::{self, SignInMethod};
+use crate::domain::models::{AccessScope, EmailAccount};
+use crate::infrastructure::storage;
+use anyhow::{Context, Result};
+use console::style;
+use inquire::{Confirm, Select};
+
+/// Add an account by running the OAuth2 sign-in flow
+pub async fn add_account(email: &str, sign_in: SignInMethod) -> Result<EmailAccount> {
+    workflow::add_account_for_email(email, AccessScope::Full, sign_in).await
+}
+
+/// List all added accounts, oldest first
+pub fn list_accounts() -> Result<Vec<EmailAccount>> {
+    let mut accounts = storage::json_store::list_accounts()?;
+    accounts.sort_by_key(|a| a.added_at);
+    Ok(accounts)
+}
+
+/// Addresses to offer when picking an account
+///
+/// Added accounts come first, oldest first, followed by addresses that only
+/// have a stored token (e.g. signed in before accounts were recorded).
+pub fn known_emails() -> Result<Vec<String>> {
+    let mut emails: Vec<String> = list_accounts()?.into_iter().map(|a| a.email).collect();
+
+    let mut token_only: Vec<String> = storage::keyring::list_token_emails()?
+        .into_iter()
+        .filter(|email| !emails.contains(email))
+        .collect();
+    token_only.sort();
+    emails.extend(token_only);
+
+    Ok(emails)
+}
+
+/// Remove an account's metadata and stored token
+pub fn remove_account(email: &str) -> Result<()> {
+    workflow::remove_account(email)
+}
+
+/// Flip an account's protection and return the saved account
+pub fn toggle_protected(email: &str) -> Result<EmailAccount> {
+    let mut account = storage::json_store::load_account(email)?
+        .with_context(|| format!("No account for {}", email))?;
+    account.protected = !account.protected;
+    storage::json_store::save_account(&account)?;
+    Ok(account)
+}
+
+/// Interactive account management menu
+pub async fn manage_accounts(sign_in: SignInMethod) -> Result<()> {
+    loop {
+        let choice = Select::new(
+            "Manage accounts:",
+            vec![
+                "List accounts",
+                "Add account",
+                "Protect or unprotect account",
+                "Remove account",
+                "Back",
+            ],
+        )
+        .prompt()?;
+
+        match choice {
+            "List accounts" => print_accounts(&list_accounts()?),
+            "Add account" => {
+                let email = super::interactive::prompt_email("Gmail address:")?;
+                let account = add_account(&email, sign_in).await?;
+                println!("  {} Added {}", style("✓").green(), account.email);
+            }
+            "Protect or unprotect account" => {
+                let accounts = list_accounts()?;
+                if accounts.is_empty() {
+                    println!("  {} No accounts added yet", style("ℹ").blue());
+                    continue;
+                }
+
+                let emails: Vec<String> = accounts.into_iter().map(|a| a.email).collect();
+                let email = Select::new("Account:", emails)
+                    .with_help_message("Cleaning a protected account asks you to type its address")
+                    .prompt()?;
+                let account = toggle_protected(&email)?;
+                let state = if account.protected {
+                    "Protected"
+                } else {
+                    "Unprotected"
+                };
+                println!("  {} {} {}", style("✓").green(), state, account.email);
+            }
+            "Remove account" => {
+                let accounts = list_accounts()?;
+                if accounts.is_empty() {
+                    println!("  {} No accounts added yet", style("ℹ").blue());
+                    continue;
+                }
+
+                let emails: Vec<String> = accounts.into_iter().map(|a| a.email).collect();
+                let email = Select::new("Account to remove:", emails).prompt()?;
+
+                let confirm = Confirm::new(&format!("Remove {} and its stored token?", email))
+                    .with_default(false)
+                    .prompt()?;
+
+                if confirm {
+                    remove_account(&email)?;
+                    println!("  {} Removed {}", style("✓").green(), email);
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        println!();
+    }
+}
+
+fn print_accounts(accounts: &[EmailAccount]) {
+    if accounts.is_empty() {
+        println!("  {} No accounts added yet", style("ℹ").blue());
+        return;
+    }
+
+    for account in accounts {
+        let protected = if account.protected {
+            format!(" {}", style("[protected]").yellow())
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {}{}",
+            account.email,
+            style(format!("(added {})", account.added_at.format("%Y-%m-%d"))).dim(),
+            protected
+        );
+    }
+}
@@ -0,0 +1,1083 @@
+//! Non-interactive command-line interface
+
+use super::accounts;
+use super::export::{self, ExportFormat};
+use crate::application::workflow::{self, ScanOptions, SignInMethod};
+use crate::domain::analysis::{self, BulkSignals, ScoreBreakdown};
+use crate::domain::models::{AccessScope, CleanupReport, CleanupSummary, SenderInfo, SenderOrder};
+use crate::domain::planner;
+use crate::infrastructure::imap::{self, connection::ImapConfig};
+use crate::infrastructure::storage;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Command-line arguments
+#[derive(Debug, Parser)]
+#[command(name = "unsubmail", version, about)]
+pub struct Cli {
+    /// Subcommand to run (interactive mode when omitted)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Minimum score for a sender to be listed in interactive mode
+    ///
+    /// Senders with an unsubscribe header or List-Id are listed regardless;
+    /// allowlisted senders are never listed. Defaults to `min_display_score`
+    /// from the config file.
+    #[arg(long, value_name = "SCORE")]
+    pub min_display_score: Option<f32>,
+
+    /// Maximum number of messages a single run may delete before asking again
+    ///
+    /// Defaults to `max_delete` from the config file.
+    #[arg(long, value_name = "COUNT")]
+    pub max_delete: Option<usize>,
+
+    /// JSON or CSV file of senders to pre-select for cleanup
+    ///
+    /// Entries are addresses or `@domain.com` wildcards; the allowlist wins.
+    #[arg(long, value_name = "PATH")]
+    pub blocklist: Option<PathBuf>,
+
+    /// Sign in by entering a code on another device instead of opening a browser
+    ///
+    /// Picked automatically when no display is available, e.g. over SSH.
+    #[arg(long)]
+    pub device_code: bool,
+
+    /// Stop fetching headers after this long, e.g. `60s` or `5m`
+    ///
+    /// The senders found so far are shown, labelled as a partial scan.
+    /// Defaults to `scan_time_budget_secs` from the config file.
+    #[arg(long, value_name = "DURATION", value_parser = parse_time_budget)]
+    pub time_budget: Option<std::time::Duration>,
+}
+
+impl Cli {
+    /// Sign-in flow to use: the device flow with `--device-code`, otherwise
+    /// [`SignInMethod::detect`]
+    pub fn sign_in_method(&self) -> SignInMethod {
+        if self.device_code {
+            SignInMethod::DeviceCode
+        } else {
+            SignInMethod::detect()
+        }
+    }
+}
+
+/// Top-level subcommands
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Scan the inbox and print detected senders
+    Scan {
+        /// Gmail address to scan
+        #[arg(long)]
+        email: String,
+
+        /// Emit machine-readable JSON instead of a table
+        #[arg(long)]
+        json: bool,
+
+        /// Stream one JSON object per sender as it is analyzed, then a summary
+        ///
+        /// Progress and logs go to stderr, so stdout stays machine-readable.
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
+
+        /// Also write results to a file (format from extension: .json or .csv)
+        #[arg(long, value_name = "PATH")]
+        export: Option<PathBuf>,
+
+        /// Only scan messages received on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// Stop fetching headers after this long, e.g. `60s` or `5m`
+        ///
+        /// Whatever was fetched is analyzed and reported as a partial scan.
+        #[arg(long, value_name = "DURATION", value_parser = parse_time_budget)]
+        time_budget: Option<std::time::Duration>,
+    },
+
+    /// Clean senders above a score threshold
+    Clean {
+        /// Gmail address to clean
+        #[arg(long)]
+        email: String,
+
+        /// Minimum heuristic score for a sender to be cleaned
+        #[arg(long, default_value_t = 1.0)]
+        min_score: f32,
+
+        /// Only clean messages older than this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than_days: Option<u32>,
+
+        /// Only scan messages received on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// Execute without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+
+        /// Stop before deleting more than this many messages in total
+        ///
+        /// Defaults to `max_delete` from the config file.
+        #[arg(long, value_name = "COUNT")]
+        max_delete: Option<usize>,
+
+        /// JSON or CSV file of senders to clean regardless of score
+        ///
+        /// Entries are addresses or `@domain.com` wildcards; the allowlist
+        /// wins.
+        #[arg(long, value_name = "PATH")]
+        blocklist: Option<PathBuf>,
+
+        /// Only clean the first N senders
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Rank senders by message count and ignore scores (requires --top)
+        ///
+        /// Personal contacts may be listed; their messages are moved to trash
+        /// without unsubscribing or reporting spam. The allowlist still wins.
+        #[arg(long, requires = "top")]
+        by_volume: bool,
+    },
+
+    /// Move messages from the last cleanup back to the inbox
+    Undo {
+        /// Gmail address to restore
+        #[arg(long)]
+        email: String,
+    },
+
+    /// Show recent cleanup reports
+    Reports {
+        /// Gmail address whose reports to show
+        #[arg(long)]
+        email: String,
+
+        /// Number of most recent reports to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// Manage stored accounts
+    Accounts {
+        #[command(subcommand)]
+        command: AccountsCommand,
+    },
+
+    /// Show local statistics from past cleanups
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommand,
+    },
+
+    /// Manage stored OAuth2 credentials
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+
+    /// Score a made-up sender and show how each signal contributes
+    ///
+    /// No mailbox is read. Weights come from the `[scoring]` table and
+    /// `newsletter_patterns` of the config file.
+    Score(ScoreArgs),
+}
+
+/// Inputs for the `score` playground
+#[derive(Debug, Clone, Args)]
+pub struct ScoreArgs {
+    /// Sender address
+    #[arg(long)]
+    pub email: String,
+
+    /// Messages carry a List-Unsubscribe header
+    #[arg(long)]
+    pub unsubscribe: bool,
+
+    /// Messages carry a List-Id header
+    #[arg(long)]
+    pub list_id: bool,
+
+    /// Number of messages from the sender
+    #[arg(long, default_value_t = 1)]
+    pub count: usize,
+
+    /// Sample subject line (repeat for several)
+    #[arg(long = "subject", value_name = "SUBJECT")]
+    pub subjects: Vec<String>,
+
+    /// Messages carry `Precedence: bulk`
+    #[arg(long)]
+    pub bulk: bool,
+
+    /// Messages carry `Auto-Submitted: auto-generated`
+    #[arg(long)]
+    pub auto_submitted: bool,
+
+    /// Config file to read weights from (default: the user's config.toml)
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+impl ScoreArgs {
+    /// Score these inputs with the weights and patterns from `config`
+    fn breakdown(&self, config: &storage::config::Config) -> ScoreBreakdown {
+        analysis::calculate_heuristic_score(
+            &config.scoring_config(),
+            &self.email,
+            self.unsubscribe,
+            self.list_id,
+            BulkSignals {
+                is_bulk: self.bulk,
+                is_auto: self.auto_submitted,
+            },
+            self.count,
+            &self.subjects,
+        )
+    }
+}
+
+/// Credential subcommands
+#[derive(Debug, Subcommand)]
+pub enum TokenCommand {
+    /// Delete an account's stored token from the OS keyring
+    ///
+    /// The account itself is kept; the next run signs in again.
+    Clear {
+        /// Gmail address whose token to delete
+        #[arg(long)]
+        email: String,
+    },
+}
+
+/// Statistics subcommands
+#[derive(Debug, Subcommand)]
+pub enum StatsCommand {
+    /// One-click unsubscribe success rate per ESP host
+    Esp,
+}
+
+/// Account management subcommands
+#[derive(Debug, Subcommand)]
+pub enum AccountsCommand {
+    /// List added accounts
+    List,
+
+    /// Remove an account and its stored token
+    Remove {
+        /// Gmail address to remove
+        email: String,
+    },
+}
+
+/// Dispatch a parsed subcommand
+pub async fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Scan {
+            email,
+            json,
+            ndjson,
+            export,
+            since,
+            time_budget,
+        } => {
+            let output = if ndjson {
+                ScanOutput::Ndjson
+            } else if json {
+                ScanOutput::Json
+            } else {
+                ScanOutput::Table
+            };
+            let config = storage::config::load_config()?;
+            let mut options = scan_options(&config, since);
+            options.fetch.time_budget = time_budget.or(options.fetch.time_budget);
+            run_scan(
+                &config.imap_config()?,
+                &email,
+                output,
+                export.as_deref(),
+                &options,
+            )
+            .await
+        }
+        Command::Clean {
+            email,
+            min_score,
+            older_than_days,
+            since,
+            yes,
+            max_delete,
+            blocklist,
+            top,
+            by_volume,
+        } => {
+            let filter = CleanFilter {
+                min_score,
+                older_than: older_than_days.map(|days| Duration::days(days.into())),
+                top,
+                by_volume,
+            };
+            run_clean(
+                &email,
+                &filter,
+                since,
+                yes,
+                max_delete,
+                blocklist.as_deref(),
+            )
+            .await
+        }
+        Command::Undo { email } => run_undo(&email).await,
+        Command::Reports { email, limit } => run_reports(&email, limit),
+        Command::Accounts { command } => run_accounts(command),
+        Command::Stats { command } => run_stats(command),
+        Command::Token { command } => run_token(command),
+        Command::Score(args) => run_score(&args),
+    }
+}
+
+/// Where `scan` prints its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanOutput {
+    /// Human-readable table
+    Table,
+
+    /// One JSON array once the scan is done
+    Json,
+
+    /// JSON lines streamed while scanning (see [`export::write_ndjson_sender`])
+    Ndjson,
+}
+
+async fn run_scan(
+    imap_config: &ImapConfig,
+    email: &str,
+    output: ScanOutput,
+    export_path: Option<&Path>,
+    options: &ScanOptions,
+) -> Result<()> {
+    let access_token = workflow::access_token_for_email(email).await?;
+    // Scan-only accounts have no IMAP access
+    let read_only = workflow::account_scope(email)? == Some(AccessScope::ReadOnly);
+    let mut progress = (0, 0);
+    let on_progress = |done, total| progress = (done, total);
+    let mut senders = if output == ScanOutput::Ndjson {
+        scan_ndjson(imap_config, email, &access_token, options, read_only).await?
+    } else if read_only {
+        workflow::scan_inbox_readonly(
+            email,
+            &access_token,
+            &imap_config.mailbox,
+            options,
+            on_progress,
+        )
+        .await?
+    } else {
+        workflow::scan_inbox_with(imap_config, email, &access_token, options, on_progress).await?
+    };
+    let (fetched, total) = progress;
+    if fetched < total {
+        eprintln!(
+            "Partial scan: time budget reached after fetching {} of {} messages",
+            fetched, total
+        );
+    }
+    planner::sort_senders(&mut senders, SenderOrder::Score);
+
+    if let Some(path) = export_path {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        export::export_senders(
+            &senders,
+            ExportFormat::from_path(path),
+            BufWriter::new(file),
+        )?;
+    }
+
+    match output {
+        ScanOutput::Json => {
+            export::export_senders(&senders, ExportFormat::Json, std::io::stdout().lock())?;
+        }
+        ScanOutput::Table => {
+            for sender in &senders {
+                println!(
+                    "{:>6.2}  {:>5} msgs  {}",
+                    sender.heuristic_score, sender.message_count, sender.email
+                );
+            }
+        }
+        // Already streamed
+        ScanOutput::Ndjson => {}
+    }
+
+    Ok(())
+}
+
+/// Scan while writing each analyzed sender to stdout as a JSON line
+///
+/// IMAP scans stream a line per changed sender after every batch; read-only
+/// (Gmail API) scans write every sender once at the end. A summary line
+/// closes the output. Progress goes to stderr.
+async fn scan_ndjson(
+    imap_config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+    options: &ScanOptions,
+    read_only: bool,
+) -> Result<Vec<SenderInfo>> {
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    pb.set_message("Scanning...");
+
+    let (senders, messages_scanned) = if read_only {
+        let senders = workflow::scan_inbox_readonly(
+            email,
+            access_token,
+            &imap_config.mailbox,
+            options,
+            |done, total| {
+                pb.set_message(format!("Reading headers {}/{}", done, total));
+            },
+        )
+        .await?;
+        for sender in &senders {
+            export::write_ndjson_sender(sender, std::io::stdout().lock())?;
+        }
+        let messages_scanned = senders.iter().map(|s| s.message_count).sum();
+        (senders, messages_scanned)
+    } else {
+        let mut session =
+            imap::connection::connect_and_auth(imap_config, email, access_token).await?;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<workflow::ScanUpdate>();
+        let scan = workflow::scan_session_streaming(
+            &mut session,
+            email,
+            &imap_config.mailbox,
+            options,
+            tx,
+        );
+        let stream = async {
+            let mut messages_scanned = 0;
+            while let Some(update) = rx.recv().await {
+                messages_scanned = update.scanned;
+                pb.set_message(format!("Fetched {}/{}", update.fetched, update.total));
+                for sender in &update.senders {
+                    export::write_ndjson_sender(sender, std::io::stdout().lock())?;
+                }
+            }
+            Ok::<_, anyhow::Error>(messages_scanned)
+        };
+        let (senders, messages_scanned) = tokio::join!(scan, stream);
+        let (senders, messages_scanned) = (senders?, messages_scanned?);
+        session.logout().await?;
+        (senders, messages_scanned)
+    };
+    pb.finish_and_clear();
+
+    export::write_ndjson_summary(
+        email,
+        messages_scanned,
+        senders.len(),
+        std::io::stdout().lock(),
+    )?;
+
+    Ok(senders)
+}
+
+/// Which scanned senders `clean` works on
+#[derive(Debug, Clone, Copy)]
+struct CleanFilter {
+    /// Minimum score, ignored when ranking by volume
+    min_score: f32,
+    /// Only clean messages older than this
+    older_than: Option<Duration>,
+    /// Keep only the first N senders after sorting
+    top: Option<usize>,
+    /// Rank by message count instead of filtering by score
+    by_volume: bool,
+}
+
+impl CleanFilter {
+    /// Pick and order the senders to clean
+    ///
+    /// Blocklisted senders pass the score filter. Allowlisted senders are never
+    /// selected, whatever their score or volume.
+    fn select(&self, scanned: &[SenderInfo], allowlist: &[String]) -> Vec<SenderInfo> {
+        let senders = if self.by_volume {
+            planner::top_by_volume(scanned, allowlist, usize::MAX)
+        } else {
+            scanned
+                .iter()
+                .filter(|s| s.blocklisted || s.heuristic_score >= self.min_score)
+                .filter(|s| !analysis::is_allowlisted(&s.email, allowlist))
+                .cloned()
+                .collect()
+        };
+
+        let mut senders = planner::filter_older_than(senders, self.older_than, Utc::now());
+        let order = if self.by_volume {
+            SenderOrder::Volume
+        } else {
+            SenderOrder::Score
+        };
+        planner::sort_senders(&mut senders, order);
+        if let Some(top) = self.top {
+            senders.truncate(top);
+        }
+        senders
+    }
+
+    /// Description of the selected senders for headings and empty results
+    fn describe(&self) -> String {
+        if self.by_volume {
+            "senders by message count (scores ignored, personal contacts may be listed)".to_string()
+        } else {
+            format!("senders with score >= {:.2}", self.min_score)
+        }
+    }
+}
+
+async fn run_clean(
+    email: &str,
+    filter: &CleanFilter,
+    since: Option<NaiveDate>,
+    yes: bool,
+    max_delete: Option<usize>,
+    blocklist: Option<&Path>,
+) -> Result<()> {
+    if yes && storage::json_store::load_account(email)?.is_some_and(|account| account.protected) {
+        anyhow::bail!(
+            "{} is protected; clean it in interactive mode or unprotect it under Manage accounts",
+            email
+        );
+    }
+
+    let config = storage::config::load_config()?;
+    let imap_config = config.imap_config()?;
+    let blocklist = match blocklist {
+        Some(path) => storage::json_store::load_blocklist(path)?,
+        None => Vec::new(),
+    };
+    let max_delete = max_delete.or(config.max_delete);
+    let access_token = workflow::access_token_with_scope(email, AccessScope::Full).await?;
+    let mut scanned = workflow::scan_inbox_with(
+        &imap_config,
+        email,
+        &access_token,
+        &scan_options(&config, since),
+        |_, _| {},
+    )
+    .await?;
+    let allowlist = storage::json_store::load_allowlist(email)?;
+    if !blocklist.is_empty() {
+        planner::mark_blocklisted(&mut scanned, &blocklist, &allowlist);
+    }
+    let newsletters = scanned
+        .iter()
+        .filter(|s| s.blocklisted || s.heuristic_score >= filter.min_score)
+        .count();
+    let senders = filter.select(&scanned, &allowlist);
+
+    if senders.is_empty() {
+        println!("No {}", filter.describe());
+        return Ok(());
+    }
+
+    println!("{} {}:", senders.len(), filter.describe());
+    for sender in &senders {
+        let tag = if sender.blocklisted {
+            " [blocklist]"
+        } else {
+            ""
+        };
+        println!("  {} ({} msgs){}", sender.email, sender.message_count, tag);
+    }
+
+    if !yes {
+        println!("\nRe-run with --yes to clean these senders");
+        return Ok(());
+    }
+
+    let actions = if filter.by_volume {
+        planner::plan_deletions(senders)
+    } else {
+        planner::plan_actions(senders)
+    };
+    let results = workflow::clean_actions(
+        &imap_config,
+        email,
+        &access_token,
+        actions,
+        max_delete,
+        &config.unsubscribe_options(),
+    )
+    .await?;
+
+    let mut failures = 0;
+    for result in &results {
+        match &result.error {
+            None => println!(
+                "✓ {}: {} messages",
+                result.sender_email, result.messages_deleted
+            ),
+            Some(e) => {
+                failures += 1;
+                println!("✗ {}: {}", result.sender_email, e);
+            }
+        }
+    }
+
+    println!("{}", CleanupSummary::from_results(&results));
+
+    let report = CleanupReport::new(email, Utc::now(), &scanned, newsletters, &results);
+    if let Err(e) = storage::json_store::append_report(email, &report) {
+        tracing::warn!(error = %e, "failed to save cleanup report");
+    }
+    if let Err(e) = storage::json_store::record_esp_results(&results) {
+        tracing::warn!(error = %e, "failed to update ESP stats");
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} of {} senders failed", failures, results.len());
+    }
+
+    Ok(())
+}
+
+/// Parse a time budget such as `90`, `60s`, `5m` or `1h` (seconds by default)
+fn parse_time_budget(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let (amount, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`", value))?;
+    let secs = match unit {
+        "" | "s" => Some(amount),
+        "m" => amount.checked_mul(60),
+        "h" => amount.checked_mul(3600),
+        _ => return Err(format!("unknown unit `{}` (use s, m or h)", unit)),
+    }
+    .ok_or_else(|| format!("duration `{}` is too long", value))?;
+    if secs == 0 {
+        return Err("time budget must be at least one second".to_string());
+    }
+    Ok(std::time::Duration::from_secs(secs))
+}
+
+/// Scan options from the config file, limited server-side to messages since `since`
+///
+/// Batch size, scan limit and time budget come from `config.fetch_options()`.
+fn scan_options(config: &storage::config::Config, since: Option<NaiveDate>) -> ScanOptions {
+    ScanOptions {
+        fetch: imap::fetch::FetchOptions {
+            since,
+            ..config.fetch_options()
+        },
+        gmail_api: config.gmail_fetch_config(),
+        newsletter_patterns: config.newsletter_patterns.clone(),
+        scoring: config.scoring.clone(),
+        ..ScanOptions::default()
+    }
+}
+
+async fn run_undo(email: &str) -> Result<()> {
+    let imap_config = storage::config::load_config()?.imap_config()?;
+    let access_token = workflow::access_token_with_scope(email, AccessScope::Full).await?;
+
+    match workflow::undo_last_cleanup(&imap_config, email, &access_token).await? {
+        Some(count) => println!("Restored {} messages", count),
+        None => println!("Nothing to undo"),
+    }
+
+    Ok(())
+}
+
+fn run_reports(email: &str, limit: usize) -> Result<()> {
+    let reports = storage::json_store::load_reports(email)?;
+
+    if reports.is_empty() {
+        println!("No reports for {}", email);
+        return Ok(());
+    }
+
+    for report in reports.iter().rev().take(limit) {
+        println!("{}\n", report);
+    }
+
+    Ok(())
+}
+
+fn run_accounts(command: AccountsCommand) -> Result<()> {
+    match command {
+        AccountsCommand::List => {
+            for account in accounts::list_accounts()? {
+                println!(
+                    "{}  (added {})",
+                    account.email,
+                    account.added_at.format("%Y-%m-%d")
+                );
+            }
+        }
+        AccountsCommand::Remove { email } => {
+            accounts::remove_account(&email)?;
+            println!("Removed {}", email);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_token(command: TokenCommand) -> Result<()> {
+    match command {
+        TokenCommand::Clear { email } => {
+            if storage::keyring::get_token(&email)?.is_none() {
+                println!("No stored credentials for {}", email);
+                return Ok(());
+            }
+
+            storage::keyring::delete_token(&email)?;
+            println!("Deleted stored credentials for {}", email);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_stats(command: StatsCommand) -> Result<()> {
+    match command {
+        StatsCommand::Esp => {
+            let stats = storage::json_store::load_esp_stats()?;
+            let hosts = stats.by_attempts();
+
+            if hosts.is_empty() {
+                println!("No unsubscribe attempts recorded yet");
+                return Ok(());
+            }
+
+            let width = hosts.iter().map(|(host, _)| host.len()).max().unwrap_or(0);
+            println!(
+                "{:width$}  {:>8}  {:>9}  {:>7}",
+                "HOST",
+                "ATTEMPTS",
+                "SUCCESSES",
+                "RATE",
+                width = width
+            );
+            for (host, stats) in hosts {
+                println!(
+                    "{:width$}  {:>8}  {:>9}  {:>6.1}%",
+                    host,
+                    stats.attempts,
+                    stats.successes,
+                    stats.success_rate() * 100.0,
+                    width = width
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_score(args: &ScoreArgs) -> Result<()> {
+    let config = match &args.config {
+        Some(path) => storage::config::load_config_from(path)?,
+        None => storage::config::load_config()?,
+    };
+    let breakdown = args.breakdown(&config);
+
+    println!("{} ({} messages)", args.email, args.count);
+    println!("{}", breakdown);
+
+    // Same rule as planner::selection_candidates
+    let listed = breakdown.total >= config.min_display_score || args.unsubscribe || args.list_id;
+    println!(
+        "{} in interactive mode (min_display_score {:.2})",
+        if listed { "Listed" } else { "Hidden" },
+        config.min_display_score
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_no_subcommand() {
+        let cli = Cli::try_parse_from(["unsubmail"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.min_display_score, None);
+
+        let cli = Cli::try_parse_from(["unsubmail", "--min-display-score", "0.4"]).unwrap();
+        assert_eq!(cli.min_display_score, Some(0.4));
+        assert!(!cli.device_code);
+
+        let cli = Cli::try_parse_from(["unsubmail", "--device-code"]).unwrap();
+        assert!(cli.device_code);
+        assert_eq!(cli.sign_in_method(), SignInMethod::DeviceCode);
+    }
+
+    #[test]
+    fn test_parse_stats_esp() {
+        let cli = Cli::try_parse_from(["unsubmail", "stats", "esp"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Stats {
+                command: StatsCommand::Esp
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_scan_ndjson() {
+        let cli = Cli::try_parse_from(["unsubmail", "scan", "--email", "me@gmail.com", "--ndjson"])
+            .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Scan {
+                ndjson: true,
+                json: false,
+                ..
+            })
+        ));
+
+        assert!(Cli::try_parse_from([
+            "unsubmail",
+            "scan",
+            "--email",
+            "me@gmail.com",
+            "--ndjson",
+            "--json",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_scan_time_budget() {
+        let cli = Cli::try_parse_from([
+            "unsubmail",
+            "scan",
+            "--email",
+            "me@gmail.com",
+            "--time-budget",
+            "60s",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Scan { time_budget, .. }) => {
+                assert_eq!(time_budget, Some(std::time::Duration::from_secs(60)))
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert_eq!(
+            parse_time_budget("90"),
+            Ok(std::time::Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_time_budget("5m"),
+            Ok(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(
+            parse_time_budget("1h"),
+            Ok(std::time::Duration::from_secs(3600))
+        );
+        assert!(parse_time_budget("0s").is_err());
+        assert!(parse_time_budget("10d").is_err());
+        assert!(parse_time_budget("s").is_err());
+        assert!(parse_time_budget(&format!("{}h", u64::MAX / 60)).is_err());
+    }
+
+    #[test]
+    fn test_parse_clean() {
+        let cli = Cli::try_parse_from([
+            "unsubmail",
+            "clean",
+            "--email",
+            "x@gmail.com",
+            "--min-score",
+            "0.8",
+            "--yes",
+            "--max-delete",
+            "300",
+            "--blocklist",
+            "spam.csv",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Some(Command::Clean {
+                email,
+                min_score,
+                yes,
+                max_delete,
+                blocklist,
+                ..
+            }) => {
+                assert_eq!(email, "x@gmail.com");
+                assert_eq!(min_score, 0.8);
+                assert!(yes);
+                assert_eq!(max_delete, Some(300));
+                assert_eq!(blocklist, Some(PathBuf::from("spam.csv")));
+            }
+            other => panic!("Expected clean command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_clean_by_volume() {
+        let cli = Cli::try_parse_from([
+            "unsubmail",
+            "clean",
+            "--email",
+            "x@gmail.com",
+            "--top",
+            "5",
+            "--by-volume",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Clean { top, by_volume, .. }) => {
+                assert_eq!(top, Some(5));
+                assert!(by_volume);
+            }
+            other => panic!("Expected clean command, got {:?}", other),
+        }
+
+        // Ranking by volume without a limit is refused
+        assert!(Cli::try_parse_from([
+            "unsubmail",
+            "clean",
+            "--email",
+            "x@gmail.com",
+            "--by-volume"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_clean_filter_by_volume_ignores_score() {
+        let sender = |email: &str, count: usize, score: f32| SenderInfo {
+            email: email.to_string(),
+            message_count: count,
+            message_uids: (1..=count as u32).collect(),
+            heuristic_score: score,
+            ..SenderInfo::default()
+        };
+        let scanned = vec![
+            sender("news@shop.com", 10, 1.5),
+            sender("friend@example.com", 50, 0.0),
+            sender("promo@shop.com", 5, 1.2),
+        ];
+        let emails = |senders: Vec<SenderInfo>| -> Vec<String> {
+            senders.into_iter().map(|s| s.email).collect()
+        };
+
+        let by_score = CleanFilter {
+            min_score: 1.0,
+            older_than: None,
+            top: Some(1),
+            by_volume: false,
+        };
+        assert_eq!(
+            emails(by_score.select(&scanned, &[])),
+            vec!["news@shop.com"]
+        );
+        assert_eq!(
+            emails(by_score.select(&scanned, &["news@shop.com".to_string()])),
+            vec!["promo@shop.com"]
+        );
+
+        let by_volume = CleanFilter {
+            by_volume: true,
+            top: Some(2),
+            ..by_score
+        };
+        assert_eq!(
+            emails(by_volume.select(&scanned, &[])),
+            vec!["friend@example.com", "news@shop.com"]
+        );
+        assert_eq!(
+            emails(by_volume.select(&scanned, &["friend@example.com".to_string()])),
+            vec!["news@shop.com", "promo@shop.com"]
+        );
+    }
+
+    fn score(args: &[&str], config: &storage::config::Config) -> ScoreBreakdown {
+        let cli = Cli::try_parse_from(["unsubmail", "score"].iter().chain(args)).unwrap();
+        let Some(Command::Score(args)) = cli.command else {
+            panic!("expected the score command");
+        };
+        args.breakdown(config)
+    }
+
+    #[test]
+    fn test_score_playground_inputs() {
+        let config = storage::config::Config::default();
+
+        // 0.5 unsubscribe + 0.3 pattern + 0.2 (>10) + 0.3 (>30)
+        let breakdown = score(
+            &[
+                "--email",
+                "newsletter@x.com",
+                "--unsubscribe",
+                "--count",
+                "35",
+            ],
+            &config,
+        );
+        assert!((breakdown.total - 1.3).abs() < 1e-6);
+        assert_eq!(breakdown.contributions.len(), 4);
+
+        // Without List-Unsubscribe, 0.3 pattern + 0.5 volume is capped at 0.5
+        let breakdown = score(&["--email", "info@shop.com", "--count", "50"], &config);
+        assert_eq!(breakdown.total, 0.5);
+        assert!(breakdown.contributions.last().unwrap().1 < 0.0);
+
+        // Subject and header signals apply after the cap
+        let breakdown = score(
+            &[
+                "--email",
+                "alice@example.com",
+                "--subject",
+                "50% off today",
+                "--bulk",
+            ],
+            &config,
+        );
+        assert!((breakdown.total - 0.5).abs() < 1e-6);
+
+        assert_eq!(score(&["--email", "alice@example.com"], &config).total, 0.0);
+    }
+
+    #[test]
+    fn test_score_playground_uses_config_weights() {
+        let config = storage::config::Config {
+            newsletter_patterns: vec!["bulletin@".to_string()],
+            scoring: analysis::ScoringConfig {
+                unsubscribe_weight: 1.0,
+                ..analysis::ScoringConfig::default()
+            },
+            ..storage::config::Config::default()
+        };
+
+        let breakdown = score(&["--email", "bulletin@mairie.fr", "--unsubscribe"], &config);
+        assert!((breakdown.total - 1.3).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,218 @@
+//! Export scan results to JSON, CSV and JSON lines
+
+use crate::domain::models::SenderInfo;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Guess the format from a file extension (defaults to JSON)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ExportFormat::Csv,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+/// Exported view of a sender (message UIDs are deliberately left out)
+#[derive(Debug, Serialize)]
+struct SenderRecord<'a> {
+    email: &'a str,
+    display_name: Option<&'a str>,
+    message_count: usize,
+    heuristic_score: f32,
+    unsubscribe_method: &'static str,
+}
+
+impl<'a> From<&'a SenderInfo> for SenderRecord<'a> {
+    fn from(sender: &'a SenderInfo) -> Self {
+        Self {
+            email: &sender.email,
+            display_name: sender.display_name.as_deref(),
+            message_count: sender.message_count,
+            heuristic_score: sender.heuristic_score,
+            unsubscribe_method: sender.unsubscribe_method.kind(),
+        }
+    }
+}
+
+const CSV_HEADER: &str = "email,display_name,message_count,heuristic_score,unsubscribe_method";
+
+/// Write senders to `writer` in the given format
+pub fn export_senders(
+    senders: &[SenderInfo],
+    format: ExportFormat,
+    mut writer: impl Write,
+) -> Result<()> {
+    let records: Vec<SenderRecord> = senders.iter().map(SenderRecord::from).collect();
+
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &records)
+                .context("Failed to write JSON export")?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Csv => {
+            writeln!(writer, "{}", CSV_HEADER)?;
+            for record in &records {
+                writeln!(
+                    writer,
+                    "{},{},{},{:.2},{}",
+                    csv_field(record.email),
+                    csv_field(record.display_name.unwrap_or("")),
+                    record.message_count,
+                    record.heuristic_score,
+                    record.unsubscribe_method
+                )?;
+            }
+        }
+    }
+
+    writer.flush().context("Failed to flush export")?;
+
+    Ok(())
+}
+
+/// One line of `scan --ndjson` output, tagged by `type`
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum NdjsonRecord<'a> {
+    /// A sender as analyzed so far
+    Sender(SenderRecord<'a>),
+
+    /// Totals, written once the scan is complete
+    Summary {
+        account: &'a str,
+        messages_scanned: usize,
+        senders: usize,
+    },
+}
+
+/// Write one sender as a JSON line and flush it
+///
+/// During a streaming scan the same sender is written again whenever a later
+/// batch adds messages; the last line for an address is the final one.
+pub fn write_ndjson_sender(sender: &SenderInfo, writer: impl Write) -> Result<()> {
+    write_ndjson(&NdjsonRecord::Sender(SenderRecord::from(sender)), writer)
+}
+
+/// Write the closing summary line of a scan
+pub fn write_ndjson_summary(
+    account: &str,
+    messages_scanned: usize,
+    senders: usize,
+    writer: impl Write,
+) -> Result<()> {
+    write_ndjson(
+        &NdjsonRecord::Summary {
+            account,
+            messages_scanned,
+            senders,
+        },
+        writer,
+    )
+}
+
+fn write_ndjson(record: &NdjsonRecord, mut writer: impl Write) -> Result<()> {
+    serde_json::to_writer(&mut writer, record).context("Failed to write JSON line")?;
+    writeln!(writer)?;
+    writer.flush().context("Failed to flush JSON line")
+}
+
+/// Quote a CSV field if it contains separators, quotes or newlines
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::UnsubscribeMethod;
+
+    fn sample_sender() -> SenderInfo {
+        SenderInfo {
+            email: "news@example.com".to_string(),
+            display_name: Some("News, Daily \"Edition\"".to_string()),
+            message_count: 12,
+            message_uids: vec![4242, 4243],
+            unsubscribe_method: UnsubscribeMethod::OneClick {
+                url: "https://example.com/unsub".to_string(),
+            },
+            heuristic_score: 1.0,
+            ..SenderInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut out = Vec::new();
+        export_senders(&[sample_sender()], ExportFormat::Csv, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("news@example.com,\"News, Daily \"\"Edition\"\"\",12,1.00,one-click")
+        );
+        assert!(!csv.contains("4242"));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let mut out = Vec::new();
+        export_senders(&[sample_sender()], ExportFormat::Json, &mut out).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(value[0]["email"], "news@example.com");
+        assert_eq!(value[0]["message_count"], 12);
+        assert_eq!(value[0]["unsubscribe_method"], "one-click");
+        assert!(value[0].get("message_uids").is_none());
+    }
+
+    #[test]
+    fn test_ndjson_lines_are_tagged() {
+        let mut out = Vec::new();
+        write_ndjson_sender(&sample_sender(), &mut out).unwrap();
+        write_ndjson_summary("me@gmail.com", 120, 1, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "sender");
+        assert_eq!(lines[0]["email"], "news@example.com");
+        assert_eq!(lines[0]["unsubscribe_method"], "one-click");
+        assert!(lines[0].get("message_uids").is_none());
+        assert_eq!(lines[1]["type"], "summary");
+        assert_eq!(lines[1]["account"], "me@gmail.com");
+        assert_eq!(lines[1]["messages_scanned"], 120);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.csv")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.json")),
+            ExportFormat::Json
+        );
+    }
+}
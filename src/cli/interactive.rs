@@ -1,78 +1,346 @@
 //! Interactive CLI - Simplified linear workflow
 
+use super::accounts;
 use crate::application::workflow;
-use crate::domain::models::{SenderInfo, UnsubscribeMethod};
+use crate::domain::email::EmailAddress;
+use crate::domain::models::{
+    AccessScope, ActionType, CleanupAction, CleanupProgress, CleanupReport, CleanupResult,
+    CleanupSummary, InboxSummary, ResolutionStep, SenderGrouping, SenderInfo, SenderOrder,
+    UnsubscribeMethod,
+};
+use crate::domain::{analysis, planner};
+use crate::infrastructure::browser::SystemBrowser;
 use crate::infrastructure::{imap, network, storage};
 use anyhow::Result;
+use chrono::{Duration, Utc};
 use console::{style, Term};
-use inquire::{Confirm, MultiSelect, Select, Text};
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, MultiSelect, Select, Text};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tracing::info;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Rows visible at once in the sender selection list
+const SENDER_PAGE_SIZE: usize = 15;
+
+/// Senders offered per selection prompt before asking to show more
+const MAX_SENDER_OPTIONS: usize = 100;
+
+/// Top senders shown live while a scan is running
+const LIVE_SENDER_LINES: usize = 5;
+
 /// Main interactive workflow with loop support
-pub async fn run_interactive() -> Result<()> {
+///
+/// Senders scoring below `min_display_score` are hidden from selection unless
+/// they have an unsubscribe method or a List-Id. Defaults come from the config
+/// file; `min_display_score`, `max_delete`, `time_budget` and `UNSUBMAIL_*`
+/// env vars override it. Signing in, re-authenticating and adding accounts
+/// use `sign_in`.
+pub async fn run_interactive(
+    min_display_score: Option<f32>,
+    max_delete: Option<usize>,
+    blocklist: Option<PathBuf>,
+    time_budget: Option<std::time::Duration>,
+    sign_in: workflow::SignInMethod,
+) -> Result<()> {
     let term = Term::stdout();
     term.clear_screen()?;
 
     print_header();
 
+    let mut config = storage::config::load_config()?;
+    if max_delete.is_some() {
+        config.max_delete = max_delete;
+    }
+    if let Some(budget) = time_budget {
+        config.scan_time_budget_secs = Some(budget.as_secs());
+    }
+    let imap_config = config.imap_config()?;
+    let min_display_score = min_display_score.unwrap_or(config.min_display_score);
+    let blocklist = match blocklist {
+        Some(path) => storage::json_store::load_blocklist(&path)?,
+        None => Vec::new(),
+    };
+
+    let dry_run = Confirm::new("Run in dry-run mode?")
+        .with_default(false)
+        .with_help_message("Show what would happen without changing your inbox")
+        .prompt()?;
+
+    if dry_run {
+        println!(
+            "{}",
+            style("Dry run: no messages will be moved or deleted").yellow()
+        );
+    }
+    if imap_config.flag_only {
+        println!(
+            "{}",
+            style(format!(
+                "Flag-only mode: cleaned messages stay in the mailbox {}",
+                staged_state(&imap_config)
+            ))
+            .yellow()
+        );
+    }
+    println!();
+
     // Main loop: allow user to clean multiple accounts or retry
     loop {
-        // Step 1: Ask for email
-        let email = Text::new("Gmail address:")
-            .with_help_message("Enter your Gmail email address")
-            .prompt()?;
+        // Step 1: Ask for email (or scan every stored account first)
+        let Some(email) = prompt_account(&config, &imap_config, min_display_score).await? else {
+            println!("{}", style("Goodbye!").cyan());
+            break;
+        };
 
         println!();
 
-        // Step 2: Get or create OAuth2 token
-        let access_token = get_or_create_token(&email).await?;
+        // Step 2: Get or create OAuth2 token (known accounts keep their scope,
+        // so their stored token is refreshed rather than replaced)
+        let scope = match workflow::account_scope(&email)? {
+            Some(scope) => scope,
+            None => prompt_access_scope(config.cleanup_scope())?,
+        };
+        let mut access_token = get_or_create_token(&email, scope, sign_in).await?;
+
+        if !scope.allows_changes() {
+            scan_only(
+                &config,
+                &email,
+                &access_token,
+                &imap_config.mailbox,
+                min_display_score,
+            )
+            .await?;
 
-        // Account loop: allow cleaning more senders from same account
-        loop {
-            // Step 3: Scan inbox
             println!();
-            println!("{}", style("Scanning inbox...").bold());
+            let upgrade = Confirm::new("Sign in with full access to clean these senders?")
+                .with_default(false)
+                .with_help_message("Cleaning needs IMAP access (https://mail.google.com/)")
+                .prompt()?;
+
+            if !upgrade {
+                let another = Confirm::new("Scan a different account?")
+                    .with_default(false)
+                    .prompt()?;
+                println!();
+                if another {
+                    continue;
+                }
+                println!("{}", style("Goodbye!").cyan());
+                break;
+            }
+
             println!();
+            access_token = get_or_create_token(&email, config.cleanup_scope(), sign_in).await?;
+        } else if !scope.covers(config.cleanup_scope()) {
+            // `gmail_filters` was turned on after this account signed in
+            access_token = get_or_create_token(&email, config.cleanup_scope(), sign_in).await?;
+        }
 
-            let pb = indicatif::ProgressBar::new_spinner();
-            pb.set_style(
-                indicatif::ProgressStyle::default_spinner()
-                    .template("{spinner:.cyan} {msg}")
-                    .unwrap(),
-            );
+        if !dry_run {
+            resume_cleanup(&imap_config, &config, &email, &mut access_token).await?;
+        }
+
+        let imap_config = prompt_mailbox(&imap_config, &email, &access_token).await?;
+        let grouping = prompt_grouping()?;
+        let contacts =
+            prompt_protect_contacts(&imap_config, &config, &email, &access_token).await?;
+
+        // Offer a recent scan instead of re-scanning
+        let mut cached = prompt_cached_scan(&email, &imap_config.mailbox, grouping)?;
+
+        // Account loop: allow cleaning more senders from same account
+        loop {
+            // Step 3: Scan inbox (or reuse the cached scan once)
+            let scan = match cached.take() {
+                Some(scan) => scan,
+                None => {
+                    println!();
+                    println!(
+                        "{}",
+                        style(format!("Scanning {}...", imap_config.mailbox)).bold()
+                    );
+                    println!();
 
-            let senders = scan_inbox(&email, &access_token, pb).await?;
+                    let pb = indicatif::ProgressBar::new_spinner();
+                    pb.set_style(
+                        indicatif::ProgressStyle::default_spinner()
+                            .template("{spinner:.cyan} {msg}")
+                            .unwrap(),
+                    );
+
+                    let scanned = workflow::with_token_refresh(
+                        &mut access_token,
+                        |token| {
+                            let (imap_config, config, email) = (&imap_config, &config, &email);
+                            let pb = pb.clone();
+                            async move {
+                                scan_inbox(imap_config, config, email, &token, grouping, pb).await
+                            }
+                        },
+                        || workflow::refreshed_access_token(&email),
+                    )
+                    .await;
+                    match scanned {
+                        Ok(scan) => scan,
+                        Err(e) if imap::connection::is_token_expired(&e) => {
+                            pb.finish_and_clear();
+                            info!(error = %format_args!("{:#}", e), "scan failed to authenticate");
+                            if !prompt_reauthenticate()? {
+                                return Err(e);
+                            }
+                            access_token = reauthenticate(&email, sign_in).await?;
+                            continue;
+                        }
+                        Err(e) => {
+                            pb.finish_and_clear();
+                            return Err(e);
+                        }
+                    }
+                }
+            };
 
+            if scan.messages_scanned == 0 {
+                println!(
+                    "{}",
+                    style(format!("{} is empty", imap_config.mailbox)).yellow()
+                );
+                break;
+            }
+            let mut senders = scan.senders;
             if senders.is_empty() {
                 println!("{}", style("No senders found").yellow());
                 break;
             }
 
-            display_results(&senders);
+            display_results(
+                &senders,
+                scan.messages_scanned,
+                scan.partial_of,
+                min_display_score,
+            );
 
-            // Step 4: Select senders
+            // Step 4: Select senders (optionally allowlisting some first)
             println!();
-            info!("Filtering senders with score >= 0.6 or unsubscribe available");
-            let selected = select_senders(&senders)?;
+            let choice = Select::new(
+                "What would you like to do?",
+                vec![
+                    "Select senders to clean",
+                    "Clean senders with the most messages",
+                    "Clean senders you never open",
+                    "Add senders to allowlist",
+                ],
+            )
+            .prompt()?;
+            let by_volume = choice == "Clean senders with the most messages";
+            let never_opened = choice == "Clean senders you never open";
+
+            if choice == "Add senders to allowlist" {
+                add_to_allowlist(&email, &senders)?;
+                println!();
+            }
+
+            let mut allowlist = storage::json_store::load_allowlist(&email)?;
+            allowlist.extend(contacts.iter().cloned());
+            if !blocklist.is_empty() {
+                let blocked = planner::mark_blocklisted(&mut senders, &blocklist, &allowlist);
+                println!(
+                    "  {} {} senders pre-selected from the blocklist",
+                    style("ℹ").blue(),
+                    blocked
+                );
+            }
+            let newsletters =
+                planner::selection_candidates(&senders, &allowlist, min_display_score).len();
+            let selected = if by_volume {
+                select_by_volume(&senders, &allowlist)?
+            } else if never_opened {
+                select_never_opened(&senders, &allowlist)?
+            } else {
+                info!(
+                    min_score = min_display_score,
+                    "filtering senders by score or unsubscribe availability"
+                );
+                select_senders(&senders, &allowlist, min_display_score)?
+            };
 
             if selected.is_empty() {
                 println!("{}", style("No senders selected").yellow());
                 break;
             }
 
-            info!("User selected {} senders for cleanup", selected.len());
+            let older_than = prompt_older_than()?;
+            let selected = planner::filter_older_than(selected, older_than, Utc::now());
+
+            if selected.is_empty() {
+                println!("{}", style("No messages older than the cutoff").yellow());
+                break;
+            }
+
+            info!(count = selected.len(), "senders selected for cleanup");
 
             // Step 5: Clean
             println!();
             println!("{}", style("Cleaning...").bold());
             println!();
 
-            execute_cleanup(&email, &access_token, &selected).await?;
+            // Volume and unread picks may be personal contacts: only ever move them to trash
+            let actions = if by_volume || never_opened {
+                Some(planner::plan_deletions(selected.clone()))
+            } else if prompt_plan_mode()? {
+                Some(planner::plan_actions(selected.clone()))
+            } else {
+                None
+            };
+            let planned = match actions {
+                Some(actions) => {
+                    execute_plan(
+                        &imap_config,
+                        &config,
+                        &email,
+                        &mut access_token,
+                        actions,
+                        dry_run,
+                    )
+                    .await?
+                }
+                None => None,
+            };
+            let results = match planned {
+                Some(results) => results,
+                None => {
+                    execute_cleanup(
+                        &imap_config,
+                        &config,
+                        &email,
+                        &mut access_token,
+                        &selected,
+                        dry_run,
+                    )
+                    .await?
+                }
+            };
 
             println!();
             println!("{}", style("Done!").green().bold());
+            if !dry_run {
+                println!("  {}", CleanupSummary::from_results(&results));
+
+                let report =
+                    CleanupReport::new(&email, Utc::now(), &senders, newsletters, &results);
+                if let Err(e) = storage::json_store::append_report(&email, &report) {
+                    tracing::warn!(error = %e, "failed to save cleanup report");
+                }
+                if let Err(e) = storage::json_store::record_esp_results(&results) {
+                    tracing::warn!(error = %e, "failed to update ESP stats");
+                }
+                println!();
+                println!("{}", report);
+            }
             println!();
 
             // Ask if user wants to clean more senders from same account
@@ -87,11 +355,40 @@ pub async fn run_interactive() -> Result<()> {
 
         // Ask what to do next
         println!();
-        let next_action = Select::new(
-            "What would you like to do next?",
-            vec!["Switch to a different account", "Exit"],
-        )
-        .prompt()?;
+        let next_action = loop {
+            let choice = Select::new(
+                "What would you like to do next?",
+                vec![
+                    "Switch to a different account",
+                    "Undo last cleanup",
+                    "Manage accounts",
+                    "Forget this account's credentials",
+                    "Sign out and forget this account",
+                    "Exit",
+                ],
+            )
+            .prompt()?;
+
+            println!();
+            match choice {
+                "Manage accounts" => accounts::manage_accounts(sign_in).await?,
+                "Undo last cleanup" => {
+                    undo_last_cleanup(&imap_config, &email, &access_token).await?
+                }
+                "Forget this account's credentials" => {
+                    if forget_credentials(&email)? {
+                        break "Switch to a different account";
+                    }
+                }
+                "Sign out and forget this account" => {
+                    if sign_out(&email).await? {
+                        break "Switch to a different account";
+                    }
+                }
+                _ => break choice,
+            }
+            println!();
+        };
 
         match next_action {
             "Switch to a different account" => {
@@ -109,6 +406,88 @@ pub async fn run_interactive() -> Result<()> {
     Ok(())
 }
 
+/// Restore the messages moved by the last cleanup run
+async fn undo_last_cleanup(
+    imap_config: &imap::connection::ImapConfig,
+    email: &str,
+    access_token: &str,
+) -> Result<()> {
+    let confirm = Confirm::new("Move messages from the last cleanup back to the inbox?")
+        .with_default(true)
+        .prompt()?;
+
+    if !confirm {
+        return Ok(());
+    }
+
+    match workflow::undo_last_cleanup(imap_config, email, access_token).await? {
+        Some(count) => {
+            info!(count, "messages restored");
+            println!("  {} Restored {} messages", style("✓").green(), count);
+        }
+        None => println!("  {} Nothing to undo", style("ℹ").blue()),
+    }
+
+    Ok(())
+}
+
+/// Delete the account's stored token from the OS keyring
+///
+/// Other local data (allowlist, reports, cleanup log) is kept, and access
+/// isn't revoked with Google; the next run signs in again. Returns whether
+/// the token was deleted.
+fn forget_credentials(email: &str) -> Result<bool> {
+    let confirm = Confirm::new(&format!("Delete the stored credentials for {}?", email))
+        .with_default(false)
+        .with_help_message("You'll sign in again next time; nothing else is removed")
+        .prompt()?;
+
+    if !confirm {
+        return Ok(false);
+    }
+
+    storage::keyring::delete_token(email)?;
+    println!(
+        "  {} Deleted stored credentials for {}",
+        style("✓").green(),
+        email
+    );
+
+    Ok(true)
+}
+
+/// Revoke the account's token and remove all local data for it
+///
+/// Returns whether the account was removed.
+async fn sign_out(email: &str) -> Result<bool> {
+    let confirm = Confirm::new(&format!(
+        "Revoke access and remove all local data for {}?",
+        email
+    ))
+    .with_default(false)
+    .prompt()?;
+
+    if !confirm {
+        return Ok(false);
+    }
+
+    match workflow::revoke_and_remove_account(email).await? {
+        workflow::RevocationStatus::Revoked => {
+            println!("  {} Access revoked with Google", style("✓").green())
+        }
+        workflow::RevocationStatus::NoToken => {
+            println!("  {} No stored token to revoke", style("ℹ").blue())
+        }
+        workflow::RevocationStatus::Failed(e) => {
+            println!("  {} Could not revoke access: {}", style("✗").red(), e);
+            println!("    Remove it manually at https://myaccount.google.com/permissions");
+        }
+    }
+    println!("  {} Removed local data for {}", style("✓").green(), email);
+
+    Ok(true)
+}
+
 fn print_header() {
     println!();
     println!("{}", style("═".repeat(60)).cyan());
@@ -127,10 +506,23 @@ fn print_header() {
 }
 
 /// Get existing token or create new one via OAuth2
-async fn get_or_create_token(email: &str) -> Result<String> {
+async fn get_or_create_token(
+    email: &str,
+    scope: AccessScope,
+    sign_in: workflow::SignInMethod,
+) -> Result<String> {
     // Check if token exists
     if let Some(token) = storage::keyring::get_token(email)? {
-        if !token.is_expired() {
+        if !token.scope.covers(scope) {
+            println!(
+                "{}",
+                style(format!(
+                    "{} was signed in with {}; this needs {}",
+                    email, token.scope, scope
+                ))
+                .yellow()
+            );
+        } else if !token.is_expired() {
             println!("{}", style("✓ Using existing authentication").dim());
             return Ok(token.access_token);
         } else {
@@ -156,7 +548,7 @@ async fn get_or_create_token(email: &str) -> Result<String> {
     println!("{}", style("Authenticating with Google...").bold());
     println!();
 
-    let account = workflow::add_account_for_email(email).await?;
+    let account = workflow::add_account_for_email(email, scope, sign_in).await?;
 
     let token = storage::keyring::get_token(&account.email)?
         .ok_or_else(|| anyhow::anyhow!("Token not found after authentication"))?;
@@ -164,251 +556,2287 @@ async fn get_or_create_token(email: &str) -> Result<String> {
     Ok(token.access_token)
 }
 
-/// Scan inbox
-async fn scan_inbox(
-    email: &str,
-    access_token: &str,
-    pb: indicatif::ProgressBar,
-) -> Result<Vec<SenderInfo>> {
-    pb.set_message("Connecting to IMAP...");
+/// Ask whether to sign in again after the server rejected the session
+fn prompt_reauthenticate() -> Result<bool> {
+    println!(
+        "  {} Gmail did not accept the stored credentials",
+        style("✗").red()
+    );
 
-    let mut session = tokio::time::timeout(
-        std::time::Duration::from_secs(30),
-        imap::connection::connect_and_auth(email, access_token),
+    Ok(
+        Confirm::new("Your session looks invalid — re-authenticate now?")
+            .with_default(true)
+            .with_help_message("Opens the browser to sign in again, then retries the scan")
+            .prompt()?,
     )
-    .await
-    .map_err(|_| {
-        anyhow::anyhow!(
-            "Connection timed out after 30 seconds.\n\
-            This usually means the OAuth2 token is invalid or network issues.\n\
-            Try re-running the program to refresh your authentication."
-        )
-    })??;
+}
 
-    pb.set_message("Fetching messages...");
-    let headers = imap::fetch::fetch_all_headers(&mut session, 200).await?;
+/// Run the browser sign-in again, replacing the stored token
+///
+/// The account keeps the scope it was signed in with.
+async fn reauthenticate(email: &str, sign_in: workflow::SignInMethod) -> Result<String> {
+    println!();
+    println!("{}", style("Authenticating with Google...").bold());
+    println!();
 
-    pb.set_message("Analyzing senders...");
-    let grouped = imap::fetch::group_by_sender(headers);
+    let scope = workflow::account_scope(email)?.unwrap_or_default();
+    workflow::add_account_for_email(email, scope, sign_in).await?;
 
-    let senders: Vec<SenderInfo> = grouped
-        .into_iter()
-        .map(|(email, messages)| {
-            let message_count = messages.len();
-            let message_uids: Vec<u32> = messages.iter().map(|m| m.uid).collect();
-            let first = &messages[0];
-            let display_name = extract_display_name(&first.from);
-            let sample_subjects: Vec<String> =
-                messages.iter().take(3).map(|m| m.subject.clone()).collect();
-
-            crate::domain::analysis::analyze_sender(
-                email,
-                display_name,
-                message_count,
-                message_uids,
-                first.list_unsubscribe.clone(),
-                first.list_unsubscribe_post.clone(),
-                sample_subjects,
-            )
-        })
-        .collect();
+    storage::keyring::get_token(email)?
+        .map(|token| token.access_token)
+        .ok_or_else(|| anyhow::anyhow!("Token not found after authentication"))
+}
 
-    session.logout().await?;
-    pb.finish_and_clear();
+/// Ask whether to sign in for scanning only or for cleanup too
+///
+/// Choosing cleanup signs in with `cleanup_scope`.
+fn prompt_access_scope(cleanup_scope: AccessScope) -> Result<AccessScope> {
+    const FULL: &str = "Scan and clean (full access)";
+    const READ_ONLY: &str = "Scan only (read-only access)";
+
+    let choice = Select::new("What do you want to do?", vec![FULL, READ_ONLY])
+        .with_help_message(
+            "Read-only access can list senders but not unsubscribe, delete, move or label",
+        )
+        .prompt()?;
 
-    Ok(senders)
+    Ok(if choice == READ_ONLY {
+        AccessScope::ReadOnly
+    } else {
+        cleanup_scope
+    })
 }
 
-fn extract_display_name(from: &str) -> Option<String> {
-    if let Some(pos) = from.find('<') {
-        let name = from[..pos].trim().trim_matches('"');
-        if !name.is_empty() {
-            return Some(name.to_string());
-        }
+/// Senders listed after a scan-only run
+const SCAN_ONLY_TOP_SENDERS: usize = 20;
+
+/// Ask which account to clean, listing the accounts signed in before
+///
+/// The address is only typed for a new account, or when none is known yet.
+/// With two or more stored accounts, scanning all of them first is offered
+/// too. Returns None when the user is done after the combined scan.
+async fn prompt_account(
+    config: &storage::config::Config,
+    imap_config: &imap::connection::ImapConfig,
+    min_display_score: f32,
+) -> Result<Option<String>> {
+    const ADD_NEW: &str = "Add new account";
+    const SCAN_ALL: &str = "Scan all my accounts";
+    const DONE: &str = "Done";
+
+    let known = accounts::known_emails()?;
+    if known.is_empty() {
+        return prompt_email("Gmail address:").map(Some);
+    }
+
+    let mut options = known;
+    options.push(ADD_NEW.to_string());
+    if accounts::list_accounts()?.len() >= 2 {
+        options.push(SCAN_ALL.to_string());
+    }
+
+    let choice = Select::new("Which account?", options)
+        .with_help_message("Previously added accounts reuse their stored sign-in")
+        .prompt()?;
+    if choice == ADD_NEW {
+        return prompt_email("Gmail address:").map(Some);
+    }
+    if choice != SCAN_ALL {
+        return Ok(Some(choice));
+    }
+
+    let emails = scan_all_accounts(config, imap_config, min_display_score).await?;
+    if emails.is_empty() {
+        return prompt_email("Gmail address:").map(Some);
     }
-    None
-}
 
-fn display_results(senders: &[SenderInfo]) {
     println!();
-    println!("{}", style("Scan Results").bold().underlined());
+    let mut options = emails;
+    options.push(DONE.to_string());
+    let email = Select::new("Continue cleaning which account?", options).prompt()?;
+
+    Ok((email != DONE).then_some(email))
+}
+
+/// Scan every stored account and print a per-account and combined report
+///
+/// Returns the accounts that were scanned successfully.
+async fn scan_all_accounts(
+    config: &storage::config::Config,
+    imap_config: &imap::connection::ImapConfig,
+    min_display_score: f32,
+) -> Result<Vec<String>> {
+    let options = workflow::ScanOptions {
+        fetch: config.fetch_options(),
+        grouping: SenderGrouping::default(),
+        newsletter_patterns: config.newsletter_patterns.clone(),
+        scoring: config.scoring.clone(),
+        gmail_api: config.gmail_fetch_config(),
+    };
+
     println!();
-    println!("  {} unique senders found", senders.len());
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    let result = workflow::scan_all_accounts(imap_config, &options, |email| {
+        pb.set_message(format!("Scanning {}...", email));
+    })
+    .await?;
+    pb.finish_and_clear();
 
-    let with_unsub = senders
-        .iter()
-        .filter(|s| s.unsubscribe_method.is_available())
-        .count();
-    let with_one_click = senders
-        .iter()
-        .filter(|s| s.unsubscribe_method.is_one_click())
-        .count();
+    for (email, e) in &result.skipped {
+        println!("{}", style(format!("Skipped {}: {:#}", email, e)).yellow());
+    }
+    if result.scans.is_empty() {
+        println!("{}", style("No account could be scanned").yellow());
+        return Ok(Vec::new());
+    }
 
-    println!("  {} with unsubscribe option", with_unsub);
-    println!("  {} with one-click unsubscribe", with_one_click);
     println!();
-}
+    println!(
+        "{}",
+        style("Scan Results (all accounts)").bold().underlined()
+    );
+    println!();
+    for scan in &result.scans {
+        let summary = scan.summary(min_display_score);
+        println!(
+            "  {}: {} messages, {} senders, ~{}% newsletters",
+            scan.email,
+            summary.total_messages,
+            summary.senders,
+            summary.newsletter_percent()
+        );
+    }
 
-fn select_senders(senders: &[SenderInfo]) -> Result<Vec<SenderInfo>> {
-    // Filter senders: only show those with score >= 0.6 OR with unsubscribe method
-    // This prevents personal emails from appearing unless they have List-Unsubscribe
-    let filtered: Vec<_> = senders
+    let all: Vec<SenderInfo> = result
+        .scans
         .iter()
-        .filter(|s| s.heuristic_score >= 0.6 || s.unsubscribe_method.is_available())
-        .cloned()
+        .flat_map(|scan| scan.senders.iter().cloned())
         .collect();
+    let total = InboxSummary::from_senders(0, &all, min_display_score);
+    let combined = planner::combine_account_scans(&result.scans);
+    println!(
+        "  Total: {} messages, {} unique senders, ~{}% newsletters",
+        total.total_messages,
+        combined.len(),
+        total.newsletter_percent()
+    );
 
-    if filtered.is_empty() {
+    println!();
+    println!("{}", style("Top newsletters across accounts").bold());
+    for sender in combined
+        .iter()
+        .filter(|s| s.heuristic_score >= min_display_score)
+        .take(SCAN_ONLY_TOP_SENDERS)
+    {
         println!(
-            "  {} No newsletters or promotional emails detected",
-            style("ℹ").blue()
+            "  {:>5.2}  {:>5} msgs  {}  ({})",
+            sender.heuristic_score,
+            sender.message_count,
+            sender.email,
+            sender.accounts.join(", ")
         );
-        println!("  All senders appear to be personal or low-volume contacts");
-        return Ok(vec![]);
     }
 
-    let mut sorted = filtered;
-    sorted.sort_by(|a, b| {
-        b.heuristic_score
-            .partial_cmp(&a.heuristic_score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    Ok(result.scans.into_iter().map(|scan| scan.email).collect())
+}
 
-    let options: Vec<String> = sorted
-        .iter()
-        .map(|s| {
-            let name = s.display_name.as_ref().unwrap_or(&s.email);
-            let method = if s.unsubscribe_method.is_one_click() {
-                "✓ One-Click"
-            } else if s.unsubscribe_method.is_available() {
-                "⚠ Manual"
-            } else {
-                "✗ No unsub"
-            };
-            format!(
-                "{} ({} msgs) {} [score: {:.2}]",
-                name, s.message_count, method, s.heuristic_score
-            )
-        })
-        .collect();
+/// Scan `mailbox` with a read-only token and list the top senders
+///
+/// Goes through the Gmail API instead of IMAP; nothing is cached or changed.
+async fn scan_only(
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &str,
+    mailbox: &str,
+    min_display_score: f32,
+) -> Result<()> {
+    let grouping = prompt_grouping()?;
 
-    let selected_strs = MultiSelect::new("Select senders to clean:", options)
-        .with_help_message("Use Space to select, Enter to confirm")
-        .prompt()?;
+    println!();
+    println!(
+        "{}",
+        style(format!("Scanning {} (read-only)...", mailbox)).bold()
+    );
+    println!();
 
-    let selected: Vec<SenderInfo> = selected_strs
-        .iter()
-        .filter_map(|s| {
-            sorted
-                .iter()
-                .find(|sender| {
-                    let name = sender.display_name.as_ref().unwrap_or(&sender.email);
-                    s.starts_with(name)
-                })
-                .cloned()
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    let options = workflow::ScanOptions {
+        fetch: config.fetch_options(),
+        grouping,
+        newsletter_patterns: config.newsletter_patterns.clone(),
+        scoring: config.scoring.clone(),
+        gmail_api: config.gmail_fetch_config(),
+    };
+    let mut progress = (0, 0);
+    let mut senders =
+        workflow::scan_inbox_readonly(email, access_token, mailbox, &options, |done, total| {
+            progress = (done, total);
+            pb.set_message(format!("Reading headers {}/{}", done, total));
         })
-        .collect();
-
-    Ok(selected)
-}
+        .await?;
+    pb.finish_and_clear();
 
-async fn execute_cleanup(email: &str, access_token: &str, senders: &[SenderInfo]) -> Result<()> {
-    info!("Starting cleanup for {} senders", senders.len());
-    let mut session = imap::connection::connect_and_auth(email, access_token).await?;
+    if senders.is_empty() {
+        println!("{}", style("No senders found").yellow());
+        return Ok(());
+    }
 
-    for (idx, sender) in senders.iter().enumerate() {
-        println!();
+    let total_messages = senders.iter().map(|s| s.message_count).sum();
+    let (read, total) = progress;
+    let partial_of = (read < total).then_some(total);
+    display_results(&senders, total_messages, partial_of, min_display_score);
+    planner::sort_senders(&mut senders, SenderOrder::Score);
+    for sender in senders.iter().take(SCAN_ONLY_TOP_SENDERS) {
         println!(
-            "{} {} ({} messages)",
-            style(format!("[{}/{}]", idx + 1, senders.len())).dim(),
-            style(&sender.email).cyan().bold(),
-            sender.message_count
+            "  {:>5.2}  {:>5} msgs  {}",
+            sender.heuristic_score, sender.message_count, sender.email
         );
+    }
 
-        let has_one_click = sender.unsubscribe_method.is_one_click();
+    Ok(())
+}
 
-        if has_one_click {
-            info!("Sender {} has one-click unsubscribe", sender.email);
-            println!("  {} One-click unsubscribe available", style("✓").green());
+/// Scan the selected mailbox and cache the result
+async fn scan_inbox(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &str,
+    grouping: SenderGrouping,
+    pb: indicatif::ProgressBar,
+) -> Result<storage::scan_cache::CachedScan> {
+    pb.set_message("Connecting to IMAP...");
 
-            let unsub = Confirm::new("Unsubscribe from this sender?")
-                .with_default(true)
-                .prompt()?;
+    // Each phase has its own timeout, so a hang is reported either as a
+    // network failure or as an authentication failure
+    let mut session = imap::connection::connect_and_auth(imap_config, email, access_token).await?;
 
-            if unsub {
-                if let UnsubscribeMethod::OneClick { url } = &sender.unsubscribe_method {
-                    info!("Attempting one-click unsubscribe to: {}", url);
-                    match network::http_client::unsubscribe_one_click(url).await {
-                        Ok(true) => {
-                            info!("One-click unsubscribe successful");
-                            println!("  {} Unsubscribed successfully", style("✓").green());
-                        }
-                        Ok(false) => {
-                            info!("One-click unsubscribe returned non-success status");
-                            println!("  {} Unsubscribe failed", style("✗").red());
+    // The total is known once the UID search returns: switch to a real bar
+    let spinner_style = pb.style();
+    pb.set_message("Fetching messages...");
+    let options = workflow::ScanOptions {
+        fetch: config.fetch_options(),
+        grouping,
+        newsletter_patterns: config.newsletter_patterns.clone(),
+        scoring: config.scoring.clone(),
+        gmail_api: config.gmail_fetch_config(),
+    };
+
+    let multi = indicatif::MultiProgress::new();
+    let pb = multi.add(pb);
+    let lines: Vec<_> = (0..LIVE_SENDER_LINES)
+        .map(|_| {
+            let line = multi.add(indicatif::ProgressBar::new_spinner());
+            line.set_style(
+                indicatif::ProgressStyle::default_spinner()
+                    .template("  {msg}")
+                    .unwrap(),
+            );
+            line
+        })
+        .collect();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<workflow::ScanUpdate>();
+    let scan =
+        workflow::scan_session_streaming(&mut session, email, &imap_config.mailbox, &options, tx);
+    let render = async {
+        let mut live: HashMap<String, SenderInfo> = HashMap::new();
+        let mut messages_scanned = 0;
+        let mut partial_of = None;
+
+        while let Some(update) = rx.recv().await {
+            let (done, total) = (update.fetched, update.total);
+            messages_scanned = update.scanned;
+            partial_of = update.partial_of();
+            if total > 0 && pb.length() != Some(total as u64) {
+                pb.set_length(total as u64);
+                pb.set_style(
+                    indicatif::ProgressStyle::default_bar()
+                        .template("{msg} [{bar:30.cyan/blue}] {pos}/{len} ({eta})")
+                        .unwrap()
+                        .progress_chars("=> "),
+                );
+            }
+            pb.set_position(done as u64);
+            if done == total {
+                pb.set_style(spinner_style.clone());
+                pb.set_message("Analyzing senders...");
+            }
+
+            live.extend(update.senders.into_iter().map(|s| (s.email.clone(), s)));
+            render_top_senders(&lines, &live);
+        }
+        (messages_scanned, partial_of)
+    };
+    let (senders, (messages_scanned, partial_of)) = tokio::join!(scan, render);
+    let senders = senders?;
+
+    for line in &lines {
+        line.finish_and_clear();
+    }
+    session.logout().await?;
+    pb.finish_and_clear();
+
+    let cache = storage::scan_cache::CachedScan {
+        scanned_at: Utc::now(),
+        messages_scanned,
+        grouping,
+        mailbox: imap_config.mailbox.clone(),
+        partial_of,
+        senders,
+    };
+    if let Err(e) = storage::scan_cache::save_scan(email, &cache) {
+        tracing::warn!(error = %e, "failed to cache scan results");
+    }
+
+    Ok(cache)
+}
+
+/// Show the senders with the most messages so far, one per line
+fn render_top_senders(lines: &[indicatif::ProgressBar], live: &HashMap<String, SenderInfo>) {
+    let mut top: Vec<&SenderInfo> = live.values().collect();
+    top.sort_by(|a, b| {
+        b.message_count
+            .cmp(&a.message_count)
+            .then(a.email.cmp(&b.email))
+    });
+
+    for (i, line) in lines.iter().enumerate() {
+        match top.get(i) {
+            Some(sender) => line.set_message(format!(
+                "{:>5}  {}",
+                sender.message_count,
+                style(&sender.email).dim()
+            )),
+            None => line.set_message(""),
+        }
+    }
+}
+
+/// Ask which mailbox to scan and clean
+///
+/// Lists the account's mailboxes and returns `imap_config` with the choice
+/// applied. If listing fails, the configured mailbox is kept.
+async fn prompt_mailbox(
+    imap_config: &imap::connection::ImapConfig,
+    email: &str,
+    access_token: &str,
+) -> Result<imap::connection::ImapConfig> {
+    let listed = async {
+        let mut session =
+            imap::connection::connect_and_auth(imap_config, email, access_token).await?;
+        let mailboxes = imap::folders::list_mailboxes(&mut session).await?;
+        session.logout().await?;
+        anyhow::Ok(mailboxes)
+    }
+    .await;
+
+    let mailboxes = match listed {
+        Ok(mailboxes) if mailboxes.len() > 1 => mailboxes,
+        Ok(_) => return Ok(imap_config.clone()),
+        Err(e) => {
+            tracing::warn!(
+                mailbox = %imap_config.mailbox,
+                error = %e,
+                "failed to list mailboxes, using the configured one"
+            );
+            return Ok(imap_config.clone());
+        }
+    };
+
+    let starting_cursor = mailboxes
+        .iter()
+        .position(|m| *m == imap_config.mailbox)
+        .unwrap_or(0);
+    let mailbox = Select::new("Mailbox to clean:", mailboxes)
+        .with_starting_cursor(starting_cursor)
+        .with_help_message("Pick a folder or label to clean instead of the Inbox")
+        .prompt()?;
+
+    Ok(imap::connection::ImapConfig {
+        mailbox,
+        ..imap_config.clone()
+    })
+}
+
+/// Offer to protect everyone the user has sent mail to
+///
+/// Returns the addresses found in the Sent folder, to be added to the
+/// session's allowlist; empty if declined or if the folder can't be read.
+async fn prompt_protect_contacts(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &str,
+) -> Result<Vec<String>> {
+    let protect = Confirm::new("Protect contacts I've emailed?")
+        .with_default(false)
+        .with_help_message(
+            "Senders you have written to (from Sent Mail) are never offered for cleanup",
+        )
+        .prompt()?;
+    if !protect {
+        return Ok(Vec::new());
+    }
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    pb.set_message(format!("Reading {}...", imap_config.sent_folder));
+    let contacts =
+        workflow::sent_correspondents(imap_config, email, access_token, &config.fetch_options())
+            .await;
+    pb.finish_and_clear();
+
+    match contacts {
+        Ok(contacts) => {
+            println!(
+                "  {} {} contacts you've emailed are protected",
+                style("ℹ").blue(),
+                contacts.len()
+            );
+            Ok(contacts)
+        }
+        Err(e) => {
+            info!(error = %format_args!("{:#}", e), "failed to read sent mail");
+            println!(
+                "  {} Couldn't read your sent mail, contacts are not protected: {:#}",
+                style("!").yellow(),
+                e
+            );
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Ask whether to list senders per address or per domain
+fn prompt_grouping() -> Result<SenderGrouping> {
+    let choice = Select::new(
+        "Group senders by:",
+        vec!["Address (news@retailer.com)", "Domain (retailer.com)"],
+    )
+    .with_help_message("Domain grouping cleans every subaddress of a domain at once")
+    .prompt()?;
+
+    Ok(if choice.starts_with("Domain") {
+        SenderGrouping::Domain
+    } else {
+        SenderGrouping::Address
+    })
+}
+
+/// Ask how senders should be ordered in the selection list
+fn prompt_sort_order() -> Result<SenderOrder> {
+    let choice = Select::new(
+        "Sort senders by:",
+        vec![
+            "Highest score first",
+            "Most recent first",
+            "Most messages first",
+        ],
+    )
+    .with_help_message("Recency helps spot senders that stopped mailing")
+    .prompt()?;
+
+    Ok(if choice.starts_with("Most recent") {
+        SenderOrder::Recency
+    } else if choice.starts_with("Most messages") {
+        SenderOrder::Volume
+    } else {
+        SenderOrder::Score
+    })
+}
+
+/// Senders offered by the volume quick-clean mode unless the user picks another count
+const DEFAULT_TOP_BY_VOLUME: usize = 20;
+
+/// Pick among the senders with the most messages, whatever their score
+///
+/// Allowlisted senders are never offered. Nothing is pre-selected, since
+/// personal contacts can rank high.
+fn select_by_volume(senders: &[SenderInfo], allowlist: &[String]) -> Result<Vec<SenderInfo>> {
+    let count = CustomType::<usize>::new("How many of the biggest senders should be listed?")
+        .with_default(DEFAULT_TOP_BY_VOLUME)
+        .with_error_message("Please enter a whole number")
+        .prompt()?;
+
+    let top = planner::top_by_volume(senders, allowlist, count);
+    if top.is_empty() {
+        println!("  {} All senders are allowlisted", style("ℹ").blue());
+        return Ok(vec![]);
+    }
+
+    println!();
+    println!(
+        "  {} Scores are ignored here: personal contacts may appear in this list",
+        style("!").yellow()
+    );
+    println!(
+        "  {} Selected senders' messages are moved to trash; nobody is unsubscribed or reported as spam",
+        style("ℹ").blue()
+    );
+    println!();
+
+    let chosen = MultiSelect::new("Select senders to delete:", sender_choices(&top))
+        .with_page_size(SENDER_PAGE_SIZE)
+        .with_help_message("Space to select, → to select all, Enter to confirm")
+        .prompt()?;
+
+    Ok(resolve_choices(&top, chosen))
+}
+
+/// Let the user pick senders whose messages are almost never opened
+fn select_never_opened(senders: &[SenderInfo], allowlist: &[String]) -> Result<Vec<SenderInfo>> {
+    let unread = planner::never_opened(senders, allowlist, planner::DEFAULT_MIN_UNREAD_RATIO);
+    if unread.is_empty() {
+        println!(
+            "  {} No sender with at least {} messages is left {:.0}% unread",
+            style("ℹ").blue(),
+            planner::MIN_MESSAGES_FOR_UNREAD,
+            planner::DEFAULT_MIN_UNREAD_RATIO * 100.0
+        );
+        return Ok(vec![]);
+    }
+
+    println!();
+    println!(
+        "  {} You never open these: read state is as of when each message was scanned",
+        style("ℹ").blue()
+    );
+    println!(
+        "  {} Selected senders' messages are moved to trash; nobody is unsubscribed or reported as spam",
+        style("ℹ").blue()
+    );
+    println!();
+
+    let chosen = MultiSelect::new("Select senders to delete:", sender_choices(&unread))
+        .with_page_size(SENDER_PAGE_SIZE)
+        .with_help_message("Space to select, → to select all, Enter to confirm")
+        .prompt()?;
+
+    Ok(resolve_choices(&unread, chosen))
+}
+
+/// Offer to reuse a recent cached scan for this account
+///
+/// Only scans of the same mailbox made with the same grouping are offered.
+fn prompt_cached_scan(
+    email: &str,
+    mailbox: &str,
+    grouping: SenderGrouping,
+) -> Result<Option<storage::scan_cache::CachedScan>> {
+    let ttl = storage::scan_cache::ttl_from_env();
+    let Some(scan) = storage::scan_cache::load_scan(email, ttl)? else {
+        return Ok(None);
+    };
+
+    if scan.grouping != grouping || scan.mailbox != mailbox {
+        return Ok(None);
+    }
+
+    let minutes = scan.age(Utc::now()).num_minutes();
+    let messages = match scan.partial_of {
+        Some(mailbox_messages) => format!(
+            "partial, {} of ~{} messages",
+            scan.messages_scanned, mailbox_messages
+        ),
+        None => format!("{} messages", scan.messages_scanned),
+    };
+    let use_cached = Confirm::new(&format!(
+        "Use cached scan from {} minute{} ago ({})?",
+        minutes,
+        if minutes == 1 { "" } else { "s" },
+        messages
+    ))
+    .with_default(true)
+    .prompt()?;
+
+    Ok(use_cached.then_some(scan))
+}
+
+/// Print the inbox overview and unsubscribe availability after a scan
+///
+/// `total_messages` is the number of messages scanned; `partial_of` is the
+/// approximate mailbox size when the time budget stopped the scan early.
+fn display_results(
+    senders: &[SenderInfo],
+    total_messages: usize,
+    partial_of: Option<usize>,
+    min_display_score: f32,
+) {
+    let mut summary = InboxSummary::from_senders(total_messages, senders, min_display_score);
+    if let Some(mailbox_messages) = partial_of {
+        summary = summary.partial(mailbox_messages);
+    }
+
+    println!();
+    println!("{}", style("Scan Results").bold().underlined());
+    println!();
+    match summary.partial_of {
+        Some(mailbox_messages) => println!(
+            "  {}",
+            style(format!(
+                "Partial scan: scanned {} of ~{} messages (time budget reached)",
+                summary.total_messages, mailbox_messages
+            ))
+            .yellow()
+        ),
+        None => println!("  {} messages in the mailbox", summary.total_messages),
+    }
+    println!("  {} unique senders found", summary.senders);
+    println!(
+        "  Newsletters account for ~{}% of your inbox volume ({} senders)",
+        summary.newsletter_percent(),
+        summary.newsletter_senders
+    );
+
+    let with_unsub = senders
+        .iter()
+        .filter(|s| s.unsubscribe_method.is_available())
+        .count();
+    let with_one_click = senders
+        .iter()
+        .filter(|s| s.unsubscribe_method.is_one_click())
+        .count();
+
+    println!("  {} with unsubscribe option", with_unsub);
+    println!("  {} with one-click unsubscribe", with_one_click);
+
+    let never_opened = planner::never_opened(senders, &[], planner::DEFAULT_MIN_UNREAD_RATIO).len();
+    if never_opened > 0 {
+        println!("  {} senders you never open", never_opened);
+    }
+    let dropped = senders.iter().filter(|s| s.unsubscribe_dropped).count();
+    if dropped > 0 {
+        println!(
+            "  {} senders whose recent messages no longer offer unsubscribe (you may already be unsubscribed)",
+            dropped
+        );
+    }
+    println!();
+}
+
+/// Let the user pick senders that should never be shown or touched again
+fn add_to_allowlist(email: &str, senders: &[SenderInfo]) -> Result<()> {
+    let allowlist = storage::json_store::load_allowlist(email)?;
+    let candidates: Vec<&SenderInfo> = senders
+        .iter()
+        .filter(|s| !analysis::is_allowlisted(&s.email, &allowlist))
+        .collect();
+
+    if candidates.is_empty() {
+        println!(
+            "  {} All senders are already allowlisted",
+            style("ℹ").blue()
+        );
+        return Ok(());
+    }
+
+    let options: Vec<String> = candidates
+        .iter()
+        .map(|s| format!("{} ({} msgs)", s.email, s.message_count))
+        .collect();
+
+    let chosen = MultiSelect::new("Select senders to allowlist:", options)
+        .with_page_size(SENDER_PAGE_SIZE)
+        .with_help_message("Type to filter, Space to select, Enter to confirm")
+        .raw_prompt()?;
+
+    if chosen.is_empty() {
+        return Ok(());
+    }
+
+    let scope = Select::new(
+        "Allowlist by:",
+        vec!["Exact address", "Entire domain (@domain.com)"],
+    )
+    .prompt()?;
+
+    for option in chosen {
+        let sender = candidates[option.index];
+        let entry = match (scope, sender.email.rsplit_once('@')) {
+            ("Entire domain (@domain.com)", Some((_, domain))) => format!("@{}", domain),
+            _ => sender.email.clone(),
+        };
+
+        storage::json_store::add_allowlist_entry(email, &entry)?;
+        info!(entry = %entry, "added to allowlist");
+        println!("  {} Allowlisted {}", style("✓").green(), entry);
+    }
+
+    Ok(())
+}
+
+/// Ask for an optional age cutoff in days
+fn prompt_older_than() -> Result<Option<Duration>> {
+    let answer = CustomType::<u32>::new("Only clean messages older than (days):")
+        .with_help_message("Leave empty to clean all messages from the selected senders")
+        .prompt_skippable()?;
+
+    Ok(answer
+        .filter(|days| *days > 0)
+        .map(|days| Duration::days(days.into())))
+}
+
+/// Ask how many of a sender's most recent messages to keep
+fn prompt_keep_latest() -> Result<usize> {
+    Ok(
+        CustomType::<usize>::new("Number of most recent messages to keep:")
+            .with_default(3)
+            .with_help_message("Older messages are deleted; messages without a date are kept")
+            .prompt()?,
+    )
+}
+
+/// Let the user pre-select senders in bulk before the checkbox list
+///
+/// Returns the indices into `senders` to check by default.
+fn prompt_bulk_selection(senders: &[SenderInfo]) -> Result<Vec<usize>> {
+    const DONE: &str = "Continue to sender list";
+    const ONE_CLICK: &str = "Select all with one-click unsubscribe";
+    const SELECT_REGEX: &str = "Select all matching regex";
+    const DESELECT_REGEX: &str = "Deselect all matching regex";
+
+    // Blocklisted senders start out selected
+    let mut checked: Vec<bool> = senders.iter().map(|s| s.blocklisted).collect();
+
+    loop {
+        let count = checked.iter().filter(|c| **c).count();
+        let choice = Select::new(
+            &format!("Bulk selection ({} of {} selected):", count, senders.len()),
+            vec![DONE, ONE_CLICK, SELECT_REGEX, DESELECT_REGEX],
+        )
+        .prompt()?;
+
+        match choice {
+            ONE_CLICK => {
+                for (check, sender) in checked.iter_mut().zip(senders) {
+                    *check |= sender.unsubscribe_method.is_one_click();
+                }
+            }
+            SELECT_REGEX | DESELECT_REGEX => {
+                let regex = prompt_regex()?;
+                let select = choice == SELECT_REGEX;
+                for (check, sender) in checked.iter_mut().zip(senders) {
+                    if sender_matches(sender, &regex) {
+                        *check = select;
+                    }
+                }
+            }
+            _ => break,
+        }
+    }
+
+    Ok(checked
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c)
+        .map(|(i, _)| i)
+        .collect())
+}
+
+/// Ask for an email address, re-prompting until it is valid
+///
+/// Warns (without blocking) when the address is not on a Gmail domain.
+pub(crate) fn prompt_email(message: &str) -> Result<String> {
+    let input = Text::new(message)
+        .with_help_message("Enter your Gmail email address")
+        .with_validator(|input: &str| {
+            Ok(match EmailAddress::parse(input) {
+                Ok(_) => Validation::Valid,
+                Err(e) => Validation::Invalid(e.to_string().into()),
+            })
+        })
+        .prompt()?;
+
+    let email = EmailAddress::parse(&input)?;
+    if !email.is_gmail() {
+        println!(
+            "{}",
+            style(format!(
+                "{} is not a Gmail address; this only works for Google accounts",
+                email
+            ))
+            .yellow()
+        );
+    }
+
+    Ok(email.to_string())
+}
+
+/// Ask for a regex, re-prompting until it compiles
+fn prompt_regex() -> Result<Regex> {
+    let pattern = Text::new("Regex:")
+        .with_help_message("Matched against sender email and display name, e.g. @retailer\\.com$")
+        .with_validator(|input: &str| {
+            Ok(match Regex::new(input) {
+                Ok(_) => Validation::Valid,
+                Err(e) => Validation::Invalid(format!("Invalid regex: {}", e).into()),
+            })
+        })
+        .prompt()?;
+
+    Ok(Regex::new(&pattern)?)
+}
+
+/// Whether the regex matches the sender's email or display name
+fn sender_matches(sender: &SenderInfo, regex: &Regex) -> bool {
+    regex.is_match(&sender.email)
+        || sender
+            .display_name
+            .as_deref()
+            .is_some_and(|name| regex.is_match(name))
+}
+
+fn select_senders(
+    senders: &[SenderInfo],
+    allowlist: &[String],
+    min_display_score: f32,
+) -> Result<Vec<SenderInfo>> {
+    // Only show likely newsletters; allowlisted senders are never shown
+    let filtered = planner::selection_candidates(senders, allowlist, min_display_score);
+
+    if filtered.is_empty() {
+        println!(
+            "  {} No newsletters or promotional emails detected",
+            style("ℹ").blue()
+        );
+        println!("  All senders appear to be personal or low-volume contacts");
+        return Ok(vec![]);
+    }
+
+    let mut sorted = filtered;
+    planner::sort_senders(&mut sorted, prompt_sort_order()?);
+
+    let choices = sender_choices(&sorted);
+
+    let preselected = prompt_bulk_selection(&sorted)?;
+
+    // Show senders in chunks so very long lists stay responsive
+    let mut selected = Vec::new();
+    let mut offset = 0;
+    for chunk in choices.chunks(MAX_SENDER_OPTIONS) {
+        if offset > 0 {
+            let more = Confirm::new(&format!("Show {} more senders?", sorted.len() - offset))
+                .with_default(false)
+                .prompt()?;
+            if !more {
+                break;
+            }
+        }
+
+        let defaults: Vec<usize> = preselected
+            .iter()
+            .filter(|&&i| i >= offset && i < offset + chunk.len())
+            .map(|i| i - offset)
+            .collect();
+
+        let chosen = MultiSelect::new("Select senders to clean:", chunk.to_vec())
+            .with_default(&defaults)
+            .with_page_size(SENDER_PAGE_SIZE)
+            .with_help_message("Type to filter, Space to select, Enter to confirm")
+            .prompt()?;
+
+        selected.extend(resolve_choices(&sorted, chosen));
+        offset += chunk.len();
+    }
+
+    Ok(selected)
+}
+
+/// A sender option that remembers its position in the sorted list
+///
+/// Selections resolve through `index`, never by matching the label, so
+/// senders with identical or prefix-sharing names can't be confused.
+#[derive(Debug, Clone)]
+struct SenderChoice {
+    index: usize,
+    label: String,
+}
+
+impl std::fmt::Display for SenderChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// Senders picked in a selection built by [`sender_choices`] from `senders`
+fn resolve_choices(senders: &[SenderInfo], chosen: Vec<SenderChoice>) -> Vec<SenderInfo> {
+    chosen
+        .into_iter()
+        .map(|choice| senders[choice.index].clone())
+        .collect()
+}
+
+/// Build one selection option per sender, in order
+fn sender_choices(senders: &[SenderInfo]) -> Vec<SenderChoice> {
+    senders
+        .iter()
+        .enumerate()
+        .map(|(index, s)| {
+            let name = s.display_name.as_ref().unwrap_or(&s.email);
+            let method = if s.unsubscribe_method.is_one_click() {
+                "✓ One-Click"
+            } else if s.unsubscribe_method.is_available() {
+                "⚠ Manual"
+            } else {
+                "✗ No unsub"
+            };
+            let last_seen = s
+                .last_message_date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let unread = if s.unread_count > 0 {
+                format!(", {} unread", s.unread_count)
+            } else {
+                String::new()
+            };
+            let dropped = if s.unsubscribe_dropped {
+                " (may already be unsubscribed)"
+            } else {
+                ""
+            };
+            SenderChoice {
+                index,
+                label: format!(
+                    "{} ({} msgs{}, last {}) {}{} [score: {:.2}]",
+                    name, s.message_count, unread, last_seen, method, dropped, s.heuristic_score
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Run the cleanup prompts for each selected sender
+///
+/// In dry-run mode no IMAP connection is opened and no HTTP request is sent;
+/// every action is only described. Returns one result per sender.
+async fn execute_cleanup(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &mut String,
+    senders: &[SenderInfo],
+    dry_run: bool,
+) -> Result<Vec<CleanupResult>> {
+    info!(senders = senders.len(), dry_run, "starting cleanup");
+    let progress = (!dry_run)
+        .then(|| CleanupProgress::new(Utc::now(), &imap_config.mailbox, senders.to_vec()));
+
+    run_cleanup(imap_config, config, email, access_token, senders, progress).await
+}
+
+/// Offer to finish a cleanup run that was interrupted, e.g. by a dropped
+/// IMAP connection
+///
+/// Resuming opens a new session and continues with the first sender that
+/// has no result yet. Declining forgets the interrupted run.
+async fn resume_cleanup(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &mut String,
+) -> Result<()> {
+    let Some(progress) = storage::json_store::load_cleanup_progress(email)? else {
+        return Ok(());
+    };
+
+    if progress.is_complete() {
+        return storage::json_store::clear_cleanup_progress(email);
+    }
+
+    let resume = Confirm::new(&format!(
+        "Resume previous cleanup ({} of {} senders done)?",
+        progress.resume_point(),
+        progress.senders.len()
+    ))
+    .with_default(true)
+    .with_help_message(&format!(
+        "Started {} in {}",
+        progress.started_at.format("%Y-%m-%d %H:%M UTC"),
+        progress.mailbox
+    ))
+    .prompt()?;
+
+    if !resume {
+        return storage::json_store::clear_cleanup_progress(email);
+    }
+
+    let imap_config = imap::connection::ImapConfig {
+        mailbox: progress.mailbox.clone(),
+        ..imap_config.clone()
+    };
+    let pending = progress.pending().to_vec();
+    let mut results = progress.results[..progress.resume_point()].to_vec();
+    info!(
+        pending = pending.len(),
+        senders = progress.senders.len(),
+        "resuming cleanup"
+    );
+
+    let resumed = run_cleanup(
+        &imap_config,
+        config,
+        email,
+        access_token,
+        &pending,
+        Some(progress),
+    )
+    .await?;
+    if let Err(e) = storage::json_store::record_esp_results(&resumed) {
+        tracing::warn!(error = %e, "failed to update ESP stats");
+    }
+    results.extend(resumed);
+
+    println!();
+    println!("{}", style("Done!").green().bold());
+    println!("  {}", CleanupSummary::from_results(&results));
+    println!();
+
+    Ok(())
+}
+
+/// Ask for the address to be typed before cleaning a protected account
+///
+/// Returns `true` straight away for accounts that are not protected.
+fn confirm_protected_account(email: &str) -> Result<bool> {
+    let Some(account) = storage::json_store::load_account(email)? else {
+        return Ok(true);
+    };
+    if !account.protected {
+        return Ok(true);
+    }
+
+    let typed = Text::new(&format!(
+        "{} is protected. Type the address to confirm cleanup:",
+        email
+    ))
+    .with_help_message("Leave empty to cancel")
+    .prompt()?;
+
+    if account.confirms(&typed) {
+        return Ok(true);
+    }
+
+    info!(email, "cleanup of protected account not confirmed");
+    println!(
+        "  {} Address did not match, nothing was changed",
+        style("!").yellow()
+    );
+    Ok(false)
+}
+
+/// Connect for a cleanup run, refreshing the access token if it expired
+///
+/// The refreshed token replaces `access_token` so the rest of the session
+/// uses it too.
+async fn connect_for_cleanup(
+    imap_config: &imap::connection::ImapConfig,
+    email: &str,
+    access_token: &mut String,
+) -> Result<(imap::connection::ImapSession, imap::connection::ImapConfig)> {
+    workflow::with_token_refresh(
+        access_token,
+        |token| async move { imap::connection::connect_and_resolve(imap_config, email, &token).await },
+        || workflow::refreshed_access_token(email),
+    )
+    .await
+}
+
+/// Clean `senders` in order, journaling each result into `progress`
+///
+/// The journal is saved after every sender and cleared once the run ends
+/// without an error, so an interrupted run can be resumed. Ctrl-C lets the
+/// current sender's move finish, then stops and returns the results so far;
+/// the journal is kept so the rest can be resumed later.
+#[tracing::instrument(name = "cleanup", skip_all, fields(email = %email, senders = senders.len()))]
+async fn run_cleanup(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &mut String,
+    senders: &[SenderInfo],
+    mut progress: Option<CleanupProgress>,
+) -> Result<Vec<CleanupResult>> {
+    let dry_run = progress.is_none();
+    if !dry_run && !confirm_protected_account(email)? {
+        return Ok(Vec::new());
+    }
+    let (mut session, imap_config) = if dry_run {
+        (None, imap_config.clone())
+    } else {
+        let (session, resolved) = connect_for_cleanup(imap_config, email, access_token).await?;
+        (Some(session), resolved)
+    };
+    let imap_config = &imap_config;
+    let journal = cleanup_journal(config, email, dry_run).await;
+    let mut unsubscribes = workflow::UnsubscribeTracker::with_options(config.unsubscribe_options());
+    let mut cap = planner::DeletionCap::new(config.max_delete);
+    let mut results = Vec::with_capacity(senders.len());
+
+    if !dry_run {
+        batch_unsubscribe(&mut unsubscribes, senders).await?;
+    }
+
+    let two_phase_help = if imap_config.is_gmail() {
+        "Messages are labelled for removal, then moved out of the Inbox only after a last confirmation"
+    } else {
+        "Messages are copied to Trash and flagged, then removed from the Inbox only after a last confirmation"
+    };
+    let two_phase = !dry_run
+        && Confirm::new("Review deletions before they are final?")
+            .with_default(false)
+            .with_help_message(two_phase_help)
+            .prompt()?;
+
+    let cancellation = workflow::CleanupCancellation::install();
+
+    for (idx, sender) in senders.iter().enumerate() {
+        if cancellation.is_cancelled() {
+            info!(cleaned = idx, senders = senders.len(), "cleanup cancelled");
+            println!();
+            println!(
+                "  {} Cancelled: cleaned {} of {} senders, {} messages removed",
+                style("!").yellow(),
+                idx,
+                senders.len(),
+                cap.deleted()
+            );
+            break;
+        }
+
+        println!();
+        println!(
+            "{} {} ({} messages)",
+            style(format!("[{}/{}]", idx + 1, senders.len())).dim(),
+            style(&sender.email).cyan().bold(),
+            sender.message_count
+        );
+        if sender.unsubscribe_dropped {
+            println!(
+                "  {} Recent messages no longer offer unsubscribe — you may already be unsubscribed",
+                style("ℹ").blue()
+            );
+        }
+
+        if !confirm_within_cap(&mut cap, sender.message_uids.len())? {
+            info!(deleted = cap.deleted(), "stopping cleanup at deletion cap");
+            break;
+        }
+
+        let result = cleanup_sender(
+            imap_config,
+            session.as_mut(),
+            &journal,
+            &mut unsubscribes,
+            sender,
+            two_phase,
+            &mut cap,
+        )
+        .await?;
+        cap.record(result.messages_deleted);
+        if let Some(progress) = progress.as_mut() {
+            progress.complete(result.clone());
+            if let Err(e) = storage::json_store::save_cleanup_progress(email, progress) {
+                tracing::warn!(error = %e, "failed to save cleanup progress");
+            }
+        }
+        results.push(result);
+    }
+
+    let cancelled = cancellation.is_cancelled() && results.len() < senders.len();
+    drop(cancellation);
+
+    if let Some(mut session) = session {
+        if two_phase {
+            keep_alive(&mut session, imap_config, &journal).await?;
+            commit_staged(&mut session, imap_config, &journal).await?;
+        }
+        session.logout().await?;
+    }
+
+    if progress.is_some() && !cancelled {
+        if let Err(e) = storage::json_store::clear_cleanup_progress(email) {
+            tracing::warn!(error = %e, "failed to clear cleanup progress");
+        }
+    }
+
+    Ok(results)
+}
+
+/// Journal for a cleanup run of `email`
+///
+/// With `gmail_filters` set, senders moved to spam also get a Gmail filter
+/// for their future mail. If the account's token lacks the filters scope,
+/// the run goes ahead without filters.
+async fn cleanup_journal(
+    config: &storage::config::Config,
+    email: &str,
+    dry_run: bool,
+) -> workflow::CleanupJournal {
+    let journal = workflow::CleanupJournal::new(email);
+    if dry_run || !config.gmail_filters {
+        return journal;
+    }
+
+    let filters = match workflow::access_token_with_scope(email, AccessScope::FullWithFilters)
+        .await
+        .and_then(network::gmail_filters::FilterManager::new)
+    {
+        Ok(filters) => filters,
+        Err(e) => {
+            info!(error = %format_args!("{:#}", e), "gmail filters unavailable");
+            println!(
+                "  {} Gmail filters unavailable, blocked senders won't be filtered: {}",
+                style("!").yellow(),
+                e
+            );
+            return journal;
+        }
+    };
+    journal.with_filters(filters)
+}
+
+/// Reconnect if the server dropped the session while the user was at a prompt
+async fn keep_alive(
+    session: &mut imap::connection::ImapSession,
+    imap_config: &imap::connection::ImapConfig,
+    journal: &workflow::CleanupJournal,
+) -> Result<()> {
+    let account = journal.account();
+    let reconnected = imap::connection::reconnect_if_needed(session, imap_config, account, || {
+        workflow::access_token_with_scope(account, AccessScope::Full)
+    })
+    .await?;
+
+    if reconnected {
+        println!(
+            "  {} The connection had timed out; reconnected",
+            style("ℹ").blue()
+        );
+    }
+
+    Ok(())
+}
+
+/// Phase two of a two-phase cleanup: expunge staged messages after confirming
+///
+/// Only the messages this run staged are committed. Declining leaves them in
+/// the source mailbox flagged as deleted, with copies in Trash or Spam.
+async fn commit_staged(
+    session: &mut imap::connection::ImapSession,
+    imap_config: &imap::connection::ImapConfig,
+    journal: &workflow::CleanupJournal,
+) -> Result<()> {
+    let staged = journal.staged_count();
+    if staged == 0 {
+        return Ok(());
+    }
+
+    if imap_config.flag_only {
+        info!(staged, "flag-only mode, staged messages left in place");
+        println!(
+            "  {} Flag-only mode: {} messages stay in {} {}",
+            style("!").yellow(),
+            staged,
+            imap_config.mailbox,
+            staged_state(imap_config)
+        );
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "  {} {} messages staged for removal from {}",
+        style("ℹ").blue(),
+        staged,
+        imap_config.mailbox
+    );
+    // On Gmail nothing reaches Trash before the commit
+    if !imap_config.is_gmail() {
+        match imap::actions::folder_message_count(session, &imap_config.trash_folder).await {
+            Ok(count) => println!(
+                "  {} {} now holds {} messages",
+                style("ℹ").blue(),
+                imap_config.trash_folder,
+                count
+            ),
+            Err(e) => info!(error = %e, "failed to count messages in trash"),
+        }
+    }
+
+    let commit = Confirm::new(&format!(
+        "Remove the staged messages from {} now?",
+        imap_config.mailbox
+    ))
+    .with_default(true)
+    .prompt()?;
+
+    if !commit {
+        info!(staged, "staged deletion not committed");
+        println!(
+            "  {} Not committed: the messages stay in {} {}",
+            style("!").yellow(),
+            imap_config.mailbox,
+            staged_state(imap_config)
+        );
+        return Ok(());
+    }
+
+    let committed = journal.commit_staged(session, imap_config).await?;
+    info!(committed, "staged deletion committed");
+    println!(
+        "  {} Removed {} messages from {}",
+        style("✓").green(),
+        committed,
+        imap_config.mailbox
+    );
+
+    Ok(())
+}
+
+/// How staged messages are left in the mailbox, for messages to the user
+fn staged_state(imap_config: &imap::connection::ImapConfig) -> String {
+    if imap_config.is_gmail() {
+        format!(
+            "labelled \"{}\" or \"{}\", for you to review and delete in Gmail",
+            imap::actions::GMAIL_TO_TRASH_LABEL,
+            imap::actions::GMAIL_TO_SPAM_LABEL
+        )
+    } else {
+        "flagged as deleted until you expunge them in your mail client".to_string()
+    }
+}
+
+/// Check the run's deletion cap before removing `count` more messages
+///
+/// When the cap would be exceeded, ask for an explicit override; accepting
+/// lifts the cap for the rest of the run. Returns `false` to stop.
+fn confirm_within_cap(cap: &mut planner::DeletionCap, count: usize) -> Result<bool> {
+    if !cap.would_exceed(count) {
+        return Ok(true);
+    }
+
+    println!(
+        "  {} This would bring the run to {} deleted messages, over the limit of {}",
+        style("!").yellow(),
+        cap.deleted() + count,
+        cap.limit().unwrap_or_default()
+    );
+
+    let proceed = Confirm::new("Continue past the deletion limit?")
+        .with_default(false)
+        .with_help_message("Answer no to stop here; raise the limit with --max-delete")
+        .prompt()?;
+
+    if proceed {
+        info!(deleted = cap.deleted(), "deletion cap lifted by user");
+        cap.lift();
+    }
+
+    Ok(proceed)
+}
+
+/// Ask whether to apply the planner's suggestions or review each sender
+fn prompt_plan_mode() -> Result<bool> {
+    const REVIEW: &str = "Review each sender";
+    const PLAN: &str = "Apply the suggested plan";
+
+    let choice = Select::new("How do you want to clean?", vec![REVIEW, PLAN])
+        .with_help_message(
+            "The plan unsubscribes where one-click is available, otherwise moves to spam",
+        )
+        .prompt()?;
+
+    Ok(choice == PLAN)
+}
+
+/// Show the planned action for every sender, confirm once, then run them
+///
+/// Returns `None` when the user declines the plan, so the caller can fall
+/// back to reviewing each sender.
+#[tracing::instrument(name = "cleanup", skip_all, fields(email = %email, senders = actions.len()))]
+async fn execute_plan(
+    imap_config: &imap::connection::ImapConfig,
+    config: &storage::config::Config,
+    email: &str,
+    access_token: &mut String,
+    actions: Vec<CleanupAction>,
+    dry_run: bool,
+) -> Result<Option<Vec<CleanupResult>>> {
+    let width = actions
+        .iter()
+        .map(|a| a.sender.email.len())
+        .max()
+        .unwrap_or(0);
+
+    println!();
+    println!("{}", style("Planned actions").bold().underlined());
+    println!();
+    for action in &actions {
+        println!(
+            "  {:width$}  {:>5} messages  {}",
+            action.sender.email,
+            action.sender.message_count,
+            style(&action.action_type).cyan(),
+            width = width
+        );
+    }
+    println!();
+
+    if dry_run {
+        info!(senders = actions.len(), "dry run, plan not applied");
+        println!("  {} Dry run: nothing was changed", style("~").dim());
+        return Ok(Some(Vec::new()));
+    }
+
+    let confirm = Confirm::new(&format!("Apply this plan to {} senders?", actions.len()))
+        .with_default(false)
+        .with_help_message("Answer no to review each sender instead")
+        .prompt()?;
+
+    if !confirm {
+        return Ok(None);
+    }
+
+    if !confirm_protected_account(email)? {
+        return Ok(Some(Vec::new()));
+    }
+
+    let total = actions.iter().map(|a| a.sender.message_uids.len()).sum();
+    let mut cap = planner::DeletionCap::new(config.max_delete);
+    if !confirm_within_cap(&mut cap, total)? {
+        info!(total, "plan not applied, deletion cap exceeded");
+        return Ok(Some(Vec::new()));
+    }
+
+    let (mut session, imap_config) = connect_for_cleanup(imap_config, email, access_token).await?;
+    let imap_config = &imap_config;
+    let journal = cleanup_journal(config, email, false).await;
+    let mut unsubscribes = workflow::UnsubscribeTracker::with_options(config.unsubscribe_options());
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message(format!("Applying plan to {} senders...", actions.len()));
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    let results = workflow::cleanup_senders_batched(
+        &mut session,
+        imap_config,
+        &journal,
+        &mut unsubscribes,
+        actions,
+        &mut cap,
+    )
+    .await;
+    pb.finish_and_clear();
+
+    for result in &results {
+        match &result.error {
+            Some(e) => println!("  {} {}: {}", style("✗").red(), result.sender_email, e),
+            None => println!(
+                "  {} {}: {} ({} messages{})",
+                style("✓").green(),
+                result.sender_email,
+                result.action,
+                result.messages_deleted,
+                match result.unsubscribe_success {
+                    Some(true) => ", unsubscribed",
+                    Some(false) => ", unsubscribe failed",
+                    None => "",
+                }
+            ),
+        }
+    }
+
+    session.logout().await?;
+
+    Ok(Some(results))
+}
+
+/// Concurrent one-click unsubscribe requests in batch mode
+const BATCH_UNSUBSCRIBE_CONCURRENCY: usize = 8;
+
+/// Warn before unsubscribing through a host unrelated to the sender
+///
+/// Returns true straight away if the sender's link looks legitimate.
+fn confirm_unsubscribe_host(sender: &SenderInfo) -> Result<bool> {
+    if !sender.suspicious_unsubscribe {
+        return Ok(true);
+    }
+
+    let host = workflow::unsubscribe_host(sender).unwrap_or_default();
+    let domain = sender
+        .email
+        .rsplit_once('@')
+        .map_or(sender.email.as_str(), |(_, d)| d);
+
+    Ok(Confirm::new(&format!(
+        "Unsubscribe link points to {}, not {} — proceed?",
+        host, domain
+    ))
+    .with_default(false)
+    .with_help_message("Mismatched unsubscribe hosts are a common spam and phishing tell")
+    .prompt()?)
+}
+
+/// Open a manual unsubscribe page and ask whether unsubscribing worked
+///
+/// Returns the reported outcome, or `None` in dry-run mode or when the
+/// browser could not be opened.
+fn open_manual_unsubscribe(url: &str, dry_run: bool) -> Result<Option<bool>> {
+    if dry_run {
+        info!(url = %url, "dry run, unsubscribe page not opened");
+        println!("  {} Would open {}", style("~").dim(), url);
+        return Ok(None);
+    }
+
+    info!(url = %url, "opening unsubscribe page");
+    if let Err(e) = workflow::open_unsubscribe_page(url, &SystemBrowser) {
+        info!(error = %format_args!("{:#}", e), "failed to open unsubscribe page");
+        println!("  {} {:#}", style("✗").red(), e);
+        println!("    Visit it manually: {}", url);
+        return Ok(None);
+    }
+    println!("  {} Opened {}", style("✓").green(), url);
+
+    let worked = Confirm::new("Did it work?")
+        .with_default(true)
+        .with_help_message("Answer once you've confirmed on the page")
+        .prompt()?;
+    if worked {
+        println!("  {} Unsubscribed", style("✓").green());
+    } else {
+        println!("  {} Unsubscribe not confirmed", style("✗").red());
+    }
+
+    Ok(Some(worked))
+}
+
+/// Fall back after a rejected one-click unsubscribe
+///
+/// Offers the sender's other unsubscribe page in a browser, if the header
+/// listed one. If there is none, or the user declines or it doesn't work,
+/// offers to move the sender's mail to spam. Returns whether to do so;
+/// declining that too leads to the usual delete prompt.
+async fn one_click_fallback(
+    imap_config: &imap::connection::ImapConfig,
+    session: Option<&mut imap::connection::ImapSession>,
+    sender: &SenderInfo,
+    result: &mut CleanupResult,
+) -> Result<bool> {
+    if let Some(url) = workflow::fallback_unsubscribe_url(sender) {
+        let open = Confirm::new("Open the sender's unsubscribe page instead?")
+            .with_default(true)
+            .with_help_message(url)
+            .prompt()?;
+
+        if open && open_manual_unsubscribe(url, session.is_none())? == Some(true) {
+            info!(
+                sender = %sender.email,
+                "unsubscribed via page after one-click failed"
+            );
+            result.unsubscribe_success = Some(true);
+            result.resolve(ResolutionStep::ManualLink);
+            return Ok(false);
+        }
+    }
+
+    confirm_with_preview(
+        "Block this sender instead (move to spam)?",
+        true,
+        session,
+        &imap_config.mailbox,
+        sender,
+    )
+    .await
+}
+
+/// Offer to unsubscribe from every selected one-click sender at once
+///
+/// Results are recorded in `unsubscribes`, so the per-sender prompts that
+/// follow skip the unsubscribe question for these senders.
+async fn batch_unsubscribe(
+    unsubscribes: &mut workflow::UnsubscribeTracker,
+    senders: &[SenderInfo],
+) -> Result<()> {
+    // Mismatched unsubscribe hosts are confirmed one by one instead
+    let one_click: Vec<SenderInfo> = senders
+        .iter()
+        .filter(|s| s.unsubscribe_method.is_one_click() && !s.suspicious_unsubscribe)
+        .cloned()
+        .collect();
+
+    if one_click.len() < 2 {
+        return Ok(());
+    }
+
+    println!();
+    let confirm = Confirm::new(&format!(
+        "Unsubscribe from all {} selected one-click senders now?",
+        one_click.len()
+    ))
+    .with_default(false)
+    .with_help_message("Requests run in parallel; you'll still choose what to do with the messages")
+    .prompt()?;
+
+    if !confirm {
+        return Ok(());
+    }
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message(format!("Unsubscribing from {} senders...", one_click.len()));
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    let results = unsubscribes
+        .unsubscribe_all(&one_click, BATCH_UNSUBSCRIBE_CONCURRENCY)
+        .await;
+    pb.finish_and_clear();
+
+    let width = results
+        .iter()
+        .map(|(email, _)| email.len())
+        .max()
+        .unwrap_or(0);
+    let mut succeeded = 0;
+
+    println!();
+    for (email, result) in &results {
+        match result {
+            Ok(true) => {
+                succeeded += 1;
+                println!("  {} {}", style("✓").green(), email);
+            }
+            Ok(false) => println!(
+                "  {} {:width$}  rejected",
+                style("✗").red(),
+                email,
+                width = width
+            ),
+            Err(e) => println!(
+                "  {} {:width$}  {}",
+                style("✗").red(),
+                email,
+                e,
+                width = width
+            ),
+        }
+    }
+    println!(
+        "\n  {} succeeded, {} failed",
+        style(succeeded).green().bold(),
+        style(results.len() - succeeded).red().bold()
+    );
+
+    Ok(())
+}
+
+/// Yes/no prompt that can also preview the sender's messages first
+///
+/// Previewing needs a session, so the option is hidden in dry-run mode.
+async fn confirm_with_preview(
+    message: &str,
+    default: bool,
+    session: Option<&mut imap::connection::ImapSession>,
+    mailbox: &str,
+    sender: &SenderInfo,
+) -> Result<bool> {
+    const YES: &str = "Yes";
+    const NO: &str = "No";
+
+    let choice = choose_with_preview(
+        message,
+        &[YES, NO],
+        if default { 0 } else { 1 },
+        session,
+        mailbox,
+        sender,
+    )
+    .await?;
+
+    Ok(choice == YES)
+}
+
+/// Select prompt with an extra "Preview messages" option
+///
+/// Previewing re-prompts afterwards; the option is hidden without a session.
+async fn choose_with_preview<'a>(
+    message: &str,
+    choices: &[&'a str],
+    starting_cursor: usize,
+    mut session: Option<&mut imap::connection::ImapSession>,
+    mailbox: &str,
+    sender: &SenderInfo,
+) -> Result<&'a str> {
+    const PREVIEW: &str = "Preview messages";
+
+    let mut options = choices.to_vec();
+    if session.is_some() {
+        options.push(PREVIEW);
+    }
+
+    loop {
+        let choice = Select::new(message, options.clone())
+            .with_starting_cursor(starting_cursor)
+            .prompt()?;
+
+        match (choice, session.as_deref_mut()) {
+            (PREVIEW, Some(session)) => preview_messages(session, mailbox, sender).await?,
+            (choice, _) => return Ok(choice),
+        }
+    }
+}
+
+/// Ask for a Gmail label name
+fn prompt_label() -> Result<String> {
+    let label = Text::new("Label name:")
+        .with_default("Newsletters/To Review")
+        .with_help_message("Use / for nested labels; Gmail creates the label if needed")
+        .with_validator(|input: &str| {
+            Ok(if input.trim().is_empty() {
+                Validation::Invalid("Label name cannot be empty".into())
+            } else {
+                Validation::Valid
+            })
+        })
+        .prompt()?;
+
+    Ok(label.trim().to_string())
+}
+
+/// Number of messages shown per preview page
+const PREVIEW_PAGE_SIZE: usize = 20;
+
+/// Print subject and date of every message from the sender, paginated
+async fn preview_messages(
+    session: &mut imap::connection::ImapSession,
+    mailbox: &str,
+    sender: &SenderInfo,
+) -> Result<()> {
+    let summaries = match session.select(mailbox).await {
+        Ok(_) => imap::fetch::fetch_subjects_for_uids(session, &sender.message_uids).await,
+        Err(e) => Err(e.into()),
+    };
+
+    let summaries = match summaries {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            info!(sender = %sender.email, error = %e, "failed to fetch preview");
+            println!("  {} Could not load messages: {}", style("✗").red(), e);
+            return Ok(());
+        }
+    };
+
+    let total = summaries.len();
+    for (page, chunk) in summaries.chunks(PREVIEW_PAGE_SIZE).enumerate() {
+        println!();
+        for summary in chunk {
+            let date = summary
+                .date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "unknown   ".to_string());
+            println!("  {}  {}", style(date).dim(), summary.subject);
+        }
+
+        let shown = (page * PREVIEW_PAGE_SIZE + chunk.len()).min(total);
+        if shown < total {
+            let more = Confirm::new(&format!("Show more? ({}/{} shown)", shown, total))
+                .with_default(true)
+                .prompt()?;
+            if !more {
+                break;
+            }
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Spinner shown while one sender's messages are moved
+///
+/// `deleted_so_far` counts the messages already removed earlier in the run.
+fn move_spinner(count: usize, destination: &str, deleted_so_far: usize) -> indicatif::ProgressBar {
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message(format!(
+        "Moving {} messages {} ({} removed so far)",
+        count, destination, deleted_so_far
+    ));
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    pb
+}
+
+/// Prompt for and perform the cleanup of a single sender
+///
+/// Only prompt errors are propagated; IMAP and HTTP failures are recorded in
+/// the returned result. `cap` holds the messages removed earlier in the run;
+/// archived messages found when deleting everywhere are checked against it.
+#[tracing::instrument(name = "clean_sender", skip_all, fields(sender = %sender.email))]
+async fn cleanup_sender(
+    imap_config: &imap::connection::ImapConfig,
+    mut session: Option<&mut imap::connection::ImapSession>,
+    journal: &workflow::CleanupJournal,
+    unsubscribes: &mut workflow::UnsubscribeTracker,
+    sender: &SenderInfo,
+    two_phase: bool,
+    cap: &mut planner::DeletionCap,
+) -> Result<CleanupResult> {
+    let dry_run = session.is_none();
+    let mut result = CleanupResult::success(sender.email.clone(), ActionType::DeleteOnly, 0, None);
+
+    let has_one_click = sender.unsubscribe_method.is_one_click();
+
+    let batch_result = if has_one_click {
+        unsubscribes.previous_result(&sender.one_click_urls())
+    } else {
+        None
+    };
+
+    let mut block = false;
+    if let Some(success) = batch_result {
+        result.action = ActionType::UnsubscribeAndDelete;
+        result.unsubscribe_success = Some(success);
+        result.unsubscribe_host = workflow::unsubscribe_host(sender);
+        result.unsubscribe_via = unsubscribes.method_used(&sender.one_click_urls());
+        if success {
+            result.resolve(ResolutionStep::OneClick);
+            println!("  {} Already unsubscribed", style("✓").green());
+        } else {
+            println!(
+                "  {} Unsubscribe already attempted and failed",
+                style("✗").red()
+            );
+            block = one_click_fallback(imap_config, session.as_deref_mut(), sender, &mut result)
+                .await?;
+        }
+    } else if has_one_click {
+        info!(sender = %sender.email, one_click = true, "unsubscribe options");
+        println!("  {} One-click unsubscribe available", style("✓").green());
+
+        let unsub = Confirm::new("Unsubscribe from this sender?")
+            .with_default(true)
+            .prompt()?
+            && confirm_unsubscribe_host(sender)?;
+
+        if unsub {
+            result.action = ActionType::UnsubscribeAndDelete;
+
+            if let UnsubscribeMethod::OneClick { url } = &sender.unsubscribe_method {
+                if dry_run {
+                    info!(url = %url, "dry run, one-click unsubscribe not sent");
+                    println!("  {} Would POST to {}", style("~").dim(), url);
+                } else {
+                    info!(url = %url, "attempting one-click unsubscribe");
+                    result.unsubscribe_host = workflow::unsubscribe_host(sender);
+                    match unsubscribes.unsubscribe(&sender.one_click_urls()).await {
+                        Ok(true) => {
+                            let via = unsubscribes.method_used(&sender.one_click_urls());
+                            info!(via = ?via, "one-click unsubscribe succeeded");
+                            println!("  {} Unsubscribed successfully", style("✓").green());
+                            result.unsubscribe_success = Some(true);
+                            result.unsubscribe_via = via;
+                            result.resolve(ResolutionStep::OneClick);
+                        }
+                        Ok(false) => {
+                            info!("one-click unsubscribe returned non-success status");
+                            println!("  {} Unsubscribe failed", style("✗").red());
+                            result.unsubscribe_success = Some(false);
                         }
                         Err(e) => {
-                            info!("One-click unsubscribe error: {}", e);
+                            info!(error = %e, "one-click unsubscribe failed");
                             println!("  {} Error: {}", style("✗").red(), e);
+                            result.unsubscribe_success = Some(false);
                         }
                     }
+
+                    if result.unsubscribe_success == Some(false) {
+                        block = one_click_fallback(
+                            imap_config,
+                            session.as_deref_mut(),
+                            sender,
+                            &mut result,
+                        )
+                        .await?;
+                    }
                 }
             }
-        } else {
-            info!("Sender {} has no one-click unsubscribe", sender.email);
-            println!("  {} No one-click unsubscribe", style("!").yellow());
+        }
+    } else {
+        info!(sender = %sender.email, one_click = false, "unsubscribe options");
+        println!("  {} No one-click unsubscribe", style("!").yellow());
 
-            let block = Confirm::new("Block this sender (move to spam)?")
-                .with_default(true)
-                .prompt()?;
+        block = match workflow::manual_unsubscribe_url(sender) {
+            Some(url) => {
+                const OPEN_PAGE: &str = "Open unsubscribe page in browser";
+                const BLOCK: &str = "Block this sender (move to spam)";
+                const NEITHER: &str = "Neither";
 
-            if block {
-                info!(
-                    "Moving {} messages to spam for {}",
-                    sender.message_uids.len(),
-                    sender.email
-                );
-                match imap::actions::move_to_spam(&mut session, &sender.message_uids).await {
-                    Ok(count) => {
-                        info!("Successfully moved {} messages to spam", count);
-                        println!("  {} Moved {} messages to spam", style("✓").green(), count);
-                        continue;
-                    }
-                    Err(e) => {
-                        info!("Failed to move to spam: {}", e);
-                        println!("  {} Error: {}", style("✗").red(), e);
+                let choice = choose_with_preview(
+                    "How do you want to stop this sender?",
+                    &[OPEN_PAGE, BLOCK, NEITHER],
+                    0,
+                    session.as_deref_mut(),
+                    &imap_config.mailbox,
+                    sender,
+                )
+                .await?;
+
+                if choice == OPEN_PAGE {
+                    result.action = ActionType::UnsubscribeAndDelete;
+                    result.unsubscribe_success = open_manual_unsubscribe(url, dry_run)?;
+                    if result.unsubscribe_success == Some(true) {
+                        result.resolve(ResolutionStep::ManualLink);
                     }
                 }
+                choice == BLOCK
+            }
+            None => {
+                confirm_with_preview(
+                    "Block this sender (move to spam)?",
+                    true,
+                    session.as_deref_mut(),
+                    &imap_config.mailbox,
+                    sender,
+                )
+                .await?
+            }
+        };
+    }
+
+    if block {
+        result.action = ActionType::SpamAndDelete;
+
+        let Some(session) = session.as_deref_mut() else {
+            info!(
+                sender = %sender.email,
+                count = sender.message_uids.len(),
+                "dry run, not moving to spam"
+            );
+            println!(
+                "  {} Would move {} messages to spam",
+                style("~").dim(),
+                sender.message_uids.len()
+            );
+            return Ok(result);
+        };
+
+        keep_alive(session, imap_config, journal).await?;
+        info!(
+            sender = %sender.email,
+            count = sender.message_uids.len(),
+            "moving to spam"
+        );
+        let pb = move_spinner(sender.message_uids.len(), "to spam", cap.deleted());
+        let moved = if two_phase {
+            journal
+                .stage_move_to_spam(session, imap_config, &sender.email, &sender.message_uids)
+                .await
+        } else {
+            journal
+                .move_to_spam(session, imap_config, &sender.email, &sender.message_uids)
+                .await
+        };
+        pb.finish_and_clear();
+        match moved {
+            Ok(count) => {
+                let verb = if two_phase { "Staged" } else { "Moved" };
+                info!(count, action = verb, "moved to spam");
+                println!(
+                    "  {} {} {} messages to spam",
+                    style("✓").green(),
+                    verb,
+                    count
+                );
+                result.messages_deleted = count;
+                result.resolve(ResolutionStep::Spam);
+                return Ok(result);
+            }
+            Err(e) => {
+                info!(error = %e, "failed to move to spam");
+                println!("  {} Error: {}", style("✗").red(), e);
+                result.error = Some(e.to_string());
             }
         }
+    }
 
-        let delete = Confirm::new(&format!(
-            "Delete all {} messages from this sender?",
+    const DELETE: &str = "Delete them";
+    const KEEP_LATEST: &str = "Keep latest N, delete older";
+    const LABEL: &str = "Label as…";
+    const KEEP: &str = "Keep them";
+
+    let choice = choose_with_preview(
+        &format!(
+            "What should happen to the {} messages from this sender?",
             sender.message_count
-        ))
-        .with_default(false)
-        .prompt()?;
+        ),
+        &[DELETE, KEEP_LATEST, LABEL, KEEP],
+        3,
+        session.as_deref_mut(),
+        &imap_config.mailbox,
+        sender,
+    )
+    .await?;
 
-        if delete {
+    if choice == LABEL {
+        let label = prompt_label()?;
+        let archive = Confirm::new(&format!("Also remove them from {}?", imap_config.mailbox))
+            .with_default(false)
+            .prompt()?;
+
+        let Some(session) = session else {
             info!(
-                "Deleting {} messages for {}",
+                sender = %sender.email,
+                count = sender.message_uids.len(),
+                label = %label,
+                "dry run, not labeling"
+            );
+            println!(
+                "  {} Would label {} messages as {}",
+                style("~").dim(),
                 sender.message_uids.len(),
-                sender.email
+                label
             );
-            match imap::actions::delete_messages(&mut session, &sender.message_uids).await {
-                Ok(count) => {
-                    info!("Successfully deleted {} messages", count);
-                    println!("  {} Deleted {} messages", style("✓").green(), count);
-                }
-                Err(e) => {
-                    info!("Failed to delete messages: {}", e);
-                    println!("  {} Error: {}", style("✗").red(), e);
-                }
+            return Ok(result);
+        };
+
+        keep_alive(session, imap_config, journal).await?;
+        info!(
+            sender = %sender.email,
+            count = sender.message_uids.len(),
+            label = %label,
+            "labeling"
+        );
+        match imap::actions::apply_label(
+            &mut journal.reconnecting(session, imap_config),
+            imap_config,
+            &imap_config.mailbox,
+            &sender.message_uids,
+            &label,
+            archive,
+        )
+        .await
+        {
+            Ok(count) => {
+                info!(count, "labeled");
+                println!(
+                    "  {} Labeled {} messages as {}",
+                    style("✓").green(),
+                    count,
+                    label
+                );
+            }
+            Err(e) => {
+                info!(error = %e, "failed to label messages");
+                println!("  {} Error: {}", style("✗").red(), e);
+                result.error = Some(e.to_string());
             }
         }
+
+        return Ok(result);
     }
 
-    session.logout().await?;
+    let uids = match choice {
+        DELETE => sender.message_uids.clone(),
+        KEEP_LATEST => {
+            let keep = prompt_keep_latest()?;
+            let (kept, older) = sender.split_keep_latest(keep);
+            println!(
+                "  {} Keeping the {} most recent messages",
+                style("ℹ").blue(),
+                kept.len()
+            );
+            if older.is_empty() {
+                println!("  {} No older messages to delete", style("✓").green());
+                return Ok(result);
+            }
+            older
+        }
+        _ => return Ok(result),
+    };
+
+    {
+        let everywhere = choice == DELETE
+            && Confirm::new("Also delete archived messages in all folders?")
+                .with_default(false)
+                .with_help_message("Searches All Mail for this sender; this cannot be undone")
+                .prompt()?;
+
+        let Some(session) = session else {
+            info!(
+                sender = %sender.email,
+                count = uids.len(),
+                "dry run, not deleting"
+            );
+            println!(
+                "  {} Would delete {} messages",
+                style("~").dim(),
+                uids.len()
+            );
+            if everywhere {
+                println!(
+                    "  {} Would delete archived messages in all folders",
+                    style("~").dim()
+                );
+            }
+            return Ok(result);
+        };
+
+        keep_alive(session, imap_config, journal).await?;
+        info!(sender = %sender.email, count = uids.len(), "deleting");
+        let pb = move_spinner(uids.len(), "to trash", cap.deleted());
+        let deleted = if two_phase {
+            journal
+                .stage_delete(session, imap_config, &sender.email, &uids)
+                .await
+        } else {
+            journal
+                .delete_messages(session, imap_config, &sender.email, &uids)
+                .await
+        };
+        pb.finish_and_clear();
+        match deleted {
+            Ok(count) if two_phase => {
+                info!(count, "staged for deletion");
+                println!(
+                    "  {} Staged {} messages for deletion",
+                    style("✓").green(),
+                    count
+                );
+                result.messages_deleted += count;
+                result.error = None;
+                result.resolve(ResolutionStep::Delete);
+            }
+            Ok(count) => {
+                info!(count, "deleted");
+                println!("  {} Deleted {} messages", style("✓").green(), count);
+                result.messages_deleted += count;
+                result.error = None;
+                result.resolve(ResolutionStep::Delete);
+            }
+            Err(e) => {
+                info!(error = %e, "failed to delete messages");
+                println!("  {} Error: {}", style("✗").red(), e);
+                result.error = Some(e.to_string());
+            }
+        }
+
+        if everywhere {
+            delete_archived(session, imap_config, journal, sender, cap, &mut result).await?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Trash a sender's messages in every folder, within the deletion cap
+///
+/// Messages still in the mailbox, such as those just staged or flagged, are
+/// left alone. The archived messages are counted first, together with those
+/// just deleted from the mailbox; over the cap, they are only trashed after
+/// an explicit override. In flag-only mode they are only labelled.
+async fn delete_archived(
+    session: &mut imap::connection::ImapSession,
+    imap_config: &imap::connection::ImapConfig,
+    journal: &workflow::CleanupJournal,
+    sender: &SenderInfo,
+    cap: &mut planner::DeletionCap,
+    result: &mut CleanupResult,
+) -> Result<()> {
+    info!(sender = %sender.email, "searching all folders");
+    let mut session = journal.reconnecting(session, imap_config);
+    let archived = match imap::actions::find_from_sender_everywhere(
+        &mut session,
+        imap_config,
+        &imap_config.mailbox,
+        &sender.email,
+    )
+    .await
+    {
+        Ok(uids) => uids,
+        Err(e) => {
+            info!(error = %e, "failed to search archived messages");
+            println!("  {} Error: {}", style("✗").red(), e);
+            result.error = Some(e.to_string());
+            return Ok(());
+        }
+    };
+
+    if archived.is_empty() {
+        println!("  {} No archived messages", style("✓").green());
+        return Ok(());
+    }
+    if !confirm_within_cap(cap, result.messages_deleted + archived.len())? {
+        info!(
+            count = archived.len(),
+            "archived messages kept at deletion cap"
+        );
+        println!(
+            "  {} Kept {} archived messages",
+            style("ℹ").blue(),
+            archived.len()
+        );
+        return Ok(());
+    }
+
+    info!(sender = %sender.email, count = archived.len(), "deleting across all folders");
+    match imap::actions::trash_from_all_mail(
+        &mut session,
+        imap_config,
+        &archived,
+        imap_config.flag_only,
+    )
+    .await
+    {
+        Ok(count) => {
+            info!(count, "deleted archived messages");
+            println!(
+                "  {} Deleted {} archived messages",
+                style("✓").green(),
+                count
+            );
+            result.messages_deleted += count;
+        }
+        Err(e) => {
+            info!(error = %e, "failed to delete archived messages");
+            println!("  {} Error: {}", style("✗").red(), e);
+            result.error = Some(e.to_string());
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender(email: &str, display_name: Option<&str>) -> SenderInfo {
+        SenderInfo {
+            email: email.to_string(),
+            display_name: display_name.map(str::to_string),
+            message_count: 3,
+            message_uids: vec![1, 2, 3],
+            heuristic_score: 0.9,
+            ..SenderInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_sender_choices_resolve_prefix_colliding_names() {
+        let senders = vec![
+            sender("news@a.com", Some("News")),
+            sender("daily@b.com", Some("News Daily")),
+            sender("news@a.com.evil", None),
+            sender("news@a.com.evil.example", None),
+        ];
+
+        let choices = sender_choices(&senders);
+        assert!(choices[1].to_string().starts_with("News Daily ("));
+
+        // Pick the longer names only, as MultiSelect would return them
+        let chosen = vec![choices[1].clone(), choices[3].clone()];
+        let picked: Vec<String> = resolve_choices(&senders, chosen)
+            .into_iter()
+            .map(|s| s.email)
+            .collect();
+
+        assert_eq!(picked, vec!["daily@b.com", "news@a.com.evil.example"]);
+    }
+}
@@ -13,6 +13,12 @@
 //!
 //! # Modules
 //!
+//! - `accounts`: Account management (add, list, remove)
+//! - `args`: Non-interactive subcommands for scripting
+//! - `export`: JSON/CSV export of scan results
 //! - `interactive`: Interactive terminal UI with guided workflows
 
+pub mod accounts;
+pub mod args;
+pub mod export;
 pub mod interactive;
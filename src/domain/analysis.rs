@@ -1,20 +1,107 @@
 //! Newsletter detection and email analysis
 
 use super::models::{SenderInfo, UnsubscribeMethod};
+use chrono::{DateTime, Utc};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::sync::OnceLock;
+use url::Url;
 
-/// Parse List-Unsubscribe header to extract HTTP URLs
+/// Targets listed in a List-Unsubscribe header, in header order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsubscribeTargets {
+    /// HTTP(S) URLs
+    pub urls: Vec<String>,
+
+    /// Mailto targets without the `mailto:` prefix, e.g. `unsub@example.com?subject=stop`
+    pub mailtos: Vec<String>,
+}
+
+/// Parse a List-Unsubscribe header into its HTTP and mailto targets
 ///
 /// Format: `<http://example.com/unsub>, <mailto:unsub@example.com>`
+///
+/// Tolerates what unfolding and sloppy senders leave behind: whitespace
+/// inside the brackets is dropped, a `>` inside a URL doesn't end it early,
+/// and targets without angle brackets are still recognized. Schemes are
+/// lowercased; other entries are ignored.
+pub fn parse_unsubscribe_targets(header: &str) -> UnsubscribeTargets {
+    let mut targets = UnsubscribeTargets::default();
+
+    for entry in split_unsubscribe_entries(header) {
+        // URLs never contain raw whitespace, so any left over is folding
+        let target: String = entry.chars().filter(|c| !c.is_whitespace()).collect();
+        let Some((scheme, rest)) = target.split_once(':') else {
+            continue;
+        };
+
+        match scheme.to_ascii_lowercase().as_str() {
+            scheme @ ("http" | "https") if rest.starts_with("//") => {
+                targets.urls.push(format!("{}:{}", scheme, rest));
+            }
+            "mailto" if !rest.is_empty() => targets.mailtos.push(rest.to_string()),
+            _ => {}
+        }
+    }
+
+    targets
+}
+
+/// Split a List-Unsubscribe value into entries, without their brackets
+///
+/// A bracketed entry ends at the first `>` followed by a comma, another
+/// entry or the end of the header. Unbracketed text is split on commas and
+/// whitespace.
+fn split_unsubscribe_entries(header: &str) -> Vec<&str> {
+    let mut entries = Vec::new();
+    let mut rest = header;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if rest.is_empty() {
+            return entries;
+        }
+
+        if let Some(inner) = rest.strip_prefix('<') {
+            let close = inner.match_indices('>').map(|(i, _)| i).find(|&i| {
+                let after = inner[i + 1..].trim_start();
+                after.is_empty() || after.starts_with(',') || after.starts_with('<')
+            });
+            // An unterminated bracket runs to the end of the header
+            let close = close.unwrap_or(inner.len());
+            entries.push(&inner[..close]);
+            rest = inner.get(close + 1..).unwrap_or_default();
+        } else {
+            let end = rest
+                .find(|c: char| c == ',' || c == '<' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            entries.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+}
+
+/// Parse List-Unsubscribe header to extract HTTP URLs
+///
+/// See [`parse_unsubscribe_targets`] for the mailto targets as well.
 pub fn parse_list_unsubscribe(header: &str) -> Vec<String> {
-    static URL_REGEX: OnceLock<Regex> = OnceLock::new();
-    let regex = URL_REGEX.get_or_init(|| Regex::new(r"<(https?://[^>]+)>").expect("Invalid regex"));
+    parse_unsubscribe_targets(header).urls
+}
+
+/// Extract the first mailto address from a List-Unsubscribe header
+///
+/// Example: `<mailto:unsub@example.com?subject=stop>` -> `unsub@example.com?subject=stop`
+pub fn parse_mailto(header: &str) -> Option<String> {
+    parse_unsubscribe_targets(header).mailtos.into_iter().next()
+}
 
-    regex
-        .captures_iter(header)
-        .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-        .collect()
+/// Order unsubscribe URLs by reliability, HTTPS before plain HTTP
+///
+/// The original header order is kept within each group.
+pub fn rank_unsubscribe_urls(mut urls: Vec<String>) -> Vec<String> {
+    urls.sort_by_key(|url| !url.starts_with("https://"));
+    urls
 }
 
 /// Detect one-click unsubscribe from List-Unsubscribe-Post header
@@ -26,116 +113,583 @@ pub fn detect_one_click(header: Option<&str>) -> bool {
         .unwrap_or(false)
 }
 
-/// Calculate heuristic score for newsletter detection
+/// Email service providers that legitimately host unsubscribe links for
+/// other brands' newsletters
+const KNOWN_ESP_DOMAINS: &[&str] = &[
+    "list-manage.com",
+    "mailchimp.com",
+    "sendgrid.net",
+    "mailgun.org",
+    "klaviyo.com",
+    "hubspot.com",
+    "substack.com",
+    "mcsv.net",
+    "exacttarget.com",
+    "sendinblue.com",
+    "brevo.com",
+];
+
+/// Registrable part of a host, approximated without a public suffix list
 ///
-/// Scoring:
-/// - Email patterns (newsletter@, noreply@, etc.): +0.3
+/// Keeps the last two labels, or three for two-letter country TLDs with a
+/// short second level (`shop.co.uk`).
+///
+/// Example: "email.news.acme.co.uk" -> "acme.co.uk"
+fn base_domain(host: &str) -> &str {
+    let labels: Vec<&str> = host.trim_end_matches('.').split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && second.len() <= 3 && labels.len() > 2 => 3,
+        _ => 2,
+    };
+
+    let skip: usize = labels
+        .iter()
+        .take(labels.len().saturating_sub(keep))
+        .map(|label| label.len() + 1)
+        .sum();
+    &host[skip..]
+}
+
+/// Whether an unsubscribe host plausibly belongs to the sender's domain
+///
+/// Related if both share a base domain, the host contains the sender's brand
+/// as a label (`acme.list-manage.com` for `acme.com`), or the host belongs to
+/// a well-known ESP.
+pub fn is_related_unsubscribe_host(from_domain: &str, host: &str) -> bool {
+    let from_domain = from_domain.to_lowercase();
+    let host = host.to_lowercase();
+    let from_base = base_domain(&from_domain);
+    let host_base = base_domain(&host);
+
+    if from_base == host_base || KNOWN_ESP_DOMAINS.contains(&host_base) {
+        return true;
+    }
+
+    let brand = from_base.split('.').next().unwrap_or_default();
+    host.split('.').any(|label| label == brand)
+}
+
+/// Whether an unsubscribe URL points somewhere unrelated to the sender
+///
+/// `sender` is an address or, for domain-grouped senders, a bare domain.
+/// URLs without a parseable host are not flagged.
+pub fn is_suspicious_unsubscribe(sender: &str, url: &str) -> bool {
+    let from_domain = sender.rsplit_once('@').map_or(sender, |(_, d)| d);
+
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .is_some_and(|host| !is_related_unsubscribe_host(from_domain, &host))
+}
+
+/// Address patterns that suggest a newsletter sender
+///
+/// A pattern ending in `@` matches the start of the local part: `info@`
+/// matches `info@` and `infos@`, but not `reinfo@`. Any other pattern matches
+/// anywhere in the address.
+pub const DEFAULT_NEWSLETTER_PATTERNS: &[&str] = &[
+    "newsletter",
+    "noreply",
+    "no-reply",
+    "notification",
+    "promo",
+    "marketing",
+    "news@",
+    "info@",
+    "updates@",
+];
+
+/// Whether `email` matches any of `patterns`
+///
+/// See [`DEFAULT_NEWSLETTER_PATTERNS`] for the pattern syntax. Matching is
+/// case-insensitive; `patterns` are expected in lowercase.
+pub fn matches_newsletter_pattern(email: &str, patterns: &[String]) -> bool {
+    let email_lower = email.to_lowercase();
+    let local_part = email_lower
+        .rsplit_once('@')
+        .map_or(email_lower.as_str(), |(local, _)| local);
+
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('@') {
+            Some(prefix) => !prefix.is_empty() && local_part.starts_with(prefix),
+            None => email_lower.contains(pattern.as_str()),
+        })
+}
+
+/// Weights and thresholds used by the newsletter heuristic
+///
+/// The defaults reproduce the built-in scoring. Tune them when a mailbox has an
+/// unusual mix of senders (e.g. lots of transactional receipts). In
+/// `config.toml` they live in a `[scoring]` table; missing keys keep their
+/// default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// Bonus when a List-Unsubscribe header is present
+    pub unsubscribe_weight: f32,
+
+    /// Bonus when a List-Id header (RFC 2919) is present
+    pub list_id_weight: f32,
+
+    /// Bonus when the address matches a newsletter pattern
+    pub pattern_weight: f32,
+
+    /// Address patterns checked for `pattern_weight`, in lowercase
+    ///
+    /// Defaults to [`DEFAULT_NEWSLETTER_PATTERNS`]; see
+    /// [`ScoringConfig::with_extra_patterns`] to add more. Not read from
+    /// `[scoring]`: the config file's `newsletter_patterns` are added instead.
+    #[serde(skip)]
+    pub newsletter_patterns: Vec<String>,
+
+    /// Bonus when the message count exceeds `low_threshold`
+    pub volume_low_weight: f32,
+
+    /// Additional bonus when the message count exceeds `high_threshold`
+    pub volume_high_weight: f32,
+
+    /// Message count above which `volume_low_weight` applies
+    pub low_threshold: usize,
+
+    /// Message count above which `volume_high_weight` applies
+    pub high_threshold: usize,
+
+    /// Maximum score for senders without a List-Unsubscribe header
+    pub cap_without_unsubscribe: f32,
+
+    /// Bonus when enough sample subjects look promotional
+    pub subject_weight: f32,
+
+    /// Fraction of sample subjects (0.0 - 1.0) that must look promotional
+    pub subject_match_ratio: f32,
+
+    /// Bonus when messages carry `Precedence: bulk` or `list`
+    pub bulk_weight: f32,
+
+    /// Bonus when messages carry `Auto-Submitted: auto-generated`
+    pub auto_submitted_weight: f32,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            unsubscribe_weight: 0.5,
+            list_id_weight: 0.5,
+            pattern_weight: 0.3,
+            newsletter_patterns: DEFAULT_NEWSLETTER_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            volume_low_weight: 0.2,
+            volume_high_weight: 0.3,
+            low_threshold: 10,
+            high_threshold: 30,
+            cap_without_unsubscribe: 0.5,
+            subject_weight: 0.3,
+            subject_match_ratio: 0.5,
+            bulk_weight: 0.2,
+            auto_submitted_weight: 0.2,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// Add user patterns (e.g. `bulletin@`) to the newsletter patterns
+    ///
+    /// Patterns are lowercased; blanks and duplicates are skipped.
+    pub fn with_extra_patterns(mut self, patterns: &[String]) -> Self {
+        for pattern in patterns {
+            let pattern = pattern.trim().to_lowercase();
+            if !pattern.is_empty() && !self.newsletter_patterns.contains(&pattern) {
+                self.newsletter_patterns.push(pattern);
+            }
+        }
+        self
+    }
+}
+
+/// Header signals that a sender's mail is sent by machines rather than people
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkSignals {
+    /// Some message carries `Precedence: bulk`, `list` or `junk`
+    pub is_bulk: bool,
+
+    /// Some message carries `Auto-Submitted: auto-generated` or `auto-notified`
+    pub is_auto: bool,
+}
+
+/// Whether a `Precedence` header value marks bulk or list mail
+///
+/// Example: "Bulk" -> true, "first-class" -> false
+pub fn is_bulk_precedence(value: Option<&str>) -> bool {
+    value.is_some_and(|v| matches!(v.trim().to_lowercase().as_str(), "bulk" | "list" | "junk"))
+}
+
+/// Whether an `Auto-Submitted` header value (RFC 3834) marks generated mail
+///
+/// `auto-replied` is not counted: vacation replies come from people too.
+///
+/// Example: "auto-generated; owner-email=x@y.com" -> true, "no" -> false
+pub fn is_auto_submitted(value: Option<&str>) -> bool {
+    value.is_some_and(|v| {
+        let keyword = v
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        matches!(keyword.as_str(), "auto-generated" | "auto-notified")
+    })
+}
+
+/// Calculate heuristic score for newsletter detection, with the points each
+/// signal added
+///
+/// Scoring, with the default [`ScoringConfig`] weights:
 /// - List-Unsubscribe header present: +0.5 (strong signal)
+/// - List-Id header present: +0.5, marks the sender as a mailing list
+/// - Email patterns (newsletter@, noreply@, etc.): +0.3
 /// - Message count > 10: +0.2
 /// - Message count > 30: +0.3 (additional)
 ///
-/// Note: Without List-Unsubscribe header, max score is capped at 0.5 to prevent
-/// false positives on personal emails with high message counts.
-pub fn calculate_heuristic_score(email: &str, has_unsubscribe: bool, message_count: usize) -> f32 {
-    let mut score = 0.0;
+/// Without a List-Unsubscribe or List-Id header the score is capped at 0.5 to
+/// prevent false positives on personal emails with high message counts.
+///
+/// Promotional subjects (see [`has_promotional_subjects`]), bulk precedence and
+/// auto-submitted mail then add `subject_weight`, `bulk_weight` and
+/// `auto_submitted_weight`. They are applied after the cap since they are
+/// content or sender-declared signals rather than address/volume-based.
+pub fn calculate_heuristic_score(
+    config: &ScoringConfig,
+    email: &str,
+    has_unsubscribe: bool,
+    has_list_id: bool,
+    signals: BulkSignals,
+    message_count: usize,
+    sample_subjects: &[String],
+) -> ScoreBreakdown {
+    let mut breakdown = ScoreBreakdown::default();
 
     // List-Unsubscribe header is the strongest signal
     if has_unsubscribe {
-        score += 0.5;
+        breakdown.add("List-Unsubscribe header", config.unsubscribe_weight);
+    }
+
+    // List-Id marks the sender as a mailing list
+    if has_list_id {
+        breakdown.add("List-Id header", config.list_id_weight);
     }
 
     // Email pattern matching (secondary signal)
-    let email_lower = email.to_lowercase();
-    let newsletter_patterns = [
-        "newsletter",
-        "noreply",
-        "no-reply",
-        "notification",
-        "promo",
-        "marketing",
-        "news@",
-        "info@",
-        "updates@",
-    ];
-
-    if newsletter_patterns.iter().any(|p| email_lower.contains(p)) {
-        score += 0.3;
+    if matches_newsletter_pattern(email, &config.newsletter_patterns) {
+        breakdown.add("newsletter address pattern", config.pattern_weight);
     }
 
     // Message count (use higher thresholds to avoid personal emails)
-    if message_count > 10 {
-        score += 0.2;
+    if message_count > config.low_threshold {
+        breakdown.add(
+            format!("more than {} messages", config.low_threshold),
+            config.volume_low_weight,
+        );
     }
-    if message_count > 30 {
-        score += 0.3;
+    if message_count > config.high_threshold {
+        breakdown.add(
+            format!("more than {} messages", config.high_threshold),
+            config.volume_high_weight,
+        );
     }
 
-    // Cap score at 0.5 if no List-Unsubscribe header
+    // Cap score if no List-Unsubscribe header
     // This prevents personal emails from appearing even with high message counts
-    if !has_unsubscribe && score > 0.5 {
-        score = 0.5;
+    if !has_unsubscribe && !has_list_id && breakdown.total > config.cap_without_unsubscribe {
+        let excess = breakdown.total - config.cap_without_unsubscribe;
+        breakdown.add("capped: no List-Unsubscribe or List-Id", -excess);
+        breakdown.total = config.cap_without_unsubscribe;
+    }
+
+    // Promotional subject lines (content signal)
+    if has_promotional_subjects(sample_subjects, config.subject_match_ratio) {
+        breakdown.add("promotional subjects", config.subject_weight);
+    }
+
+    // Bulk and machine-generated mail (header signals)
+    if signals.is_bulk {
+        breakdown.add("Precedence: bulk", config.bulk_weight);
+    }
+    if signals.is_auto {
+        breakdown.add("Auto-Submitted header", config.auto_submitted_weight);
     }
 
-    score
+    breakdown
+}
+
+/// How a heuristic score was reached, see [`calculate_heuristic_score`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    /// Signals that matched and their points, in the order they were applied
+    ///
+    /// The no-unsubscribe cap shows up as a negative entry.
+    pub contributions: Vec<(String, f32)>,
+
+    /// Final score
+    pub total: f32,
+}
+
+impl ScoreBreakdown {
+    fn add(&mut self, signal: impl Into<String>, points: f32) {
+        self.contributions.push((signal.into(), points));
+        self.total += points;
+    }
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (signal, points) in &self.contributions {
+            writeln!(f, "  {:+.2}  {}", points, signal)?;
+        }
+        write!(f, "  ={:.2}  score", self.total)
+    }
+}
+
+/// Check whether at least `min_ratio` of the subjects look promotional
+///
+/// Matches phrases like "20% off", "sale", "newsletter", "weekly digest" or
+/// "unsubscribe". Returns false for an empty subject list.
+pub fn has_promotional_subjects(subjects: &[String], min_ratio: f32) -> bool {
+    static SUBJECT_REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = SUBJECT_REGEX.get_or_init(|| {
+        Regex::new(
+            r"(?i)\d+\s*% off|\bsale\b|newsletter|weekly digest|unsubscribe|\bdeals?\b|discount|limited time|promo code",
+        )
+        .expect("Invalid regex")
+    });
+
+    if subjects.is_empty() {
+        return false;
+    }
+
+    let matches = subjects.iter().filter(|s| regex.is_match(s)).count();
+
+    matches as f32 / subjects.len() as f32 >= min_ratio
+}
+
+/// Check whether a sender is covered by an allowlist
+///
+/// Entries are either exact addresses (`alice@example.com`) or domain
+/// wildcards (`@example.com`). Matching is case-insensitive.
+pub fn is_allowlisted(email: &str, allowlist: &[String]) -> bool {
+    matches_any_entry(email, allowlist)
+}
+
+/// Check whether a sender is covered by a blocklist and not allowlisted
+///
+/// Uses the same entry syntax as [`is_allowlisted`]. The allowlist wins, so a
+/// blocklisted domain can still have individual addresses kept.
+pub fn is_blocklisted(email: &str, blocklist: &[String], allowlist: &[String]) -> bool {
+    matches_any_entry(email, blocklist) && !is_allowlisted(email, allowlist)
+}
+
+fn matches_any_entry(email: &str, entries: &[String]) -> bool {
+    let email_lower = email.trim().to_lowercase();
+    // Domain-grouped senders carry a bare domain instead of an address
+    let domain = email_lower
+        .rsplit_once('@')
+        .map_or(email_lower.as_str(), |(_, d)| d);
+
+    entries.iter().any(|entry| {
+        let entry = entry.trim().to_lowercase();
+        match entry.strip_prefix('@') {
+            Some(entry_domain) => domain == entry_domain,
+            None => entry == email_lower,
+        }
+    })
+}
+
+/// Unsubscribe headers of one message from a sender
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsubscribeHeaders {
+    /// Message date, used to tell recent messages from older ones
+    pub date: Option<DateTime<Utc>>,
+
+    /// Raw List-Unsubscribe header
+    pub list_unsubscribe: Option<String>,
+
+    /// Raw List-Unsubscribe-Post header
+    pub list_unsubscribe_post: Option<String>,
+}
+
+/// Recent messages without List-Unsubscribe needed before calling it a trend
+pub const MIN_RECENT_WITHOUT_UNSUBSCRIBE: usize = 2;
+
+impl UnsubscribeHeaders {
+    /// Headers of an undated message
+    pub fn new(list_unsubscribe: Option<String>, list_unsubscribe_post: Option<String>) -> Self {
+        Self {
+            date: None,
+            list_unsubscribe,
+            list_unsubscribe_post,
+        }
+    }
+}
+
+/// Unsubscribe targets combined across a sender's messages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CombinedUnsubscribe {
+    /// URLs from messages that also offer one-click, HTTPS first
+    pub one_click_urls: Vec<String>,
+
+    /// URLs from every message, HTTPS first
+    pub urls: Vec<String>,
+
+    /// Mailto targets from every message
+    pub mailtos: Vec<String>,
+}
+
+/// Union of the unsubscribe targets of all `messages`
+///
+/// Messages are read newest first (undated ones last) and duplicates are
+/// dropped. RFC 8058 ties the one-click POST to the URLs of the same message,
+/// so only URLs from messages with a one-click List-Unsubscribe-Post header
+/// count as one-click candidates.
+pub fn combine_unsubscribe_headers(messages: &[UnsubscribeHeaders]) -> CombinedUnsubscribe {
+    let mut ordered: Vec<&UnsubscribeHeaders> = messages.iter().collect();
+    // `None` sorts before any date, so reversed it ends up last
+    ordered.sort_by_key(|m| std::cmp::Reverse(m.date));
+
+    let mut combined = CombinedUnsubscribe::default();
+    for message in ordered {
+        let Some(header) = message.list_unsubscribe.as_deref() else {
+            continue;
+        };
+        let targets = parse_unsubscribe_targets(header);
+
+        if detect_one_click(message.list_unsubscribe_post.as_deref()) {
+            extend_unique(&mut combined.one_click_urls, &targets.urls);
+        }
+        extend_unique(&mut combined.urls, &targets.urls);
+        extend_unique(&mut combined.mailtos, &targets.mailtos);
+    }
+
+    combined.one_click_urls = rank_unsubscribe_urls(combined.one_click_urls);
+    combined.urls = rank_unsubscribe_urls(combined.urls);
+    combined
+}
+
+fn extend_unique(into: &mut Vec<String>, items: &[String]) {
+    for item in items {
+        if !into.contains(item) {
+            into.push(item.clone());
+        }
+    }
+}
+
+/// Whether the sender's recent messages dropped List-Unsubscribe
+///
+/// True when the newest [`MIN_RECENT_WITHOUT_UNSUBSCRIBE`] or more messages
+/// lack the header while an older one had it, which usually means the user
+/// already unsubscribed and only transactional mail is still arriving.
+/// Undated messages are ignored since they can't be placed in the trend.
+pub fn unsubscribe_dropped(messages: &[UnsubscribeHeaders]) -> bool {
+    let mut dated: Vec<_> = messages
+        .iter()
+        .filter_map(|m| m.date.map(|date| (date, m.list_unsubscribe.is_some())))
+        .collect();
+    dated.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+
+    let recent_without = dated.iter().take_while(|(_, has)| !has).count();
+    recent_without >= MIN_RECENT_WITHOUT_UNSUBSCRIBE && recent_without < dated.len()
 }
 
 /// Analyze sender to determine unsubscribe method
+///
+/// `unsubscribe_headers` holds one entry per message; see
+/// [`analyze_sender_with`].
 pub fn analyze_sender(
     email: String,
     display_name: Option<String>,
     message_count: usize,
     message_uids: Vec<u32>,
-    list_unsubscribe: Option<String>,
-    list_unsubscribe_post: Option<String>,
+    unsubscribe_headers: &[UnsubscribeHeaders],
+    sample_subjects: Vec<String>,
+) -> SenderInfo {
+    analyze_sender_with(
+        &ScoringConfig::default(),
+        email,
+        display_name,
+        message_count,
+        message_uids,
+        unsubscribe_headers,
+        false,
+        BulkSignals::default(),
+        sample_subjects,
+    )
+}
+
+/// Analyze sender using custom scoring weights and the List-Id, Precedence
+/// and Auto-Submitted signals
+///
+/// `unsubscribe_headers` holds one entry per message. The unsubscribe method
+/// is picked from all of them (see [`combine_unsubscribe_headers`]), so one
+/// message offering one-click is enough, and [`unsubscribe_dropped`] flags
+/// senders whose recent messages stopped offering unsubscribe.
+#[allow(clippy::too_many_arguments)]
+pub fn analyze_sender_with(
+    config: &ScoringConfig,
+    email: String,
+    display_name: Option<String>,
+    message_count: usize,
+    message_uids: Vec<u32>,
+    unsubscribe_headers: &[UnsubscribeHeaders],
+    has_list_id: bool,
+    signals: BulkSignals,
     sample_subjects: Vec<String>,
 ) -> SenderInfo {
-    // Parse unsubscribe URLs from List-Unsubscribe header
-    let unsubscribe_urls = list_unsubscribe
-        .as_ref()
-        .map(|h| parse_list_unsubscribe(h))
-        .unwrap_or_default();
+    // Parse unsubscribe URLs and mailto targets from every message
+    let combined = combine_unsubscribe_headers(unsubscribe_headers);
+    let has_list_unsubscribe = unsubscribe_headers
+        .iter()
+        .any(|m| m.list_unsubscribe.is_some());
 
-    // Check for one-click unsubscribe support
-    let has_one_click = detect_one_click(list_unsubscribe_post.as_deref());
+    // RFC 8058: One-click unsubscribe requires both headers and an HTTPS URL.
+    // URLs are ranked HTTPS-first, so only the first one needs checking.
+    let one_click_url = combined
+        .one_click_urls
+        .first()
+        .filter(|url| url.starts_with("https://"));
 
     // Determine unsubscribe method based on available headers
     // Priority: OneClick > HttpLink > Mailto > None
-    let unsubscribe_method = if has_one_click {
-        // RFC 8058: One-click unsubscribe requires both headers
-        if !unsubscribe_urls.is_empty() {
-            UnsubscribeMethod::OneClick {
-                url: unsubscribe_urls[0].clone(),
-            }
-        } else {
-            // Invalid state: has one-click flag but no URL
-            // This shouldn't happen with compliant senders
-            UnsubscribeMethod::None
-        }
-    } else if !unsubscribe_urls.is_empty() {
+    let unsubscribe_method = if let Some(url) = one_click_url {
+        UnsubscribeMethod::OneClick { url: url.clone() }
+    } else if let Some(url) = combined.urls.first() {
         // Standard HTTP unsubscribe link (requires manual click)
-        UnsubscribeMethod::HttpLink {
-            url: unsubscribe_urls[0].clone(),
-        }
-    } else if let Some(ref header) = list_unsubscribe {
-        // Check for mailto-only unsubscribe
-        if header.contains("mailto:") {
-            let mailto = header
-                .split('<')
-                .find(|s| s.contains("mailto:"))
-                .and_then(|s| s.split('>').next())
-                .unwrap_or("")
-                .replace("mailto:", "");
-            UnsubscribeMethod::Mailto { address: mailto }
-        } else {
-            UnsubscribeMethod::None
-        }
+        UnsubscribeMethod::HttpLink { url: url.clone() }
+    } else if let Some(address) = combined.mailtos.first().cloned() {
+        // Mailto-only unsubscribe, even if a (bogus) one-click flag is set
+        UnsubscribeMethod::Mailto { address }
     } else {
         UnsubscribeMethod::None
     };
 
+    let suspicious_unsubscribe = match &unsubscribe_method {
+        UnsubscribeMethod::OneClick { url } | UnsubscribeMethod::HttpLink { url } => {
+            is_suspicious_unsubscribe(&email, url)
+        }
+        _ => false,
+    };
+
     // Calculate heuristic score
-    let heuristic_score =
-        calculate_heuristic_score(&email, list_unsubscribe.is_some(), message_count);
+    let heuristic_score = calculate_heuristic_score(
+        config,
+        &email,
+        has_list_unsubscribe,
+        has_list_id,
+        signals,
+        message_count,
+        &sample_subjects,
+    )
+    .total;
 
     SenderInfo {
         email,
@@ -145,6 +699,19 @@ pub fn analyze_sender(
         unsubscribe_method,
         heuristic_score,
         sample_subjects,
+        message_dates: Vec::new(),
+        last_message_date: None,
+        has_list_id,
+        // Every candidate is worth trying for one-click unsubscribe
+        unsubscribe_urls: if one_click_url.is_some() {
+            combined.one_click_urls
+        } else {
+            Vec::new()
+        },
+        suspicious_unsubscribe,
+        blocklisted: false,
+        unread_count: 0,
+        unsubscribe_dropped: unsubscribe_dropped(unsubscribe_headers),
     }
 }
 
@@ -152,6 +719,40 @@ pub fn analyze_sender(
 mod tests {
     use super::*;
 
+    /// Score with the default weights and no List-Id, header or subject signals
+    fn default_score(email: &str, has_unsubscribe: bool, message_count: usize) -> f32 {
+        calculate_heuristic_score(
+            &ScoringConfig::default(),
+            email,
+            has_unsubscribe,
+            false,
+            BulkSignals::default(),
+            message_count,
+            &[],
+        )
+        .total
+    }
+
+    /// Score an address with `config` and no unsubscribe header
+    fn score_with(
+        config: &ScoringConfig,
+        email: &str,
+        has_list_id: bool,
+        message_count: usize,
+        sample_subjects: &[String],
+    ) -> f32 {
+        calculate_heuristic_score(
+            config,
+            email,
+            false,
+            has_list_id,
+            BulkSignals::default(),
+            message_count,
+            sample_subjects,
+        )
+        .total
+    }
+
     #[test]
     fn test_parse_list_unsubscribe() {
         let header = "<https://example.com/unsub?id=123>, <mailto:unsub@example.com>";
@@ -160,6 +761,59 @@ mod tests {
         assert_eq!(urls[0], "https://example.com/unsub?id=123");
     }
 
+    #[test]
+    fn test_parse_unsubscribe_targets_mixed_mailto_and_https() {
+        let targets = parse_unsubscribe_targets(
+            "<mailto:unsub@example.com?subject=stop>, <https://example.com/u?id=1>, <MAILTO:list@example.com>",
+        );
+
+        assert_eq!(targets.urls, vec!["https://example.com/u?id=1"]);
+        assert_eq!(
+            targets.mailtos,
+            vec!["unsub@example.com?subject=stop", "list@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_unsubscribe_targets_without_brackets() {
+        let targets =
+            parse_unsubscribe_targets("https://example.com/u?id=1, mailto:unsub@example.com");
+        assert_eq!(targets.urls, vec!["https://example.com/u?id=1"]);
+        assert_eq!(targets.mailtos, vec!["unsub@example.com"]);
+
+        let targets = parse_unsubscribe_targets("HTTPS://Example.com/u <mailto:a@b.com>");
+        assert_eq!(targets.urls, vec!["https://Example.com/u"]);
+        assert_eq!(targets.mailtos, vec!["a@b.com"]);
+    }
+
+    #[test]
+    fn test_parse_unsubscribe_targets_whitespace_inside_brackets() {
+        let targets = parse_unsubscribe_targets(
+            "< https://example.com/\r\n unsub?id=42 >,\t< mailto:u@x.com >",
+        );
+        assert_eq!(targets.urls, vec!["https://example.com/unsub?id=42"]);
+        assert_eq!(targets.mailtos, vec!["u@x.com"]);
+    }
+
+    #[test]
+    fn test_parse_unsubscribe_targets_angle_bracket_in_url() {
+        let targets =
+            parse_unsubscribe_targets("<https://example.com/u?q=a>b>, <https://example.com/v>");
+        assert_eq!(
+            targets.urls,
+            vec!["https://example.com/u?q=a>b", "https://example.com/v"]
+        );
+
+        // An unterminated bracket keeps the rest of the header
+        let targets = parse_unsubscribe_targets("<https://example.com/u?id=1");
+        assert_eq!(targets.urls, vec!["https://example.com/u?id=1"]);
+
+        assert_eq!(
+            parse_unsubscribe_targets("<ftp://example.com>, not a url, <>"),
+            UnsubscribeTargets::default()
+        );
+    }
+
     #[test]
     fn test_detect_one_click() {
         assert!(detect_one_click(Some("List-Unsubscribe=One-Click")));
@@ -171,7 +825,7 @@ mod tests {
     fn test_heuristic_score() {
         // Newsletter email with unsubscribe and many messages
         // Expected: 0.5 (List-Unsubscribe) + 0.3 (pattern) + 0.2 (>10) + 0.3 (>30) = 1.3
-        let score = calculate_heuristic_score("newsletter@example.com", true, 35);
+        let score = default_score("newsletter@example.com", true, 35);
         assert!(
             score > 1.0,
             "Newsletter with unsubscribe should score > 1.0, got {}",
@@ -180,7 +834,7 @@ mod tests {
 
         // Regular email without List-Unsubscribe but high message count
         // Expected: capped at 0.5 (no List-Unsubscribe)
-        let score = calculate_heuristic_score("john@example.com", false, 50);
+        let score = default_score("john@example.com", false, 50);
         assert_eq!(
             score, 0.5,
             "Personal email without unsubscribe should be capped at 0.5"
@@ -188,16 +842,551 @@ mod tests {
 
         // Regular email with low message count
         // Expected: 0.0
-        let score = calculate_heuristic_score("jane@example.com", false, 2);
+        let score = default_score("jane@example.com", false, 2);
         assert_eq!(score, 0.0, "Low-volume personal email should score 0.0");
 
         // Marketing email with List-Unsubscribe
         // Expected: 0.5 (List-Unsubscribe) + 0.3 (pattern) = 0.8
-        let score = calculate_heuristic_score("marketing@example.com", true, 5);
+        let score = default_score("marketing@example.com", true, 5);
         assert!(
             score >= 0.8,
             "Marketing email with unsubscribe should score >= 0.8, got {}",
             score
         );
     }
+
+    #[test]
+    fn test_heuristic_score_without_pattern_weight() {
+        let config = ScoringConfig {
+            pattern_weight: 0.0,
+            ..ScoringConfig::default()
+        };
+
+        // With patterns disabled, marketing@ is indistinguishable from a personal address
+        let marketing = score_with(&config, "marketing@example.com", false, 5, &[]);
+        let personal = score_with(&config, "john@example.com", false, 5, &[]);
+        assert_eq!(marketing, personal);
+        assert_eq!(marketing, 0.0);
+
+        // Default weights still reward the pattern
+        let score = default_score("marketing@example.com", false, 5);
+        assert!(
+            score >= 0.3,
+            "Default config should match pattern, got {}",
+            score
+        );
+    }
+
+    #[test]
+    fn test_newsletter_patterns_prefix_vs_substring() {
+        let patterns: Vec<String> = DEFAULT_NEWSLETTER_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+
+        // `info@` is anchored to the start of the local part
+        assert!(matches_newsletter_pattern("info@shop.com", &patterns));
+        assert!(matches_newsletter_pattern("Infos@shop.fr", &patterns));
+        assert!(!matches_newsletter_pattern("reinfo@shop.com", &patterns));
+
+        // Patterns without `@` match anywhere
+        assert!(matches_newsletter_pattern(
+            "team-noreply@shop.com",
+            &patterns
+        ));
+        assert!(matches_newsletter_pattern(
+            "weekly.newsletter@shop.com",
+            &patterns
+        ));
+        assert!(!matches_newsletter_pattern("jenny@acme.io", &patterns));
+    }
+
+    #[test]
+    fn test_custom_newsletter_patterns() {
+        let config = ScoringConfig::default()
+            .with_extra_patterns(&["Bulletin@".to_string(), "actualites".to_string()]);
+
+        let bulletin = score_with(&config, "bulletin@mairie.fr", false, 5, &[]);
+        let news = score_with(&config, "lettre-actualites@journal.fr", false, 5, &[]);
+        let defaults_still_apply = score_with(&config, "marketing@shop.com", false, 5, &[]);
+        assert_eq!(bulletin, 0.3);
+        assert_eq!(news, 0.3);
+        assert_eq!(defaults_still_apply, 0.3);
+
+        // Default patterns alone don't know the French senders
+        assert_eq!(default_score("bulletin@mairie.fr", false, 5), 0.0);
+
+        // Merging is idempotent
+        let merged = ScoringConfig::default().with_extra_patterns(&["news@".to_string()]);
+        assert_eq!(
+            merged.newsletter_patterns.len(),
+            DEFAULT_NEWSLETTER_PATTERNS.len()
+        );
+    }
+
+    #[test]
+    fn test_promotional_subjects() {
+        let promotional = vec![
+            "Summer SALE: 30% off everything".to_string(),
+            "Your weekly digest".to_string(),
+            "Hi there".to_string(),
+        ];
+        let personal = vec![
+            "Lunch tomorrow?".to_string(),
+            "Re: wholesale pricing".to_string(),
+            "Photos from the trip".to_string(),
+        ];
+
+        assert!(has_promotional_subjects(&promotional, 0.5));
+        assert!(!has_promotional_subjects(&personal, 0.5));
+        assert!(!has_promotional_subjects(&[], 0.5));
+
+        let config = ScoringConfig::default();
+        let promo_score = score_with(&config, "jenny@acme.io", false, 5, &promotional);
+        let personal_score = score_with(&config, "jenny@acme.io", false, 5, &personal);
+        assert_eq!(promo_score, 0.3);
+        assert_eq!(personal_score, 0.0);
+    }
+
+    #[test]
+    fn test_one_click_without_url_falls_back_to_mailto() {
+        let sender = analyze_sender(
+            "news@example.com".to_string(),
+            None,
+            1,
+            vec![1],
+            &[UnsubscribeHeaders::new(
+                Some("<mailto:unsub@example.com>".to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::Mailto {
+                address: "unsub@example.com".to_string()
+            }
+        );
+        assert!(sender.unsubscribe_urls.is_empty());
+    }
+
+    #[test]
+    fn test_one_click_requires_https() {
+        let sender = analyze_sender(
+            "news@example.com".to_string(),
+            None,
+            1,
+            vec![1],
+            &[UnsubscribeHeaders::new(
+                Some("<http://example.com/unsub>".to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::HttpLink {
+                url: "http://example.com/unsub".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_prefers_https_url() {
+        let header = "<http://track.example.com/u?id=1>, <mailto:u@example.com>, \
+                      <https://example.com/unsub>, <https://backup.example.com/unsub>";
+
+        let sender = analyze_sender(
+            "news@example.com".to_string(),
+            None,
+            1,
+            vec![1],
+            &[UnsubscribeHeaders::new(
+                Some(header.to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::OneClick {
+                url: "https://example.com/unsub".to_string()
+            }
+        );
+        assert_eq!(
+            sender.unsubscribe_urls,
+            vec![
+                "https://example.com/unsub",
+                "https://backup.example.com/unsub",
+                "http://track.example.com/u?id=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_urls_only_kept_for_one_click() {
+        let sender = analyze_sender(
+            "news@example.com".to_string(),
+            None,
+            1,
+            vec![1],
+            &[UnsubscribeHeaders::new(
+                Some("<http://a.example.com/u>, <https://b.example.com/u>".to_string()),
+                None,
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::HttpLink {
+                url: "https://b.example.com/u".to_string()
+            }
+        );
+        assert!(sender.unsubscribe_urls.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_sender_with_config() {
+        let config = ScoringConfig {
+            unsubscribe_weight: 0.1,
+            ..ScoringConfig::default()
+        };
+
+        let sender = analyze_sender_with(
+            &config,
+            "john@example.com".to_string(),
+            None,
+            2,
+            vec![1],
+            &[UnsubscribeHeaders {
+                list_unsubscribe: Some("<https://example.com/unsub>".to_string()),
+                ..UnsubscribeHeaders::default()
+            }],
+            false,
+            BulkSignals::default(),
+            vec![],
+        );
+
+        assert_eq!(sender.heuristic_score, 0.1);
+    }
+
+    #[test]
+    fn test_list_id_signal() {
+        let config = ScoringConfig::default();
+
+        // List-Id alone is worth as much as List-Unsubscribe and lifts the cap
+        let score = score_with(&config, "team@lists.example.org", true, 50, &[]);
+        assert_eq!(score, 1.0);
+
+        let sender = analyze_sender_with(
+            &config,
+            "team@lists.example.org".to_string(),
+            None,
+            3,
+            vec![1, 2, 3],
+            &[],
+            true,
+            BulkSignals::default(),
+            vec![],
+        );
+        assert!(sender.has_list_id);
+        assert_eq!(sender.heuristic_score, 0.5);
+    }
+
+    #[test]
+    fn test_bulk_and_auto_submitted_headers() {
+        assert!(is_bulk_precedence(Some("bulk")));
+        assert!(is_bulk_precedence(Some(" List ")));
+        assert!(!is_bulk_precedence(Some("first-class")));
+        assert!(!is_bulk_precedence(None));
+
+        assert!(is_auto_submitted(Some("auto-generated")));
+        assert!(is_auto_submitted(Some(
+            "Auto-Notified; owner-email=ops@x.com"
+        )));
+        assert!(!is_auto_submitted(Some("auto-replied")));
+        assert!(!is_auto_submitted(Some("no")));
+        assert!(!is_auto_submitted(None));
+    }
+
+    #[test]
+    fn test_bulk_signals_add_modest_bonus() {
+        let config = ScoringConfig::default();
+        let score = |signals| {
+            calculate_heuristic_score(
+                &config,
+                "billing@utility.example",
+                false,
+                false,
+                signals,
+                40,
+                &[],
+            )
+            .total
+        };
+
+        // Volume alone is capped without an unsubscribe header
+        let plain = score(BulkSignals::default());
+        assert_eq!(plain, 0.5);
+
+        let bulk = score(BulkSignals {
+            is_bulk: true,
+            is_auto: false,
+        });
+        assert!((bulk - (plain + config.bulk_weight)).abs() < f32::EPSILON);
+
+        let both = score(BulkSignals {
+            is_bulk: true,
+            is_auto: true,
+        });
+        assert!(
+            (both - (plain + config.bulk_weight + config.auto_submitted_weight)).abs()
+                < f32::EPSILON
+        );
+
+        // A single signal on its own stays below the default display threshold
+        let quiet = calculate_heuristic_score(
+            &config,
+            "alice@example.com",
+            false,
+            false,
+            BulkSignals {
+                is_bulk: true,
+                is_auto: false,
+            },
+            2,
+            &[],
+        )
+        .total;
+        assert!(quiet < crate::domain::planner::DEFAULT_MIN_DISPLAY_SCORE);
+    }
+
+    #[test]
+    fn test_is_blocklisted_wildcards_and_allowlist_precedence() {
+        let blocklist = vec!["@spam.example".to_string(), "Deals@Shop.com".to_string()];
+        let allowlist = vec!["receipts@spam.example".to_string()];
+
+        assert!(is_blocklisted("promo@spam.example", &blocklist, &allowlist));
+        assert!(is_blocklisted("deals@shop.com", &blocklist, &allowlist));
+        assert!(is_blocklisted("spam.example", &blocklist, &[]));
+        assert!(!is_blocklisted("news@shop.com", &blocklist, &allowlist));
+        assert!(!is_blocklisted(
+            "promo@notspam.example",
+            &blocklist,
+            &allowlist
+        ));
+
+        // The allowlist wins over the blocklist, by address or by domain
+        assert!(!is_blocklisted(
+            "receipts@spam.example",
+            &blocklist,
+            &allowlist
+        ));
+        let allow_domain = vec!["@shop.com".to_string()];
+        assert!(!is_blocklisted("deals@shop.com", &blocklist, &allow_domain));
+    }
+
+    #[test]
+    fn test_is_allowlisted() {
+        let allowlist = vec!["alice@example.com".to_string(), "@bank.com".to_string()];
+
+        assert!(is_allowlisted("alice@example.com", &allowlist));
+        assert!(is_allowlisted("Alice@Example.com", &allowlist));
+        assert!(is_allowlisted("alerts@bank.com", &allowlist));
+        assert!(!is_allowlisted("bob@example.com", &allowlist));
+        assert!(!is_allowlisted("alerts@notbank.com", &allowlist));
+        assert!(!is_allowlisted("alerts@bank.com", &[]));
+
+        // Domain-grouped senders
+        assert!(is_allowlisted("bank.com", &allowlist));
+        assert!(!is_allowlisted("example.com", &allowlist));
+    }
+
+    #[test]
+    fn test_base_domain() {
+        assert_eq!(base_domain("email.news.acme.com"), "acme.com");
+        assert_eq!(base_domain("acme.com"), "acme.com");
+        assert_eq!(base_domain("email.news.acme.co.uk"), "acme.co.uk");
+        assert_eq!(base_domain("localhost"), "localhost");
+    }
+
+    #[test]
+    fn test_matched_unsubscribe_domains() {
+        for url in [
+            "https://acme.com/unsub",
+            "https://email.acme.com/u?id=1",
+            "https://acme.us12.list-manage.com/unsubscribe",
+            "https://u123.ct.sendgrid.net/lu/unsubscribe",
+        ] {
+            assert!(
+                !is_suspicious_unsubscribe("news@mail.acme.com", url),
+                "{url}"
+            );
+        }
+        assert!(!is_suspicious_unsubscribe(
+            "acme.com",
+            "https://links.acme.com/u"
+        ));
+        assert!(!is_suspicious_unsubscribe("news@acme.com", "not a url"));
+    }
+
+    #[test]
+    fn test_mismatched_unsubscribe_domain() {
+        assert!(is_suspicious_unsubscribe(
+            "news@acme.com",
+            "https://tracker.example/unsub"
+        ));
+        assert!(is_suspicious_unsubscribe(
+            "deals@acme.co.uk",
+            "https://click.other-shop.co.uk/u"
+        ));
+
+        let sender = analyze_sender(
+            "news@acme.com".to_string(),
+            None,
+            3,
+            vec![1, 2, 3],
+            &[UnsubscribeHeaders::new(
+                Some("<https://tracker.example/unsub>".to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            )],
+            vec![],
+        );
+        assert!(sender.suspicious_unsubscribe);
+
+        let sender = analyze_sender(
+            "news@acme.com".to_string(),
+            None,
+            3,
+            vec![1, 2, 3],
+            &[UnsubscribeHeaders::new(
+                Some("<https://acme.com/unsub>".to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            )],
+            vec![],
+        );
+        assert!(!sender.suspicious_unsubscribe);
+    }
+
+    #[test]
+    fn test_one_click_on_second_message_only() {
+        let messages = [
+            UnsubscribeHeaders::new(None, None),
+            UnsubscribeHeaders::new(
+                Some("<mailto:unsub@shop.com>, <https://shop.com/one-click>".to_string()),
+                Some("List-Unsubscribe=One-Click".to_string()),
+            ),
+            UnsubscribeHeaders::new(Some("<https://shop.com/manual>".to_string()), None),
+        ];
+
+        let sender = analyze_sender(
+            "news@shop.com".to_string(),
+            None,
+            3,
+            vec![1, 2, 3],
+            &messages,
+            vec![],
+        );
+
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::OneClick {
+                url: "https://shop.com/one-click".to_string()
+            }
+        );
+        // The manual link has no one-click POST of its own, so it isn't a candidate
+        assert_eq!(sender.unsubscribe_urls, vec!["https://shop.com/one-click"]);
+
+        let combined = combine_unsubscribe_headers(&messages);
+        assert_eq!(
+            combined.urls,
+            vec!["https://shop.com/one-click", "https://shop.com/manual"]
+        );
+        assert_eq!(combined.mailtos, vec!["unsub@shop.com"]);
+    }
+
+    fn dated(day: u32, list_unsubscribe: Option<&str>) -> UnsubscribeHeaders {
+        UnsubscribeHeaders {
+            date: Some(chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 1, day, 9, 0, 0).unwrap()),
+            list_unsubscribe: list_unsubscribe.map(str::to_string),
+            list_unsubscribe_post: None,
+        }
+    }
+
+    #[test]
+    fn test_unsubscribe_dropped_trend() {
+        let unsub = Some("<https://shop.com/unsub>");
+
+        // Two newest messages lack the header, older ones had it (any order)
+        let dropped = [
+            dated(3, None),
+            dated(1, unsub),
+            dated(4, None),
+            dated(2, unsub),
+        ];
+        assert!(unsubscribe_dropped(&dropped));
+
+        // A single recent message without it is not a trend yet
+        assert!(!unsubscribe_dropped(&[dated(1, unsub), dated(2, None)]));
+
+        // Never had it, or still has it on the newest message
+        assert!(!unsubscribe_dropped(&[
+            dated(1, None),
+            dated(2, None),
+            dated(3, None)
+        ]));
+        assert!(!unsubscribe_dropped(&[
+            dated(1, None),
+            dated(2, None),
+            dated(3, unsub)
+        ]));
+
+        // Undated messages can't be placed in the trend
+        let undated = UnsubscribeHeaders {
+            list_unsubscribe: unsub.map(str::to_string),
+            ..UnsubscribeHeaders::default()
+        };
+        assert!(!unsubscribe_dropped(&[
+            undated,
+            dated(1, None),
+            dated(2, None)
+        ]));
+    }
+
+    #[test]
+    fn test_method_comes_from_newest_message_with_header() {
+        let messages = [
+            dated(3, None),
+            dated(1, Some("<https://shop.com/old>")),
+            dated(2, Some("<https://shop.com/new>")),
+            dated(4, None),
+        ];
+        assert_eq!(
+            combine_unsubscribe_headers(&messages).urls,
+            vec!["https://shop.com/new", "https://shop.com/old"]
+        );
+
+        let sender = analyze_sender_with(
+            &ScoringConfig::default(),
+            "news@shop.com".to_string(),
+            None,
+            4,
+            vec![1, 2, 3, 4],
+            &messages,
+            false,
+            BulkSignals::default(),
+            vec![],
+        );
+        assert_eq!(
+            sender.unsubscribe_method,
+            UnsubscribeMethod::HttpLink {
+                url: "https://shop.com/new".to_string()
+            }
+        );
+        assert!(sender.unsubscribe_dropped);
+    }
 }
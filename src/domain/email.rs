@@ -0,0 +1,271 @@
+//! Email address validation and parsing
+
+use mailparse::{addrparse, MailAddr};
+use std::fmt;
+use std::str::FromStr;
+
+/// Domains served by Gmail's IMAP endpoint
+pub const GMAIL_DOMAINS: &[&str] = &["gmail.com", "googlemail.com"];
+
+/// Why an email address was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EmailAddressError {
+    #[error("Email address is empty")]
+    Empty,
+
+    #[error("\"{0}\" is not an email address (missing @)")]
+    MissingAt(String),
+
+    #[error("\"{0}\" has an invalid part before the @")]
+    InvalidLocalPart(String),
+
+    #[error("\"{0}\" has an invalid domain")]
+    InvalidDomain(String),
+}
+
+/// A trimmed, shape-checked email address with a lowercased domain
+///
+/// Validation is intentionally loose (no quoted local parts or IP literals):
+/// it only catches typos before they surface as an IMAP auth failure.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    /// Parse and normalize user input
+    ///
+    /// Example: " John.Doe@Gmail.COM " -> "John.Doe@gmail.com"
+    pub fn parse(input: &str) -> Result<Self, EmailAddressError> {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Err(EmailAddressError::Empty);
+        }
+
+        let (local, domain) = input
+            .rsplit_once('@')
+            .ok_or_else(|| EmailAddressError::MissingAt(input.to_string()))?;
+
+        if !is_valid_local_part(local) {
+            return Err(EmailAddressError::InvalidLocalPart(input.to_string()));
+        }
+
+        let domain = domain.to_lowercase();
+        if !is_valid_domain(&domain) {
+            return Err(EmailAddressError::InvalidDomain(input.to_string()));
+        }
+
+        Ok(Self(format!("{}@{}", local, domain)))
+    }
+
+    /// The normalized address
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The lowercased domain
+    pub fn domain(&self) -> &str {
+        self.0.rsplit_once('@').map(|(_, d)| d).unwrap_or_default()
+    }
+
+    /// Whether the address is on a consumer Gmail domain
+    ///
+    /// Google Workspace addresses on custom domains also work, so callers
+    /// should only warn when this is false.
+    pub fn is_gmail(&self) -> bool {
+        GMAIL_DOMAINS.contains(&self.domain())
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for EmailAddress {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = EmailAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// A mailbox from an address header such as `From` or `To`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mailbox {
+    /// The address as written, e.g. `news@example.com`
+    pub address: String,
+    /// The display name, without quotes, if there is one
+    pub display_name: Option<String>,
+}
+
+impl Mailbox {
+    /// Parse the first mailbox of an RFC 5322 address list
+    ///
+    /// Quoted display names, comments, groups and multiple addresses are
+    /// handled; a group yields its first member. Returns `None` when the
+    /// header can't be parsed or names no mailbox, e.g. `undisclosed-recipients:;`.
+    ///
+    /// Example: `"Doe, John" <j@x.com>` -> `j@x.com` named `Doe, John`
+    pub fn parse_first(header: &str) -> Option<Self> {
+        Self::parse_all(header).into_iter().next()
+    }
+
+    /// Parse every mailbox of an RFC 5322 address list, in order
+    ///
+    /// Group members are listed in place of the group. Returns an empty list
+    /// when the header can't be parsed.
+    ///
+    /// Example: `a@x.com, Team: b@y.com, c@y.com;` -> `a@x.com`, `b@y.com`, `c@y.com`
+    pub fn parse_all(header: &str) -> Vec<Self> {
+        let Ok(list) = addrparse(header) else {
+            return Vec::new();
+        };
+
+        list.iter()
+            .flat_map(|addr| match addr {
+                MailAddr::Single(info) => vec![info.clone()],
+                MailAddr::Group(group) => group.addrs.clone(),
+            })
+            .map(|info| Self {
+                address: info.addr,
+                display_name: info
+                    .display_name
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty()),
+            })
+            .collect()
+    }
+}
+
+fn is_valid_local_part(local: &str) -> bool {
+    !local.is_empty()
+        && local.len() <= 64
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c))
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+
+    labels.len() >= 2
+        && domain.len() <= 253
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes() {
+        let email = EmailAddress::parse("  John.Doe+news@Gmail.COM \n").unwrap();
+        assert_eq!(email.as_str(), "John.Doe+news@gmail.com");
+        assert_eq!(email.domain(), "gmail.com");
+        assert!(email.is_gmail());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert_eq!(EmailAddress::parse("   "), Err(EmailAddressError::Empty));
+        assert!(matches!(
+            EmailAddress::parse("john.gmail.com"),
+            Err(EmailAddressError::MissingAt(_))
+        ));
+        for input in [
+            "@gmail.com",
+            "jo hn@gmail.com",
+            ".john@gmail.com",
+            "jo..hn@gmail.com",
+        ] {
+            assert!(
+                matches!(
+                    EmailAddress::parse(input),
+                    Err(EmailAddressError::InvalidLocalPart(_))
+                ),
+                "{}",
+                input
+            );
+        }
+        for input in [
+            "john@",
+            "john@gmail",
+            "john@gmail..com",
+            "john@-gmail.com",
+            "john@gm ail.com",
+        ] {
+            assert!(
+                matches!(
+                    EmailAddress::parse(input),
+                    Err(EmailAddressError::InvalidDomain(_))
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_gmail() {
+        assert!(EmailAddress::parse("a@googlemail.com").unwrap().is_gmail());
+        assert!(!EmailAddress::parse("a@company.com").unwrap().is_gmail());
+    }
+
+    #[test]
+    fn test_mailbox_parse_first() {
+        let mailbox = Mailbox::parse_first("\"Doe, John\" <j@x.com>").unwrap();
+        assert_eq!(mailbox.address, "j@x.com");
+        assert_eq!(mailbox.display_name.as_deref(), Some("Doe, John"));
+
+        let mailbox = Mailbox::parse_first("john@example.com (John Doe)").unwrap();
+        assert_eq!(mailbox.address, "john@example.com");
+        assert_eq!(mailbox.display_name, None);
+
+        let mailbox = Mailbox::parse_first("John (via list) <john@example.com>").unwrap();
+        assert_eq!(mailbox.address, "john@example.com");
+        assert_eq!(mailbox.display_name.as_deref(), Some("John"));
+
+        let mailbox = Mailbox::parse_first("\"a <b>\" <c@x.com>, d@y.com").unwrap();
+        assert_eq!(mailbox.address, "c@x.com");
+        assert_eq!(mailbox.display_name.as_deref(), Some("a <b>"));
+
+        let mailbox = Mailbox::parse_first("Team: a@x.com, b@y.com;").unwrap();
+        assert_eq!(mailbox.address, "a@x.com");
+
+        assert_eq!(Mailbox::parse_first("undisclosed-recipients:;"), None);
+        assert_eq!(Mailbox::parse_first("not an address"), None);
+    }
+
+    #[test]
+    fn test_mailbox_parse_all() {
+        let addresses = |header: &str| -> Vec<String> {
+            Mailbox::parse_all(header)
+                .into_iter()
+                .map(|m| m.address)
+                .collect()
+        };
+
+        assert_eq!(
+            addresses("\"Doe, John\" <j@x.com>, c@z.com, Team: a@y.com, b@y.com;"),
+            vec!["j@x.com", "c@z.com", "a@y.com", "b@y.com"]
+        );
+        assert!(addresses("undisclosed-recipients:;").is_empty());
+        assert!(addresses("not an address").is_empty());
+    }
+}
@@ -17,6 +17,7 @@
 //!
 //! - `models`: Core data structures (EmailAccount, SenderInfo, etc.)
 //! - `analysis`: Newsletter detection and email analysis heuristics
+//! - `email`: Email address validation and normalization
 //! - `planner`: Cleanup action planning and strategy selection
 //!
 //! # Design Principles
@@ -27,5 +28,6 @@
 //! - **Single Responsibility**: Each module has one clear purpose
 
 pub mod analysis;
+pub mod email;
 pub mod models;
 pub mod planner;
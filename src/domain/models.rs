@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Email account metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,10 +12,27 @@ pub struct EmailAccount {
 
     /// When the account was added
     pub added_at: DateTime<Utc>,
+
+    /// Require typing the address before any destructive cleanup
+    #[serde(default)]
+    pub protected: bool,
+}
+
+impl EmailAccount {
+    /// Whether `typed` confirms a cleanup on this account
+    ///
+    /// The address must be typed in full; case and surrounding whitespace
+    /// are ignored.
+    pub fn confirms(&self, typed: &str) -> bool {
+        typed.trim().eq_ignore_ascii_case(&self.email)
+    }
 }
 
 /// Information about a unique sender
-#[derive(Debug, Clone)]
+///
+/// `Default` is an empty sender with no messages, handy as the base of a
+/// struct update in tests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SenderInfo {
     /// Sender email address
     pub email: String,
@@ -36,10 +54,121 @@ pub struct SenderInfo {
 
     /// Sample subject lines
     pub sample_subjects: Vec<String>,
+
+    /// Message dates, aligned with `message_uids` (None if unknown)
+    pub message_dates: Vec<Option<DateTime<Utc>>>,
+
+    /// Date of the newest message from this sender (None if no date is known)
+    #[serde(default)]
+    pub last_message_date: Option<DateTime<Utc>>,
+
+    /// Whether messages carry a List-Id header (RFC 2919)
+    pub has_list_id: bool,
+
+    /// One-click unsubscribe URLs in preference order (HTTPS first)
+    #[serde(default)]
+    pub unsubscribe_urls: Vec<String>,
+
+    /// Whether the unsubscribe link's host is unrelated to the sender's domain
+    #[serde(default)]
+    pub suspicious_unsubscribe: bool,
+
+    /// Whether the sender matches the imported blocklist
+    #[serde(default)]
+    pub blocklisted: bool,
+
+    /// Number of scanned messages that were still unread
+    ///
+    /// Counted over the whole scan, even when cleanup is later restricted by
+    /// age. Messages whose read state is unknown count as read.
+    #[serde(default)]
+    pub unread_count: usize,
+
+    /// Whether recent messages stopped offering unsubscribe while older ones
+    /// did, a hint that the user already unsubscribed
+    #[serde(default)]
+    pub unsubscribe_dropped: bool,
+}
+
+impl SenderInfo {
+    /// URLs to try for one-click unsubscribe, in order
+    ///
+    /// Falls back to the method's URL for senders analyzed before
+    /// `unsubscribe_urls` existed (e.g. cached scans).
+    pub fn one_click_urls(&self) -> Vec<String> {
+        match &self.unsubscribe_method {
+            UnsubscribeMethod::OneClick { url } if self.unsubscribe_urls.is_empty() => {
+                vec![url.clone()]
+            }
+            UnsubscribeMethod::OneClick { .. } => self.unsubscribe_urls.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Keep only messages dated strictly before `cutoff`
+    ///
+    /// Messages with an unknown date are dropped, so they are never cleaned
+    /// by an age-restricted run. `message_count` is updated to match.
+    pub fn retain_older_than(&mut self, cutoff: DateTime<Utc>) {
+        let (uids, dates): (Vec<u32>, Vec<Option<DateTime<Utc>>>) = self
+            .message_uids
+            .iter()
+            .zip(&self.message_dates)
+            .filter(|(_, date)| matches!(date, Some(d) if *d < cutoff))
+            .map(|(uid, date)| (*uid, *date))
+            .unzip();
+
+        self.message_uids = uids;
+        self.set_message_dates(dates);
+        self.message_count = self.message_uids.len();
+    }
+
+    /// Split UIDs into the `keep` newest messages and the older rest
+    ///
+    /// Returns `(kept, older)`. Messages are ordered by date, newest first,
+    /// with the higher UID winning ties. Messages with an unknown date are
+    /// always kept and don't count toward `keep`, so they are never deleted.
+    pub fn split_keep_latest(&self, keep: usize) -> (Vec<u32>, Vec<u32>) {
+        let mut dated: Vec<(DateTime<Utc>, u32)> = Vec::new();
+        let mut kept = Vec::new();
+
+        for (uid, date) in self.message_uids.iter().zip(&self.message_dates) {
+            match date {
+                Some(date) => dated.push((*date, *uid)),
+                None => kept.push(*uid),
+            }
+        }
+        // Senders analyzed without dates keep everything
+        kept.extend(self.message_uids.iter().skip(self.message_dates.len()));
+
+        dated.sort_by(|a, b| b.cmp(a));
+        let older = dated.split_off(keep.min(dated.len()));
+        kept.extend(dated.into_iter().map(|(_, uid)| uid));
+        kept.sort_unstable();
+
+        let mut older: Vec<u32> = older.into_iter().map(|(_, uid)| uid).collect();
+        older.sort_unstable();
+
+        (kept, older)
+    }
+
+    /// Share of this sender's messages that were never opened (0.0 - 1.0)
+    pub fn unread_ratio(&self) -> f32 {
+        if self.message_count == 0 {
+            return 0.0;
+        }
+        (self.unread_count as f32 / self.message_count as f32).min(1.0)
+    }
+
+    /// Set the per-message dates and update `last_message_date` to the newest
+    pub fn set_message_dates(&mut self, dates: Vec<Option<DateTime<Utc>>>) {
+        self.last_message_date = dates.iter().flatten().max().copied();
+        self.message_dates = dates;
+    }
 }
 
 /// Unsubscribe method
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum UnsubscribeMethod {
     /// One-click HTTP POST unsubscribe
     OneClick { url: String },
@@ -51,6 +180,7 @@ pub enum UnsubscribeMethod {
     Mailto { address: String },
 
     /// No unsubscribe method found
+    #[default]
     None,
 }
 
@@ -64,6 +194,42 @@ impl UnsubscribeMethod {
     pub fn is_available(&self) -> bool {
         !matches!(self, UnsubscribeMethod::None)
     }
+
+    /// Short machine-readable name of the method
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UnsubscribeMethod::OneClick { .. } => "one-click",
+            UnsubscribeMethod::HttpLink { .. } => "http-link",
+            UnsubscribeMethod::Mailto { .. } => "mailto",
+            UnsubscribeMethod::None => "none",
+        }
+    }
+}
+
+/// How scanned messages are grouped into senders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SenderGrouping {
+    /// One entry per From address (default)
+    #[default]
+    Address,
+
+    /// One entry per From domain, merging all subaddresses
+    Domain,
+}
+
+/// Order in which senders are offered for selection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SenderOrder {
+    /// Highest heuristic score first (default)
+    #[default]
+    Score,
+
+    /// Most recent message first; senders with no known date last
+    Recency,
+
+    /// Most messages first, regardless of score
+    Volume,
 }
 
 /// Planned cleanup action for a sender
@@ -77,8 +243,7 @@ pub struct CleanupAction {
 }
 
 /// Type of cleanup action
-#[derive(Debug, Clone, PartialEq)]
-
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ActionType {
     /// Unsubscribe via one-click, then delete
     UnsubscribeAndDelete,
@@ -90,8 +255,18 @@ pub enum ActionType {
     DeleteOnly,
 }
 
+impl std::fmt::Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ActionType::UnsubscribeAndDelete => "Unsubscribe + delete",
+            ActionType::SpamAndDelete => "Move to spam",
+            ActionType::DeleteOnly => "Delete",
+        })
+    }
+}
+
 /// Result of a cleanup operation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanupResult {
     /// Sender email
     pub sender_email: String,
@@ -105,6 +280,17 @@ pub struct CleanupResult {
     /// Whether unsubscribe succeeded (if attempted)
     pub unsubscribe_success: Option<bool>,
 
+    /// Host of the one-click unsubscribe URL (if attempted)
+    pub unsubscribe_host: Option<String>,
+
+    /// HTTP method the endpoint accepted (if the unsubscribe succeeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unsubscribe_via: Option<UnsubscribeVia>,
+
+    /// Step that finally dealt with the sender (None if nothing succeeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<ResolutionStep>,
+
     /// Error message if any
     pub error: Option<String>,
 }
@@ -122,6 +308,9 @@ impl CleanupResult {
             action,
             messages_deleted,
             unsubscribe_success,
+            unsubscribe_host: None,
+            unsubscribe_via: None,
+            resolved_by: None,
             error: None,
         }
     }
@@ -133,9 +322,635 @@ impl CleanupResult {
             action,
             messages_deleted: 0,
             unsubscribe_success: None,
+            unsubscribe_host: None,
+            unsubscribe_via: None,
+            resolved_by: None,
             error: Some(error),
         }
     }
+
+    /// Record the unsubscribe URL host, if an unsubscribe was attempted
+    pub fn with_unsubscribe_host(mut self, host: Option<String>) -> Self {
+        if self.unsubscribe_success.is_some() {
+            self.unsubscribe_host = host;
+        }
+        self
+    }
+
+    /// Record the HTTP method that unsubscribed, if the unsubscribe succeeded
+    pub fn with_unsubscribe_via(mut self, via: Option<UnsubscribeVia>) -> Self {
+        if self.unsubscribe_success == Some(true) {
+            self.unsubscribe_via = via;
+        }
+        self
+    }
+
+    /// Record that `step` succeeded, unless an earlier step already did
+    pub fn resolve(&mut self, step: ResolutionStep) {
+        self.resolved_by.get_or_insert(step);
+    }
+}
+
+/// Step of the unsubscribe cascade that dealt with a sender
+///
+/// When one-click unsubscribe is rejected, interactive cleanup falls back to
+/// the sender's other unsubscribe page, then to moving its mail to spam or
+/// trash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolutionStep {
+    /// One-click unsubscribe succeeded
+    OneClick,
+
+    /// The user confirmed unsubscribing on the page opened in a browser
+    ManualLink,
+
+    /// Messages were moved to spam
+    Spam,
+
+    /// Messages were moved to trash
+    Delete,
+}
+
+impl std::fmt::Display for ResolutionStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::OneClick => "one-click unsubscribe",
+            Self::ManualLink => "unsubscribe page",
+            Self::Spam => "moved to spam",
+            Self::Delete => "deleted",
+        })
+    }
+}
+
+/// HTTP method a one-click unsubscribe endpoint accepted
+///
+/// RFC 8058 asks for a POST, but some endpoints reject it and treat a GET of
+/// the same URL as the confirmation instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsubscribeVia {
+    Post,
+    Get,
+}
+
+impl std::fmt::Display for UnsubscribeVia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Post => "POST",
+            Self::Get => "GET",
+        })
+    }
+}
+
+/// Aggregate counts over the results of a cleanup run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupSummary {
+    /// Number of senders processed
+    pub senders: usize,
+
+    /// Total messages removed from the inbox
+    pub messages_deleted: usize,
+
+    /// Unsubscribe attempts that succeeded
+    pub unsubscribes_succeeded: usize,
+
+    /// Unsubscribe attempts that failed
+    pub unsubscribes_failed: usize,
+
+    /// Senders whose cleanup reported an error
+    pub errors: usize,
+}
+
+impl CleanupSummary {
+    /// Summarize a list of per-sender results
+    pub fn from_results(results: &[CleanupResult]) -> Self {
+        results.iter().fold(Self::default(), |mut summary, result| {
+            summary.senders += 1;
+            summary.messages_deleted += result.messages_deleted;
+            match result.unsubscribe_success {
+                Some(true) => summary.unsubscribes_succeeded += 1,
+                Some(false) => summary.unsubscribes_failed += 1,
+                None => {}
+            }
+            if result.error.is_some() {
+                summary.errors += 1;
+            }
+            summary
+        })
+    }
+}
+
+impl std::fmt::Display for CleanupSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Deleted {} across {}, {} succeeded, {} failed",
+            plural(self.messages_deleted, "message"),
+            plural(self.senders, "sender"),
+            plural(self.unsubscribes_succeeded, "unsubscribe"),
+            self.unsubscribes_failed
+        )?;
+
+        if self.errors > 0 {
+            write!(f, " ({} with errors)", plural(self.errors, "sender"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What happened to a sender's subscription in a cleanup run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnsubscribeOutcome {
+    /// The unsubscribe request succeeded
+    Unsubscribed,
+
+    /// An unsubscribe was attempted and failed
+    Failed,
+
+    /// The sender offers no one-click unsubscribe, so none could be sent
+    NoMethod,
+
+    /// One-click unsubscribe was available but not attempted
+    Skipped,
+}
+
+impl UnsubscribeOutcome {
+    /// Every outcome, in report order
+    pub const ALL: [Self; 4] = [
+        Self::Unsubscribed,
+        Self::Failed,
+        Self::NoMethod,
+        Self::Skipped,
+    ];
+
+    /// Classify a sender's result given its unsubscribe method
+    pub fn classify(result: &CleanupResult, method: &UnsubscribeMethod) -> Self {
+        match result.unsubscribe_success {
+            Some(true) => Self::Unsubscribed,
+            Some(false) => Self::Failed,
+            None if method.is_one_click() => Self::Skipped,
+            None => Self::NoMethod,
+        }
+    }
+}
+
+impl std::fmt::Display for UnsubscribeOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Unsubscribed => "unsubscribed",
+            Self::Failed => "unsubscribe failed",
+            Self::NoMethod => "no unsubscribe method",
+            Self::Skipped => "skipped",
+        })
+    }
+}
+
+/// Per-sender unsubscribe outcomes of a cleanup run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsubscribeReport {
+    /// Sender email and outcome, in result order
+    pub entries: Vec<(String, UnsubscribeOutcome)>,
+}
+
+impl UnsubscribeReport {
+    /// Classify each result using the method of the sender it belongs to
+    ///
+    /// Results without a matching sender are treated as having no method.
+    pub fn from_results(results: &[CleanupResult], senders: &[SenderInfo]) -> Self {
+        let entries = results
+            .iter()
+            .map(|result| {
+                let method = senders
+                    .iter()
+                    .find(|s| s.email == result.sender_email)
+                    .map_or(&UnsubscribeMethod::None, |s| &s.unsubscribe_method);
+                (
+                    result.sender_email.clone(),
+                    UnsubscribeOutcome::classify(result, method),
+                )
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Number of senders with `outcome`
+    pub fn count(&self, outcome: UnsubscribeOutcome) -> usize {
+        self.entries.iter().filter(|(_, o)| *o == outcome).count()
+    }
+
+    /// Senders with `outcome`, in result order
+    pub fn senders_with(&self, outcome: UnsubscribeOutcome) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(move |(_, o)| *o == outcome)
+            .map(|(email, _)| email.as_str())
+    }
+}
+
+impl std::fmt::Display for UnsubscribeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts: Vec<String> = UnsubscribeOutcome::ALL
+            .iter()
+            .map(|outcome| format!("{} {}", self.count(*outcome), outcome))
+            .collect();
+        f.write_str(&counts.join(", "))
+    }
+}
+
+/// One-click unsubscribe outcomes for one ESP host
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EspHostStats {
+    /// Unsubscribe requests sent to this host
+    pub attempts: usize,
+
+    /// Requests the host accepted
+    pub successes: usize,
+}
+
+impl EspHostStats {
+    /// Fraction of attempts that succeeded (0.0 without attempts)
+    pub fn success_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.successes as f64 / self.attempts as f64
+    }
+}
+
+/// One-click unsubscribe outcomes per ESP host, kept locally across runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EspStats {
+    /// Outcomes keyed by unsubscribe URL host
+    pub hosts: BTreeMap<String, EspHostStats>,
+}
+
+impl EspStats {
+    /// Count every result with an attempted unsubscribe and a known host
+    pub fn record_results(&mut self, results: &[CleanupResult]) {
+        for result in results {
+            let (Some(success), Some(host)) =
+                (result.unsubscribe_success, &result.unsubscribe_host)
+            else {
+                continue;
+            };
+
+            let stats = self.hosts.entry(host.clone()).or_default();
+            stats.attempts += 1;
+            if success {
+                stats.successes += 1;
+            }
+        }
+    }
+
+    /// Hosts ordered by most attempts, then by name
+    pub fn by_attempts(&self) -> Vec<(&str, EspHostStats)> {
+        let mut hosts: Vec<(&str, EspHostStats)> = self
+            .hosts
+            .iter()
+            .map(|(host, stats)| (host.as_str(), *stats))
+            .collect();
+        hosts.sort_by(|a, b| b.1.attempts.cmp(&a.1.attempts).then(a.0.cmp(b.0)));
+        hosts
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Overview of a scanned mailbox, shown before choosing what to clean
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InboxSummary {
+    /// Messages in the scanned mailbox
+    pub total_messages: usize,
+
+    /// Unique senders (or domains) found
+    pub senders: usize,
+
+    /// Senders scoring at least the newsletter threshold
+    pub newsletter_senders: usize,
+
+    /// Messages sent by those senders
+    pub newsletter_messages: usize,
+
+    /// Approximate mailbox size when the scan stopped at its time budget
+    ///
+    /// None for a complete scan. When set, `total_messages` only counts the
+    /// messages that were scanned.
+    pub partial_of: Option<usize>,
+}
+
+impl InboxSummary {
+    /// Summarize analyzed senders
+    ///
+    /// Senders scoring at least `min_score` count as newsletters.
+    /// `total_messages` is the mailbox size from the scan; if it is smaller
+    /// than the messages attributed to senders, the latter is used instead.
+    pub fn from_senders(total_messages: usize, senders: &[SenderInfo], min_score: f32) -> Self {
+        let attributed: usize = senders.iter().map(|s| s.message_count).sum();
+        let newsletters: Vec<&SenderInfo> = senders
+            .iter()
+            .filter(|s| s.heuristic_score >= min_score)
+            .collect();
+
+        Self {
+            total_messages: total_messages.max(attributed),
+            senders: senders.len(),
+            newsletter_senders: newsletters.len(),
+            newsletter_messages: newsletters.iter().map(|s| s.message_count).sum(),
+            partial_of: None,
+        }
+    }
+
+    /// Mark the summary as covering part of a mailbox of about
+    /// `mailbox_messages` messages
+    pub fn partial(mut self, mailbox_messages: usize) -> Self {
+        self.partial_of = Some(mailbox_messages.max(self.total_messages));
+        self
+    }
+
+    /// Share of the mailbox sent by newsletters, as a whole percentage
+    pub fn newsletter_percent(&self) -> u32 {
+        if self.total_messages == 0 {
+            return 0;
+        }
+        (self.newsletter_messages as f64 * 100.0 / self.total_messages as f64).round() as u32
+    }
+}
+
+/// Scan result for one of several stored accounts
+#[derive(Debug, Clone)]
+pub struct AccountScan {
+    /// Account that was scanned
+    pub email: String,
+
+    /// Analyzed senders in that account's inbox
+    pub senders: Vec<SenderInfo>,
+}
+
+impl AccountScan {
+    /// Overview of this account's inbox
+    pub fn summary(&self, min_score: f32) -> InboxSummary {
+        InboxSummary::from_senders(0, &self.senders, min_score)
+    }
+}
+
+/// A sender aggregated across several accounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedSender {
+    /// Sender email address (or domain, depending on grouping)
+    pub email: String,
+
+    /// Messages from this sender in all accounts
+    pub message_count: usize,
+
+    /// Highest score the sender got in any account
+    pub heuristic_score: f32,
+
+    /// Accounts the sender was found in, sorted
+    pub accounts: Vec<String>,
+}
+
+/// Persisted statistics for one cleanup run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleanupReport {
+    /// Account that was cleaned
+    pub account: String,
+
+    /// When the run finished
+    pub created_at: DateTime<Utc>,
+
+    /// Messages whose headers were scanned
+    pub messages_scanned: usize,
+
+    /// Distinct senders found by the scan
+    pub unique_senders: usize,
+
+    /// Senders that looked like newsletters and were offered for cleanup
+    pub newsletters: usize,
+
+    /// Senders with at least one message removed or a successful unsubscribe
+    pub senders_cleaned: usize,
+
+    /// Messages removed from the inbox
+    pub messages_deleted: usize,
+
+    /// One-click unsubscribe requests attempted
+    pub unsubscribes_attempted: usize,
+
+    /// One-click unsubscribe requests that succeeded
+    pub unsubscribes_succeeded: usize,
+}
+
+impl CleanupReport {
+    /// Build a report from the scanned senders and the cleanup results
+    pub fn new(
+        account: &str,
+        created_at: DateTime<Utc>,
+        senders: &[SenderInfo],
+        newsletters: usize,
+        results: &[CleanupResult],
+    ) -> Self {
+        let summary = CleanupSummary::from_results(results);
+
+        Self {
+            account: account.to_string(),
+            created_at,
+            messages_scanned: senders.iter().map(|s| s.message_count).sum(),
+            unique_senders: senders.len(),
+            newsletters,
+            senders_cleaned: results
+                .iter()
+                .filter(|r| r.messages_deleted > 0 || r.unsubscribe_success == Some(true))
+                .count(),
+            messages_deleted: summary.messages_deleted,
+            unsubscribes_attempted: summary.unsubscribes_succeeded + summary.unsubscribes_failed,
+            unsubscribes_succeeded: summary.unsubscribes_succeeded,
+        }
+    }
+}
+
+impl std::fmt::Display for CleanupReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} ({})",
+            self.account,
+            self.created_at.format("%Y-%m-%d %H:%M UTC")
+        )?;
+        writeln!(f, "  Messages scanned:     {}", self.messages_scanned)?;
+        writeln!(f, "  Unique senders:       {}", self.unique_senders)?;
+        writeln!(f, "  Newsletters detected: {}", self.newsletters)?;
+        writeln!(f, "  Senders cleaned:      {}", self.senders_cleaned)?;
+        writeln!(f, "  Messages deleted:     {}", self.messages_deleted)?;
+        write!(
+            f,
+            "  Unsubscribes:         {}/{} succeeded",
+            self.unsubscribes_succeeded, self.unsubscribes_attempted
+        )
+    }
+}
+
+/// Messages moved out of the inbox by one cleanup step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleanupOperation {
+    /// Sender whose messages were moved
+    pub sender_email: String,
+
+    /// Mailbox the messages were moved out of (restored there on undo)
+    #[serde(default = "default_source_mailbox")]
+    pub source: String,
+
+    /// Folder the messages were moved to (trash or spam)
+    pub folder: String,
+
+    /// UIDs in the source mailbox at the time of the cleanup
+    pub uids: Vec<u32>,
+
+    /// Message-ID headers, used to find the messages again since UIDs
+    /// differ between folders
+    pub message_ids: Vec<String>,
+
+    /// When the messages were moved
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Operations logged before mailbox selection existed all came from INBOX
+fn default_source_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+/// All operations performed during one cleanup run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleanupRecord {
+    /// When the run started (groups operations of the same run)
+    pub started_at: DateTime<Utc>,
+
+    /// Operations in execution order
+    pub operations: Vec<CleanupOperation>,
+}
+
+/// Progress of an interactive cleanup run, persisted so it can be resumed
+///
+/// Senders are cleaned in order, so everything before the first sender
+/// without a result is done.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupProgress {
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+
+    /// Mailbox being cleaned
+    pub mailbox: String,
+
+    /// Senders selected for cleanup, in processing order
+    pub senders: Vec<SenderInfo>,
+
+    /// Results of the senders completed so far
+    pub results: Vec<CleanupResult>,
+}
+
+impl CleanupProgress {
+    /// Start tracking a run over `senders`
+    pub fn new(started_at: DateTime<Utc>, mailbox: &str, senders: Vec<SenderInfo>) -> Self {
+        Self {
+            started_at,
+            mailbox: mailbox.to_string(),
+            senders,
+            results: Vec::new(),
+        }
+    }
+
+    /// Index of the next sender to clean
+    ///
+    /// Counts leading senders that have a result, so a journal whose results
+    /// don't line up with the senders resumes at the first mismatch.
+    pub fn resume_point(&self) -> usize {
+        self.senders
+            .iter()
+            .zip(&self.results)
+            .take_while(|(sender, result)| sender.email == result.sender_email)
+            .count()
+    }
+
+    /// Senders not cleaned yet
+    pub fn pending(&self) -> &[SenderInfo] {
+        &self.senders[self.resume_point()..]
+    }
+
+    /// Whether every sender has been cleaned
+    pub fn is_complete(&self) -> bool {
+        self.resume_point() == self.senders.len()
+    }
+
+    /// Record a finished sender
+    pub fn complete(&mut self, result: CleanupResult) {
+        self.results.truncate(self.resume_point());
+        self.results.push(result);
+    }
+}
+
+/// Gmail access requested when signing in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessScope {
+    /// `gmail.readonly`: list and read messages through the Gmail API.
+    /// No IMAP, so nothing can be deleted, moved, labeled or restored.
+    ReadOnly,
+
+    /// `https://mail.google.com/`: full IMAP access, needed for cleanup and
+    /// undo
+    #[default]
+    Full,
+
+    /// [`AccessScope::Full`] plus `gmail.settings.basic`, which lets blocked
+    /// senders get a Gmail filter for their future mail
+    FullWithFilters,
+}
+
+impl AccessScope {
+    /// OAuth2 scope to request (space-separated URLs)
+    pub fn url(self) -> &'static str {
+        match self {
+            AccessScope::ReadOnly => "https://www.googleapis.com/auth/gmail.readonly",
+            AccessScope::Full => "https://mail.google.com/",
+            AccessScope::FullWithFilters => {
+                "https://mail.google.com/ https://www.googleapis.com/auth/gmail.settings.basic"
+            }
+        }
+    }
+
+    /// Whether messages can be changed with this scope
+    pub fn allows_changes(self) -> bool {
+        self != AccessScope::ReadOnly
+    }
+
+    /// Whether Gmail filters can be created with this scope
+    pub fn allows_filters(self) -> bool {
+        self == AccessScope::FullWithFilters
+    }
+
+    /// Whether a token with this scope can be used where `needed` is required
+    pub fn covers(self, needed: AccessScope) -> bool {
+        (self.allows_changes() || !needed.allows_changes())
+            && (self.allows_filters() || !needed.allows_filters())
+    }
+}
+
+impl std::fmt::Display for AccessScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessScope::ReadOnly => write!(f, "scan only"),
+            AccessScope::Full => write!(f, "full access"),
+            AccessScope::FullWithFilters => write!(f, "full access with filters"),
+        }
+    }
 }
 
 /// OAuth2 token storage
@@ -149,6 +964,11 @@ pub struct OAuth2Token {
 
     /// Token expiry time
     pub expires_at: DateTime<Utc>,
+
+    /// Scope granted at sign-in (tokens stored before scopes were
+    /// selectable always had full access)
+    #[serde(default)]
+    pub scope: AccessScope,
 }
 
 impl OAuth2Token {
@@ -1,6 +1,66 @@
 //! Action planning logic
 
-use super::models::{ActionType, CleanupAction, SenderInfo};
+use super::analysis;
+use super::models::{
+    AccountScan, ActionType, CleanupAction, CombinedSender, SenderInfo, SenderOrder,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Default minimum score for a sender to be offered for cleanup
+pub const DEFAULT_MIN_DISPLAY_SCORE: f32 = 0.6;
+
+/// Default maximum number of messages a single run may delete
+pub const DEFAULT_MAX_DELETE: usize = 5000;
+
+/// Default share of unread messages for a sender to count as never opened
+pub const DEFAULT_MIN_UNREAD_RATIO: f32 = 0.9;
+
+/// Fewest messages a sender needs before its unread ratio is trusted
+pub const MIN_MESSAGES_FOR_UNREAD: usize = 3;
+
+/// Running count of messages removed in one run, checked against a cap
+///
+/// Messages moved to trash or spam both count. The cap is a guardrail on top
+/// of per-sender confirmations; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeletionCap {
+    limit: Option<usize>,
+    deleted: usize,
+}
+
+impl DeletionCap {
+    /// Start a run with no deletions
+    pub fn new(limit: Option<usize>) -> Self {
+        Self { limit, deleted: 0 }
+    }
+
+    /// Whether removing `count` more messages would go over the cap
+    pub fn would_exceed(&self, count: usize) -> bool {
+        self.limit
+            .is_some_and(|limit| self.deleted.saturating_add(count) > limit)
+    }
+
+    /// Add `count` removed messages to the running total
+    pub fn record(&mut self, count: usize) {
+        self.deleted = self.deleted.saturating_add(count);
+    }
+
+    /// Remove the cap for the rest of the run after an explicit override
+    pub fn lift(&mut self) {
+        self.limit = None;
+    }
+
+    /// Messages removed so far
+    pub fn deleted(&self) -> usize {
+        self.deleted
+    }
+
+    /// Configured cap, if any
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+}
 
 /// Plan cleanup action for a sender
 ///
@@ -25,11 +85,215 @@ pub fn plan_actions(senders: Vec<SenderInfo>) -> Vec<CleanupAction> {
     senders.into_iter().map(plan_action).collect()
 }
 
+/// Plan moving every sender's messages to trash, without unsubscribing or
+/// reporting spam
+///
+/// Used when cleaning by volume, where senders may be personal contacts.
+pub fn plan_deletions(senders: Vec<SenderInfo>) -> Vec<CleanupAction> {
+    senders
+        .into_iter()
+        .map(|sender| CleanupAction {
+            sender,
+            action_type: ActionType::DeleteOnly,
+        })
+        .collect()
+}
+
+/// The `count` senders with the most messages, ignoring scores
+///
+/// Unlike [`selection_candidates`] this may include personal contacts.
+/// Allowlisted senders are never included.
+pub fn top_by_volume(
+    senders: &[SenderInfo],
+    allowlist: &[String],
+    count: usize,
+) -> Vec<SenderInfo> {
+    let mut top: Vec<SenderInfo> = senders
+        .iter()
+        .filter(|s| !analysis::is_allowlisted(&s.email, allowlist))
+        .cloned()
+        .collect();
+
+    sort_senders(&mut top, SenderOrder::Volume);
+    top.truncate(count);
+    top
+}
+
+/// Senders whose messages are mostly left unread, most unread first
+///
+/// Only senders with at least [`MIN_MESSAGES_FOR_UNREAD`] messages and an
+/// unread ratio of `min_unread_ratio` or more are kept. Scores are ignored,
+/// but allowlisted senders are never included.
+pub fn never_opened(
+    senders: &[SenderInfo],
+    allowlist: &[String],
+    min_unread_ratio: f32,
+) -> Vec<SenderInfo> {
+    let mut unread: Vec<SenderInfo> = senders
+        .iter()
+        .filter(|s| !analysis::is_allowlisted(&s.email, allowlist))
+        .filter(|s| s.message_count >= MIN_MESSAGES_FOR_UNREAD)
+        .filter(|s| s.unread_ratio() >= min_unread_ratio)
+        .cloned()
+        .collect();
+
+    unread.sort_by(|a, b| {
+        b.unread_count
+            .cmp(&a.unread_count)
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    unread
+}
+
+/// Merge the senders of several account scans, most messages first
+///
+/// A sender found in more than one account keeps its highest score. Ties
+/// are broken by address so the order is stable.
+pub fn combine_account_scans(scans: &[AccountScan]) -> Vec<CombinedSender> {
+    let mut combined: HashMap<&str, CombinedSender> = HashMap::new();
+
+    for scan in scans {
+        for sender in &scan.senders {
+            let entry = combined
+                .entry(sender.email.as_str())
+                .or_insert_with(|| CombinedSender {
+                    email: sender.email.clone(),
+                    message_count: 0,
+                    heuristic_score: sender.heuristic_score,
+                    accounts: Vec::new(),
+                });
+            entry.message_count += sender.message_count;
+            entry.heuristic_score = entry.heuristic_score.max(sender.heuristic_score);
+            if !entry.accounts.contains(&scan.email) {
+                entry.accounts.push(scan.email.clone());
+            }
+        }
+    }
+
+    let mut combined: Vec<CombinedSender> = combined
+        .into_values()
+        .map(|mut sender| {
+            sender.accounts.sort();
+            sender
+        })
+        .collect();
+    combined.sort_by(|a, b| {
+        b.message_count
+            .cmp(&a.message_count)
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    combined
+}
+
+/// Senders worth offering for cleanup
+///
+/// A sender is offered when its score reaches `min_display_score`, or when it
+/// has an unsubscribe method or a List-Id regardless of score. This keeps
+/// personal contacts out of the list unless they look like mailing lists.
+///
+/// Blocklisted senders are always offered. The allowlist still wins:
+/// allowlisted senders are never offered, however high their score or
+/// `min_display_score` is set.
+pub fn selection_candidates(
+    senders: &[SenderInfo],
+    allowlist: &[String],
+    min_display_score: f32,
+) -> Vec<SenderInfo> {
+    senders
+        .iter()
+        .filter(|s| !analysis::is_allowlisted(&s.email, allowlist))
+        .filter(|s| {
+            s.blocklisted
+                || s.heuristic_score >= min_display_score
+                || s.unsubscribe_method.is_available()
+                || s.has_list_id
+        })
+        .cloned()
+        .collect()
+}
+
+/// Flag senders matching `blocklist`, returning how many were flagged
+///
+/// Senders covered by `allowlist` are never flagged.
+pub fn mark_blocklisted(
+    senders: &mut [SenderInfo],
+    blocklist: &[String],
+    allowlist: &[String],
+) -> usize {
+    let mut count = 0;
+    for sender in senders {
+        sender.blocklisted = analysis::is_blocklisted(&sender.email, blocklist, allowlist);
+        count += usize::from(sender.blocklisted);
+    }
+    count
+}
+
+/// Sort senders in place for display
+///
+/// Ties are broken by email, so equal scores or dates keep the same order
+/// from one run to the next.
+pub fn sort_senders(senders: &mut [SenderInfo], order: SenderOrder) {
+    match order {
+        SenderOrder::Score => senders.sort_by(|a, b| {
+            b.heuristic_score
+                .partial_cmp(&a.heuristic_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.email.cmp(&b.email))
+        }),
+        // `None` sorts before `Some`, so reversing puts undated senders last
+        SenderOrder::Recency => senders.sort_by(|a, b| {
+            b.last_message_date
+                .cmp(&a.last_message_date)
+                .then_with(|| a.email.cmp(&b.email))
+        }),
+        SenderOrder::Volume => senders.sort_by(|a, b| {
+            b.message_count
+                .cmp(&a.message_count)
+                .then_with(|| a.email.cmp(&b.email))
+        }),
+    }
+}
+
+/// Restrict senders to messages older than `older_than` relative to `now`
+///
+/// Senders left without any matching message are dropped. `None` keeps
+/// everything unchanged.
+pub fn filter_older_than(
+    senders: Vec<SenderInfo>,
+    older_than: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<SenderInfo> {
+    let Some(older_than) = older_than else {
+        return senders;
+    };
+
+    let cutoff = now - older_than;
+
+    senders
+        .into_iter()
+        .filter_map(|mut sender| {
+            sender.retain_older_than(cutoff);
+            (sender.message_count > 0).then_some(sender)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::domain::models::UnsubscribeMethod;
 
+    /// A sender with `count` messages and no unsubscribe method
+    fn sender(email: &str, count: usize, score: f32) -> SenderInfo {
+        SenderInfo {
+            email: email.to_string(),
+            message_count: count,
+            message_uids: (1..=count as u32).collect(),
+            heuristic_score: score,
+            ..SenderInfo::default()
+        }
+    }
+
     #[test]
     fn test_plan_action_one_click() {
         let sender = SenderInfo {
@@ -41,7 +305,7 @@ mod tests {
                 url: "https://example.com/unsub".to_string(),
             },
             heuristic_score: 0.8,
-            sample_subjects: vec![],
+            ..SenderInfo::default()
         };
 
         let action = plan_action(sender);
@@ -52,15 +316,230 @@ mod tests {
     fn test_plan_action_no_unsubscribe() {
         let sender = SenderInfo {
             email: "spam@example.com".to_string(),
-            display_name: None,
             message_count: 5,
             message_uids: vec![1, 2],
-            unsubscribe_method: UnsubscribeMethod::None,
             heuristic_score: 0.3,
-            sample_subjects: vec![],
+            ..SenderInfo::default()
         };
 
         let action = plan_action(sender);
         assert_eq!(action.action_type, ActionType::SpamAndDelete);
     }
+
+    #[test]
+    fn test_selection_candidates_threshold() {
+        let sender = SenderInfo {
+            email: "shop@example.com".to_string(),
+            message_count: 8,
+            message_uids: vec![1],
+            heuristic_score: 0.55,
+            ..SenderInfo::default()
+        };
+        let senders = vec![sender];
+
+        assert!(selection_candidates(&senders, &[], DEFAULT_MIN_DISPLAY_SCORE).is_empty());
+        assert_eq!(selection_candidates(&senders, &[], 0.5).len(), 1);
+
+        // Allowlist takes precedence over any threshold
+        let allowlist = vec!["@example.com".to_string()];
+        assert!(selection_candidates(&senders, &allowlist, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_blocklisted_senders_are_offered_unless_allowlisted() {
+        let mut senders = vec![SenderInfo {
+            email: "shop@example.com".to_string(),
+            message_count: 2,
+            message_uids: vec![1],
+            heuristic_score: 0.1,
+            ..SenderInfo::default()
+        }];
+        let blocklist = vec!["@example.com".to_string()];
+
+        assert_eq!(mark_blocklisted(&mut senders, &blocklist, &[]), 1);
+        assert_eq!(
+            selection_candidates(&senders, &[], DEFAULT_MIN_DISPLAY_SCORE).len(),
+            1
+        );
+
+        // The allowlist wins over the blocklist
+        let allowlist = vec!["shop@example.com".to_string()];
+        assert_eq!(mark_blocklisted(&mut senders, &blocklist, &allowlist), 0);
+        assert!(!senders[0].blocklisted);
+        assert!(selection_candidates(&senders, &allowlist, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_filter_older_than() {
+        let now = Utc::now();
+        let sender = SenderInfo {
+            email: "news@example.com".to_string(),
+            message_count: 3,
+            message_uids: vec![1, 2, 3],
+            heuristic_score: 0.8,
+            message_dates: vec![
+                Some(now - Duration::days(120)),
+                Some(now - Duration::days(10)),
+                None,
+            ],
+            ..SenderInfo::default()
+        };
+
+        let unchanged = filter_older_than(vec![sender.clone()], None, now);
+        assert_eq!(unchanged[0].message_uids, vec![1, 2, 3]);
+
+        let filtered = filter_older_than(vec![sender.clone()], Some(Duration::days(90)), now);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].message_uids, vec![1]);
+        assert_eq!(filtered[0].message_count, 1);
+
+        let none_left = filter_older_than(vec![sender], Some(Duration::days(365)), now);
+        assert!(none_left.is_empty());
+    }
+
+    #[test]
+    fn test_deletion_cap_halts_at_limit() {
+        let mut cap = DeletionCap::new(Some(100));
+        let mut cleaned = Vec::new();
+
+        for (sender, count) in [("a", 40), ("b", 60), ("c", 1), ("d", 10)] {
+            if cap.would_exceed(count) {
+                break;
+            }
+            cap.record(count);
+            cleaned.push(sender);
+        }
+
+        assert_eq!(cleaned, vec!["a", "b"]);
+        assert_eq!(cap.deleted(), 100);
+
+        cap.lift();
+        assert!(!cap.would_exceed(usize::MAX));
+        assert!(!DeletionCap::new(None).would_exceed(1_000_000));
+    }
+
+    #[test]
+    fn test_sort_senders_by_recency() {
+        let now = Utc::now();
+        let mut senders: Vec<SenderInfo> = [
+            ("old@example.com", Some(now - Duration::days(300))),
+            ("undated@example.com", None),
+            ("new@example.com", Some(now - Duration::days(1))),
+        ]
+        .into_iter()
+        .map(|(email, last)| SenderInfo {
+            email: email.to_string(),
+            message_count: 1,
+            message_uids: vec![1],
+            heuristic_score: 1.0,
+            message_dates: vec![last],
+            last_message_date: last,
+            ..SenderInfo::default()
+        })
+        .collect();
+
+        sort_senders(&mut senders, SenderOrder::Recency);
+
+        let order: Vec<&str> = senders.iter().map(|s| s.email.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["new@example.com", "old@example.com", "undated@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_sort_senders_breaks_score_ties_by_email() {
+        let mut forward = vec![
+            sender("b@example.com", 1, 0.8),
+            sender("c@example.com", 1, 0.9),
+            sender("a@example.com", 1, 0.8),
+        ];
+        let mut reversed: Vec<SenderInfo> = forward.iter().rev().cloned().collect();
+
+        sort_senders(&mut forward, SenderOrder::Score);
+        sort_senders(&mut reversed, SenderOrder::Score);
+
+        let order = |senders: &[SenderInfo]| -> Vec<String> {
+            senders.iter().map(|s| s.email.clone()).collect()
+        };
+        assert_eq!(
+            order(&forward),
+            vec!["c@example.com", "a@example.com", "b@example.com"]
+        );
+        assert_eq!(order(&forward), order(&reversed));
+    }
+
+    #[test]
+    fn test_top_by_volume_ignores_score_and_respects_allowlist() {
+        let senders = vec![
+            sender("news@shop.com", 40, 0.9),
+            sender("mom@family.com", 120, 0.0),
+            sender("boss@work.com", 80, 0.1),
+            sender("alerts@bank.com", 80, 0.3),
+        ];
+
+        let top = top_by_volume(&senders, &["@work.com".to_string()], 2);
+        let emails: Vec<&str> = top.iter().map(|s| s.email.as_str()).collect();
+        assert_eq!(emails, vec!["mom@family.com", "alerts@bank.com"]);
+
+        assert_eq!(top_by_volume(&senders, &[], 10).len(), 4);
+
+        let actions = plan_deletions(top);
+        assert!(actions
+            .iter()
+            .all(|a| a.action_type == ActionType::DeleteOnly));
+    }
+
+    #[test]
+    fn test_never_opened_keeps_mostly_unread_senders() {
+        let sender = |email: &str, count: usize, unread: usize| SenderInfo {
+            email: email.to_string(),
+            message_count: count,
+            message_uids: (1..=count as u32).collect(),
+            unread_count: unread,
+            ..SenderInfo::default()
+        };
+        let senders = vec![
+            sender("news@shop.com", 20, 19),
+            sender("digest@forum.org", 50, 50),
+            sender("mom@family.com", 30, 2),
+            sender("promo@work.com", 10, 10),
+            sender("once@new.com", 2, 2),
+        ];
+
+        let unread = never_opened(&senders, &["@work.com".to_string()], 0.9);
+        let emails: Vec<&str> = unread.iter().map(|s| s.email.as_str()).collect();
+        assert_eq!(emails, vec!["digest@forum.org", "news@shop.com"]);
+
+        assert!(never_opened(&senders, &[], 1.0)
+            .iter()
+            .all(|s| s.unread_count == s.message_count));
+    }
+
+    #[test]
+    fn test_combine_account_scans_merges_shared_senders() {
+        let scans = vec![
+            AccountScan {
+                email: "work@gmail.com".to_string(),
+                senders: vec![
+                    sender("news@shop.com", 10, 0.6),
+                    sender("boss@work.com", 30, 0.1),
+                ],
+            },
+            AccountScan {
+                email: "me@gmail.com".to_string(),
+                senders: vec![sender("news@shop.com", 25, 0.9)],
+            },
+        ];
+
+        let combined = combine_account_scans(&scans);
+
+        assert_eq!(combined.len(), 2);
+        assert_eq!(combined[0].email, "news@shop.com");
+        assert_eq!(combined[0].message_count, 35);
+        assert_eq!(combined[0].heuristic_score, 0.9);
+        assert_eq!(combined[0].accounts, vec!["me@gmail.com", "work@gmail.com"]);
+        assert_eq!(combined[1].email, "boss@work.com");
+        assert_eq!(combined[1].accounts, vec!["work@gmail.com"]);
+    }
 }
@@ -0,0 +1,49 @@
+//! Opening URLs in the user's browser
+//!
+//! The OAuth2 sign-in and manual unsubscribe pages go through
+//! [`BrowserOpener`], so tests and headless runs can swap the system browser
+//! for an implementation that doesn't launch anything.
+
+use anyhow::{Context, Result};
+
+/// Opens a URL for the user
+pub trait BrowserOpener: Send + Sync {
+    /// Open `url`, failing if no browser could be launched
+    fn open(&self, url: &str) -> Result<()>;
+}
+
+/// The default browser, launched through the `open` crate
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemBrowser;
+
+impl BrowserOpener for SystemBrowser {
+    fn open(&self, url: &str) -> Result<()> {
+        open::that(url).with_context(|| format!("Failed to open {} in a browser", url))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records every URL instead of opening it
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingBrowser {
+        opened: Mutex<Vec<String>>,
+    }
+
+    impl RecordingBrowser {
+        /// URLs opened so far, oldest first
+        pub fn opened(&self) -> Vec<String> {
+            self.opened.lock().unwrap().clone()
+        }
+    }
+
+    impl BrowserOpener for RecordingBrowser {
+        fn open(&self, url: &str) -> Result<()> {
+            self.opened.lock().unwrap().push(url.to_string());
+            Ok(())
+        }
+    }
+}
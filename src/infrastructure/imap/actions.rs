@@ -1,87 +1,832 @@
-//! IMAP actions (delete, move to spam)
+//! IMAP actions (delete, move to spam, label)
 
-use super::connection::ImapSession;
+use super::connection::{ImapConfig, ImapSession};
+use super::mailbox::MailboxOps;
+use super::retry::retry;
 use anyhow::{Context, Result};
-use futures::TryStreamExt;
 
-/// Delete messages by UIDs using Gmail's trash label
-pub async fn delete_messages(session: &mut ImapSession, uids: &[u32]) -> Result<usize> {
+/// Gmail label for messages staged or flagged for trash
+///
+/// On Gmail, copying a message to Trash or Spam takes it out of every other
+/// label, and `\Deleted` alone archives it under the default "Auto-Expunge"
+/// setting. Messages that must stay in the mailbox until a later
+/// confirmation (flag-only mode, two-phase deletes) get this label instead.
+pub const GMAIL_TO_TRASH_LABEL: &str = "Unsubmail/To Trash";
+
+/// Gmail label for messages staged or flagged for spam
+///
+/// See [`GMAIL_TO_TRASH_LABEL`].
+pub const GMAIL_TO_SPAM_LABEL: &str = "Unsubmail/To Spam";
+
+/// Messages staged by [`stage_delete`] and [`stage_move_to_spam`], waiting
+/// for [`commit_delete`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StagedUids {
+    /// UIDs staged for trash
+    pub trash: Vec<u32>,
+    /// UIDs staged for spam
+    pub spam: Vec<u32>,
+}
+
+impl StagedUids {
+    /// Number of staged messages
+    pub fn len(&self) -> usize {
+        self.trash.len() + self.spam.len()
+    }
+
+    /// Whether nothing is staged
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Delete messages by UIDs from `mailbox` by moving them to the configured
+/// trash folder
+///
+/// Equivalent to [`stage_delete`] followed by [`commit_delete`], as a single
+/// retried operation. Transient failures are retried according to `config.retry`.
+///
+/// With `flag_only`, the messages are copied to trash and flagged `\Deleted`
+/// but `mailbox` is not expunged: they stay visible there until the user
+/// expunges it in their mail client (see [`ImapConfig::flag_only`]). On
+/// Gmail they are only labelled [`GMAIL_TO_TRASH_LABEL`], since a copy to
+/// Trash would remove them from `mailbox` at once.
+pub async fn delete_messages<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    flag_only: bool,
+) -> Result<usize> {
+    move_uids(
+        session,
+        config,
+        mailbox,
+        uids,
+        &config.trash_folder,
+        flag_only,
+    )
+    .await
+}
+
+/// Move messages from `mailbox` to the configured spam folder
+///
+/// Transient failures are retried according to `config.retry`. See
+/// [`delete_messages`] for `flag_only`.
+pub async fn move_to_spam<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    flag_only: bool,
+) -> Result<usize> {
+    move_uids(
+        session,
+        config,
+        mailbox,
+        uids,
+        &config.spam_folder,
+        flag_only,
+    )
+    .await
+}
+
+async fn move_uids<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    folder: &str,
+    flag_only: bool,
+) -> Result<usize> {
     if uids.is_empty() {
         return Ok(0);
     }
 
     let uid_set = format_uid_set(uids);
-    let count = uids.len();
+    let result = if flag_only {
+        mark_for(session, config, mailbox, &uid_set, folder).await
+    } else {
+        move_from(session, config, mailbox, &uid_set, folder).await
+    };
+    result.with_context(|| {
+        format!(
+            "Failed to move {} messages to {} (gave up after retrying)",
+            uids.len(),
+            folder
+        )
+    })?;
 
-    // Ensure INBOX is selected (critical for IMAP operations)
-    session
-        .select("INBOX")
+    Ok(uids.len())
+}
+
+/// Phase one of a two-phase delete: copy messages to trash and flag them
+///
+/// The originals stay in `mailbox` flagged `\Deleted` until [`commit_delete`]
+/// expunges them. If the run is aborted before that, they remain flagged
+/// (and also present in trash). On Gmail, copying to Trash would take them
+/// out of `mailbox` right away, so they are only labelled
+/// [`GMAIL_TO_TRASH_LABEL`] and stay untouched until the commit.
+pub async fn stage_delete<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+) -> Result<usize> {
+    stage_move(session, config, mailbox, uids, &config.trash_folder).await
+}
+
+/// Phase one of a two-phase move to spam
+///
+/// See [`stage_delete`].
+pub async fn stage_move_to_spam<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+) -> Result<usize> {
+    stage_move(session, config, mailbox, uids, &config.spam_folder).await
+}
+
+async fn stage_move<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    folder: &str,
+) -> Result<usize> {
+    if uids.is_empty() {
+        return Ok(0);
+    }
+
+    mark_for(session, config, mailbox, &format_uid_set(uids), folder)
         .await
-        .context("Failed to select INBOX")?;
+        .with_context(|| format!("Failed to stage {} messages for {}", uids.len(), folder))?;
 
-    // Move messages to Gmail's Trash folder (more reliable than \Deleted flag)
-    session
-        .uid_copy(&uid_set, "[Gmail]/Trash")
+    Ok(uids.len())
+}
+
+/// Phase two of a two-phase delete: remove the `staged` messages from `mailbox`
+///
+/// On Gmail, only the `staged` UIDs lose [`GMAIL_TO_TRASH_LABEL`] or
+/// [`GMAIL_TO_SPAM_LABEL`] and are moved to trash or spam, so messages left
+/// labelled by an earlier flag-only or uncommitted run stay where they are.
+/// Other servers can only expunge `mailbox` as a whole. Returns the number
+/// of messages committed.
+pub async fn commit_delete<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    staged: &StagedUids,
+) -> Result<usize> {
+    if staged.is_empty() {
+        return Ok(0);
+    }
+
+    commit_staged(session, config, mailbox, staged)
         .await
-        .context("Failed to move messages to trash")?;
+        .context("Failed to expunge staged messages (gave up after retrying)")?;
+    Ok(staged.len())
+}
 
-    // Mark as deleted in INBOX
-    let _: Vec<_> = session
-        .uid_store(&uid_set, "+FLAGS.SILENT (\\Deleted)")
+async fn commit_staged<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    staged: &StagedUids,
+) -> Result<()> {
+    select_mailbox(session, config, mailbox).await?;
+    if !config.is_gmail() {
+        return expunge_in(session, config, mailbox).await;
+    }
+
+    for (label, folder, uids) in [
+        (GMAIL_TO_TRASH_LABEL, &config.trash_folder, &staged.trash),
+        (GMAIL_TO_SPAM_LABEL, &config.spam_folder, &staged.spam),
+    ] {
+        if uids.is_empty() {
+            continue;
+        }
+        let mut uids = uids.clone();
+        uids.sort_unstable();
+        uids.dedup();
+        let uid_set = format_uid_set(&uids);
+
+        uid_step(
+            session,
+            config,
+            mailbox,
+            &uid_set,
+            Step::Store(format!("-X-GM-LABELS ({})", quote_label(label))),
+        )
         .await
-        .context("Failed to mark messages as deleted")?
-        .try_collect()
-        .await?;
+        .with_context(|| format!("Failed to remove label {}", label))?;
+        move_from(session, config, mailbox, &uid_set, folder).await?;
+    }
+    Ok(())
+}
 
-    // Expunge to permanently remove from INBOX
-    let _: Vec<_> = session
-        .expunge()
+/// Number of messages currently in `folder`
+pub async fn folder_message_count(session: &mut ImapSession, folder: &str) -> Result<u32> {
+    let mailbox = session
+        .examine(folder)
+        .await
+        .with_context(|| format!("Failed to open {}", folder))?;
+
+    Ok(mailbox.exists)
+}
+
+/// Maximum UIDs per COPY/STORE command in batched moves
+const MOVE_CHUNK_SIZE: usize = 500;
+
+/// Move messages from many senders to trash with a single expunge
+///
+/// Gmail throttles rapid copy/expunge sequences, so instead of one round
+/// per sender the UIDs are copied and flagged in chunks and `mailbox` is
+/// expunged once at the end (with `flag_only`, not at all and only labelled
+/// on Gmail, see [`delete_messages`]). UIDs no longer in `mailbox` are
+/// skipped; returns the UIDs that were moved, sorted.
+pub async fn delete_messages_batched<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    flag_only: bool,
+) -> Result<Vec<u32>> {
+    move_batched(
+        session,
+        config,
+        mailbox,
+        uids,
+        &config.trash_folder,
+        flag_only,
+    )
+    .await
+}
+
+/// Move messages from many senders to spam with a single expunge
+///
+/// See [`delete_messages_batched`].
+pub async fn move_to_spam_batched<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    flag_only: bool,
+) -> Result<Vec<u32>> {
+    move_batched(
+        session,
+        config,
+        mailbox,
+        uids,
+        &config.spam_folder,
+        flag_only,
+    )
+    .await
+}
+
+async fn move_batched<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    folder: &str,
+    flag_only: bool,
+) -> Result<Vec<u32>> {
+    let mut uids = uids.to_vec();
+    uids.sort_unstable();
+    uids.dedup();
+
+    let uids = existing_uids(session, config, mailbox, &uids).await?;
+    if uids.is_empty() {
+        return Ok(uids);
+    }
+
+    move_chunks(session, config, mailbox, &uids, folder, flag_only)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to move {} messages to {} (gave up after retrying)",
+                uids.len(),
+                folder
+            )
+        })?;
+
+    Ok(uids)
+}
+
+/// The sorted `uids` that are still in `mailbox`
+///
+/// Messages can disappear between the scan and the cleanup, e.g. when
+/// deleted from another client, so batched moves only count what is left.
+async fn existing_uids<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+) -> Result<Vec<u32>> {
+    let mut existing = Vec::with_capacity(uids.len());
+    for chunk in uids.chunks(MOVE_CHUNK_SIZE) {
+        let query = format!("UID {}", format_uid_set(chunk));
+        let found = retry(&config.retry, session, |session| {
+            let mailbox = mailbox.to_string();
+            let query = query.clone();
+            Box::pin(async move {
+                session
+                    .select(&mailbox)
+                    .await
+                    .with_context(|| format!("Failed to select {}", mailbox))?;
+                session.uid_search(&query).await
+            })
+        })
         .await
-        .context("Failed to expunge deleted messages")?
-        .try_collect()
-        .await?;
+        .with_context(|| format!("Failed to look up messages in {}", mailbox))?;
+        existing.extend(found);
+    }
+
+    existing.sort_unstable();
+    existing.dedup();
+    Ok(existing)
+}
+
+async fn move_chunks<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    folder: &str,
+    flag_only: bool,
+) -> Result<()> {
+    let uid_sets: Vec<String> = uids.chunks(MOVE_CHUNK_SIZE).map(format_uid_set).collect();
+
+    if flag_only {
+        for uid_set in &uid_sets {
+            mark_for(session, config, mailbox, uid_set, folder).await?;
+        }
+        return Ok(());
+    }
+
+    select_mailbox(session, config, mailbox).await?;
+    if session.supports_move().await {
+        for uid_set in &uid_sets {
+            uid_step(
+                session,
+                config,
+                mailbox,
+                uid_set,
+                Step::Move(folder.to_string()),
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    for uid_set in &uid_sets {
+        copy_and_flag(session, config, mailbox, uid_set, folder).await?;
+    }
+    expunge_in(session, config, mailbox).await
+}
+
+/// Move messages from `mailbox` to `folder`
+///
+/// Uses UID MOVE when the server supports it. Otherwise the messages are
+/// copied, flagged and expunged, with each step retried on its own so a
+/// retry never copies them twice.
+async fn move_from<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uid_set: &str,
+    folder: &str,
+) -> Result<()> {
+    select_mailbox(session, config, mailbox).await?;
+    if session.supports_move().await {
+        return uid_step(
+            session,
+            config,
+            mailbox,
+            uid_set,
+            Step::Move(folder.to_string()),
+        )
+        .await;
+    }
+
+    copy_and_flag(session, config, mailbox, uid_set, folder).await?;
+    expunge_in(session, config, mailbox).await
+}
+
+/// Mark messages in `mailbox` for `folder` while leaving them in `mailbox`
+///
+/// Copies and flags them `\Deleted`, or on Gmail applies
+/// [`GMAIL_TO_TRASH_LABEL`] or [`GMAIL_TO_SPAM_LABEL`] instead.
+async fn mark_for<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uid_set: &str,
+    folder: &str,
+) -> Result<()> {
+    select_mailbox(session, config, mailbox).await?;
+    if !config.is_gmail() {
+        return copy_and_flag(session, config, mailbox, uid_set, folder).await;
+    }
+
+    let label = if folder == config.spam_folder {
+        GMAIL_TO_SPAM_LABEL
+    } else {
+        GMAIL_TO_TRASH_LABEL
+    };
+    uid_step(
+        session,
+        config,
+        mailbox,
+        uid_set,
+        Step::Store(format!("+X-GM-LABELS ({})", quote_label(label))),
+    )
+    .await
+    .with_context(|| format!("Failed to label messages {}", label))
+}
+
+/// Copy messages from the selected `mailbox` to `folder` and flag the
+/// originals as deleted
+async fn copy_and_flag<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uid_set: &str,
+    folder: &str,
+) -> Result<()> {
+    // Copy to the target folder (more reliable than the \Deleted flag alone)
+    uid_step(
+        session,
+        config,
+        mailbox,
+        uid_set,
+        Step::Copy(folder.to_string()),
+    )
+    .await?;
+
+    // Mark as deleted in the source mailbox
+    uid_step(
+        session,
+        config,
+        mailbox,
+        uid_set,
+        Step::Store("+FLAGS.SILENT (\\Deleted)".to_string()),
+    )
+    .await
+    .context("Failed to mark messages as deleted")
+}
+
+/// One UID command of a move
+#[derive(Debug, Clone)]
+enum Step {
+    /// UID COPY to a folder
+    Copy(String),
+
+    /// UID MOVE to a folder
+    Move(String),
+
+    /// UID STORE with a flags or labels query
+    Store(String),
+}
+
+/// Select `mailbox`, retrying transient failures according to `config.retry`
+async fn select_mailbox<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+) -> Result<()> {
+    retry(&config.retry, session, |session| {
+        let mailbox = mailbox.to_string();
+        Box::pin(async move {
+            session
+                .select(&mailbox)
+                .await
+                .with_context(|| format!("Failed to select {}", mailbox))?;
+            Ok(())
+        })
+    })
+    .await
+}
+
+/// Run one UID command on messages in the selected `mailbox`, retrying only
+/// that command
+///
+/// Transient failures are retried according to `config.retry`. Since the
+/// retry may run on a reconnected session, `mailbox` is selected again first.
+async fn uid_step<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uid_set: &str,
+    step: Step,
+) -> Result<()> {
+    let mut retrying = false;
+    retry(&config.retry, session, |session| {
+        let reselect = std::mem::replace(&mut retrying, true);
+        let mailbox = mailbox.to_string();
+        let uid_set = uid_set.to_string();
+        let step = step.clone();
+        Box::pin(async move {
+            // UIDs are per mailbox, and a reconnected session has none selected
+            if reselect {
+                session
+                    .select(&mailbox)
+                    .await
+                    .with_context(|| format!("Failed to select {}", mailbox))?;
+            }
+
+            match step {
+                Step::Copy(folder) => session
+                    .uid_copy(&uid_set, &folder)
+                    .await
+                    .with_context(|| format!("Failed to copy messages to {}", folder)),
+                Step::Move(folder) => session
+                    .uid_move(&uid_set, &folder)
+                    .await
+                    .with_context(|| format!("Failed to move messages to {}", folder)),
+                Step::Store(query) => session
+                    .uid_store(&uid_set, &query)
+                    .await
+                    .with_context(|| format!("Failed to store {}", query)),
+            }
+        })
+    })
+    .await
+}
+
+/// Expunge messages flagged as deleted from the selected `mailbox`
+///
+/// Retried like [`uid_step`].
+async fn expunge_in<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+) -> Result<()> {
+    let mut retrying = false;
+    retry(&config.retry, session, |session| {
+        let reselect = std::mem::replace(&mut retrying, true);
+        let mailbox = mailbox.to_string();
+        Box::pin(async move {
+            if reselect {
+                session
+                    .select(&mailbox)
+                    .await
+                    .with_context(|| format!("Failed to select {}", mailbox))?;
+            }
+            session
+                .expunge()
+                .await
+                .context("Failed to expunge deleted messages")
+        })
+    })
+    .await
+}
+
+/// Apply a Gmail label to messages in `mailbox`
+///
+/// Gmail creates the label if it does not exist yet. With `archive`, the
+/// source label (`\Inbox` for INBOX) is removed as well so the messages only
+/// live under the new label. Transient failures are retried according to
+/// `config.retry`.
+/// Returns the number of messages labeled.
+pub async fn apply_label<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    uids: &[u32],
+    label: &str,
+    archive: bool,
+) -> Result<usize> {
+    if uids.is_empty() {
+        return Ok(0);
+    }
+
+    let uid_set = format_uid_set(uids);
+    let add_label = format!("+X-GM-LABELS ({})", quote_label(label));
+    let remove_source = format!("-X-GM-LABELS ({})", source_label(mailbox));
 
-    Ok(count)
+    retry(&config.retry, session, |session| {
+        let uid_set = uid_set.clone();
+        let add_label = add_label.clone();
+        let remove_source = remove_source.clone();
+        let mailbox = mailbox.to_string();
+        Box::pin(async move {
+            session
+                .select(&mailbox)
+                .await
+                .with_context(|| format!("Failed to select {}", mailbox))?;
+
+            session
+                .uid_store(&uid_set, &add_label)
+                .await
+                .context("Failed to apply label")?;
+
+            if archive {
+                session
+                    .uid_store(&uid_set, &remove_source)
+                    .await
+                    .with_context(|| format!("Failed to remove messages from {}", mailbox))?;
+            }
+
+            Ok(())
+        })
+    })
+    .await
+    .with_context(|| format!("Failed to label {} messages as {}", uids.len(), label))?;
+
+    Ok(uids.len())
+}
+
+/// Quote a label name for an `X-GM-LABELS` list
+///
+/// Example: `Newsletters/To Review` -> `"Newsletters/To Review"`
+fn quote_label(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Gmail label corresponding to a mailbox, for `X-GM-LABELS`
+///
+/// Example: `INBOX` -> `\Inbox`, `Promotions` -> `"Promotions"`
+fn source_label(mailbox: &str) -> String {
+    if mailbox.eq_ignore_ascii_case("INBOX") {
+        "\\Inbox".to_string()
+    } else {
+        quote_label(mailbox)
+    }
+}
+
+/// UIDs in the All Mail folder of every message from `sender_email` outside
+/// `mailbox`
+///
+/// Uses Gmail's `X-GM-RAW` search, so archived copies are found too.
+/// `sender_email` may also be a bare domain. Messages still in `mailbox`
+/// (the ones the run just deleted, staged or flagged there) are left out.
+/// Nothing is moved: check the count against the run's deletion cap, then
+/// pass the UIDs to [`trash_from_all_mail`].
+pub async fn find_from_sender_everywhere<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    mailbox: &str,
+    sender_email: &str,
+) -> Result<Vec<u32>> {
+    let query = format!(
+        "{} NOT X-GM-LABELS {}",
+        build_gmail_from_query(sender_email),
+        source_label(mailbox)
+    );
+
+    let mut uids = retry(&config.retry, session, |session| {
+        let query = query.clone();
+        let config = config.clone();
+        Box::pin(async move {
+            session
+                .select(&config.all_mail_folder)
+                .await
+                .with_context(|| format!("Failed to select {}", config.all_mail_folder))?;
+
+            session
+                .uid_search(&query)
+                .await
+                .context("Failed to search all folders")
+        })
+    })
+    .await
+    .with_context(|| {
+        format!(
+            "Failed to search for messages from {} across all folders",
+            sender_email
+        )
+    })?;
+
+    uids.sort_unstable();
+    Ok(uids)
 }
 
-/// Move messages to spam folder
-pub async fn move_to_spam(session: &mut ImapSession, uids: &[u32]) -> Result<usize> {
+/// Move messages found by [`find_from_sender_everywhere`] to trash
+///
+/// With `flag_only`, they are only labelled [`GMAIL_TO_TRASH_LABEL`] (see
+/// [`delete_messages`]). Returns the number of messages trashed.
+pub async fn trash_from_all_mail<S: MailboxOps>(
+    session: &mut S,
+    config: &ImapConfig,
+    uids: &[u32],
+    flag_only: bool,
+) -> Result<usize> {
     if uids.is_empty() {
         return Ok(0);
     }
 
+    let folder = &config.all_mail_folder;
     let uid_set = format_uid_set(uids);
-    let count = uids.len();
+    let result = if flag_only {
+        mark_for(session, config, folder, &uid_set, &config.trash_folder).await
+    } else {
+        // In Gmail, copying to Trash removes every other label
+        select_mailbox(session, config, folder).await?;
+        uid_step(
+            session,
+            config,
+            folder,
+            &uid_set,
+            Step::Copy(config.trash_folder.clone()),
+        )
+        .await
+    };
+    result.with_context(|| format!("Failed to delete {} messages from {}", uids.len(), folder))?;
+
+    Ok(uids.len())
+}
+
+/// Build a Gmail extended search for messages from a sender
+///
+/// Example: `X-GM-RAW "from:news@example.com"`
+fn build_gmail_from_query(sender_email: &str) -> String {
+    format!(
+        "X-GM-RAW \"from:{}\"",
+        sender_email.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Move messages identified by Message-ID from `folder` back to `destination`
+///
+/// UIDs are per-folder, so messages are located by searching for their
+/// Message-ID header. Returns the number of messages restored.
+pub async fn restore_messages<S: MailboxOps>(
+    session: &mut S,
+    folder: &str,
+    destination: &str,
+    message_ids: &[String],
+) -> Result<usize> {
+    if message_ids.is_empty() {
+        return Ok(0);
+    }
 
-    // Ensure INBOX is selected
     session
-        .select("INBOX")
+        .select(folder)
         .await
-        .context("Failed to select INBOX")?;
+        .with_context(|| format!("Failed to select {}", folder))?;
+
+    let mut uids = Vec::new();
+    for chunk in message_ids.chunks(RESTORE_SEARCH_CHUNK) {
+        let found = session
+            .uid_search(&build_message_id_query(chunk))
+            .await
+            .context("Failed to search for messages to restore")?;
+        uids.extend(found);
+    }
+
+    if uids.is_empty() {
+        return Ok(0);
+    }
+
+    uids.sort_unstable();
+    uids.dedup();
+
+    let uid_set = format_uid_set(&uids);
 
-    // Copy messages to Gmail's Spam folder
     session
-        .uid_copy(&uid_set, "[Gmail]/Spam")
+        .uid_copy(&uid_set, destination)
         .await
-        .context("Failed to copy messages to spam")?;
+        .with_context(|| format!("Failed to copy messages back to {}", destination))?;
 
-    // Mark as deleted in INBOX
-    let _: Vec<_> = session
+    // Remove from the source folder
+    session
         .uid_store(&uid_set, "+FLAGS.SILENT (\\Deleted)")
         .await
-        .context("Failed to mark messages as deleted")?
-        .try_collect()
-        .await?;
+        .context("Failed to mark restored messages as deleted")?;
 
-    // Expunge to remove from INBOX
-    let _: Vec<_> = session
+    session
         .expunge()
         .await
-        .context("Failed to expunge deleted messages")?
-        .try_collect()
-        .await?;
+        .context("Failed to expunge restored messages")?;
+
+    Ok(uids.len())
+}
 
-    Ok(count)
+/// Maximum Message-IDs per SEARCH command when restoring
+const RESTORE_SEARCH_CHUNK: usize = 50;
+
+/// Build a SEARCH query matching any of the given Message-IDs
+///
+/// Example: `OR HEADER Message-ID "<a>" HEADER Message-ID "<b>"`
+fn build_message_id_query(message_ids: &[String]) -> String {
+    let terms: Vec<String> = message_ids
+        .iter()
+        .map(|id| {
+            format!(
+                "HEADER Message-ID \"{}\"",
+                id.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        })
+        .collect();
+
+    format!(
+        "{}{}",
+        "OR ".repeat(terms.len().saturating_sub(1)),
+        terms.join(" ")
+    )
 }
 
 /// Format UIDs for IMAP command
@@ -106,3 +851,362 @@ fn format_uid_set(uids: &[u32]) -> String {
             .join(",")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::imap::mailbox::mock::MockMailbox;
+    use crate::infrastructure::imap::retry::RetryPolicy;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_find_everywhere_searches_without_moving() {
+        let mut mailbox = MockMailbox::with_messages(&[(40, ""), (12, "")]);
+        let config = ImapConfig::default();
+
+        let uids = find_from_sender_everywhere(&mut mailbox, &config, "INBOX", "news@example.com")
+            .await
+            .unwrap();
+        assert_eq!(uids, vec![12, 40]);
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT [Gmail]/All Mail",
+                "UID SEARCH X-GM-RAW \"from:news@example.com\" NOT X-GM-LABELS \\Inbox"
+            ]
+        );
+
+        let count = trash_from_all_mail(&mut mailbox, &config, &uids[..1], false)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            mailbox.commands[2..],
+            ["SELECT [Gmail]/All Mail", "UID COPY 12 [Gmail]/Trash"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trash_from_all_mail_flag_only_labels() {
+        let mut mailbox = MockMailbox::default();
+        let config = ImapConfig::default();
+
+        let count = trash_from_all_mail(&mut mailbox, &config, &[12, 40], true)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT [Gmail]/All Mail",
+                "UID STORE 12,40 +X-GM-LABELS (\"Unsubmail/To Trash\")"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_gmail_from_query() {
+        assert_eq!(
+            build_gmail_from_query("news@example.com"),
+            "X-GM-RAW \"from:news@example.com\""
+        );
+        assert_eq!(
+            build_gmail_from_query("a\"b@example.com"),
+            "X-GM-RAW \"from:a\\\"b@example.com\""
+        );
+    }
+
+    #[test]
+    fn test_quote_label() {
+        assert_eq!(
+            quote_label("Newsletters/To Review"),
+            "\"Newsletters/To Review\""
+        );
+        assert_eq!(quote_label("a\"b"), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn test_source_label() {
+        assert_eq!(source_label("INBOX"), "\\Inbox");
+        assert_eq!(source_label("Promotions"), "\"Promotions\"");
+    }
+
+    #[test]
+    fn test_build_message_id_query() {
+        assert_eq!(
+            build_message_id_query(&["<a@x>".to_string()]),
+            "HEADER Message-ID \"<a@x>\""
+        );
+        assert_eq!(
+            build_message_id_query(&["<a@x>".to_string(), "<b@x>".to_string(), "<c@x>".to_string()]),
+            "OR OR HEADER Message-ID \"<a@x>\" HEADER Message-ID \"<b@x>\" HEADER Message-ID \"<c@x>\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_messages_command_sequence() {
+        let mut mailbox = MockMailbox::default();
+
+        let count = delete_messages(
+            &mut mailbox,
+            &ImapConfig::default(),
+            "INBOX",
+            &[1, 2, 3],
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID COPY 1:3 [Gmail]/Trash",
+                "UID STORE 1:3 +FLAGS.SILENT (\\Deleted)",
+                "EXPUNGE",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_copy_does_not_copy_again() {
+        let mut mailbox = MockMailbox {
+            fail_once: vec!["UID STORE".to_string()],
+            ..MockMailbox::default()
+        };
+        let config = ImapConfig {
+            retry: RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::ZERO,
+            },
+            ..ImapConfig::default()
+        };
+
+        delete_messages(&mut mailbox, &config, "INBOX", &[1, 2, 3], false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID COPY 1:3 [Gmail]/Trash",
+                "UID STORE 1:3 +FLAGS.SILENT (\\Deleted)",
+                "NOOP",
+                "RECONNECT",
+                "SELECT INBOX",
+                "UID STORE 1:3 +FLAGS.SILENT (\\Deleted)",
+                "EXPUNGE",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_uses_move_when_supported() {
+        let mut mailbox = MockMailbox {
+            move_capable: true,
+            ..MockMailbox::default()
+        };
+
+        delete_messages(
+            &mut mailbox,
+            &ImapConfig::default(),
+            "INBOX",
+            &[1, 2, 3],
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec!["SELECT INBOX", "UID MOVE 1:3 [Gmail]/Trash"]
+        );
+    }
+
+    /// A non-Gmail server, where folders are real folders
+    fn other_server() -> ImapConfig {
+        ImapConfig {
+            host: "imap.example.com".to_string(),
+            trash_folder: "Trash".to_string(),
+            spam_folder: "Junk".to_string(),
+            ..ImapConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flag_only_never_expunges() {
+        let mut mailbox = MockMailbox::default();
+        let config = other_server();
+
+        delete_messages(&mut mailbox, &config, "INBOX", &[1, 2], true)
+            .await
+            .unwrap();
+        move_to_spam(&mut mailbox, &config, "INBOX", &[4], true)
+            .await
+            .unwrap();
+        delete_messages_batched(&mut mailbox, &config, "INBOX", &[6, 7], true)
+            .await
+            .unwrap();
+
+        assert!(!mailbox.commands.iter().any(|c| c == "EXPUNGE"));
+        assert_eq!(
+            mailbox.commands[..3],
+            [
+                "SELECT INBOX",
+                "UID COPY 1:2 Trash",
+                "UID STORE 1:2 +FLAGS.SILENT (\\Deleted)",
+            ]
+        );
+        assert!(mailbox.commands.contains(&"UID COPY 4 Junk".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_flag_only_on_gmail_labels_without_copying() {
+        let mut mailbox = MockMailbox::with_messages(&[(1, ""), (2, ""), (4, "")]);
+        let config = ImapConfig::default();
+
+        delete_messages(&mut mailbox, &config, "INBOX", &[1, 2], true)
+            .await
+            .unwrap();
+        move_to_spam_batched(&mut mailbox, &config, "INBOX", &[4], true)
+            .await
+            .unwrap();
+
+        // A copy to Trash would take the messages out of INBOX right away
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID STORE 1:2 +X-GM-LABELS (\"Unsubmail/To Trash\")",
+                "SELECT INBOX",
+                "UID SEARCH UID 4",
+                "SELECT INBOX",
+                "UID STORE 4 +X-GM-LABELS (\"Unsubmail/To Spam\")",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_two_phase_on_gmail_moves_only_on_commit() {
+        let mut mailbox = MockMailbox::with_messages(&[(5, ""), (9, "")]);
+        let config = ImapConfig::default();
+
+        stage_delete(&mut mailbox, &config, "INBOX", &[5, 9])
+            .await
+            .unwrap();
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID STORE 5,9 +X-GM-LABELS (\"Unsubmail/To Trash\")",
+            ]
+        );
+
+        mailbox.commands.clear();
+        let staged = StagedUids {
+            trash: vec![9, 5],
+            spam: Vec::new(),
+        };
+        let count = commit_delete(&mut mailbox, &config, "INBOX", &staged)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            mailbox.commands[..6],
+            [
+                "SELECT INBOX",
+                "UID STORE 5,9 -X-GM-LABELS (\"Unsubmail/To Trash\")",
+                "SELECT INBOX",
+                "UID COPY 5,9 [Gmail]/Trash",
+                "UID STORE 5,9 +FLAGS.SILENT (\\Deleted)",
+                "EXPUNGE",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stage_delete_does_not_expunge() {
+        let mut mailbox = MockMailbox::default();
+        let config = other_server();
+
+        stage_delete(&mut mailbox, &config, "INBOX", &[5, 9])
+            .await
+            .unwrap();
+        assert!(!mailbox.commands.iter().any(|c| c == "EXPUNGE"));
+
+        let staged = StagedUids {
+            trash: vec![5, 9],
+            spam: Vec::new(),
+        };
+        commit_delete(&mut mailbox, &config, "INBOX", &staged)
+            .await
+            .unwrap();
+        assert_eq!(
+            mailbox.commands[mailbox.commands.len() - 2..],
+            ["SELECT INBOX", "EXPUNGE"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batched_move_skips_vanished_messages() {
+        let mut mailbox = MockMailbox::with_messages(&[(5, ""), (8, "")]);
+        let config = ImapConfig::default();
+
+        let moved = delete_messages_batched(&mut mailbox, &config, "INBOX", &[8, 5, 6], false)
+            .await
+            .unwrap();
+        assert_eq!(moved, vec![5, 8]);
+        assert_eq!(
+            mailbox.commands[..4],
+            [
+                "SELECT INBOX",
+                "UID SEARCH UID 5,6,8",
+                "SELECT INBOX",
+                "UID COPY 5,8 [Gmail]/Trash",
+            ]
+        );
+
+        mailbox.commands.clear();
+        let moved = delete_messages_batched(&mut mailbox, &config, "INBOX", &[6], false)
+            .await
+            .unwrap();
+        assert!(moved.is_empty());
+        assert_eq!(mailbox.commands, vec!["SELECT INBOX", "UID SEARCH UID 6"]);
+    }
+
+    #[tokio::test]
+    async fn test_actions_work_from_chosen_mailbox() {
+        let mut mailbox = MockMailbox::with_messages(&[(7, ""), (8, ""), (9, "")]);
+        let config = ImapConfig::default();
+
+        move_to_spam_batched(&mut mailbox, &config, "Promotions", &[7, 8], false)
+            .await
+            .unwrap();
+        apply_label(
+            &mut mailbox,
+            &config,
+            "Promotions",
+            &[9],
+            "Read later",
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT Promotions",
+                "UID SEARCH UID 7:8",
+                "SELECT Promotions",
+                "UID COPY 7:8 [Gmail]/Spam",
+                "UID STORE 7:8 +FLAGS.SILENT (\\Deleted)",
+                "EXPUNGE",
+                "SELECT Promotions",
+                "UID STORE 9 +X-GM-LABELS (\"Read later\")",
+            ]
+        );
+    }
+}
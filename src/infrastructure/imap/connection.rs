@@ -1,6 +1,8 @@
 //! IMAP connection management
 
 use super::auth::build_xoauth2_string;
+use super::mailbox::MailboxOps;
+use super::retry::RetryPolicy;
 use anyhow::{Context, Result};
 use async_imap::Session;
 use async_native_tls::{TlsConnector, TlsStream};
@@ -9,6 +11,162 @@ use tokio_util::compat::TokioAsyncReadCompatExt;
 
 const GMAIL_IMAP_HOST: &str = "imap.gmail.com";
 const GMAIL_IMAP_PORT: u16 = 993;
+const GMAIL_TRASH_FOLDER: &str = "[Gmail]/Trash";
+const GMAIL_SPAM_FOLDER: &str = "[Gmail]/Spam";
+const GMAIL_ALL_MAIL_FOLDER: &str = "[Gmail]/All Mail";
+const GMAIL_SENT_FOLDER: &str = "[Gmail]/Sent Mail";
+
+/// Mailbox scanned and cleaned unless another one is chosen
+pub const DEFAULT_MAILBOX: &str = "INBOX";
+
+/// IMAP server settings
+///
+/// Defaults to Gmail. Other providers work at the IMAP level as long as they
+/// accept XOAUTH2, but the OAuth2 flow itself still needs provider-specific setup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapConfig {
+    /// IMAP server hostname
+    pub host: String,
+
+    /// IMAP server port (implicit TLS)
+    pub port: u16,
+
+    /// Mailbox (folder or Gmail label) that is scanned and cleaned
+    pub mailbox: String,
+
+    /// Folder messages are moved to when deleted
+    pub trash_folder: String,
+
+    /// Folder messages are moved to when marked as spam
+    pub spam_folder: String,
+
+    /// Folder containing every message (searched when deleting everywhere)
+    pub all_mail_folder: String,
+
+    /// Folder holding mail sent from the account (read to protect contacts)
+    pub sent_folder: String,
+
+    /// Retry behaviour for cleanup actions
+    pub retry: RetryPolicy,
+
+    /// Never expunge: cleaned messages stay in `mailbox` for the user to
+    /// review
+    ///
+    /// Elsewhere they are copied to trash or spam and flagged `\Deleted`
+    /// until the user expunges `mailbox` in their mail client. On Gmail, where
+    /// copying to Trash removes every other label, they only get a staging
+    /// label (see [`super::actions::GMAIL_TO_TRASH_LABEL`]).
+    pub flag_only: bool,
+}
+
+impl Default for ImapConfig {
+    fn default() -> Self {
+        Self {
+            host: GMAIL_IMAP_HOST.to_string(),
+            port: GMAIL_IMAP_PORT,
+            mailbox: DEFAULT_MAILBOX.to_string(),
+            trash_folder: GMAIL_TRASH_FOLDER.to_string(),
+            spam_folder: GMAIL_SPAM_FOLDER.to_string(),
+            all_mail_folder: GMAIL_ALL_MAIL_FOLDER.to_string(),
+            sent_folder: GMAIL_SENT_FOLDER.to_string(),
+            retry: RetryPolicy::default(),
+            flag_only: false,
+        }
+    }
+}
+
+impl ImapConfig {
+    /// Whether `host` is Gmail, whose folders are labels
+    pub fn is_gmail(&self) -> bool {
+        self.host.eq_ignore_ascii_case(GMAIL_IMAP_HOST)
+    }
+
+    /// Build config from environment variables, falling back to Gmail defaults
+    ///
+    /// Reads `UNSUBMAIL_IMAP_HOST`, `UNSUBMAIL_IMAP_PORT`, `UNSUBMAIL_MAILBOX`,
+    /// `UNSUBMAIL_TRASH_FOLDER`, `UNSUBMAIL_SPAM_FOLDER`,
+    /// `UNSUBMAIL_ALL_MAIL_FOLDER`, `UNSUBMAIL_SENT_FOLDER` and
+    /// `UNSUBMAIL_FLAG_ONLY` (`1` or `true`).
+    pub fn from_env() -> Result<Self> {
+        Self::default().with_env_overrides()
+    }
+
+    /// Apply the `UNSUBMAIL_*` environment variables on top of this config
+    pub fn with_env_overrides(self) -> Result<Self> {
+        let mut config = self;
+
+        if let Ok(host) = std::env::var("UNSUBMAIL_IMAP_HOST") {
+            config.host = host;
+        }
+        if let Ok(port) = std::env::var("UNSUBMAIL_IMAP_PORT") {
+            config.port = port
+                .parse()
+                .context("UNSUBMAIL_IMAP_PORT must be a valid port number")?;
+        }
+        if let Ok(mailbox) = std::env::var("UNSUBMAIL_MAILBOX") {
+            config.mailbox = mailbox;
+        }
+        if let Ok(folder) = std::env::var("UNSUBMAIL_TRASH_FOLDER") {
+            config.trash_folder = folder;
+        }
+        if let Ok(folder) = std::env::var("UNSUBMAIL_SPAM_FOLDER") {
+            config.spam_folder = folder;
+        }
+        if let Ok(folder) = std::env::var("UNSUBMAIL_ALL_MAIL_FOLDER") {
+            config.all_mail_folder = folder;
+        }
+        if let Ok(folder) = std::env::var("UNSUBMAIL_SENT_FOLDER") {
+            config.sent_folder = folder;
+        }
+        if let Ok(flag_only) = std::env::var("UNSUBMAIL_FLAG_ONLY") {
+            config.flag_only = matches!(flag_only.to_ascii_lowercase().as_str(), "1" | "true");
+        }
+
+        Ok(config)
+    }
+}
+
+/// The server didn't accept the OAuth2 token
+///
+/// Kept in the error chain of [`authenticate`] failures so callers can tell
+/// an invalid session (sign in again) from a network problem (retry later).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("XOAUTH2 authentication timed out")]
+    Timeout,
+
+    #[error("XOAUTH2 authentication rejected: {0}")]
+    Rejected(String),
+}
+
+/// Whether `error` means the credentials are invalid rather than unreachable
+///
+/// Only [`AuthError`] counts; timeouts while connecting, during the TLS
+/// handshake or while reading the greeting are network failures.
+pub fn is_auth_failure(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<AuthError>())
+}
+
+/// Whether `error` looks like the access token expired during an operation
+///
+/// Covers a rejected [`AuthError`] when reconnecting, and commands on an open
+/// session answered with `NO [AUTHENTICATIONFAILED]` or a session-expired
+/// message. Signing in again is only needed if refreshing the token fails.
+pub fn is_token_expired(error: &anyhow::Error) -> bool {
+    if is_auth_failure(error) {
+        return true;
+    }
+
+    error.chain().any(
+        |cause| match cause.downcast_ref::<async_imap::error::Error>() {
+            Some(async_imap::error::Error::No(text) | async_imap::error::Error::Bad(text)) => {
+                let text = text.to_ascii_lowercase();
+                text.contains("authenticationfailed") || text.contains("expired")
+            }
+            _ => false,
+        },
+    )
+}
 
 /// IMAP session type
 pub type ImapSession = Session<TlsStream<tokio_util::compat::Compat<TcpStream>>>;
@@ -26,20 +184,31 @@ impl async_imap::Authenticator for XOAuth2 {
     }
 }
 
-/// Connect to Gmail IMAP server with TLS
+/// Connect to the IMAP server with TLS
 pub async fn connect(
+    config: &ImapConfig,
 ) -> Result<async_imap::Client<TlsStream<tokio_util::compat::Compat<TcpStream>>>> {
-    tracing::info!("Connecting to {}:{}", GMAIL_IMAP_HOST, GMAIL_IMAP_PORT);
+    tracing::info!(host = %config.host, port = config.port, "connecting");
 
     let tcp_stream = tokio::time::timeout(
         std::time::Duration::from_secs(10),
-        TcpStream::connect((GMAIL_IMAP_HOST, GMAIL_IMAP_PORT)),
+        TcpStream::connect((config.host.as_str(), config.port)),
     )
     .await
-    .context("Timeout while connecting to Gmail IMAP - Check your network connection")?
-    .context("Failed to connect to Gmail IMAP - Verify port 993 is not blocked by firewall")?;
+    .with_context(|| {
+        format!(
+            "Timeout while connecting to {} - Check your network connection",
+            config.host
+        )
+    })?
+    .with_context(|| {
+        format!(
+            "Failed to connect to {} - Verify port {} is not blocked by firewall",
+            config.host, config.port
+        )
+    })?;
 
-    tracing::info!("✓ TCP connection established, starting TLS handshake");
+    tracing::info!("TCP connection established, starting TLS handshake");
 
     // Convert tokio stream to futures-compatible stream
     let compat_stream = tcp_stream.compat();
@@ -47,17 +216,17 @@ pub async fn connect(
     let tls = TlsConnector::new();
     let tls_stream = tokio::time::timeout(
         std::time::Duration::from_secs(10),
-        tls.connect(GMAIL_IMAP_HOST, compat_stream),
+        tls.connect(config.host.as_str(), compat_stream),
     )
     .await
     .context("Timeout during TLS handshake")?
     .context("Failed to establish TLS connection")?;
 
-    tracing::info!("✓ TLS handshake complete, creating IMAP client");
+    tracing::info!("TLS handshake complete, creating IMAP client");
 
     let client = async_imap::Client::new(tls_stream);
 
-    tracing::info!("✓ IMAP client created successfully");
+    tracing::info!("IMAP client created");
 
     Ok(client)
 }
@@ -68,29 +237,30 @@ pub async fn authenticate(
     email: &str,
     access_token: &str,
 ) -> Result<ImapSession> {
-    tracing::info!("Starting XOAUTH2 authentication for {}", email);
+    tracing::info!(email, "starting XOAUTH2 authentication");
 
     // WORKAROUND for async-imap issue #84:
     // Gmail sends a greeting that must be consumed before authentication
     // See: https://github.com/async-email/async-imap/issues/84
-    tracing::info!("Reading server greeting...");
+    tracing::info!("reading server greeting");
     let greeting = tokio::time::timeout(std::time::Duration::from_secs(10), client.read_response())
         .await
         .context("Timeout while reading server greeting")?
         .context("Failed to read server greeting")?;
 
-    tracing::info!("Server greeting received: {:?}", greeting);
+    tracing::info!(greeting = ?greeting, "server greeting received");
 
     let auth_str = build_xoauth2_string(email, access_token);
     let authenticator = XOAuth2 { auth_str };
 
-    tracing::info!("Sending AUTHENTICATE XOAUTH2 command...");
+    tracing::info!("sending AUTHENTICATE XOAUTH2 command");
 
     let session = tokio::time::timeout(
         std::time::Duration::from_secs(15),
         client.authenticate("XOAUTH2", authenticator),
     )
     .await
+    .map_err(|_| AuthError::Timeout)
     .context(
         "Timeout during XOAUTH2 authentication - This usually means:\n\
              1. OAuth2 token is invalid or expired\n\
@@ -100,8 +270,8 @@ pub async fn authenticate(
              Please check: https://mail.google.com/mail/u/0/#settings/fwdandpop",
     )?
     .map_err(|(err, _client)| {
-        tracing::error!("XOAUTH2 authentication failed: {:?}", err);
-        anyhow::anyhow!(
+        tracing::error!(error = ?err, "XOAUTH2 authentication failed");
+        anyhow::Error::new(AuthError::Rejected(err.to_string())).context(format!(
             "XOAUTH2 authentication failed: {:?}\n\n\
              Common causes:\n\
              1. OAuth2 token is invalid or expired (try re-authenticating)\n\
@@ -111,16 +281,243 @@ pub async fn authenticate(
              Enable IMAP: https://mail.google.com/mail/u/0/#settings/fwdandpop\n\
              Check 'IMAP Access' section and enable it",
             err
-        )
+        ))
     })?;
 
-    tracing::info!("✓ XOAUTH2 authentication successful");
+    tracing::info!("XOAUTH2 authentication succeeded");
 
     Ok(session)
 }
 
 /// Connect and authenticate in one step
-pub async fn connect_and_auth(email: &str, access_token: &str) -> Result<ImapSession> {
-    let client = connect().await?;
+pub async fn connect_and_auth(
+    config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+) -> Result<ImapSession> {
+    let client = connect(config).await?;
     authenticate(client, email, access_token).await
 }
+
+/// How long a NOOP may take before the session is considered dropped
+const NOOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Whether the server has closed `session`
+///
+/// Sends a NOOP: an error, such as a broken pipe after Gmail closed an idle
+/// connection, or no answer within [`NOOP_TIMEOUT`] means it has.
+pub async fn is_dropped<S: MailboxOps>(session: &mut S) -> bool {
+    match tokio::time::timeout(NOOP_TIMEOUT, session.noop()).await {
+        Ok(Ok(())) => false,
+        Ok(Err(e)) => {
+            tracing::debug!(error = %format_args!("{:#}", e), "NOOP failed, session dropped");
+            true
+        }
+        Err(_) => {
+            tracing::debug!("NOOP timed out, session dropped");
+            true
+        }
+    }
+}
+
+/// Replace `session` with a new one if the server dropped it
+///
+/// Gmail closes connections left idle for a few minutes, e.g. while the user
+/// deliberates at a prompt; call this before acting after user input.
+/// `access_token` is only called when reconnecting, so it can refresh an
+/// expired token. The new session has no mailbox selected; every action
+/// selects its own. Returns whether it reconnected.
+pub async fn reconnect_if_needed<F, Fut>(
+    session: &mut ImapSession,
+    config: &ImapConfig,
+    email: &str,
+    access_token: F,
+) -> Result<bool>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<String>>,
+{
+    if !is_dropped(session).await {
+        return Ok(false);
+    }
+
+    tracing::info!(email, "IMAP session was dropped, reconnecting");
+    let access_token = access_token().await?;
+    *session = connect_and_auth(config, email, &access_token)
+        .await
+        .context("Failed to reconnect after the IMAP session was dropped")?;
+
+    Ok(true)
+}
+
+/// An [`ImapSession`] that logs in again when the server has dropped it
+///
+/// Actions retry transient failures (see [`super::retry::retry`]); on a bare
+/// session every retry would hit the same dead connection. Through this
+/// wrapper, [`MailboxOps::reconnect`] replaces it with a new one first.
+/// `access_token` is only called when reconnecting, so it can refresh an
+/// expired token.
+pub struct ReconnectingSession<'s, F> {
+    session: &'s mut ImapSession,
+    config: &'s ImapConfig,
+    email: &'s str,
+    access_token: F,
+}
+
+impl<'s, F> ReconnectingSession<'s, F> {
+    pub fn new(
+        session: &'s mut ImapSession,
+        config: &'s ImapConfig,
+        email: &'s str,
+        access_token: F,
+    ) -> Self {
+        Self {
+            session,
+            config,
+            email,
+            access_token,
+        }
+    }
+}
+
+impl<F, Fut> MailboxOps for ReconnectingSession<'_, F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = Result<String>> + Send,
+{
+    async fn select(&mut self, mailbox: &str) -> Result<async_imap::types::Mailbox> {
+        MailboxOps::select(&mut *self.session, mailbox).await
+    }
+
+    async fn uid_search(&mut self, query: &str) -> Result<Vec<u32>> {
+        MailboxOps::uid_search(&mut *self.session, query).await
+    }
+
+    async fn uid_fetch(
+        &mut self,
+        uid_set: &str,
+        query: &str,
+    ) -> Result<Vec<super::mailbox::FetchedMessage>> {
+        MailboxOps::uid_fetch(&mut *self.session, uid_set, query).await
+    }
+
+    async fn uid_copy(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+        MailboxOps::uid_copy(&mut *self.session, uid_set, mailbox).await
+    }
+
+    async fn uid_store(&mut self, uid_set: &str, query: &str) -> Result<()> {
+        MailboxOps::uid_store(&mut *self.session, uid_set, query).await
+    }
+
+    async fn uid_move(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+        MailboxOps::uid_move(&mut *self.session, uid_set, mailbox).await
+    }
+
+    async fn supports_move(&mut self) -> bool {
+        MailboxOps::supports_move(&mut *self.session).await
+    }
+
+    async fn expunge(&mut self) -> Result<()> {
+        MailboxOps::expunge(&mut *self.session).await
+    }
+
+    async fn noop(&mut self) -> Result<()> {
+        MailboxOps::noop(&mut *self.session).await
+    }
+
+    async fn reconnect(&mut self) -> Result<()> {
+        tracing::info!(email = self.email, "IMAP session was dropped, reconnecting");
+        let access_token = (self.access_token)().await?;
+        *self.session = connect_and_auth(self.config, self.email, &access_token)
+            .await
+            .context("Failed to reconnect after the IMAP session was dropped")?;
+        Ok(())
+    }
+}
+
+/// Connect, authenticate and resolve the account's localized folder names
+///
+/// The returned config carries the trash, spam, All Mail and Sent folders found via
+/// [`super::folders::resolve`]; use it for every action on this session.
+pub async fn connect_and_resolve(
+    config: &ImapConfig,
+    email: &str,
+    access_token: &str,
+) -> Result<(ImapSession, ImapConfig)> {
+    let mut session = connect_and_auth(config, email, access_token).await?;
+    let resolved = super::folders::resolve(&mut session, config).await;
+    Ok((session, resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::imap::mailbox::mock::MockMailbox;
+
+    #[test]
+    fn test_auth_failure_asks_to_reauthenticate() {
+        let rejected = anyhow::Error::new(AuthError::Rejected("NO [AUTHENTICATIONFAILED]".into()))
+            .context("XOAUTH2 authentication failed");
+        assert!(is_auth_failure(&rejected));
+
+        let timed_out: Result<()> = Err(AuthError::Timeout).context("Timeout during XOAUTH2");
+        assert!(is_auth_failure(
+            &timed_out.unwrap_err().context("Scan failed")
+        ));
+    }
+
+    #[test]
+    fn test_network_failure_is_not_auth_failure() {
+        let refused =
+            anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+                .context("Failed to connect to imap.gmail.com");
+        assert!(!is_auth_failure(&refused));
+
+        let greeting = anyhow::anyhow!("deadline has elapsed")
+            .context("Timeout while reading server greeting");
+        assert!(!is_auth_failure(&greeting));
+    }
+
+    #[test]
+    fn test_expired_token_during_session() {
+        let expired = anyhow::Error::new(async_imap::error::Error::No(
+            "[AUTHENTICATIONFAILED] Invalid credentials (Failure)".into(),
+        ))
+        .context("Failed to fetch headers");
+        assert!(is_token_expired(&expired));
+
+        let rejected = anyhow::Error::new(AuthError::Rejected("NO [AUTHENTICATIONFAILED]".into()));
+        assert!(is_token_expired(&rejected));
+
+        let missing = anyhow::Error::new(async_imap::error::Error::No(
+            "[NONEXISTENT] Unknown Mailbox".into(),
+        ));
+        assert!(!is_token_expired(&missing));
+        assert!(!is_token_expired(&anyhow::Error::new(
+            async_imap::error::Error::ConnectionLost
+        )));
+    }
+
+    #[test]
+    fn test_default_config_is_gmail() {
+        let config = ImapConfig::default();
+        assert_eq!(config.host, "imap.gmail.com");
+        assert_eq!(config.port, 993);
+        assert_eq!(config.trash_folder, "[Gmail]/Trash");
+        assert_eq!(config.spam_folder, "[Gmail]/Spam");
+        assert_eq!(config.all_mail_folder, "[Gmail]/All Mail");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_session_needs_reconnect() {
+        let mut alive = MockMailbox::default();
+        assert!(!is_dropped(&mut alive).await);
+        assert_eq!(alive.commands, vec!["NOOP"]);
+
+        let mut dropped = MockMailbox {
+            dropped: true,
+            ..MockMailbox::default()
+        };
+        assert!(is_dropped(&mut dropped).await);
+    }
+}
@@ -1,40 +1,261 @@
 //! IMAP message fetching and header parsing
 
-use super::connection::ImapSession;
+use super::mailbox::MailboxOps;
+use crate::domain::email;
 use anyhow::{Context, Result};
-use futures::TryStreamExt; // Required for try_next()
-use mailparse::{parse_mail, MailHeaderMap};
+use async_imap::types::Mailbox;
+use chrono::{DateTime, NaiveDate, Utc};
+use mailparse::{dateparse, parse_mail, MailHeaderMap};
 use rayon::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 /// Message header data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageHeader {
     pub uid: u32,
     pub from: String,
     pub subject: String,
     pub list_unsubscribe: Option<String>,
     pub list_unsubscribe_post: Option<String>,
+    pub list_id: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub precedence: Option<String>,
+    #[serde(default)]
+    pub auto_submitted: Option<String>,
+    /// Whether the message had been read when its header was fetched
+    ///
+    /// None when the source did not report read state.
+    #[serde(default)]
+    pub seen: Option<bool>,
 }
 
-/// Search for all message UIDs in INBOX
-pub async fn search_all_uids(session: &mut ImapSession) -> Result<Vec<u32>> {
-    session
-        .select("INBOX")
-        .await
-        .context("Failed to select INBOX")?;
+/// Subject and date of a single message, for previews
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageSummary {
+    pub uid: u32,
+    pub subject: String,
+    pub date: Option<DateTime<Utc>>,
+}
 
-    let search_result = session
-        .uid_search("ALL")
-        .await
-        .context("Failed to search messages")?;
+/// Options controlling how headers are fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// Number of UIDs requested per FETCH command
+    pub batch_size: usize,
+
+    /// Maximum number of messages to scan (None = whole mailbox)
+    ///
+    /// When set, the most recent messages are kept.
+    pub max_messages: Option<usize>,
+
+    /// Only scan messages received on or after this date (None = all)
+    ///
+    /// Applied by the server through `UID SEARCH SINCE`, so older headers are
+    /// never downloaded.
+    pub since: Option<NaiveDate>,
+
+    /// Stop fetching new batches once a scan has run this long (None = no
+    /// limit)
+    ///
+    /// At least one batch is always fetched. Newest messages are fetched
+    /// first, so a scan cut short still covers recent mail.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 200,
+            max_messages: None,
+            since: None,
+            time_budget: None,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// UID SEARCH criteria for these options
+    pub fn search_criteria(&self) -> String {
+        match self.since {
+            Some(date) => since_criteria(date),
+            None => SEARCH_ALL.to_string(),
+        }
+    }
+
+    /// Whether a scan started at `started` has used up its time budget
+    pub fn budget_spent(&self, started: Instant) -> bool {
+        self.time_budget
+            .is_some_and(|budget| started.elapsed() >= budget)
+    }
+}
+
+/// Search criteria matching every message
+const SEARCH_ALL: &str = "ALL";
+
+/// `SINCE` criteria in the IMAP date format (RFC 3501), e.g. `SINCE 01-Jan-2024`
+pub fn since_criteria(date: NaiveDate) -> String {
+    format!("SINCE {}", date.format("%d-%b-%Y"))
+}
+
+/// Headers of every scanned message in one mailbox, kept for incremental rescans
+///
+/// UIDs are only stable while the mailbox's UIDVALIDITY is unchanged, so a
+/// snapshot is discarded as soon as it differs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MailboxSnapshot {
+    /// UIDVALIDITY of the mailbox when the headers were fetched (0 = unknown)
+    pub uid_validity: u32,
+
+    /// Highest UID fetched so far
+    pub highest_uid: u32,
 
-    Ok(search_result.into_iter().collect())
+    /// Headers of the messages that were in the mailbox at the last scan
+    pub headers: Vec<MessageHeader>,
+}
+
+impl MailboxSnapshot {
+    /// Whether the cached headers can be reused for a mailbox in this state
+    pub fn is_valid_for(&self, uid_validity: u32) -> bool {
+        uid_validity != 0 && self.uid_validity == uid_validity
+    }
+
+    /// UIDs from `current` whose headers still have to be fetched
+    ///
+    /// Only UIDs above the high-water mark, or all of them if UIDVALIDITY
+    /// changed.
+    pub fn uids_to_fetch(&self, uid_validity: u32, current: &[u32]) -> Vec<u32> {
+        if !self.is_valid_for(uid_validity) {
+            return current.to_vec();
+        }
+
+        current
+            .iter()
+            .copied()
+            .filter(|uid| *uid > self.highest_uid)
+            .collect()
+    }
+
+    /// Combine this snapshot with freshly fetched headers
+    ///
+    /// Cached headers for messages no longer in `current` (deleted or moved)
+    /// are dropped.
+    pub fn merge(
+        self,
+        uid_validity: u32,
+        current: &[u32],
+        new_headers: Vec<MessageHeader>,
+    ) -> Self {
+        let present: HashSet<u32> = current.iter().copied().collect();
+        let reusable = self.is_valid_for(uid_validity);
+        let previous_highest = if reusable { self.highest_uid } else { 0 };
+
+        let mut headers: Vec<MessageHeader> = if reusable {
+            self.headers
+                .into_iter()
+                .filter(|h| present.contains(&h.uid))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        headers.extend(new_headers);
+        headers.sort_unstable_by_key(|h| h.uid);
+        headers.dedup_by_key(|h| h.uid);
+
+        let highest_uid = current
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
+            .max(previous_highest);
+
+        Self {
+            uid_validity,
+            highest_uid,
+            headers,
+        }
+    }
+}
+
+/// Search for message UIDs in `mailbox`
+///
+/// `criteria` restricts the search server-side (e.g. `SINCE 01-Jan-2024`);
+/// `None` searches `ALL` messages.
+pub async fn search_all_uids<S: MailboxOps>(
+    session: &mut S,
+    mailbox: &str,
+    criteria: Option<&str>,
+) -> Result<Vec<u32>> {
+    let criteria = criteria.unwrap_or(SEARCH_ALL);
+    let (_, uids) = select_and_search(session, mailbox, criteria, EMPTY_SEARCH_RETRY_DELAY).await?;
+    Ok(uids)
+}
+
+/// Total SELECT + UID SEARCH attempts when the search comes back empty
+const EMPTY_SEARCH_ATTEMPTS: u32 = 2;
+
+/// Pause before repeating an empty search
+const EMPTY_SEARCH_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Whether an empty UID SEARCH should be repeated
+///
+/// Gmail sometimes answers the first search right after authentication with
+/// no UIDs at all, so one empty result isn't trusted on its own.
+fn should_retry_empty_search(uids: &[u32], attempt: u32) -> bool {
+    uids.is_empty() && attempt < EMPTY_SEARCH_ATTEMPTS
+}
+
+/// SELECT `mailbox` and search its UIDs, repeating an empty search once
+///
+/// For an `ALL` search, the mailbox is only considered empty if every search
+/// returns nothing and SELECT reports no messages. If SELECT still reports
+/// messages, the search is treated as failed instead. Narrower criteria can
+/// legitimately match nothing.
+async fn select_and_search<S: MailboxOps>(
+    session: &mut S,
+    mailbox: &str,
+    criteria: &str,
+    retry_delay: Duration,
+) -> Result<(Mailbox, Vec<u32>)> {
+    let mut attempt = 1;
+
+    loop {
+        let selected = session
+            .select(mailbox)
+            .await
+            .with_context(|| format!("Failed to select {}", mailbox))?;
+
+        let uids = session
+            .uid_search(criteria)
+            .await
+            .context("Failed to search messages")?;
+
+        if !should_retry_empty_search(&uids, attempt) {
+            if uids.is_empty() && selected.exists > 0 && criteria == SEARCH_ALL {
+                anyhow::bail!(
+                    "{} reports {} messages but the UID search returned none; try again later",
+                    mailbox,
+                    selected.exists
+                );
+            }
+            return Ok((selected, uids));
+        }
+
+        tracing::warn!(
+            mailbox = %mailbox,
+            delay = ?retry_delay,
+            "UID SEARCH returned no messages, retrying"
+        );
+        tokio::time::sleep(retry_delay).await;
+        attempt += 1;
+    }
 }
 
 /// Fetch headers for a batch of UIDs
-pub async fn fetch_headers_batch(
-    session: &mut ImapSession,
+pub async fn fetch_headers_batch<S: MailboxOps>(
+    session: &mut S,
     uids: &[u32],
 ) -> Result<Vec<MessageHeader>> {
     if uids.is_empty() {
@@ -43,80 +264,403 @@ pub async fn fetch_headers_batch(
 
     let uid_set = format_uid_set(uids);
 
-    tracing::debug!("Fetching headers for UID set: {}", uid_set);
+    tracing::debug!(uid_set = %uid_set, "fetching headers");
 
-    let mut messages_stream = session
-        .uid_fetch(&uid_set, "BODY.PEEK[HEADER]")
+    let messages = session
+        .uid_fetch(&uid_set, "(FLAGS BODY.PEEK[HEADER])")
         .await
         .context("Failed to fetch headers")?;
 
     let mut headers = Vec::new();
 
-    // IMPORTANT: Use try_next() instead of next() to properly handle stream termination
-    while let Some(msg) = messages_stream
-        .try_next()
-        .await
-        .context("Error reading from fetch stream")?
-    {
+    for msg in messages {
         tracing::trace!(
-            "Received FETCH response - UID: {:?}, has header: {}, message: {}",
-            msg.uid,
-            msg.header().is_some(),
-            msg.message
+            uid = ?msg.uid,
+            has_header = msg.header.is_some(),
+            "received FETCH response"
         );
 
-        // IMPORTANT: Use msg.header() for BODY.PEEK[HEADER] requests, NOT msg.body()
-        if let (Some(uid), Some(header_bytes)) = (msg.uid, msg.header()) {
+        if let (Some(uid), Some(header_bytes)) = (msg.uid, msg.header.as_deref()) {
             match parse_message_header(uid, header_bytes) {
-                Ok(header) => {
-                    tracing::trace!("Parsed header for UID {}: from={}", uid, header.from);
+                Ok(mut header) => {
+                    header.seen = msg.seen;
+                    tracing::trace!(uid, from = %header.from, "parsed header");
                     headers.push(header);
                 }
                 Err(e) => {
-                    tracing::warn!("Failed to parse header for UID {}: {}", uid, e);
+                    tracing::warn!(uid, error = %e, "failed to parse header");
                 }
             }
         } else {
             tracing::warn!(
-                "Message missing UID={:?} or header={}",
-                msg.uid,
-                msg.header().is_some()
+                uid = ?msg.uid,
+                has_header = msg.header.is_some(),
+                "message missing UID or header"
             );
         }
     }
 
-    tracing::debug!("Successfully fetched {} headers", headers.len());
+    tracing::debug!(count = headers.len(), "fetched headers");
 
     Ok(headers)
 }
 
-/// Fetch all headers with batching
-pub async fn fetch_all_headers(
-    session: &mut ImapSession,
-    batch_size: usize,
+/// Fetch Message-ID headers for UIDs in the selected mailbox
+///
+/// Messages without a Message-ID are skipped.
+pub async fn fetch_message_ids<S: MailboxOps>(
+    session: &mut S,
+    uids: &[u32],
+) -> Result<Vec<String>> {
+    if uids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let uid_set = format_uid_set(uids);
+
+    let messages = session
+        .uid_fetch(&uid_set, "BODY.PEEK[HEADER.FIELDS (MESSAGE-ID)]")
+        .await
+        .context("Failed to fetch Message-IDs")?;
+
+    let message_ids = messages
+        .iter()
+        .filter_map(|msg| {
+            msg.header
+                .as_deref()
+                .and_then(|raw| parse_mail(raw).ok())
+                .and_then(|mail| mail.headers.get_first_value("Message-ID"))
+        })
+        .map(|message_id| message_id.trim().to_string())
+        .collect();
+
+    Ok(message_ids)
+}
+
+/// Fetch subject and date for the given UIDs, newest first
+///
+/// The mailbox containing the UIDs must already be selected. Messages without
+/// a date are listed last.
+pub async fn fetch_subjects_for_uids<S: MailboxOps>(
+    session: &mut S,
+    uids: &[u32],
+) -> Result<Vec<MessageSummary>> {
+    let mut summaries = Vec::with_capacity(uids.len());
+
+    for chunk in uids.chunks(FetchOptions::default().batch_size) {
+        let messages = session
+            .uid_fetch(
+                &format_uid_set(chunk),
+                "BODY.PEEK[HEADER.FIELDS (SUBJECT DATE)]",
+            )
+            .await
+            .context("Failed to fetch subjects")?;
+
+        for msg in messages {
+            if let (Some(uid), Some(raw)) = (msg.uid, msg.header.as_deref()) {
+                match parse_message_summary(uid, raw) {
+                    Ok(summary) => summaries.push(summary),
+                    Err(e) => tracing::warn!(uid, error = %e, "failed to parse subject"),
+                }
+            }
+        }
+    }
+
+    sort_newest_first(&mut summaries);
+
+    Ok(summaries)
+}
+
+/// Header fields naming the recipients of a sent message
+const RECIPIENT_FIELDS: &str = "BODY.PEEK[HEADER.FIELDS (TO CC BCC)]";
+
+/// Collect every address written to from `sent_folder`
+///
+/// Reads the To, Cc and Bcc headers of the messages in the Sent folder,
+/// limited to the most recent `options.max_messages` when set. Addresses
+/// are lowercased.
+pub async fn fetch_correspondents<S: MailboxOps>(
+    session: &mut S,
+    sent_folder: &str,
+    options: &FetchOptions,
+) -> Result<HashSet<String>> {
+    let uids = search_all_uids(session, sent_folder, None).await?;
+    let uids = limit_uids(&uids, options.max_messages);
+
+    let mut correspondents = HashSet::new();
+    for chunk in uids.chunks(options.batch_size.max(1)) {
+        let messages = session
+            .uid_fetch(&format_uid_set(chunk), RECIPIENT_FIELDS)
+            .await
+            .with_context(|| format!("Failed to fetch recipients from {}", sent_folder))?;
+
+        for raw in messages.iter().filter_map(|msg| msg.header.as_deref()) {
+            correspondents.extend(parse_recipients(raw));
+        }
+    }
+
+    tracing::debug!(
+        correspondents = correspondents.len(),
+        messages = uids.len(),
+        "read sent mail"
+    );
+
+    Ok(correspondents)
+}
+
+/// Lowercased To, Cc and Bcc addresses of a raw header block
+fn parse_recipients(raw: &[u8]) -> Vec<String> {
+    let Ok(mail) = parse_mail(raw) else {
+        return Vec::new();
+    };
+
+    ["To", "Cc", "Bcc"]
+        .iter()
+        .flat_map(|field| mail.headers.get_all_values(field))
+        .flat_map(|value| email::Mailbox::parse_all(&value))
+        .map(|mailbox| mailbox.address.trim().to_lowercase())
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Sort by date descending, undated messages last
+fn sort_newest_first(summaries: &mut [MessageSummary]) {
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.date));
+}
+
+/// Parse subject and date from a raw header block
+fn parse_message_summary(uid: u32, raw: &[u8]) -> Result<MessageSummary> {
+    let header = parse_message_header(uid, raw)?;
+
+    Ok(MessageSummary {
+        uid,
+        subject: header.subject,
+        date: header.date,
+    })
+}
+
+/// Fetch all headers in `mailbox` with batching
+pub async fn fetch_all_headers<S: MailboxOps>(
+    session: &mut S,
+    mailbox: &str,
+    options: &FetchOptions,
 ) -> Result<Vec<MessageHeader>> {
-    let uids = search_all_uids(session).await?;
+    fetch_all_headers_with_progress(session, mailbox, options, |_, _| {}).await
+}
+
+/// Fetch all headers with batching, reporting progress after each batch
+///
+/// `on_progress` receives the number of messages processed so far and the
+/// total number of messages to scan. If the time budget runs out, the last
+/// call reports fewer processed messages than the total.
+pub async fn fetch_all_headers_with_progress<S, F>(
+    session: &mut S,
+    mailbox: &str,
+    options: &FetchOptions,
+    mut on_progress: F,
+) -> Result<Vec<MessageHeader>>
+where
+    S: MailboxOps,
+    F: FnMut(usize, usize),
+{
+    let criteria = options.search_criteria();
+    let mut uids = search_all_uids(session, mailbox, Some(&criteria)).await?;
+    uids.sort_unstable();
+
+    let uids = limit_uids(&uids, options.max_messages);
+
+    fetch_uids_with_progress(session, uids, options, &mut |_, done, total| {
+        on_progress(done, total)
+    })
+    .await
+}
+
+/// Fetch only headers missing from `previous`, then merge them into it
+///
+/// Uses the UIDVALIDITY of `mailbox` and the snapshot's highest UID: only
+/// newer UIDs are downloaded, and a full rescan happens if UIDVALIDITY
+/// changed. The read state of cached messages is fetched again, since it
+/// changes without a new UID. `on_progress` counts only the messages
+/// actually fetched.
+pub async fn fetch_headers_incremental<S, F>(
+    session: &mut S,
+    mailbox: &str,
+    options: &FetchOptions,
+    previous: Option<MailboxSnapshot>,
+    mut on_progress: F,
+) -> Result<MailboxSnapshot>
+where
+    S: MailboxOps,
+    F: FnMut(usize, usize),
+{
+    fetch_headers_incremental_batched(session, mailbox, options, previous, |_, done, total| {
+        on_progress(done, total)
+    })
+    .await
+}
+
+/// Like [`fetch_headers_incremental`], but hands every batch of headers to
+/// `on_batch` as soon as it is available
+///
+/// Reusable cached headers are passed first (with 0 fetched), then each
+/// fetched batch along with the fetched and total counts. A scan stopped by
+/// its time budget ends with fewer fetched than total; its snapshot must not
+/// be cached, since the missing messages are below its highest UID.
+pub async fn fetch_headers_incremental_batched<S, F>(
+    session: &mut S,
+    mailbox: &str,
+    options: &FetchOptions,
+    previous: Option<MailboxSnapshot>,
+    mut on_batch: F,
+) -> Result<MailboxSnapshot>
+where
+    S: MailboxOps,
+    F: FnMut(&[MessageHeader], usize, usize),
+{
+    let criteria = options.search_criteria();
+    let (selected, mut uids) =
+        select_and_search(session, mailbox, &criteria, EMPTY_SEARCH_RETRY_DELAY).await?;
+    let uid_validity = selected.uid_validity.unwrap_or_default();
+    uids.sort_unstable();
+    let uids = limit_uids(&uids, options.max_messages);
+
+    let mut previous = previous.unwrap_or_default();
+    if previous.highest_uid > 0 && !previous.is_valid_for(uid_validity) {
+        tracing::info!(mailbox = %mailbox, "UIDVALIDITY changed, rescanning all messages");
+    }
+    if previous.is_valid_for(uid_validity) && !previous.headers.is_empty() {
+        refresh_seen(session, &mut previous).await?;
+    }
 
-    let mut all_headers = Vec::new();
+    let to_fetch = previous.uids_to_fetch(uid_validity, uids);
+    tracing::info!(
+        to_fetch = to_fetch.len(),
+        total = uids.len(),
+        "incremental header fetch"
+    );
 
-    for chunk in uids.chunks(batch_size) {
+    // Report cached headers for messages still in the mailbox right away
+    if previous.is_valid_for(uid_validity) {
+        let present: HashSet<u32> = uids.iter().copied().collect();
+        let cached: Vec<MessageHeader> = previous
+            .headers
+            .iter()
+            .filter(|h| present.contains(&h.uid))
+            .cloned()
+            .collect();
+        if !cached.is_empty() {
+            on_batch(&cached, 0, to_fetch.len());
+        }
+    }
+
+    let new_headers = fetch_uids_with_progress(session, &to_fetch, options, &mut on_batch).await?;
+
+    Ok(previous.merge(uid_validity, uids, new_headers))
+}
+
+/// Re-read the read state of every cached header in `snapshot`
+///
+/// Reading a message doesn't change its UID, so cached `seen` values go
+/// stale, and snapshots saved before read state was tracked have none.
+async fn refresh_seen<S: MailboxOps>(
+    session: &mut S,
+    snapshot: &mut MailboxSnapshot,
+) -> Result<()> {
+    let seen: HashMap<u32, bool> = session
+        .uid_fetch(&format!("1:{}", snapshot.highest_uid), "(FLAGS)")
+        .await
+        .context("Failed to fetch read state")?
+        .into_iter()
+        .filter_map(|msg| Some((msg.uid?, msg.seen?)))
+        .collect();
+
+    for header in &mut snapshot.headers {
+        if let Some(seen) = seen.get(&header.uid) {
+            header.seen = Some(*seen);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch headers for `uids` in batches, passing each batch to `on_batch`
+///
+/// With a time budget, batches are fetched newest first and the loop stops
+/// once the budget is spent.
+async fn fetch_uids_with_progress<S, F>(
+    session: &mut S,
+    uids: &[u32],
+    options: &FetchOptions,
+    on_batch: &mut F,
+) -> Result<Vec<MessageHeader>>
+where
+    S: MailboxOps,
+    F: FnMut(&[MessageHeader], usize, usize),
+{
+    let started = Instant::now();
+    let total = uids.len();
+    let batch_size = options.batch_size.max(1);
+
+    tracing::info!(total, batch_size, "scanning messages in batches");
+
+    let mut all_headers = Vec::with_capacity(total);
+    let mut processed = 0;
+
+    on_batch(&[], processed, total);
+
+    let mut chunks: Vec<&[u32]> = uids.chunks(batch_size).collect();
+    if options.time_budget.is_some() {
+        chunks.reverse();
+    }
+
+    for chunk in chunks {
         let headers = fetch_headers_batch(session, chunk).await?;
+
+        processed += chunk.len();
+        tracing::debug!(processed, total, "fetched batch");
+        on_batch(&headers, processed, total);
+
         all_headers.extend(headers);
+
+        if processed < total && options.budget_spent(started) {
+            tracing::info!(processed, total, "scan time budget reached, stopping");
+            break;
+        }
     }
 
     Ok(all_headers)
 }
 
+/// Keep only the most recent `max` UIDs (UIDs must be sorted ascending)
+fn limit_uids(uids: &[u32], max: Option<usize>) -> &[u32] {
+    match max {
+        Some(max) if uids.len() > max => &uids[uids.len() - max..],
+        _ => uids,
+    }
+}
+
 /// Parse message header from raw bytes
-fn parse_message_header(uid: u32, raw: &[u8]) -> Result<MessageHeader> {
+///
+/// `get_first_value` decodes RFC 2047 encoded-words (Base64 and
+/// Quoted-Printable, any charset), so `from` and `subject` are plain UTF-8.
+pub(crate) fn parse_message_header(uid: u32, raw: &[u8]) -> Result<MessageHeader> {
     let mail = parse_mail(raw).context("Failed to parse email")?;
 
     let from = mail.headers.get_first_value("From").unwrap_or_default();
 
     let subject = mail.headers.get_first_value("Subject").unwrap_or_default();
 
-    let list_unsubscribe = mail.headers.get_first_value("List-Unsubscribe");
+    let list_unsubscribe = join_list_unsubscribe(&mail.headers.get_all_values("List-Unsubscribe"));
     let list_unsubscribe_post = mail.headers.get_first_value("List-Unsubscribe-Post");
+    let list_id = mail.headers.get_first_value("List-Id");
+    let precedence = mail.headers.get_first_value("Precedence");
+    let auto_submitted = mail.headers.get_first_value("Auto-Submitted");
+
+    let date = mail
+        .headers
+        .get_first_value("Date")
+        .and_then(|d| dateparse(&d).ok())
+        .and_then(|ts| DateTime::from_timestamp(ts, 0));
 
     Ok(MessageHeader {
         uid,
@@ -124,9 +668,57 @@ fn parse_message_header(uid: u32, raw: &[u8]) -> Result<MessageHeader> {
         subject,
         list_unsubscribe,
         list_unsubscribe_post,
+        list_id,
+        date,
+        precedence,
+        auto_submitted,
+        seen: None,
     })
 }
 
+/// Combine every List-Unsubscribe occurrence into one unfolded value
+///
+/// Some senders put the mailto and https entries in separate headers, and
+/// long values are folded across lines. Whitespace inside `<...>` is dropped
+/// as RFC 2369 requires, so a URL folded mid-way is rejoined.
+fn join_list_unsubscribe(values: &[String]) -> Option<String> {
+    let joined = values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if joined.is_empty() {
+        return None;
+    }
+
+    let mut unfolded = String::with_capacity(joined.len());
+    let mut in_brackets = false;
+    let mut pending_space = false;
+
+    for c in joined.chars() {
+        match c {
+            '<' => in_brackets = true,
+            '>' => in_brackets = false,
+            _ => {}
+        }
+
+        if c.is_whitespace() {
+            pending_space = !in_brackets;
+            continue;
+        }
+
+        if pending_space && !unfolded.is_empty() {
+            unfolded.push(' ');
+        }
+        pending_space = false;
+        unfolded.push(c);
+    }
+
+    Some(unfolded)
+}
+
 /// Format UIDs for IMAP command (e.g., "1,2,3" or "1:100")
 fn format_uid_set(uids: &[u32]) -> String {
     if uids.is_empty() {
@@ -150,29 +742,122 @@ fn format_uid_set(uids: &[u32]) -> String {
     }
 }
 
+/// Headers grouped by sender, in a deterministic order
+///
+/// Groups are sorted by message count (descending), then by key; messages
+/// within a group are sorted by UID.
+pub type SenderGroups = Vec<(String, Vec<MessageHeader>)>;
+
 /// Group headers by sender email
-pub fn group_by_sender(headers: Vec<MessageHeader>) -> HashMap<String, Vec<MessageHeader>> {
-    headers
+pub fn group_by_sender(headers: Vec<MessageHeader>) -> SenderGroups {
+    group_by(headers, |header| extract_email(&header.from))
+}
+
+/// Group headers by sender domain
+///
+/// `news@retailer.com` and `promo@retailer.com` both end up under
+/// `retailer.com`. Addresses without a domain are kept as-is.
+pub fn group_by_domain(headers: Vec<MessageHeader>) -> SenderGroups {
+    group_by(headers, |header| {
+        extract_domain(&extract_email(&header.from))
+    })
+}
+
+fn group_by<F>(headers: Vec<MessageHeader>, key: F) -> SenderGroups
+where
+    F: Fn(&MessageHeader) -> String + Sync + Send,
+{
+    let grouped = headers
         .into_par_iter()
         .fold(HashMap::new, |mut acc, header| {
-            let email = extract_email(&header.from);
-            acc.entry(email).or_insert_with(Vec::new).push(header);
+            acc.entry(key(&header))
+                .or_insert_with(Vec::new)
+                .push(header);
             acc
         })
         .reduce(HashMap::new, |mut acc, map| {
-            for (email, mut msgs) in map {
-                acc.entry(email).or_insert_with(Vec::new).append(&mut msgs);
+            for (key, mut msgs) in map {
+                acc.entry(key).or_insert_with(Vec::new).append(&mut msgs);
             }
             acc
+        });
+
+    sort_groups(grouped)
+}
+
+/// Order groups by message count (descending), then key, and their messages by UID
+pub fn sort_groups(grouped: HashMap<String, Vec<MessageHeader>>) -> SenderGroups {
+    let mut groups: SenderGroups = grouped
+        .into_iter()
+        .map(|(key, mut messages)| {
+            messages.sort_unstable_by_key(|h| h.uid);
+            (key, messages)
         })
+        .collect();
+
+    groups.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+/// Drop messages sent from the account's own mailbox
+///
+/// Self-sent mail and drafts would otherwise show up as a high-volume sender.
+/// Addresses are compared with [`normalize_mailbox`], so `+tag` and dotted
+/// Gmail variants of the account address are excluded too.
+pub fn exclude_own_messages(headers: Vec<MessageHeader>, own_email: &str) -> Vec<MessageHeader> {
+    let own = normalize_mailbox(own_email);
+
+    headers
+        .into_iter()
+        .filter(|header| normalize_mailbox(&extract_email(&header.from)) != own)
+        .collect()
+}
+
+/// Normalize an address to the mailbox it is delivered to
+///
+/// Lowercases, strips any `+tag` from the local part and, for Gmail, removes
+/// dots and maps `googlemail.com` to `gmail.com`.
+///
+/// Example: "J.Ohn+news@googlemail.com" -> "john@gmail.com"
+pub fn normalize_mailbox(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return email;
+    };
+
+    let local = local.split('+').next().unwrap_or(local);
+
+    match domain {
+        "gmail.com" | "googlemail.com" => format!("{}@gmail.com", local.replace('.', "")),
+        _ => format!("{}@{}", local, domain),
+    }
+}
+
+/// Extract the lowercased domain of an email address
+///
+/// Example: "News@Retailer.com" -> "retailer.com"
+fn extract_domain(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((_, domain)) => domain.to_lowercase(),
+        None => email.to_string(),
+    }
 }
 
 /// Extract email address from From header
 ///
+/// Uses the first mailbox found by [`email::Mailbox::parse_first`]; headers it
+/// can't parse fall back to the text between `<` and `>`, or the whole
+/// trimmed header.
+///
 /// Examples:
 /// - "John Doe <john@example.com>" -> "john@example.com"
 /// - "john@example.com" -> "john@example.com"
 fn extract_email(from: &str) -> String {
+    if let Some(mailbox) = email::Mailbox::parse_first(from) {
+        return mailbox.address;
+    }
+
     if let Some(start) = from.find('<') {
         if let Some(end) = from.find('>') {
             return from[start + 1..end].to_string();
@@ -185,6 +870,7 @@ fn extract_email(from: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::imap::mailbox::mock::MockMailbox;
 
     #[test]
     fn test_format_uid_set_consecutive() {
@@ -198,6 +884,88 @@ mod tests {
         assert_eq!(format_uid_set(&uids), "1,3,5,7");
     }
 
+    #[test]
+    fn test_limit_uids() {
+        let uids = vec![1, 2, 3, 4, 5];
+        assert_eq!(limit_uids(&uids, None), &[1, 2, 3, 4, 5]);
+        assert_eq!(limit_uids(&uids, Some(2)), &[4, 5]);
+        assert_eq!(limit_uids(&uids, Some(10)), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_parse_message_header_date() {
+        let raw = b"From: News <news@example.com>\r\nSubject: Hi\r\nDate: Tue, 1 Jul 2025 10:00:00 +0000\r\n\r\n";
+        let header = parse_message_header(7, raw).unwrap();
+
+        assert_eq!(
+            header.date.map(|d| d.to_rfc3339()),
+            Some("2025-07-01T10:00:00+00:00".to_string())
+        );
+
+        let raw = b"From: news@example.com\r\nSubject: Hi\r\n\r\n";
+        assert!(parse_message_header(8, raw).unwrap().date.is_none());
+    }
+
+    #[test]
+    fn test_parse_message_header_list_id() {
+        let raw = b"From: dev@lists.example.org\r\nSubject: [dev] Release\r\nList-Id: Developers <dev.lists.example.org>\r\n\r\n";
+        let header = parse_message_header(9, raw).unwrap();
+        assert_eq!(
+            header.list_id.as_deref(),
+            Some("Developers <dev.lists.example.org>")
+        );
+
+        let raw = b"From: friend@example.com\r\nSubject: Hi\r\n\r\n";
+        assert!(parse_message_header(10, raw).unwrap().list_id.is_none());
+    }
+
+    #[test]
+    fn test_parse_message_header_precedence_and_auto_submitted() {
+        let raw = b"From: alerts@bank.example\r\nSubject: Statement ready\r\n\
+                    Precedence: bulk\r\nAuto-Submitted: auto-generated\r\n\r\n";
+        let header = parse_message_header(11, raw).unwrap();
+        assert_eq!(header.precedence.as_deref(), Some("bulk"));
+        assert_eq!(header.auto_submitted.as_deref(), Some("auto-generated"));
+
+        let raw = b"From: friend@example.com\r\nSubject: Hi\r\n\r\n";
+        let header = parse_message_header(12, raw).unwrap();
+        assert!(header.precedence.is_none());
+        assert!(header.auto_submitted.is_none());
+    }
+
+    #[test]
+    fn test_parse_message_header_folded_list_unsubscribe() {
+        let raw = b"From: news@example.com\r\nSubject: Hi\r\nList-Unsubscribe: <mailto:unsub@example.com?subject=stop>,\r\n <https://example.com/unsub?id=42>\r\n\r\n";
+        let header = parse_message_header(11, raw).unwrap();
+        let value = header.list_unsubscribe.unwrap();
+
+        assert_eq!(
+            value,
+            "<mailto:unsub@example.com?subject=stop>, <https://example.com/unsub?id=42>"
+        );
+        assert_eq!(
+            crate::domain::analysis::parse_list_unsubscribe(&value),
+            vec!["https://example.com/unsub?id=42"]
+        );
+    }
+
+    #[test]
+    fn test_parse_message_header_repeated_list_unsubscribe() {
+        let raw = b"From: news@example.com\r\nList-Unsubscribe: <mailto:unsub@example.com>\r\nSubject: Hi\r\nList-Unsubscribe: <https://example.com/\r\n unsub?id=42>\r\n\r\n";
+        let header = parse_message_header(12, raw).unwrap();
+
+        assert_eq!(
+            header.list_unsubscribe.as_deref(),
+            Some("<mailto:unsub@example.com>, <https://example.com/unsub?id=42>")
+        );
+
+        let raw = b"From: friend@example.com\r\nSubject: Hi\r\n\r\n";
+        assert!(parse_message_header(13, raw)
+            .unwrap()
+            .list_unsubscribe
+            .is_none());
+    }
+
     #[test]
     fn test_extract_email() {
         assert_eq!(
@@ -205,5 +973,552 @@ mod tests {
             "john@example.com"
         );
         assert_eq!(extract_email("john@example.com"), "john@example.com");
+        assert_eq!(extract_email("\"Doe, John\" <j@x.com>"), "j@x.com");
+        assert_eq!(
+            extract_email("\"Sales <EU>\" <sales@shop.example>"),
+            "sales@shop.example"
+        );
+        assert_eq!(
+            extract_email("john@example.com (John Doe)"),
+            "john@example.com"
+        );
+        // No mailbox at all: the header itself is the group key
+        assert_eq!(
+            extract_email("undisclosed-recipients:;"),
+            "undisclosed-recipients:;"
+        );
+    }
+
+    #[test]
+    fn test_parse_base64_encoded_from() {
+        let raw = b"From: =?UTF-8?B?Q2Fmw6kgTcO8bGxlcg==?= <news@cafe.example>\r\n\
+                    Subject: =?UTF-8?B?U29sZGVzIGQnw6l0w6kg4piA?=\r\n\r\n";
+
+        let header = parse_message_header(1, raw).unwrap();
+
+        assert_eq!(header.from, "Café Müller <news@cafe.example>");
+        assert_eq!(header.subject, "Soldes d'été ☀");
+        assert_eq!(extract_email(&header.from), "news@cafe.example");
+    }
+
+    #[test]
+    fn test_parse_quoted_printable_encoded_from() {
+        let raw = b"From: =?ISO-8859-1?Q?Jos=E9_Garc=EDa?= <jose@example.com>\r\n\
+                    Subject: =?UTF-8?Q?R=C3=A9sum=C3=A9_de_la_semaine?=\r\n\r\n";
+
+        let header = parse_message_header(1, raw).unwrap();
+
+        assert_eq!(header.from, "José García <jose@example.com>");
+        assert_eq!(header.subject, "Résumé de la semaine");
+    }
+
+    #[test]
+    fn test_parse_message_summary_and_sort() {
+        let older = parse_message_summary(
+            1,
+            b"Subject: Weekly digest\r\nDate: Mon, 1 Jan 2024 10:00:00 +0000\r\n\r\n",
+        )
+        .unwrap();
+        let newer = parse_message_summary(
+            2,
+            b"Subject: =?UTF-8?Q?Derni=C3=A8re_chance?=\r\nDate: Tue, 2 Jan 2024 10:00:00 +0000\r\n\r\n",
+        )
+        .unwrap();
+        let undated = parse_message_summary(3, b"Subject: No date\r\n\r\n").unwrap();
+
+        assert_eq!(newer.subject, "Dernière chance");
+
+        let mut summaries = vec![undated, older, newer];
+        sort_newest_first(&mut summaries);
+        let uids: Vec<u32> = summaries.iter().map(|s| s.uid).collect();
+        assert_eq!(uids, vec![2, 1, 3]);
+    }
+
+    fn header(uid: u32, from: &str) -> MessageHeader {
+        MessageHeader {
+            uid,
+            from: from.to_string(),
+            subject: String::new(),
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            list_id: None,
+            date: None,
+            precedence: None,
+            auto_submitted: None,
+            seen: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by_domain_merges_subaddresses() {
+        let headers = vec![
+            header(1, "Retailer News <news@retailer.com>"),
+            header(2, "promo@Retailer.com"),
+            header(3, "offers@retailer.com"),
+            header(4, "friend@example.com"),
+        ];
+
+        let grouped = group_by_domain(headers);
+
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].0, "retailer.com");
+        let uids: Vec<u32> = grouped[0].1.iter().map(|h| h.uid).collect();
+        assert_eq!(uids, vec![1, 2, 3]);
+        assert_eq!(grouped[1].0, "example.com");
+        assert_eq!(grouped[1].1.len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_sender_order_is_stable() {
+        let headers: Vec<MessageHeader> = (1..=400)
+            .map(|uid| {
+                let from = match uid % 4 {
+                    0 => "zeta@example.com",
+                    1 => "alpha@example.com",
+                    2 => "mid@example.com",
+                    _ => "beta@example.com",
+                };
+                header(uid, from)
+            })
+            .chain([header(401, "alpha@example.com")])
+            .collect();
+
+        let keys = |groups: &SenderGroups| -> Vec<String> {
+            groups.iter().map(|(key, _)| key.clone()).collect()
+        };
+        let first = group_by_sender(headers.clone());
+        let second = group_by_sender(headers);
+
+        // Most messages first, ties broken by address
+        assert_eq!(
+            keys(&first),
+            vec![
+                "alpha@example.com",
+                "beta@example.com",
+                "mid@example.com",
+                "zeta@example.com"
+            ]
+        );
+        assert_eq!(keys(&first), keys(&second));
+        assert!(first
+            .iter()
+            .all(|(_, messages)| messages.windows(2).all(|w| w[0].uid < w[1].uid)));
+    }
+
+    fn snapshot(uid_validity: u32, uids: &[u32]) -> MailboxSnapshot {
+        MailboxSnapshot {
+            uid_validity,
+            highest_uid: uids.iter().copied().max().unwrap_or_default(),
+            headers: uids
+                .iter()
+                .map(|uid| header(*uid, "a@example.com"))
+                .collect(),
+        }
+    }
+
+    fn uids_of(snapshot: &MailboxSnapshot) -> Vec<u32> {
+        snapshot.headers.iter().map(|h| h.uid).collect()
+    }
+
+    #[test]
+    fn test_snapshot_fetches_only_new_uids() {
+        let cached = snapshot(7, &[1, 2, 3]);
+        let current = [2, 3, 4, 5];
+
+        assert_eq!(cached.uids_to_fetch(7, &current), vec![4, 5]);
+
+        let merged = cached.merge(
+            7,
+            &current,
+            vec![header(5, "b@example.com"), header(4, "b@example.com")],
+        );
+
+        // UID 1 was deleted from INBOX since the last scan
+        assert_eq!(uids_of(&merged), vec![2, 3, 4, 5]);
+        assert_eq!(merged.highest_uid, 5);
+        assert_eq!(merged.uid_validity, 7);
+    }
+
+    #[test]
+    fn test_snapshot_full_rescan_on_uid_validity_change() {
+        let cached = snapshot(7, &[1, 2, 3]);
+        let current = [1, 2];
+
+        assert_eq!(cached.uids_to_fetch(8, &current), vec![1, 2]);
+
+        let merged = cached.merge(8, &current, vec![header(1, "new@example.com")]);
+        assert_eq!(uids_of(&merged), vec![1]);
+        assert_eq!(merged.headers[0].from, "new@example.com");
+        assert_eq!(merged.highest_uid, 2);
+        assert_eq!(merged.uid_validity, 8);
+    }
+
+    #[test]
+    fn test_snapshot_without_cache_or_uid_validity() {
+        let empty = MailboxSnapshot::default();
+        assert_eq!(empty.uids_to_fetch(7, &[1, 2]), vec![1, 2]);
+
+        // Servers that don't report UIDVALIDITY always get a full scan
+        let cached = snapshot(0, &[1, 2]);
+        assert_eq!(cached.uids_to_fetch(0, &[1, 2, 3]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_snapshot_keeps_high_water_mark_when_newest_deleted() {
+        let merged = snapshot(7, &[1, 2, 9]).merge(7, &[1, 2], vec![]);
+
+        assert_eq!(uids_of(&merged), vec![1, 2]);
+        assert_eq!(merged.highest_uid, 9);
+    }
+
+    #[test]
+    fn test_normalize_mailbox() {
+        assert_eq!(normalize_mailbox("j.ohn+news@gmail.com"), "john@gmail.com");
+        assert_eq!(normalize_mailbox("J.Ohn@GoogleMail.com"), "john@gmail.com");
+        assert_eq!(normalize_mailbox(" john@gmail.com "), "john@gmail.com");
+        assert_eq!(
+            normalize_mailbox("first.last+tag@example.com"),
+            "first.last@example.com"
+        );
+        assert_eq!(normalize_mailbox("not-an-address"), "not-an-address");
+    }
+
+    #[test]
+    fn test_exclude_own_messages() {
+        let headers = vec![
+            header(1, "Me <j.ohn+drafts@gmail.com>"),
+            header(2, "john@gmail.com"),
+            header(3, "news@retailer.com"),
+        ];
+
+        let remaining = exclude_own_messages(headers, "John@gmail.com");
+
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].uid, 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_headers_batch_parses_responses() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (
+                3,
+                "From: Shop <deals@shop.com>\r\nSubject: Sale\r\nList-Unsubscribe: <https://shop.com/u>\r\n\r\n",
+            ),
+            (4, "From: alice@example.com\r\nSubject: Lunch\r\n\r\n"),
+        ]);
+        mailbox.seen.insert(4);
+
+        let headers = fetch_headers_batch(&mut mailbox, &[3, 4, 5]).await.unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec!["UID FETCH 3:5 (FLAGS BODY.PEEK[HEADER])"]
+        );
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[0].uid, 3);
+        assert_eq!(headers[0].from, "Shop <deals@shop.com>");
+        assert_eq!(
+            headers[0].list_unsubscribe.as_deref(),
+            Some("<https://shop.com/u>")
+        );
+        assert_eq!(headers[1].subject, "Lunch");
+        assert_eq!(headers[0].seen, Some(false));
+        assert_eq!(headers[1].seen, Some(true));
+    }
+
+    #[test]
+    fn test_parse_recipients() {
+        let raw = b"To: \"Doe, John\" <John.Doe@Example.com>, bob@work.com\r\n\
+            Cc: Team: carol@work.com, dan@work.com;\r\n\
+            Bcc: =?UTF-8?Q?=C3=89ve?= <eve@example.org>\r\n\r\n";
+
+        assert_eq!(
+            parse_recipients(raw),
+            vec![
+                "john.doe@example.com",
+                "bob@work.com",
+                "carol@work.com",
+                "dan@work.com",
+                "eve@example.org",
+            ]
+        );
+        assert!(parse_recipients(b"To: undisclosed-recipients:;\r\n\r\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_correspondents_reads_sent_folder() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (10, "To: alice@example.com\r\nCc: bob@example.com\r\n\r\n"),
+            (11, "To: Alice <ALICE@example.com>\r\n\r\n"),
+            (12, "To: carol@example.com\r\n\r\n"),
+        ]);
+        let options = FetchOptions {
+            max_messages: Some(2),
+            ..FetchOptions::default()
+        };
+
+        let correspondents = fetch_correspondents(&mut mailbox, "[Gmail]/Sent Mail", &options)
+            .await
+            .unwrap();
+
+        assert_eq!(mailbox.commands[0], "SELECT [Gmail]/Sent Mail");
+        assert_eq!(
+            mailbox.commands[2],
+            "UID FETCH 11:12 BODY.PEEK[HEADER.FIELDS (TO CC BCC)]"
+        );
+        let mut correspondents: Vec<String> = correspondents.into_iter().collect();
+        correspondents.sort();
+        assert_eq!(
+            correspondents,
+            vec!["alice@example.com", "carol@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_should_retry_empty_search_once() {
+        assert!(should_retry_empty_search(&[], 1));
+        assert!(!should_retry_empty_search(&[], 2));
+        assert!(!should_retry_empty_search(&[4, 5], 1));
+    }
+
+    #[tokio::test]
+    async fn test_select_and_search_retries_spurious_empty_result() {
+        let mut mailbox = MockMailbox::with_messages(&[(4, "From: a@example.com\r\n\r\n")]);
+        mailbox.empty_searches = 1;
+
+        let (_, uids) = select_and_search(&mut mailbox, "INBOX", "ALL", Duration::ZERO)
+            .await
+            .unwrap();
+
+        assert_eq!(uids, vec![4]);
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID SEARCH ALL",
+                "SELECT INBOX",
+                "UID SEARCH ALL"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_and_search_distinguishes_empty_from_failed() {
+        let mut empty = MockMailbox::default();
+        let (_, uids) = select_and_search(&mut empty, "INBOX", "ALL", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(uids.is_empty());
+        assert_eq!(empty.commands.len(), 4);
+
+        let mut failing = MockMailbox::with_messages(&[(4, "From: a@example.com\r\n\r\n")]);
+        failing.empty_searches = 2;
+        let err = select_and_search(&mut failing, "INBOX", "ALL", Duration::ZERO)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("reports 1 messages"));
+    }
+
+    #[test]
+    fn test_search_criteria_formats_imap_date() {
+        let since = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        assert_eq!(since_criteria(since), "SINCE 05-Jan-2024");
+
+        let options = FetchOptions {
+            since: Some(NaiveDate::from_ymd_opt(2023, 11, 20).unwrap()),
+            ..FetchOptions::default()
+        };
+        assert_eq!(options.search_criteria(), "SINCE 20-Nov-2023");
+        assert_eq!(FetchOptions::default().search_criteria(), "ALL");
+    }
+
+    #[tokio::test]
+    async fn test_since_search_may_match_nothing() {
+        let mut mailbox = MockMailbox::with_messages(&[(4, "From: a@example.com\r\n\r\n")]);
+        mailbox.empty_searches = 2;
+
+        let uids = search_all_uids(&mut mailbox, "INBOX", Some("SINCE 05-Jan-2024"))
+            .await
+            .unwrap();
+
+        assert!(uids.is_empty());
+        assert_eq!(mailbox.commands[1], "UID SEARCH SINCE 05-Jan-2024");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_headers_incremental_selects_and_searches_inbox() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (1, "From: a@example.com\r\nSubject: One\r\n\r\n"),
+            (2, "From: b@example.com\r\nSubject: Two\r\n\r\n"),
+        ]);
+        mailbox.uid_validity = Some(9);
+
+        let snapshot = fetch_headers_incremental(
+            &mut mailbox,
+            "INBOX",
+            &FetchOptions::default(),
+            None,
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID SEARCH ALL",
+                "UID FETCH 1:2 (FLAGS BODY.PEEK[HEADER])"
+            ]
+        );
+        assert_eq!(snapshot.uid_validity, 9);
+        assert_eq!(snapshot.headers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_headers_uses_given_mailbox() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (4, "From: deals@shop.com\r\nSubject: Sale\r\n\r\n"),
+            (5, "From: news@shop.com\r\nSubject: News\r\n\r\n"),
+        ]);
+
+        let headers = fetch_all_headers(&mut mailbox, "Promotions", &FetchOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT Promotions",
+                "UID SEARCH ALL",
+                "UID FETCH 4:5 (FLAGS BODY.PEEK[HEADER])"
+            ]
+        );
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_headers_incremental_batched_reports_cache_then_batches() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (1, "From: a@example.com\r\nSubject: One\r\n\r\n"),
+            (2, "From: b@example.com\r\nSubject: Two\r\n\r\n"),
+            (3, "From: c@example.com\r\nSubject: Three\r\n\r\n"),
+        ]);
+        mailbox.uid_validity = Some(9);
+        let previous = MailboxSnapshot {
+            uid_validity: 9,
+            highest_uid: 1,
+            headers: vec![header(1, "a@example.com")],
+        };
+        let options = FetchOptions {
+            batch_size: 1,
+            ..FetchOptions::default()
+        };
+
+        let mut batches = Vec::new();
+        let snapshot = fetch_headers_incremental_batched(
+            &mut mailbox,
+            "INBOX",
+            &options,
+            Some(previous),
+            |h, done, total| {
+                let uids: Vec<u32> = h.iter().map(|h| h.uid).collect();
+                batches.push((uids, done, total));
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            batches,
+            vec![
+                (vec![1], 0, 2),
+                (vec![], 0, 2),
+                (vec![2], 1, 2),
+                (vec![3], 2, 2)
+            ]
+        );
+        assert_eq!(snapshot.headers.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_fetch_refreshes_cached_read_state() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (1, "From: a@example.com\r\nSubject: One\r\n\r\n"),
+            (2, "From: b@example.com\r\nSubject: Two\r\n\r\n"),
+            (3, "From: c@example.com\r\nSubject: Three\r\n\r\n"),
+        ]);
+        mailbox.uid_validity = Some(9);
+        mailbox.seen.insert(1);
+
+        // UID 1 was read since, and UID 2 comes from a snapshot saved before
+        // read state was cached
+        let previous = MailboxSnapshot {
+            uid_validity: 9,
+            highest_uid: 2,
+            headers: vec![
+                MessageHeader {
+                    seen: Some(false),
+                    ..header(1, "a@example.com")
+                },
+                header(2, "b@example.com"),
+            ],
+        };
+
+        let snapshot = fetch_headers_incremental(
+            &mut mailbox,
+            "INBOX",
+            &FetchOptions::default(),
+            Some(previous),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID SEARCH ALL",
+                "UID FETCH 1:2 (FLAGS)",
+                "UID FETCH 3 (FLAGS BODY.PEEK[HEADER])"
+            ]
+        );
+        let seen: Vec<_> = snapshot.headers.iter().map(|h| h.seen).collect();
+        assert_eq!(seen, vec![Some(true), Some(false), Some(false)]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stops_when_time_budget_is_spent() {
+        let mut mailbox = MockMailbox::with_messages(&[
+            (1, "From: a@example.com\r\nSubject: One\r\n\r\n"),
+            (2, "From: b@example.com\r\nSubject: Two\r\n\r\n"),
+            (3, "From: c@example.com\r\nSubject: Three\r\n\r\n"),
+        ]);
+        let options = FetchOptions {
+            batch_size: 1,
+            time_budget: Some(Duration::ZERO),
+            ..FetchOptions::default()
+        };
+
+        let mut progress = Vec::new();
+        let headers = fetch_all_headers_with_progress(&mut mailbox, "INBOX", &options, |d, t| {
+            progress.push((d, t))
+        })
+        .await
+        .unwrap();
+
+        // Only the newest batch is fetched before the budget stops the loop
+        assert_eq!(
+            mailbox.commands,
+            vec![
+                "SELECT INBOX",
+                "UID SEARCH ALL",
+                "UID FETCH 3 (FLAGS BODY.PEEK[HEADER])"
+            ]
+        );
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].uid, 3);
+        assert_eq!(progress, vec![(0, 3), (1, 3)]);
     }
 }
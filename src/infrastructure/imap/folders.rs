@@ -0,0 +1,230 @@
+//! Special-use folder discovery (RFC 6154)
+//!
+//! Gmail localizes its system folders (`[Google Mail]/Papierkorb` instead of
+//! `[Gmail]/Trash`), so the trash, spam, All Mail and Sent names are looked
+//! up from the `\Trash`, `\Junk`, `\All` and `\Sent` attributes returned by
+//! LIST. The same
+//! listing provides the mailboxes a user can pick to scan and clean.
+
+use super::connection::{ImapConfig, ImapSession, DEFAULT_MAILBOX};
+use anyhow::{Context, Result};
+use async_imap::types::NameAttribute;
+use futures::TryStreamExt;
+
+/// Folder names advertised by the server for each special use
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecialFolders {
+    /// Mailbox with the `\Trash` attribute
+    pub trash: Option<String>,
+
+    /// Mailbox with the `\Junk` attribute
+    pub junk: Option<String>,
+
+    /// Mailbox with the `\All` attribute
+    pub all: Option<String>,
+
+    /// Mailbox with the `\Sent` attribute
+    pub sent: Option<String>,
+}
+
+impl SpecialFolders {
+    /// Pick the first mailbox carrying each special-use attribute
+    pub fn from_names<'a, I>(names: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a [NameAttribute<'a>])>,
+    {
+        let mut folders = Self::default();
+
+        for (name, attributes) in names {
+            for attribute in attributes {
+                let slot = match attribute {
+                    NameAttribute::Trash => &mut folders.trash,
+                    NameAttribute::Junk => &mut folders.junk,
+                    NameAttribute::All => &mut folders.all,
+                    NameAttribute::Sent => &mut folders.sent,
+                    _ => continue,
+                };
+                slot.get_or_insert_with(|| name.to_string());
+            }
+        }
+
+        folders
+    }
+
+    /// Apply discovered names to `config`
+    ///
+    /// Only folders still set to the built-in Gmail defaults are replaced, so
+    /// names configured explicitly (env vars or config file) always win.
+    pub fn apply_to(&self, config: &ImapConfig) -> ImapConfig {
+        let defaults = ImapConfig::default();
+        let mut resolved = config.clone();
+
+        let pairs = [
+            (
+                &mut resolved.trash_folder,
+                &defaults.trash_folder,
+                &self.trash,
+            ),
+            (&mut resolved.spam_folder, &defaults.spam_folder, &self.junk),
+            (
+                &mut resolved.all_mail_folder,
+                &defaults.all_mail_folder,
+                &self.all,
+            ),
+            (&mut resolved.sent_folder, &defaults.sent_folder, &self.sent),
+        ];
+        for (folder, default, discovered) in pairs {
+            if let Some(discovered) = discovered {
+                if folder == default {
+                    *folder = discovered.clone();
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+/// Mailboxes that can be selected, INBOX first and the rest sorted by name
+///
+/// Containers flagged `\Noselect` (such as `[Gmail]`) are left out.
+pub fn selectable_names<'a, I>(names: I) -> Vec<String>
+where
+    I: IntoIterator<Item = (&'a str, &'a [NameAttribute<'a>])>,
+{
+    let mut selectable: Vec<String> = names
+        .into_iter()
+        .filter(|(_, attributes)| !attributes.contains(&NameAttribute::NoSelect))
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    selectable.sort_by_key(|name| (name != DEFAULT_MAILBOX, name.to_lowercase()));
+    selectable
+}
+
+/// List every mailbox and collect its special-use folders
+pub async fn discover(session: &mut ImapSession) -> Result<SpecialFolders> {
+    let names = list_all(session).await?;
+
+    Ok(SpecialFolders::from_names(
+        names.iter().map(|n| (n.name(), n.attributes())),
+    ))
+}
+
+/// List the mailboxes that can be scanned and cleaned
+///
+/// See [`selectable_names`].
+pub async fn list_mailboxes(session: &mut ImapSession) -> Result<Vec<String>> {
+    let names = list_all(session).await?;
+
+    Ok(selectable_names(
+        names.iter().map(|n| (n.name(), n.attributes())),
+    ))
+}
+
+async fn list_all(session: &mut ImapSession) -> Result<Vec<async_imap::types::Name>> {
+    session
+        .list(Some(""), Some("*"))
+        .await
+        .context("Failed to list mailboxes")?
+        .try_collect()
+        .await
+        .context("Failed to read mailbox list")
+}
+
+/// Resolve localized folder names for this session
+///
+/// Falls back to `config` unchanged if discovery fails.
+pub async fn resolve(session: &mut ImapSession, config: &ImapConfig) -> ImapConfig {
+    match discover(session).await {
+        Ok(folders) => {
+            let resolved = folders.apply_to(config);
+            tracing::debug!(
+                trash = %resolved.trash_folder,
+                spam = %resolved.spam_folder,
+                all_mail = %resolved.all_mail_folder,
+                sent = %resolved.sent_folder,
+                "using folders"
+            );
+            resolved
+        }
+        Err(e) => {
+            tracing::warn!(
+                error = %format_args!("{:#}", e),
+                "folder discovery failed, using configured names"
+            );
+            config.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_names_localized_gmail() {
+        let inbox = [];
+        let trash = [
+            NameAttribute::Extension("\\HasNoChildren".into()),
+            NameAttribute::Trash,
+        ];
+        let junk = [NameAttribute::Junk];
+        let all = [NameAttribute::All];
+        let sent = [NameAttribute::Sent];
+        let names: Vec<(&str, &[NameAttribute])> = vec![
+            ("INBOX", &inbox),
+            ("[Google Mail]/Papierkorb", &trash),
+            ("[Google Mail]/Spam", &junk),
+            ("[Google Mail]/Alle Nachrichten", &all),
+            ("[Google Mail]/Gesendet", &sent),
+        ];
+
+        let folders = SpecialFolders::from_names(names);
+
+        assert_eq!(folders.trash.as_deref(), Some("[Google Mail]/Papierkorb"));
+        assert_eq!(folders.junk.as_deref(), Some("[Google Mail]/Spam"));
+        assert_eq!(
+            folders.all.as_deref(),
+            Some("[Google Mail]/Alle Nachrichten")
+        );
+        assert_eq!(folders.sent.as_deref(), Some("[Google Mail]/Gesendet"));
+    }
+
+    #[test]
+    fn test_apply_to_keeps_explicit_folders() {
+        let folders = SpecialFolders {
+            trash: Some("[Google Mail]/Papierkorb".to_string()),
+            junk: Some("[Google Mail]/Spam".to_string()),
+            all: None,
+            sent: None,
+        };
+        let config = ImapConfig {
+            spam_folder: "Custom/Junk".to_string(),
+            ..ImapConfig::default()
+        };
+
+        let resolved = folders.apply_to(&config);
+
+        assert_eq!(resolved.trash_folder, "[Google Mail]/Papierkorb");
+        assert_eq!(resolved.spam_folder, "Custom/Junk");
+        assert_eq!(resolved.all_mail_folder, "[Gmail]/All Mail");
+    }
+
+    #[test]
+    fn test_selectable_names_skips_containers() {
+        let none = [];
+        let container = [NameAttribute::NoSelect];
+        let names: Vec<(&str, &[NameAttribute])> = vec![
+            ("Promotions", &none),
+            ("[Gmail]", &container),
+            ("INBOX", &none),
+            ("archive", &none),
+        ];
+
+        assert_eq!(
+            selectable_names(names),
+            vec!["INBOX", "archive", "Promotions"]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Mailbox command abstraction
+//!
+//! [`MailboxOps`] covers the IMAP commands used by fetching and cleanup
+//! actions. It is implemented for the real [`ImapSession`] and, in tests, for
+//! a mock that records every command so the issued sequence can be asserted.
+
+use super::connection::ImapSession;
+use anyhow::Result;
+use async_imap::types::{Flag, Mailbox};
+use futures::TryStreamExt;
+use std::future::Future;
+
+/// One FETCH response with its UID and raw header block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchedMessage {
+    /// Message UID (None if the server omitted it)
+    pub uid: Option<u32>,
+
+    /// Raw header bytes from `BODY[HEADER…]` (None if not returned)
+    pub header: Option<Vec<u8>>,
+
+    /// Whether the message carries `\Seen` (None unless FLAGS was requested)
+    pub seen: Option<bool>,
+}
+
+/// IMAP commands needed by the fetch and action functions
+///
+/// Streams are collected before returning, so implementations only deal with
+/// owned values.
+pub trait MailboxOps: Send {
+    /// SELECT a mailbox for reading and writing
+    fn select(&mut self, mailbox: &str) -> impl Future<Output = Result<Mailbox>> + Send;
+
+    /// UID SEARCH the selected mailbox
+    fn uid_search(&mut self, query: &str) -> impl Future<Output = Result<Vec<u32>>> + Send;
+
+    /// UID FETCH header data for a UID set
+    fn uid_fetch(
+        &mut self,
+        uid_set: &str,
+        query: &str,
+    ) -> impl Future<Output = Result<Vec<FetchedMessage>>> + Send;
+
+    /// UID COPY messages to another mailbox
+    fn uid_copy(&mut self, uid_set: &str, mailbox: &str)
+        -> impl Future<Output = Result<()>> + Send;
+
+    /// UID STORE flags or labels on messages
+    fn uid_store(&mut self, uid_set: &str, query: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// UID MOVE messages to another mailbox (RFC 6851)
+    fn uid_move(&mut self, uid_set: &str, mailbox: &str)
+        -> impl Future<Output = Result<()>> + Send;
+
+    /// Whether the server advertises the MOVE capability
+    fn supports_move(&mut self) -> impl Future<Output = bool> + Send;
+
+    /// EXPUNGE messages flagged `\Deleted` from the selected mailbox
+    fn expunge(&mut self) -> impl Future<Output = Result<()>> + Send;
+
+    /// NOOP, to check that the connection is still alive
+    fn noop(&mut self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Replace a dropped connection with a new one
+    ///
+    /// A bare session has no credentials to log in again, so the default does
+    /// nothing; see [`super::connection::ReconnectingSession`].
+    fn reconnect(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl MailboxOps for ImapSession {
+    async fn select(&mut self, mailbox: &str) -> Result<Mailbox> {
+        Ok(async_imap::Session::select(self, mailbox).await?)
+    }
+
+    async fn uid_search(&mut self, query: &str) -> Result<Vec<u32>> {
+        Ok(async_imap::Session::uid_search(self, query)
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    async fn uid_fetch(&mut self, uid_set: &str, query: &str) -> Result<Vec<FetchedMessage>> {
+        let with_flags = query.contains("FLAGS");
+        let mut stream = async_imap::Session::uid_fetch(self, uid_set, query).await?;
+        let mut messages = Vec::new();
+
+        // Use try_next() instead of next() to properly handle stream termination
+        while let Some(msg) = stream.try_next().await? {
+            // BODY.PEEK[HEADER…] responses are exposed via header(), not body()
+            messages.push(FetchedMessage {
+                uid: msg.uid,
+                header: msg.header().map(<[u8]>::to_vec),
+                seen: with_flags.then(|| msg.flags().any(|flag| flag == Flag::Seen)),
+            });
+        }
+
+        Ok(messages)
+    }
+
+    async fn uid_copy(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+        Ok(async_imap::Session::uid_copy(self, uid_set, mailbox).await?)
+    }
+
+    async fn uid_store(&mut self, uid_set: &str, query: &str) -> Result<()> {
+        let _: Vec<_> = async_imap::Session::uid_store(self, uid_set, query)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(())
+    }
+
+    async fn uid_move(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+        Ok(async_imap::Session::uid_mv(self, uid_set, mailbox).await?)
+    }
+
+    async fn supports_move(&mut self) -> bool {
+        match async_imap::Session::capabilities(self).await {
+            Ok(capabilities) => capabilities.has_str("MOVE"),
+            Err(e) => {
+                tracing::debug!(error = %e, "CAPABILITY failed, assuming no MOVE");
+                false
+            }
+        }
+    }
+
+    async fn expunge(&mut self) -> Result<()> {
+        let _: Vec<_> = async_imap::Session::expunge(self)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(())
+    }
+
+    async fn noop(&mut self) -> Result<()> {
+        Ok(async_imap::Session::noop(self).await?)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// In-memory mailbox that records each command as IMAP-like text
+    #[derive(Debug, Default)]
+    pub(crate) struct MockMailbox {
+        /// Commands issued so far, e.g. `UID COPY 1:3 [Gmail]/Trash`
+        pub commands: Vec<String>,
+
+        /// Raw headers returned by FETCH and matched by SEARCH, keyed by UID
+        pub messages: BTreeMap<u32, Vec<u8>>,
+
+        /// UIDs reported as `\Seen` when FLAGS is fetched
+        pub seen: BTreeSet<u32>,
+
+        /// UIDVALIDITY reported on SELECT
+        pub uid_validity: Option<u32>,
+
+        /// Number of initial SEARCH commands answered with no UIDs
+        pub empty_searches: usize,
+
+        /// Whether the server has closed the connection (NOOP fails)
+        pub dropped: bool,
+
+        /// Whether the server advertises MOVE
+        pub move_capable: bool,
+
+        /// Command prefixes that drop the connection once, e.g. `UID STORE`
+        ///
+        /// The command is recorded (the server ran it) but fails as if the
+        /// connection was lost before the response arrived.
+        pub fail_once: Vec<String>,
+    }
+
+    impl MockMailbox {
+        pub(crate) fn with_messages(messages: &[(u32, &str)]) -> Self {
+            Self {
+                messages: messages
+                    .iter()
+                    .map(|(uid, raw)| (*uid, raw.as_bytes().to_vec()))
+                    .collect(),
+                ..Self::default()
+            }
+        }
+
+        /// Record `command`, failing it if it matches [`Self::fail_once`]
+        fn run(&mut self, command: String) -> Result<()> {
+            let failing = self
+                .fail_once
+                .iter()
+                .position(|prefix| command.starts_with(prefix.as_str()));
+            self.commands.push(command);
+            if let Some(index) = failing {
+                self.fail_once.remove(index);
+                self.dropped = true;
+                return Err(async_imap::error::Error::ConnectionLost.into());
+            }
+            Ok(())
+        }
+
+        fn uids_in(&self, uid_set: &str) -> Vec<u32> {
+            uid_set
+                .split(',')
+                .flat_map(|part| match part.split_once(':') {
+                    Some((start, end)) => {
+                        let (start, end): (u32, u32) =
+                            (start.parse().unwrap(), end.parse().unwrap());
+                        (start..=end).collect::<Vec<_>>()
+                    }
+                    None => vec![part.parse().unwrap()],
+                })
+                .collect()
+        }
+    }
+
+    impl MailboxOps for MockMailbox {
+        async fn select(&mut self, mailbox: &str) -> Result<Mailbox> {
+            self.commands.push(format!("SELECT {}", mailbox));
+            Ok(Mailbox {
+                exists: self.messages.len() as u32,
+                uid_validity: self.uid_validity,
+                ..Mailbox::default()
+            })
+        }
+
+        async fn uid_search(&mut self, query: &str) -> Result<Vec<u32>> {
+            self.commands.push(format!("UID SEARCH {}", query));
+            if self.empty_searches > 0 {
+                self.empty_searches -= 1;
+                return Ok(vec![]);
+            }
+            // `UID <set>` matches the listed messages that exist
+            if let Some(uid_set) = query.strip_prefix("UID ") {
+                return Ok(self
+                    .uids_in(uid_set)
+                    .into_iter()
+                    .filter(|uid| self.messages.contains_key(uid))
+                    .collect());
+            }
+            Ok(self.messages.keys().copied().collect())
+        }
+
+        async fn uid_fetch(&mut self, uid_set: &str, query: &str) -> Result<Vec<FetchedMessage>> {
+            self.commands
+                .push(format!("UID FETCH {} {}", uid_set, query));
+            let with_flags = query.contains("FLAGS");
+            Ok(self
+                .uids_in(uid_set)
+                .into_iter()
+                .filter_map(|uid| {
+                    self.messages.get(&uid).map(|raw| FetchedMessage {
+                        uid: Some(uid),
+                        header: Some(raw.clone()),
+                        seen: with_flags.then(|| self.seen.contains(&uid)),
+                    })
+                })
+                .collect())
+        }
+
+        async fn uid_copy(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+            self.run(format!("UID COPY {} {}", uid_set, mailbox))
+        }
+
+        async fn uid_store(&mut self, uid_set: &str, query: &str) -> Result<()> {
+            self.run(format!("UID STORE {} {}", uid_set, query))
+        }
+
+        async fn uid_move(&mut self, uid_set: &str, mailbox: &str) -> Result<()> {
+            self.run(format!("UID MOVE {} {}", uid_set, mailbox))
+        }
+
+        async fn supports_move(&mut self) -> bool {
+            self.move_capable
+        }
+
+        async fn expunge(&mut self) -> Result<()> {
+            self.run("EXPUNGE".to_string())
+        }
+
+        async fn noop(&mut self) -> Result<()> {
+            self.commands.push("NOOP".to_string());
+            if self.dropped {
+                anyhow::bail!("Broken pipe (os error 32)");
+            }
+            Ok(())
+        }
+
+        async fn reconnect(&mut self) -> Result<()> {
+            self.commands.push("RECONNECT".to_string());
+            self.dropped = false;
+            Ok(())
+        }
+    }
+}
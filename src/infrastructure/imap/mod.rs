@@ -4,3 +4,6 @@ pub mod actions;
 pub mod auth;
 pub mod connection;
 pub mod fetch;
+pub mod folders;
+pub mod mailbox;
+pub mod retry;
@@ -0,0 +1,205 @@
+//! Retry transient IMAP failures with exponential backoff
+
+use super::connection::is_dropped;
+use super::mailbox::MailboxOps;
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::time::Duration;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// How often and how patiently to retry a failed IMAP operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled after each further failure
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after the given (1-based) failed attempt
+    fn delay_after(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+/// Run `op` against `session`, retrying transient failures
+///
+/// Permanent errors (e.g. a NO response for a missing folder) are returned
+/// immediately. After the last attempt the final error is returned. Before
+/// each retry, a session the server has dropped is reconnected (see
+/// [`MailboxOps::reconnect`]), so `op` must select its own mailbox. `op` is
+/// run again in full, so keep it to one command that is safe to repeat.
+pub async fn retry<S, T, F>(policy: &RetryPolicy, session: &mut S, mut op: F) -> Result<T>
+where
+    S: MailboxOps,
+    F: for<'a> FnMut(&'a mut S) -> BoxFuture<'a, Result<T>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        match op(session).await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = policy.delay_after(attempt);
+                tracing::warn!(
+                    attempt,
+                    max_attempts = policy.max_attempts,
+                    delay = ?delay,
+                    error = %format_args!("{:#}", e),
+                    "IMAP attempt failed, retrying"
+                );
+                tokio::time::sleep(delay).await;
+                if is_dropped(session).await {
+                    session
+                        .reconnect()
+                        .await
+                        .context("Failed to reconnect before retrying")?;
+                }
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether an error is worth retrying (connection drops, timeouts)
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<async_imap::error::Error>() {
+            return matches!(
+                e,
+                async_imap::error::Error::Io(_) | async_imap::error::Error::ConnectionLost
+            );
+        }
+
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+                    | std::io::ErrorKind::Interrupted
+            );
+        }
+
+        cause.is::<tokio::time::error::Elapsed>()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::imap::mailbox::mock::MockMailbox;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_transient_error() {
+        let attempts = AtomicU32::new(0);
+        let mut session = MockMailbox::default();
+
+        let result = retry(&fast_policy(), &mut session, |_| {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move {
+                if attempt == 1 {
+                    Err(async_imap::error::Error::ConnectionLost).context("Failed to copy messages")
+                } else {
+                    Ok(42)
+                }
+            })
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.into_inner(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_reconnects_dropped_session() {
+        let mut session = MockMailbox {
+            fail_once: vec!["UID COPY".to_string()],
+            ..MockMailbox::default()
+        };
+
+        retry(&fast_policy(), &mut session, |session| {
+            Box::pin(async move { session.uid_copy("1", "[Gmail]/Trash").await })
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            session.commands,
+            [
+                "UID COPY 1 [Gmail]/Trash",
+                "NOOP",
+                "RECONNECT",
+                "UID COPY 1 [Gmail]/Trash"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_permanent_error() {
+        let attempts = AtomicU32::new(0);
+        let mut session = MockMailbox::default();
+
+        let result: Result<()> = retry(&fast_policy(), &mut session, |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                Err(async_imap::error::Error::No("[TRYCREATE] No such folder".into()).into())
+            })
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.into_inner(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let attempts = AtomicU32::new(0);
+        let mut session = MockMailbox::default();
+
+        let result: Result<()> = retry(&fast_policy(), &mut session, |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into()) })
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.into_inner(), 3);
+    }
+
+    #[test]
+    fn test_delay_doubles() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+        };
+
+        assert_eq!(policy.delay_after(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_after(3), Duration::from_millis(400));
+    }
+}
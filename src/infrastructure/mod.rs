@@ -16,8 +16,9 @@
 //! # Modules
 //!
 //! - `imap`: IMAP client for Gmail (connection, authentication, message operations)
-//! - `storage`: Data persistence (keyring for tokens, JSON for metadata)
-//! - `network`: HTTP client for one-click unsubscribe operations
+//! - `storage`: Data persistence (keyring for tokens, JSON for metadata, scan cache)
+//! - `network`: HTTP client for one-click unsubscribe and Gmail filters
+//! - `browser`: Opening sign-in and unsubscribe pages in the user's browser
 //!
 //! # Design Principles
 //!
@@ -26,6 +27,7 @@
 //! - **Async/Await**: All I/O operations are asynchronous
 //! - **Testability**: Support mock implementations for testing
 
+pub mod browser;
 pub mod imap;
 pub mod network;
 pub mod storage;
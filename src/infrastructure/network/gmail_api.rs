@@ -0,0 +1,570 @@
+//! Read-only header scan via the Gmail REST API
+//!
+//! The `gmail.readonly` scope doesn't grant IMAP access, so scan-only
+//! accounts list messages with `users.messages.list` and read their headers
+//! with `users.messages.get?format=metadata`. Gmail API message ids are not
+//! IMAP UIDs: headers get sequential placeholder UIDs and can't be used for
+//! cleanup.
+
+use super::http_client::{backoff_delay, is_retryable_status};
+use crate::infrastructure::imap::fetch::{self, FetchOptions, MessageHeader};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use futures::{StreamExt, TryStreamExt};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+const MESSAGES_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/messages";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest page `users.messages.list` returns
+const LIST_PAGE_SIZE: usize = 500;
+
+/// Metadata requests in flight at once
+const FETCH_CONCURRENCY: usize = 10;
+
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// How hard to push the Gmail API while reading headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GmailFetchConfig {
+    /// Metadata requests in flight at once
+    ///
+    /// Lower it if a restricted OAuth project runs into its quota.
+    pub concurrency: usize,
+
+    /// Attempts per request; 429, 5xx and network errors are retried
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled (plus jitter) after each failure
+    ///
+    /// A `Retry-After` header from Gmail takes precedence.
+    pub retry_base_delay: Duration,
+}
+
+impl Default for GmailFetchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: FETCH_CONCURRENCY,
+            max_attempts: MAX_ATTEMPTS,
+            retry_base_delay: RETRY_BASE_DELAY,
+        }
+    }
+}
+
+/// Headers requested for each message, the same ones the IMAP scan reads
+const METADATA_HEADERS: &[&str] = &[
+    "From",
+    "Subject",
+    "Date",
+    "List-Unsubscribe",
+    "List-Unsubscribe-Post",
+    "List-Id",
+    "Precedence",
+    "Auto-Submitted",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageList {
+    #[serde(default)]
+    messages: Vec<MessageRef>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageRef {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageMetadata {
+    #[serde(default)]
+    label_ids: Vec<String>,
+    #[serde(default)]
+    payload: Payload,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Payload {
+    #[serde(default)]
+    headers: Vec<HeaderField>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeaderField {
+    name: String,
+    value: String,
+}
+
+/// Reads message headers for an account authorized with `gmail.readonly`
+pub struct GmailApiClient {
+    client: Client,
+    access_token: String,
+    url: String,
+    fetch_config: GmailFetchConfig,
+}
+
+impl GmailApiClient {
+    /// Create a client using an OAuth2 access token
+    pub fn new(access_token: impl Into<String>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            access_token: access_token.into(),
+            url: MESSAGES_URL.to_string(),
+            fetch_config: GmailFetchConfig::default(),
+        })
+    }
+
+    /// Use `fetch_config` for concurrency and retries
+    pub fn with_fetch_config(mut self, fetch_config: GmailFetchConfig) -> Self {
+        self.fetch_config = fetch_config;
+        self
+    }
+
+    /// Client talking to a test server at `url` instead of Gmail
+    #[cfg(test)]
+    fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Send a GET, retrying 429/5xx responses and network errors
+    ///
+    /// Waits as long as a `Retry-After` header asks, otherwise backs off
+    /// exponentially. Returns the last response once it is not retryable or
+    /// the attempts are exhausted.
+    async fn get_with_retry(&self, url: &str, query: &[(&str, String)]) -> Result<Response> {
+        let max_attempts = self.fetch_config.max_attempts.max(1);
+        let mut attempt = 1;
+
+        loop {
+            let result = self
+                .client
+                .get(url)
+                .bearer_auth(&self.access_token)
+                .query(query)
+                .send()
+                .await;
+            let retryable = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            };
+
+            if !retryable || attempt >= max_attempts {
+                return Ok(result?);
+            }
+
+            let delay = result
+                .as_ref()
+                .ok()
+                .and_then(retry_after)
+                .unwrap_or_else(|| backoff_delay(self.fetch_config.retry_base_delay, attempt));
+            match &result {
+                Ok(response) => tracing::debug!(
+                    status = %response.status(),
+                    delay = ?delay,
+                    "gmail api returned an error status, retrying"
+                ),
+                Err(e) => tracing::debug!(
+                    delay = ?delay,
+                    error = %e,
+                    "gmail api request failed, retrying"
+                ),
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Ids of the messages in the IMAP `mailbox`, newest first
+    ///
+    /// Stops after `max` ids when set, and skips messages received before
+    /// `since`. See [`list_params`] for how mailboxes map to Gmail labels.
+    pub async fn list_message_ids(
+        &self,
+        mailbox: &str,
+        max: Option<usize>,
+        since: Option<NaiveDate>,
+    ) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = list_params(mailbox, since);
+            query.push(("maxResults", LIST_PAGE_SIZE.to_string()));
+            if let Some(token) = &page_token {
+                query.push(("pageToken", token.clone()));
+            }
+
+            let response = self
+                .get_with_retry(&self.url, &query)
+                .await
+                .context("Failed to send message list request")?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                bail!("Gmail refused to list messages ({}): {}", status, body);
+            }
+
+            let page: MessageList = response
+                .json()
+                .await
+                .context("Failed to parse message list")?;
+            ids.extend(page.messages.into_iter().map(|m| m.id));
+
+            if max.is_some_and(|max| ids.len() >= max) {
+                ids.truncate(max.unwrap_or_default());
+                break;
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Headers of one message, tagged with the placeholder `uid`
+    async fn get_header(&self, id: &str, uid: u32) -> Result<Option<MessageHeader>> {
+        let mut query = vec![("format", "metadata".to_string())];
+        query.extend(
+            METADATA_HEADERS
+                .iter()
+                .map(|h| ("metadataHeaders", h.to_string())),
+        );
+
+        let response = self
+            .get_with_retry(&format!("{}/{}", self.url, id), &query)
+            .await
+            .context("Failed to send message request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                "Gmail refused to read message {} ({}): {}",
+                id,
+                status,
+                body
+            );
+        }
+
+        let metadata: MessageMetadata = response
+            .json()
+            .await
+            .context("Failed to parse message metadata")?;
+
+        Ok(header_from_metadata(uid, &metadata))
+    }
+
+    /// Fetch the headers of every message in the IMAP `mailbox`
+    ///
+    /// `on_progress` receives the number of messages read so far and the
+    /// total. Reading stops early once `options.time_budget` is spent; the
+    /// API lists newest messages first, so those are the ones kept.
+    pub async fn fetch_headers<F>(
+        &self,
+        mailbox: &str,
+        options: &FetchOptions,
+        mut on_progress: F,
+    ) -> Result<Vec<MessageHeader>>
+    where
+        F: FnMut(usize, usize),
+    {
+        let ids = self
+            .list_message_ids(mailbox, options.max_messages, options.since)
+            .await?;
+        let started = Instant::now();
+        let total = ids.len();
+
+        tracing::info!(total, "reading headers via the Gmail API");
+        on_progress(0, total);
+
+        let mut requests = futures::stream::iter(ids.iter().enumerate())
+            .map(|(i, id)| self.get_header(id, i as u32 + 1))
+            .buffer_unordered(self.fetch_config.concurrency.max(1));
+
+        let mut headers = Vec::with_capacity(total);
+        let mut done = 0;
+        while let Some(header) = requests.try_next().await? {
+            done += 1;
+            headers.extend(header);
+            on_progress(done, total);
+
+            if done < total && options.budget_spent(started) {
+                tracing::info!(done, total, "scan time budget reached, stopping");
+                break;
+            }
+        }
+
+        headers.sort_unstable_by_key(|h| h.uid);
+        Ok(headers)
+    }
+}
+
+/// Delay asked for by a `Retry-After: <seconds>` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Parse metadata headers the same way as an IMAP header block
+fn header_from_metadata(uid: u32, metadata: &MessageMetadata) -> Option<MessageHeader> {
+    let raw: String = metadata
+        .payload
+        .headers
+        .iter()
+        .map(|h| format!("{}: {}\r\n", h.name, h.value))
+        .chain(std::iter::once("\r\n".to_string()))
+        .collect();
+
+    match fetch::parse_message_header(uid, raw.as_bytes()) {
+        Ok(mut header) => {
+            header.seen = Some(!metadata.label_ids.iter().any(|l| l == "UNREAD"));
+            Some(header)
+        }
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "failed to parse message headers");
+            None
+        }
+    }
+}
+
+/// `users.messages.list` parameters selecting the IMAP `mailbox`
+///
+/// INBOX and Gmail's special folders map to their system label ids and All
+/// Mail to no label at all. `labelIds` only takes ids, so user labels are
+/// matched by name with a `label:` search instead.
+fn list_params(mailbox: &str, since: Option<NaiveDate>) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    let mut search = Vec::new();
+
+    if mailbox == ALL_MAIL {
+        // Every message outside Spam and Trash
+    } else if let Some(label) = system_label(mailbox) {
+        params.push(("labelIds", label.to_string()));
+        if label == "SPAM" || label == "TRASH" {
+            params.push(("includeSpamTrash", "true".to_string()));
+        }
+    } else {
+        // Gmail search writes spaces and nesting slashes in label names as `-`
+        search.push(format!("label:{}", mailbox.replace([' ', '/'], "-")));
+    }
+
+    if let Some(date) = since {
+        search.push(format!("after:{}", date.format("%Y/%m/%d")));
+    }
+    if !search.is_empty() {
+        params.push(("q", search.join(" ")));
+    }
+    params
+}
+
+/// Gmail's IMAP name for the folder holding every message
+const ALL_MAIL: &str = "[Gmail]/All Mail";
+
+/// System label id of INBOX or one of Gmail's `[Gmail]/` folders
+fn system_label(mailbox: &str) -> Option<&'static str> {
+    if mailbox.eq_ignore_ascii_case("INBOX") {
+        return Some("INBOX");
+    }
+    match mailbox.strip_prefix("[Gmail]/")? {
+        "Sent Mail" => Some("SENT"),
+        "Spam" => Some("SPAM"),
+        "Trash" => Some("TRASH"),
+        "Starred" => Some("STARRED"),
+        "Important" => Some("IMPORTANT"),
+        "Drafts" => Some("DRAFT"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Requests in flight right now and at most, plus the requests served
+    #[derive(Default)]
+    struct ApiStats {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        served: AtomicUsize,
+    }
+
+    /// Serve a list of three messages; the first read of `m1` is rate limited
+    async fn spawn_messages_api() -> (String, Arc<ApiStats>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/messages", listener.local_addr().unwrap());
+        let stats = Arc::new(ApiStats::default());
+        let limited = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let server_stats = Arc::clone(&stats);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let stats = Arc::clone(&server_stats);
+                let limited = Arc::clone(&limited);
+                tokio::spawn(async move {
+                    let now = stats.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    stats.max_in_flight.fetch_max(now, Ordering::SeqCst);
+
+                    let mut buf = [0u8; 4096];
+                    let n = stream.read(&mut buf).await.unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+                    // Give concurrent requests a chance to overlap
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+
+                    let (status, extra, body) = if path.starts_with("/messages?") {
+                        (
+                            "200 OK",
+                            "",
+                            r#"{"messages": [{"id": "m1"}, {"id": "m2"}, {"id": "m3"}]}"#
+                                .to_string(),
+                        )
+                    } else if path.starts_with("/messages/m1?")
+                        && !limited.swap(true, Ordering::SeqCst)
+                    {
+                        (
+                            "429 Too Many Requests",
+                            "Retry-After: 0\r\n",
+                            "{}".to_string(),
+                        )
+                    } else {
+                        (
+                            "200 OK",
+                            "",
+                            r#"{"labelIds": ["INBOX"], "payload": {"headers": [{"name": "From", "value": "news@example.com"}]}}"#
+                                .to_string(),
+                        )
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        extra,
+                        body.len(),
+                        body
+                    );
+                    stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    stats.served.fetch_add(1, Ordering::SeqCst);
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        (url, stats)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_headers_retries_rate_limit_one_at_a_time() {
+        let (url, stats) = spawn_messages_api().await;
+        let client = GmailApiClient::new("token")
+            .unwrap()
+            .with_url(url)
+            .with_fetch_config(GmailFetchConfig {
+                concurrency: 1,
+                ..GmailFetchConfig::default()
+            });
+
+        let headers = client
+            .fetch_headers("INBOX", &FetchOptions::default(), |_, _| {})
+            .await
+            .unwrap();
+
+        assert_eq!(headers.len(), 3);
+        // One list request, three reads and one retried read
+        assert_eq!(stats.served.load(Ordering::SeqCst), 5);
+        assert_eq!(stats.max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_list_params_select_mailbox() {
+        let since = NaiveDate::from_ymd_opt(2024, 1, 5);
+        let params = |mailbox: &str, since: Option<NaiveDate>| -> Vec<(&str, String)> {
+            list_params(mailbox, since)
+        };
+
+        assert_eq!(
+            params("INBOX", None),
+            vec![("labelIds", "INBOX".to_string())]
+        );
+        assert_eq!(
+            params("[Gmail]/Spam", None),
+            vec![
+                ("labelIds", "SPAM".to_string()),
+                ("includeSpamTrash", "true".to_string())
+            ]
+        );
+        assert_eq!(
+            params("[Gmail]/All Mail", since),
+            vec![("q", "after:2024/01/05".to_string())]
+        );
+        assert_eq!(
+            params("Newsletters/Tech Weekly", since),
+            vec![(
+                "q",
+                "label:Newsletters-Tech-Weekly after:2024/01/05".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_header_from_metadata() {
+        let metadata: MessageMetadata = serde_json::from_value(serde_json::json!({
+            "id": "18c2f",
+            "labelIds": ["INBOX", "UNREAD"],
+            "payload": {
+                "headers": [
+                    { "name": "From", "value": "Shop <deals@shop.com>" },
+                    { "name": "Subject", "value": "=?UTF-8?Q?Soldes_d=C3=A9but?=" },
+                    { "name": "List-Unsubscribe", "value": "<https://shop.com/u>" },
+                    { "name": "List-Unsubscribe-Post", "value": "List-Unsubscribe=One-Click" }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let header = header_from_metadata(3, &metadata).unwrap();
+
+        assert_eq!(header.uid, 3);
+        assert_eq!(header.from, "Shop <deals@shop.com>");
+        assert_eq!(header.subject, "Soldes début");
+        assert_eq!(
+            header.list_unsubscribe.as_deref(),
+            Some("<https://shop.com/u>")
+        );
+        assert!(header.list_unsubscribe_post.is_some());
+        assert_eq!(header.seen, Some(false));
+    }
+
+    #[test]
+    fn test_message_list_without_messages() {
+        let list: MessageList = serde_json::from_str(r#"{"resultSizeEstimate": 0}"#).unwrap();
+
+        assert!(list.messages.is_empty());
+        assert!(list.next_page_token.is_none());
+    }
+}
@@ -0,0 +1,386 @@
+//! Gmail filters via the Gmail REST API
+//!
+//! IMAP has no notion of filters, so blocking future mail from a sender goes
+//! through `users.settings.filters`. The token must have been granted the
+//! `gmail.settings.basic` scope ([`AccessScope::FullWithFilters`]).
+//!
+//! [`AccessScope::FullWithFilters`]: crate::domain::models::AccessScope::FullWithFilters
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const FILTERS_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/settings/filters";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages a filter applies to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterCriteria {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
+/// What a filter does to matching messages
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterAction {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub add_label_ids: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_label_ids: Option<Vec<String>>,
+}
+
+impl FilterAction {
+    /// Send matching messages to Trash
+    pub fn trash() -> Self {
+        Self {
+            add_label_ids: Some(vec!["TRASH".into()]),
+            remove_label_ids: Some(vec!["INBOX".into()]),
+        }
+    }
+
+    /// Send matching messages to Spam
+    pub fn spam() -> Self {
+        Self {
+            add_label_ids: Some(vec!["SPAM".into()]),
+            remove_label_ids: Some(vec!["INBOX".into()]),
+        }
+    }
+}
+
+impl FilterAction {
+    /// Whether both actions add and remove the same labels, in any order
+    fn same_labels(&self, other: &Self) -> bool {
+        fn sorted(labels: &Option<Vec<String>>) -> Vec<&str> {
+            let mut labels: Vec<&str> = labels.iter().flatten().map(String::as_str).collect();
+            labels.sort_unstable();
+            labels
+        }
+
+        sorted(&self.add_label_ids) == sorted(&other.add_label_ids)
+            && sorted(&self.remove_label_ids) == sorted(&other.remove_label_ids)
+    }
+}
+
+/// Gmail filter resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub criteria: FilterCriteria,
+    pub action: FilterAction,
+}
+
+/// Response of `users.settings.filters.list` (the key is absent when empty)
+#[derive(Debug, Default, Deserialize)]
+struct FilterList {
+    #[serde(default)]
+    filter: Vec<Filter>,
+}
+
+/// Creates Gmail filters for an authenticated account
+pub struct FilterManager {
+    client: Client,
+    access_token: String,
+    url: String,
+}
+
+impl FilterManager {
+    /// Create a filter manager using an OAuth2 access token
+    pub fn new(access_token: impl Into<String>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            access_token: access_token.into(),
+            url: FILTERS_URL.to_string(),
+        })
+    }
+
+    /// Send requests to `url` instead of the Gmail API
+    #[cfg(test)]
+    pub(crate) fn with_url(access_token: &str, url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            access_token: access_token.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    /// Create a filter that trashes all future mail from `sender`
+    ///
+    /// Returns the created filter id.
+    pub async fn create_trash_filter(&self, sender: &str) -> Result<String> {
+        self.create_filter(sender_filter(sender, FilterAction::trash()))
+            .await
+    }
+
+    /// Trash all future mail from `sender`, reusing an identical filter
+    ///
+    /// Returns the id of the existing filter if one already matches the same
+    /// sender with the same action, otherwise the id of a newly created one.
+    pub async fn ensure_trash_filter(&self, sender: &str) -> Result<String> {
+        let wanted = sender_filter(sender, FilterAction::trash());
+        let existing = self.list_filters().await?;
+
+        if let Some(id) = find_existing(&existing, &wanted) {
+            tracing::debug!(filter = %id, sender = %sender, "reusing trash filter");
+            return Ok(id.to_string());
+        }
+
+        self.create_filter(wanted).await
+    }
+
+    /// Create a filter that sends all future mail from `sender` to spam
+    ///
+    /// Returns the created filter id.
+    pub async fn create_spam_filter(&self, sender: &str) -> Result<String> {
+        self.create_filter(sender_filter(sender, FilterAction::spam()))
+            .await
+    }
+
+    /// List the account's existing filters
+    pub async fn list_filters(&self) -> Result<Vec<Filter>> {
+        let response = self
+            .client
+            .get(&self.url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .context("Failed to send filter list request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("Gmail refused to list filters ({}): {}", status, body);
+        }
+
+        let list: FilterList = response
+            .json()
+            .await
+            .context("Failed to parse filter list")?;
+
+        Ok(list.filter)
+    }
+
+    async fn create_filter(&self, filter: Filter) -> Result<String> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.access_token)
+            .json(&filter)
+            .send()
+            .await
+            .context("Failed to send filter request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("Gmail rejected filter ({}): {}", status, body);
+        }
+
+        let created: Filter = response
+            .json()
+            .await
+            .context("Failed to parse created filter")?;
+
+        created.id.context("Gmail returned a filter without an id")
+    }
+}
+
+/// Build a filter matching a single sender
+fn sender_filter(sender: &str, action: FilterAction) -> Filter {
+    Filter {
+        id: None,
+        criteria: FilterCriteria {
+            from: Some(sender.to_string()),
+        },
+        action,
+    }
+}
+
+/// Id of an existing filter with the same sender and action as `wanted`
+///
+/// Addresses are compared case-insensitively; label order doesn't matter.
+fn find_existing<'a>(existing: &'a [Filter], wanted: &Filter) -> Option<&'a str> {
+    let from = wanted.criteria.from.as_deref()?;
+
+    existing
+        .iter()
+        .filter(|filter| {
+            filter
+                .criteria
+                .from
+                .as_deref()
+                .is_some_and(|f| f.eq_ignore_ascii_case(from))
+                && filter.action.same_labels(&wanted.action)
+        })
+        .find_map(|filter| filter.id.as_deref())
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve one canned JSON response per connection, recording each request
+    pub(crate) async fn spawn_filters_api(
+        bodies: Vec<&'static str>,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/filters", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for body in bodies {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = String::new();
+                let mut buf = [0u8; 4096];
+
+                // The POST body may arrive after the headers
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.push_str(&String::from_utf8_lossy(&buf[..n]));
+                    let complete = request.split_once("\r\n\r\n").is_some_and(|(head, body)| {
+                        let length = head
+                            .lines()
+                            .find_map(|l| {
+                                l.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        body.len() >= length
+                    });
+                    if complete || n == 0 {
+                        break;
+                    }
+                }
+                requests.push(request);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::spawn_filters_api;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ensure_trash_filter_creates_missing_filter() {
+        let (url, server) = spawn_filters_api(vec![
+            "{}",
+            r#"{"id": "new-1", "criteria": {"from": "news@example.com"}, "action": {}}"#,
+        ])
+        .await;
+
+        let manager = FilterManager::with_url("token", &url);
+        let id = manager
+            .ensure_trash_filter("news@example.com")
+            .await
+            .unwrap();
+
+        assert_eq!(id, "new-1");
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /filters HTTP/1.1"));
+        assert!(requests[0]
+            .to_ascii_lowercase()
+            .contains("authorization: bearer token"));
+        assert!(requests[1].starts_with("POST /filters HTTP/1.1"));
+        assert!(requests[1].contains(r#""from":"news@example.com""#));
+        assert!(requests[1].contains(r#""addLabelIds":["TRASH"]"#));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_trash_filter_reuses_existing_filter() {
+        let (url, server) = spawn_filters_api(vec![
+            r#"{"filter": [{"id": "trash-1", "criteria": {"from": "news@example.com"},
+                "action": {"addLabelIds": ["TRASH"], "removeLabelIds": ["INBOX"]}}]}"#,
+        ])
+        .await;
+
+        let manager = FilterManager::with_url("token", &url);
+        let id = manager
+            .ensure_trash_filter("news@example.com")
+            .await
+            .unwrap();
+
+        assert_eq!(id, "trash-1");
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_trash_filter_action() {
+        let filter = sender_filter("news@example.com", FilterAction::trash());
+
+        assert_eq!(filter.criteria.from.as_deref(), Some("news@example.com"));
+        assert_eq!(filter.action.add_label_ids, Some(vec!["TRASH".to_string()]));
+    }
+
+    #[test]
+    fn test_spam_filter_action() {
+        let filter = sender_filter("news@example.com", FilterAction::spam());
+
+        assert_eq!(filter.action.add_label_ids, Some(vec!["SPAM".to_string()]));
+    }
+
+    #[test]
+    fn test_filter_serializes_camel_case() {
+        let filter = sender_filter("news@example.com", FilterAction::trash());
+        let json = serde_json::to_value(&filter).unwrap();
+
+        assert_eq!(json["criteria"]["from"], "news@example.com");
+        assert_eq!(json["action"]["addLabelIds"][0], "TRASH");
+        assert!(json.get("id").is_none());
+    }
+
+    #[test]
+    fn test_find_existing_dedupes_same_sender_and_action() {
+        let existing: FilterList = serde_json::from_value(serde_json::json!({
+            "filter": [
+                {
+                    "id": "spam-1",
+                    "criteria": { "from": "news@example.com" },
+                    "action": { "addLabelIds": ["SPAM"], "removeLabelIds": ["INBOX"] }
+                },
+                {
+                    "id": "trash-1",
+                    "criteria": { "from": "News@Example.com" },
+                    "action": { "removeLabelIds": ["INBOX"], "addLabelIds": ["TRASH"] }
+                },
+                {
+                    "id": "trash-2",
+                    "criteria": { "from": "other@example.com" },
+                    "action": { "addLabelIds": ["TRASH"], "removeLabelIds": ["INBOX"] }
+                }
+            ]
+        }))
+        .unwrap();
+
+        let wanted = sender_filter("news@example.com", FilterAction::trash());
+        assert_eq!(find_existing(&existing.filter, &wanted), Some("trash-1"));
+
+        let wanted = sender_filter("new@example.com", FilterAction::trash());
+        assert_eq!(find_existing(&existing.filter, &wanted), None);
+
+        let empty: FilterList = serde_json::from_str("{}").unwrap();
+        let wanted = sender_filter("news@example.com", FilterAction::trash());
+        assert_eq!(find_existing(&empty.filter, &wanted), None);
+    }
+}
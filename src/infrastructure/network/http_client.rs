@@ -1,40 +1,467 @@
 //! HTTP client for one-click unsubscribe
+//!
+//! [`HttpClient`] owns one configured `reqwest::Client`, so a run's requests
+//! share its connection pool.
 
+use crate::domain::models::UnsubscribeVia;
 use anyhow::{bail, Context, Result};
-use reqwest::Client;
-use std::time::Duration;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method, StatusCode};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
 use url::Url;
 
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REDIRECTS: usize = 5;
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const REQUEST_DELAY: Duration = Duration::from_millis(500);
+const HOST_DELAY: Duration = Duration::from_secs(2);
 
-/// Perform one-click unsubscribe via HTTP POST
+/// POST body required by RFC 8058 for one-click unsubscribe
+const ONE_CLICK_BODY: &str = "List-Unsubscribe=One-Click";
+
+/// Options for one-click unsubscribe requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsubscribeOptions {
+    /// Total time allowed per request
+    pub timeout: Duration,
+
+    /// Custom User-Agent (some endpoints block the reqwest default)
+    pub user_agent: Option<String>,
+
+    /// Follow up to 5 redirects; when disabled, any 3xx counts as success
+    pub follow_redirects: bool,
+
+    /// Attempts per request; 429, 5xx and network errors are retried
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled (plus jitter) after each failure
+    pub retry_base_delay: Duration,
+
+    /// Minimum time between the start of two unsubscribe requests
+    pub request_delay: Duration,
+
+    /// Minimum time between two requests to the same host
+    ///
+    /// Spaces out requests to one ESP further than requests to different
+    /// hosts. Zero leaves only `request_delay`.
+    pub host_delay: Duration,
+}
+
+impl Default for UnsubscribeOptions {
+    fn default() -> Self {
+        Self {
+            timeout: REQUEST_TIMEOUT,
+            user_agent: None,
+            follow_redirects: true,
+            max_attempts: MAX_ATTEMPTS,
+            retry_base_delay: RETRY_BASE_DELAY,
+            request_delay: REQUEST_DELAY,
+            host_delay: HOST_DELAY,
+        }
+    }
+}
+
+/// Spaces out unsubscribe requests so a run doesn't look abusive to WAFs
+///
+/// Each request starts at least `request_delay` after the previous one and
+/// `host_delay` after the previous one to the same host. Slots are reserved
+/// in call order, so concurrent requests queue up instead of bursting.
+#[derive(Debug)]
+pub struct Throttle {
+    request_delay: Duration,
+    host_delay: Duration,
+    schedule: Mutex<Schedule>,
+}
+
+/// Earliest start of the next request, overall and per host
+#[derive(Debug, Default)]
+struct Schedule {
+    next: Option<Instant>,
+    next_by_host: HashMap<String, Instant>,
+}
+
+impl Throttle {
+    /// Throttle using the delays in `options`
+    pub fn new(options: &UnsubscribeOptions) -> Self {
+        Self {
+            request_delay: options.request_delay,
+            host_delay: options.host_delay,
+            schedule: Mutex::new(Schedule::default()),
+        }
+    }
+
+    /// Wait until a request to `host` may start
+    pub async fn wait(&self, host: &str) {
+        let start = self.reserve(host, Instant::now());
+        tokio::time::sleep_until(start).await;
+    }
+
+    /// Reserve the earliest start for a request to `host`, as seen at `now`
+    fn reserve(&self, host: &str, now: Instant) -> Instant {
+        let mut schedule = self.schedule.lock().unwrap_or_else(|e| e.into_inner());
+        let start = [schedule.next, schedule.next_by_host.get(host).copied()]
+            .into_iter()
+            .flatten()
+            .fold(now, Instant::max);
+
+        schedule.next = Some(start + self.request_delay);
+        schedule.next_by_host.insert(
+            host.to_string(),
+            start + self.host_delay.max(self.request_delay),
+        );
+        start
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(&UnsubscribeOptions::default())
+    }
+}
+
+/// HTTP client for unsubscribe requests, reused across a run
+///
+/// Wraps a `reqwest::Client` configured from [`UnsubscribeOptions`] (timeout,
+/// redirect policy, User-Agent). Cloning is cheap and clones share the
+/// connection pool, so repeated requests to one ESP reuse its connection.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    options: UnsubscribeOptions,
+}
+
+impl HttpClient {
+    /// Build a client configured from `options`
+    pub fn new(options: UnsubscribeOptions) -> Result<Self> {
+        Ok(Self {
+            client: build_client(&options)?,
+            options,
+        })
+    }
+
+    /// The options this client was built with
+    pub fn options(&self) -> &UnsubscribeOptions {
+        &self.options
+    }
+
+    /// Perform one-click unsubscribe, returning the method that succeeded
+    ///
+    /// Candidate URLs are tried in order until one succeeds. Redirects are
+    /// followed (up to 5). The POST body carries `List-Unsubscribe=One-Click`
+    /// followed by the URL's own query parameters, for endpoints that expect
+    /// their token echoed in the body. If the POST is rejected with 400 or 405, a
+    /// GET to the same URL is tried since some senders treat it as the
+    /// confirmation. Rate limits (429), server errors (5xx) and network errors
+    /// are retried with backoff. `None` means every endpoint answered but none
+    /// accepted the request.
+    ///
+    /// Security: Only HTTPS URLs are allowed; other candidates are skipped
+    pub async fn unsubscribe_one_click(&self, urls: &[String]) -> Result<Option<UnsubscribeVia>> {
+        let https_urls = https_candidates(urls)?;
+        send_to_candidates(&self.client, &https_urls, &self.options).await
+    }
+
+    /// One-click unsubscribe from many senders concurrently
+    ///
+    /// `targets` pairs a key (usually the sender address) with its candidate
+    /// URLs. At most `concurrency` requests run at once, and at most
+    /// [`MAX_CONCURRENT_PER_HOST`] against the same host; their starts wait on
+    /// `throttle`, which can be shared with other requests of the same run.
+    /// Results are returned in input order.
+    pub async fn unsubscribe_all(
+        &self,
+        targets: &[(String, Vec<String>)],
+        concurrency: usize,
+        throttle: &Throttle,
+    ) -> Vec<(String, Result<Option<UnsubscribeVia>>)> {
+        let candidates: Vec<(String, Result<Vec<&str>>)> = targets
+            .iter()
+            .map(|(key, urls)| (key.clone(), https_candidates(urls)))
+            .collect();
+
+        send_all(
+            &self.client,
+            candidates,
+            concurrency,
+            &self.options,
+            throttle,
+        )
+        .await
+    }
+}
+
+/// Maximum concurrent unsubscribe requests against a single host
+pub const MAX_CONCURRENT_PER_HOST: usize = 2;
+
+/// Send every target's candidates with global and per-host concurrency limits
+async fn send_all(
+    client: &Client,
+    targets: Vec<(String, Result<Vec<&str>>)>,
+    concurrency: usize,
+    options: &UnsubscribeOptions,
+    throttle: &Throttle,
+) -> Vec<(String, Result<Option<UnsubscribeVia>>)> {
+    let global = Semaphore::new(concurrency.max(1));
+
+    let mut hosts: HashMap<String, Semaphore> = HashMap::new();
+    for (_, urls) in &targets {
+        if let Ok(urls) = urls {
+            hosts
+                .entry(host_key(urls))
+                .or_insert_with(|| Semaphore::new(MAX_CONCURRENT_PER_HOST));
+        }
+    }
+
+    let requests = targets.into_iter().map(|(key, urls)| {
+        let global = &global;
+        let hosts = &hosts;
+        async move {
+            let urls = match urls {
+                Ok(urls) => urls,
+                Err(e) => return (key, Err(e)),
+            };
+
+            // Wait for the host first so a busy host doesn't hold global slots
+            let host = host_key(&urls);
+            let _host = hosts[&host].acquire().await;
+            let _permit = global.acquire().await;
+            throttle.wait(&host).await;
+
+            let result = send_to_candidates(client, &urls, options).await;
+            (key, result)
+        }
+    });
+
+    futures::future::join_all(requests).await
+}
+
+/// Host of the first HTTPS candidate URL, lowercased
+///
+/// Identifies the ESP handling the unsubscribe, e.g. for local statistics.
+pub fn unsubscribe_host(urls: &[String]) -> Option<String> {
+    urls.iter()
+        .filter_map(|url| Url::parse(url).ok())
+        .find(|url| url.scheme() == "https")
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+}
+
+/// Whether `url` is a valid HTTPS URL, the only scheme unsubscribe links use
+pub fn is_https_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| url.scheme() == "https")
+}
+
+/// Host used for per-host rate limiting (the first candidate's host)
+fn host_key(urls: &[&str]) -> String {
+    urls.first()
+        .and_then(|url| Url::parse(url).ok())
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_default()
+}
+
+/// Validate candidate URLs and keep only the HTTPS ones
+///
+/// Unparsable and non-HTTPS candidates are skipped; it's an error only when
+/// none remain.
+fn https_candidates(urls: &[String]) -> Result<Vec<&str>> {
+    let mut https_urls = Vec::with_capacity(urls.len());
+    for url in urls {
+        match Url::parse(url) {
+            // Security: Only HTTPS
+            Ok(parsed_url) if parsed_url.scheme() == "https" => https_urls.push(url.as_str()),
+            Ok(_) => tracing::debug!(url = %url, "skipping non-HTTPS unsubscribe URL"),
+            Err(e) => tracing::debug!(url = %url, error = %e, "skipping invalid unsubscribe URL"),
+        }
+    }
+
+    if https_urls.is_empty() {
+        bail!("No valid HTTPS unsubscribe URL");
+    }
+
+    Ok(https_urls)
+}
+
+/// Create the HTTP client for the given options
+fn build_client(options: &UnsubscribeOptions) -> Result<Client> {
+    let redirect = if options.follow_redirects {
+        Policy::limited(MAX_REDIRECTS)
+    } else {
+        Policy::none()
+    };
+
+    let mut builder = Client::builder()
+        .timeout(options.timeout)
+        .redirect(redirect);
+    if let Some(user_agent) = &options.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+
+    builder.build().context("Failed to create HTTP client")
+}
+
+/// Try each URL in order, stopping at the first success
 ///
-/// Security: Only HTTPS URLs are allowed
-pub async fn unsubscribe_one_click(url: &str) -> Result<bool> {
-    // Validate URL
-    let parsed_url = Url::parse(url).context("Invalid unsubscribe URL")?;
+/// Returns `Ok(None)` if at least one endpoint answered but none accepted the
+/// request, or the last error if every request failed outright.
+async fn send_to_candidates(
+    client: &Client,
+    urls: &[&str],
+    options: &UnsubscribeOptions,
+) -> Result<Option<UnsubscribeVia>> {
+    let mut last_error = None;
+    let mut any_response = false;
 
-    // Security: Only HTTPS
-    if parsed_url.scheme() != "https" {
-        bail!("Only HTTPS unsubscribe URLs are allowed");
+    for url in urls {
+        match send_unsubscribe(client, url, options).await {
+            Ok(Some(via)) => return Ok(Some(via)),
+            Ok(None) => {
+                tracing::debug!(url = %url, "unsubscribe rejected");
+                any_response = true;
+            }
+            Err(e) => {
+                tracing::debug!(url = %url, error = %e, "unsubscribe failed");
+                last_error = Some(e);
+            }
+        }
     }
 
-    // Create HTTP client
-    let client = Client::builder()
-        .timeout(REQUEST_TIMEOUT)
-        .build()
-        .context("Failed to create HTTP client")?;
+    match last_error {
+        Some(e) if !any_response => Err(e),
+        _ => Ok(None),
+    }
+}
 
+/// POST the unsubscribe request, falling back to GET if the POST is refused
+///
+/// Returns the method that succeeded, or `None` if the endpoint rejected it.
+async fn send_unsubscribe(
+    client: &Client,
+    url: &str,
+    options: &UnsubscribeOptions,
+) -> Result<Option<UnsubscribeVia>> {
     // Send POST request
-    let response = client
-        .post(url)
-        .header("List-Unsubscribe", "One-Click")
-        .send()
+    let status = send_with_retry(client, Method::POST, url, options)
         .await
         .context("Failed to send unsubscribe request")?;
 
-    // Check if successful
-    Ok(response.status().is_success())
+    if is_unsubscribed(status) {
+        return Ok(Some(UnsubscribeVia::Post));
+    }
+    if !should_fall_back_to_get(status) {
+        return Ok(None);
+    }
+
+    tracing::debug!(status = %status, "unsubscribe POST rejected, retrying with GET");
+
+    let status = send_with_retry(client, Method::GET, url, options)
+        .await
+        .context("Failed to send unsubscribe GET request")?;
+
+    Ok(is_unsubscribed(status).then_some(UnsubscribeVia::Get))
+}
+
+/// Whether a refused POST should be retried as a GET
+///
+/// 400 and 405 usually mean the endpoint doesn't implement the RFC 8058 POST
+/// and confirms on a plain GET instead.
+fn should_fall_back_to_get(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_REQUEST | StatusCode::METHOD_NOT_ALLOWED
+    )
+}
+
+/// Form body of the one-click POST
+///
+/// The RFC 8058 field comes first, followed by the URL's query parameters for
+/// endpoints that expect their token echoed in the body.
+fn one_click_body(url: &str) -> String {
+    let mut body = url::form_urlencoded::Serializer::for_suffix(ONE_CLICK_BODY.to_string(), 0);
+    if let Ok(parsed) = Url::parse(url) {
+        body.extend_pairs(parsed.query_pairs());
+    }
+    body.finish()
+}
+
+/// Send one request, retrying 429/5xx responses and network errors
+///
+/// Returns the status of the last response once it is not retryable or the
+/// attempts are exhausted.
+async fn send_with_retry(
+    client: &Client,
+    method: Method,
+    url: &str,
+    options: &UnsubscribeOptions,
+) -> Result<StatusCode> {
+    let max_attempts = options.max_attempts.max(1);
+    let mut attempt = 1;
+
+    loop {
+        let mut request = client.request(method.clone(), url);
+        if method == Method::POST {
+            request = request
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                )
+                .body(one_click_body(url));
+        }
+
+        let result = request.send().await;
+        let retryable = match &result {
+            Ok(response) => is_retryable_status(response.status()),
+            Err(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+        };
+
+        if !retryable || attempt >= max_attempts {
+            return Ok(result?.status());
+        }
+
+        let delay = backoff_delay(options.retry_base_delay, attempt);
+        match &result {
+            Ok(response) => tracing::debug!(
+                method = %method,
+                status = %response.status(),
+                delay = ?delay,
+                "unsubscribe request returned an error status, retrying"
+            ),
+            Err(e) => tracing::debug!(
+                method = %method,
+                delay = ?delay,
+                error = %e,
+                "unsubscribe request failed, retrying"
+            ),
+        }
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Rate limited or server-side failure
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Exponential backoff after the given (1-based) failed attempt, plus up to
+/// 50% jitter so parallel clients don't retry in lockstep
+pub(crate) fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let delay = base.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+
+    delay + delay.mul_f64(f64::from(nanos % 1000) / 2000.0)
+}
+
+/// 2xx, or a 3xx that wasn't followed (redirect to a confirmation page)
+fn is_unsubscribed(status: StatusCode) -> bool {
+    status.is_success() || status.is_redirection()
 }
 
 #[cfg(test)]
@@ -42,14 +469,476 @@ mod tests {
     use super::*;
 
     #[tokio::test]
-    async fn test_reject_http() {
-        let result = unsubscribe_one_click("http://example.com/unsub").await;
-        assert!(result.is_err());
+    async fn test_tiny_timeout_errors() {
+        // Accepts TCP connections but never completes the TLS handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}/unsub", listener.local_addr().unwrap());
+
+        let options = UnsubscribeOptions {
+            timeout: Duration::from_micros(100),
+            max_attempts: 1,
+            ..UnsubscribeOptions::default()
+        };
+
+        let http = HttpClient::new(options).unwrap();
+        let err = tokio::time::timeout(Duration::from_secs(5), http.unsubscribe_one_click(&[url]))
+            .await
+            .expect("request should time out instead of hanging")
+            .unwrap_err();
+
+        let is_timeout = err
+            .chain()
+            .any(|e| matches!(e.downcast_ref::<reqwest::Error>(), Some(e) if e.is_timeout()));
+        assert!(is_timeout, "Expected timeout error, got {:?}", err);
     }
 
     #[tokio::test]
     async fn test_reject_invalid_url() {
-        let result = unsubscribe_one_click("not-a-url").await;
+        let http = HttpClient::new(UnsubscribeOptions::default()).unwrap();
+        let result = http.unsubscribe_one_click(&["not-a-url".to_string()]).await;
         assert!(result.is_err());
     }
+
+    /// Serve one canned response per connection, recording request lines
+    async fn spawn_server(
+        responses: Vec<&'static str>,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let mut request_lines = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                request_lines.push(request.lines().next().unwrap_or_default().to_string());
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            request_lines
+        });
+
+        (base, handle)
+    }
+
+    /// Accept one connection and return the full raw request (headers and body)
+    async fn capture_request(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+
+            // Headers and body may arrive in separate writes
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (base, handle)
+    }
+
+    fn fast_retries() -> UnsubscribeOptions {
+        UnsubscribeOptions {
+            retry_base_delay: Duration::from_millis(1),
+            request_delay: Duration::ZERO,
+            host_delay: Duration::ZERO,
+            ..UnsubscribeOptions::default()
+        }
+    }
+
+    const FOUND: &str =
+        "HTTP/1.1 302 Found\r\nLocation: /done\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const NOT_ALLOWED: &str =
+        "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    const BAD_REQUEST: &str =
+        "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const NOT_FOUND: &str =
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let (base, server) = spawn_server(vec![UNAVAILABLE, UNAVAILABLE, OK]).await;
+        let client = Client::new();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests, vec!["POST /unsub HTTP/1.1"; 3]);
+    }
+
+    #[tokio::test]
+    async fn test_post_sends_rfc8058_form_body() {
+        let (base, server) = capture_request(OK).await;
+        let client = Client::new();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let request = server.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let head = head.to_ascii_lowercase();
+        assert!(head.starts_with("post /unsub http/1.1"));
+        assert!(head.contains("content-type: application/x-www-form-urlencoded"));
+        assert!(!head.contains("list-unsubscribe:"));
+        assert_eq!(body, "List-Unsubscribe=One-Click");
+    }
+
+    #[test]
+    fn test_is_https_url() {
+        assert!(is_https_url("https://shop.com/unsubscribe?id=1"));
+        assert!(!is_https_url("http://shop.com/unsubscribe"));
+        assert!(!is_https_url("javascript:alert(1)"));
+        assert!(!is_https_url("not a url"));
+    }
+
+    #[test]
+    fn test_https_candidates_skips_invalid_urls() {
+        let urls = vec![
+            "not a url".to_string(),
+            "http://shop.com/unsub".to_string(),
+            "https://shop.com/unsub?id=1".to_string(),
+        ];
+        assert_eq!(
+            https_candidates(&urls).unwrap(),
+            vec!["https://shop.com/unsub?id=1"]
+        );
+        assert!(https_candidates(&urls[..2]).is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_host_skips_non_https() {
+        let urls = vec![
+            "http://track.example.com/u".to_string(),
+            "https://List.Sendgrid.net/unsub?id=1".to_string(),
+        ];
+        assert_eq!(
+            unsubscribe_host(&urls).as_deref(),
+            Some("list.sendgrid.net")
+        );
+        assert_eq!(unsubscribe_host(&[]), None);
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_with_jitter() {
+        let base = Duration::from_millis(100);
+
+        let first = backoff_delay(base, 1);
+        assert!(first >= base && first <= base * 3 / 2);
+
+        let third = backoff_delay(base, 3);
+        assert!(third >= base * 4 && third <= base * 6);
+    }
+
+    /// Serve `count` keep-alive responses on a single connection
+    ///
+    /// A client that opens a second connection gets no answer.
+    async fn spawn_keep_alive_server(count: usize) -> (String, tokio::task::JoinHandle<usize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut served = 0;
+            let mut pending = Vec::new();
+            let mut buf = [0u8; 4096];
+
+            while served < count {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                pending.extend_from_slice(&buf[..n]);
+
+                // Answer every complete request (headers plus form body)
+                while let Some(end) = String::from_utf8_lossy(&pending).find("One-Click") {
+                    pending.drain(..end + "One-Click".len());
+                    stream
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                        .await
+                        .unwrap();
+                    served += 1;
+                }
+            }
+            served
+        });
+
+        (base, handle)
+    }
+
+    #[tokio::test]
+    async fn test_http_client_reuses_connection() {
+        let (base, server) = spawn_keep_alive_server(2).await;
+        let http = HttpClient::new(UnsubscribeOptions {
+            timeout: Duration::from_secs(2),
+            ..fast_retries()
+        })
+        .unwrap();
+
+        for path in ["a", "b"] {
+            let url = format!("{}/{}", base, path);
+            let result = send_unsubscribe(&http.client, &url, http.options()).await;
+            assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+        }
+
+        assert_eq!(server.await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_http_client_sends_configured_user_agent() {
+        let (base, server) = capture_request(OK).await;
+        let http = HttpClient::new(UnsubscribeOptions {
+            user_agent: Some("UnsubMail-Test/1.0".to_string()),
+            ..fast_retries()
+        })
+        .unwrap();
+
+        let url = format!("{}/unsub", base);
+        let result = send_unsubscribe(&http.client, &url, http.options()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let request = server.await.unwrap().to_ascii_lowercase();
+        assert!(request.contains("user-agent: unsubmail-test/1.0"));
+    }
+
+    #[tokio::test]
+    async fn test_http_client_without_redirects_stops_at_3xx() {
+        let (base, server) = spawn_server(vec![FOUND]).await;
+        let http = HttpClient::new(UnsubscribeOptions {
+            follow_redirects: false,
+            ..fast_retries()
+        })
+        .unwrap();
+
+        let url = format!("{}/unsub", base);
+        let result = send_unsubscribe(&http.client, &url, http.options()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+        assert_eq!(server.await.unwrap(), vec!["POST /unsub HTTP/1.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_http_client_rejects_http_urls() {
+        let http = HttpClient::new(UnsubscribeOptions::default()).unwrap();
+
+        let result = http
+            .unsubscribe_one_click(&["http://example.com/unsub".to_string()])
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_follows_redirect() {
+        let (base, server) = spawn_server(vec![FOUND, OK]).await;
+        let client = Client::builder()
+            .redirect(Policy::limited(MAX_REDIRECTS))
+            .build()
+            .unwrap();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0], "POST /unsub HTTP/1.1");
+        assert!(requests[1].ends_with("/done HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_tries_next_url_after_rejection() {
+        let (base, server) = spawn_server(vec![NOT_ALLOWED, NOT_ALLOWED, OK]).await;
+        let client = Client::new();
+        let first = format!("{}/tracking", base);
+        let second = format!("{}/unsub", base);
+
+        let result = send_to_candidates(&client, &[&first, &second], &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            requests,
+            vec![
+                "POST /tracking HTTP/1.1",
+                "GET /tracking HTTP/1.1",
+                "POST /unsub HTTP/1.1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_urls_rejected() {
+        let (base, server) = spawn_server(vec![NOT_ALLOWED; 4]).await;
+        let client = Client::new();
+        let first = format!("{}/a", base);
+        let second = format!("{}/b", base);
+
+        let result = send_to_candidates(&client, &[&first, &second], &fast_retries()).await;
+        assert_eq!(result.unwrap(), None);
+        assert_eq!(server.await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_unfollowed_redirect_is_success() {
+        let (base, server) = spawn_server(vec![FOUND]).await;
+        let client = Client::builder().redirect(Policy::none()).build().unwrap();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_fallback_when_post_rejected() {
+        let (base, server) = spawn_server(vec![NOT_ALLOWED, OK]).await;
+        let client = Client::new();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Get));
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            requests,
+            vec!["POST /unsub HTTP/1.1", "GET /unsub HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_only_endpoint_keeps_query() {
+        // Rejects the POST as a bad request, accepts the GET confirmation
+        let (base, server) = spawn_server(vec![BAD_REQUEST, OK]).await;
+        let client = Client::new();
+        let url = format!("{}/unsub?id=42&token=abc", base);
+
+        let result = send_to_candidates(&client, &[&url], &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Get));
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            requests,
+            vec![
+                "POST /unsub?id=42&token=abc HTTP/1.1",
+                "GET /unsub?id=42&token=abc HTTP/1.1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_get_fallback_for_other_rejections() {
+        let (base, server) = spawn_server(vec![NOT_FOUND]).await;
+        let client = Client::new();
+
+        let result = send_unsubscribe(&client, &format!("{}/unsub", base), &fast_retries()).await;
+        assert_eq!(result.unwrap(), None);
+        assert_eq!(server.await.unwrap(), vec!["POST /unsub HTTP/1.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_post_echoes_query_parameters() {
+        let (base, server) = capture_request(OK).await;
+        let client = Client::new();
+        let url = format!("{}/unsub?id=42&token=a%20b", base);
+
+        let result = send_unsubscribe(&client, &url, &fast_retries()).await;
+        assert_eq!(result.unwrap(), Some(UnsubscribeVia::Post));
+
+        let request = server.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /unsub?id=42&token=a%20b HTTP/1.1"));
+        assert_eq!(body, "List-Unsubscribe=One-Click&id=42&token=a+b");
+    }
+
+    #[tokio::test]
+    async fn test_send_all_keeps_input_order() {
+        let (base, server) = spawn_server(vec![OK, OK]).await;
+        let client = Client::new();
+        let first = format!("{}/a", base);
+        let third = format!("{}/c", base);
+
+        let targets = vec![
+            ("a@example.com".to_string(), Ok(vec![first.as_str()])),
+            (
+                "b@example.com".to_string(),
+                Err(anyhow::anyhow!("Only HTTPS unsubscribe URLs are allowed")),
+            ),
+            ("c@example.com".to_string(), Ok(vec![third.as_str()])),
+        ];
+
+        let options = fast_retries();
+        let results = send_all(&client, targets, 4, &options, &Throttle::new(&options)).await;
+
+        let keys: Vec<&str> = results.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["a@example.com", "b@example.com", "c@example.com"]
+        );
+        assert_eq!(results[0].1.as_ref().unwrap(), &Some(UnsubscribeVia::Post));
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].1.as_ref().unwrap(), &Some(UnsubscribeVia::Post));
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_throttle_spaces_requests_to_same_host() {
+        let throttle = Throttle::new(&UnsubscribeOptions {
+            request_delay: Duration::from_millis(500),
+            host_delay: Duration::from_secs(2),
+            ..UnsubscribeOptions::default()
+        });
+        let now = Instant::now();
+
+        let first = throttle.reserve("esp.example.com", now);
+        let other_host = throttle.reserve("news.example.org", now);
+        let same_host = throttle.reserve("esp.example.com", now);
+
+        assert_eq!(first, now);
+        assert_eq!(other_host - first, Duration::from_millis(500));
+        assert!(same_host - first >= Duration::from_secs(2));
+
+        // Later requests still respect the global spacing
+        let later = throttle.reserve("news.example.org", now + Duration::from_secs(10));
+        assert_eq!(later, now + Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_host_key() {
+        assert_eq!(
+            host_key(&["https://ESP.example.com/u?id=1", "https://other.com/"]),
+            "esp.example.com"
+        );
+        assert_eq!(host_key(&[]), "");
+    }
 }
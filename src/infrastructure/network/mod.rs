@@ -1,3 +1,6 @@
 //! Network operations
 
+pub mod gmail_api;
+pub mod gmail_filters;
 pub mod http_client;
+pub mod token_revocation;
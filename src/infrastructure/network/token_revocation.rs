@@ -0,0 +1,68 @@
+//! Google OAuth2 token revocation
+
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use std::time::Duration;
+
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Revoke an OAuth2 token with Google
+///
+/// Revoking a refresh token also invalidates every access token issued from it.
+pub async fn revoke_token(token: &str) -> Result<()> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    revoke_token_at(&client, REVOKE_URL, token).await
+}
+
+async fn revoke_token_at(client: &Client, url: &str, token: &str) -> Result<()> {
+    let response = client
+        .post(url)
+        .form(&[("token", token)])
+        .send()
+        .await
+        .context("Failed to send revocation request")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("Google rejected token revocation ({}): {}", status, body);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_revoke_posts_token_form() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/revoke", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        revoke_token_at(&Client::new(), &url, "refresh/token")
+            .await
+            .unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /revoke HTTP/1.1"));
+        assert!(request.ends_with("token=refresh%2Ftoken"));
+    }
+}
@@ -0,0 +1,255 @@
+//! User configuration file
+//!
+//! Defaults for interactive mode and the subcommands are read from
+//! `config.toml` in the project config directory (e.g.
+//! `~/.config/unsubmail/config.toml` on Linux). The file is created with the
+//! built-in defaults on first run; missing keys fall back to those defaults.
+//! Environment variables and CLI flags take precedence.
+
+use crate::domain::analysis::ScoringConfig;
+use crate::domain::models::AccessScope;
+use crate::domain::planner::{DEFAULT_MAX_DELETE, DEFAULT_MIN_DISPLAY_SCORE};
+use crate::infrastructure::imap::connection::ImapConfig;
+use crate::infrastructure::imap::fetch::FetchOptions;
+use crate::infrastructure::network::gmail_api::GmailFetchConfig;
+use crate::infrastructure::network::http_client::UnsubscribeOptions;
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Defaults loaded from `config.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Maximum number of messages to scan (unset = whole inbox)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_max_messages: Option<usize>,
+
+    /// Number of UIDs requested per FETCH command
+    pub batch_size: usize,
+
+    /// Stop fetching headers after this many seconds (unset = no limit)
+    ///
+    /// The senders found so far are shown, labelled as a partial scan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_time_budget_secs: Option<u64>,
+
+    /// Gmail API requests in flight at once when scanning read-only
+    pub gmail_api_concurrency: usize,
+
+    /// Attempts per Gmail API request; 429, 5xx and network errors are retried
+    pub gmail_api_max_attempts: u32,
+
+    /// Delay before the first Gmail API retry, in milliseconds, doubled after
+    /// each failure unless Gmail sends `Retry-After`
+    pub gmail_api_retry_delay_ms: u64,
+
+    /// Minimum score for a sender to be listed in interactive mode
+    pub min_display_score: f32,
+
+    /// IMAP server hostname
+    pub imap_host: String,
+
+    /// Folder messages are moved to when deleted
+    pub trash_folder: String,
+
+    /// Folder messages are moved to when marked as spam
+    pub spam_folder: String,
+
+    /// Timeout for each one-click unsubscribe request, in seconds
+    pub http_timeout_secs: u64,
+
+    /// Minimum delay between two unsubscribe requests, in milliseconds
+    pub unsubscribe_delay_ms: u64,
+
+    /// Minimum delay between two unsubscribe requests to the same host, in
+    /// milliseconds (0 = only `unsubscribe_delay_ms`)
+    pub unsubscribe_host_delay_ms: u64,
+
+    /// Maximum number of messages a single run may delete or move to spam
+    pub max_delete: Option<usize>,
+
+    /// Leave cleaned messages flagged as deleted instead of expunging them
+    ///
+    /// They are still copied to trash or spam, but only disappear from the
+    /// mailbox once expunged in a mail client.
+    pub flag_only: bool,
+
+    /// Add a Gmail filter that trashes future mail from senders moved to spam
+    ///
+    /// Needs the extra `gmail.settings.basic` permission, asked for at the
+    /// next sign-in.
+    pub gmail_filters: bool,
+
+    /// Newsletter address patterns added to the built-in ones
+    ///
+    /// A pattern ending in `@` matches the start of the local part
+    /// (`bulletin@`); any other pattern matches anywhere in the address.
+    pub newsletter_patterns: Vec<String>,
+
+    /// Heuristic weights and thresholds, the `[scoring]` table
+    pub scoring: ScoringConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let imap = ImapConfig::default();
+        let fetch = FetchOptions::default();
+        let unsubscribe = UnsubscribeOptions::default();
+        let gmail_api = GmailFetchConfig::default();
+
+        Self {
+            scan_max_messages: fetch.max_messages,
+            batch_size: fetch.batch_size,
+            scan_time_budget_secs: fetch.time_budget.map(|budget| budget.as_secs()),
+            gmail_api_concurrency: gmail_api.concurrency,
+            gmail_api_max_attempts: gmail_api.max_attempts,
+            gmail_api_retry_delay_ms: gmail_api.retry_base_delay.as_millis() as u64,
+            min_display_score: DEFAULT_MIN_DISPLAY_SCORE,
+            imap_host: imap.host,
+            trash_folder: imap.trash_folder,
+            spam_folder: imap.spam_folder,
+            http_timeout_secs: unsubscribe.timeout.as_secs(),
+            unsubscribe_delay_ms: unsubscribe.request_delay.as_millis() as u64,
+            unsubscribe_host_delay_ms: unsubscribe.host_delay.as_millis() as u64,
+            max_delete: Some(DEFAULT_MAX_DELETE),
+            flag_only: imap.flag_only,
+            gmail_filters: false,
+            newsletter_patterns: Vec::new(),
+            scoring: ScoringConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// IMAP settings from this file, overridden by `UNSUBMAIL_*` env vars
+    pub fn imap_config(&self) -> Result<ImapConfig> {
+        ImapConfig {
+            host: self.imap_host.clone(),
+            trash_folder: self.trash_folder.clone(),
+            spam_folder: self.spam_folder.clone(),
+            flag_only: self.flag_only,
+            ..ImapConfig::default()
+        }
+        .with_env_overrides()
+    }
+
+    /// Fetch batching and scan limit
+    pub fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            batch_size: self.batch_size.max(1),
+            max_messages: self.scan_max_messages,
+            time_budget: self.scan_time_budget_secs.map(Duration::from_secs),
+            ..FetchOptions::default()
+        }
+    }
+
+    /// Gmail API concurrency and retries for read-only scans
+    pub fn gmail_fetch_config(&self) -> GmailFetchConfig {
+        GmailFetchConfig {
+            concurrency: self.gmail_api_concurrency.max(1),
+            max_attempts: self.gmail_api_max_attempts.max(1),
+            retry_base_delay: Duration::from_millis(self.gmail_api_retry_delay_ms),
+        }
+    }
+
+    /// Access needed to clean: full, plus Gmail filters when `gmail_filters`
+    /// is set
+    pub fn cleanup_scope(&self) -> AccessScope {
+        if self.gmail_filters {
+            AccessScope::FullWithFilters
+        } else {
+            AccessScope::Full
+        }
+    }
+
+    /// Scoring weights with `newsletter_patterns` added to the built-in ones
+    pub fn scoring_config(&self) -> ScoringConfig {
+        self.scoring
+            .clone()
+            .with_extra_patterns(&self.newsletter_patterns)
+    }
+
+    /// Options for one-click unsubscribe requests
+    pub fn unsubscribe_options(&self) -> UnsubscribeOptions {
+        UnsubscribeOptions {
+            timeout: Duration::from_secs(self.http_timeout_secs),
+            request_delay: Duration::from_millis(self.unsubscribe_delay_ms),
+            host_delay: Duration::from_millis(self.unsubscribe_host_delay_ms),
+            ..UnsubscribeOptions::default()
+        }
+    }
+}
+
+/// Path of the config file
+pub fn config_path() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    Ok(proj_dirs.config_dir().join(CONFIG_FILE))
+}
+
+/// Load the config file, creating it with defaults if it doesn't exist
+pub fn load_config() -> Result<Config> {
+    load_config_from(&config_path()?)
+}
+
+/// Load a config file at `path`, creating it with defaults if it doesn't exist
+pub fn load_config_from(path: &Path) -> Result<Config> {
+    confy::load_path(path).with_context(|| format!("Failed to load config file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_builtin_defaults() {
+        let config = Config::default();
+
+        assert_eq!(config.imap_host, ImapConfig::default().host);
+        assert_eq!(config.trash_folder, ImapConfig::default().trash_folder);
+        assert_eq!(config.fetch_options().batch_size, 200);
+        assert_eq!(config.fetch_options().max_messages, None);
+        assert_eq!(config.fetch_options().time_budget, None);
+        assert_eq!(config.gmail_fetch_config(), GmailFetchConfig::default());
+        assert_eq!(config.min_display_score, DEFAULT_MIN_DISPLAY_SCORE);
+        assert_eq!(config.unsubscribe_options(), UnsubscribeOptions::default());
+        assert_eq!(config.cleanup_scope(), AccessScope::Full);
+    }
+
+    #[test]
+    fn test_partial_file_falls_back_to_defaults() {
+        let path =
+            std::env::temp_dir().join(format!("unsubmail-config-test-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "batch_size = 50\nscan_max_messages = 1000\n\n[scoring]\nunsubscribe_weight = 0.8\n",
+        )
+        .unwrap();
+
+        let config = load_config_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.batch_size, 50);
+        assert_eq!(config.scan_max_messages, Some(1000));
+        assert_eq!(config.imap_host, Config::default().imap_host);
+        assert_eq!(config.http_timeout_secs, 10);
+        assert_eq!(config.max_delete, Some(DEFAULT_MAX_DELETE));
+
+        let scoring = config.scoring_config();
+        assert_eq!(scoring.unsubscribe_weight, 0.8);
+        assert_eq!(
+            scoring.pattern_weight,
+            ScoringConfig::default().pattern_weight
+        );
+        assert_eq!(
+            scoring.newsletter_patterns,
+            ScoringConfig::default().newsletter_patterns
+        );
+    }
+}
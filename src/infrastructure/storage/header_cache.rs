@@ -0,0 +1,100 @@
+//! Cache of fetched headers for incremental rescans, one file per mailbox
+
+use super::json_store::sanitize_email;
+use crate::infrastructure::imap::connection::DEFAULT_MAILBOX;
+use crate::infrastructure::imap::fetch::MailboxSnapshot;
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::fs;
+use std::path::PathBuf;
+
+/// Get the header cache directory
+fn cache_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.cache_dir().join("headers");
+
+    fs::create_dir_all(&dir).context("Failed to create header cache directory")?;
+
+    Ok(dir)
+}
+
+/// Snapshot file name for an account's mailbox
+///
+/// INBOX keeps the plain per-account name used before other mailboxes could
+/// be scanned. Example: `me@x.com` + `Promotions` -> `me_at_x_com--Promotions.json`
+fn snapshot_file_name(email: &str, mailbox: &str) -> String {
+    if mailbox == DEFAULT_MAILBOX {
+        return format!("{}.json", sanitize_email(email));
+    }
+
+    let mailbox: String = mailbox
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}--{}.json", sanitize_email(email), mailbox)
+}
+
+/// Get snapshot file path for an account's mailbox
+fn snapshot_path(email: &str, mailbox: &str) -> Result<PathBuf> {
+    Ok(cache_dir()?.join(snapshot_file_name(email, mailbox)))
+}
+
+/// Save the header snapshot of a mailbox
+pub fn save_snapshot(email: &str, mailbox: &str, snapshot: &MailboxSnapshot) -> Result<()> {
+    let path = snapshot_path(email, mailbox)?;
+    let json = serde_json::to_string(snapshot).context("Failed to serialize header cache")?;
+
+    fs::write(&path, json).context("Failed to write header cache")?;
+
+    Ok(())
+}
+
+/// Load the header snapshot of a mailbox
+pub fn load_snapshot(email: &str, mailbox: &str) -> Result<Option<MailboxSnapshot>> {
+    let path = snapshot_path(email, mailbox)?;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read header cache")?;
+
+    // An unreadable cache just means a full rescan
+    Ok(serde_json::from_str(&json).ok())
+}
+
+/// Drop the header snapshots of every mailbox of an account
+pub fn invalidate(email: &str) -> Result<()> {
+    let dir = cache_dir()?;
+    let inbox = snapshot_file_name(email, DEFAULT_MAILBOX);
+    let prefix = format!("{}--", sanitize_email(email));
+
+    for entry in fs::read_dir(&dir).context("Failed to read header cache directory")? {
+        let entry = entry.context("Failed to read header cache directory")?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == inbox || name.starts_with(&prefix) {
+            fs::remove_file(entry.path()).context("Failed to remove header cache")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_file_name_per_mailbox() {
+        assert_eq!(
+            snapshot_file_name("me@gmail.com", "INBOX"),
+            "me_at_gmail_com.json"
+        );
+        assert_eq!(
+            snapshot_file_name("me@gmail.com", "[Gmail]/Promotions"),
+            "me_at_gmail_com--_Gmail__Promotions.json"
+        );
+    }
+}
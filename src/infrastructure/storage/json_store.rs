@@ -1,10 +1,14 @@
 //! Account metadata storage
 
-use crate::domain::models::EmailAccount;
+use crate::domain::models::{
+    CleanupOperation, CleanupProgress, CleanupRecord, CleanupReport, CleanupResult, EmailAccount,
+    EspStats,
+};
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 /// Get accounts directory path
 fn accounts_dir() -> Result<PathBuf> {
@@ -25,8 +29,86 @@ fn account_path(email: &str) -> Result<PathBuf> {
     Ok(dir.join(filename))
 }
 
+/// Get allowlist file path for an account
+fn allowlist_path(email: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.config_dir().join("allowlists");
+
+    fs::create_dir_all(&dir).context("Failed to create allowlists directory")?;
+
+    Ok(dir.join(format!("{}.json", sanitize_email(email))))
+}
+
+/// Get cleanup log file path for an account
+fn cleanup_log_path(email: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.data_dir().join("cleanup_logs");
+
+    fs::create_dir_all(&dir).context("Failed to create cleanup log directory")?;
+
+    Ok(dir.join(format!("{}.json", sanitize_email(email))))
+}
+
+/// Get cleanup report file path for an account
+fn reports_path(email: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.data_dir().join("reports");
+
+    fs::create_dir_all(&dir).context("Failed to create reports directory")?;
+
+    Ok(dir.join(format!("{}.jsonl", sanitize_email(email))))
+}
+
+/// Get the in-progress cleanup journal path for an account
+fn cleanup_progress_path(email: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.data_dir().join("progress");
+
+    fs::create_dir_all(&dir).context("Failed to create progress directory")?;
+
+    Ok(dir.join(format!("{}.json", sanitize_email(email))))
+}
+
+/// Get the per-ESP unsubscribe statistics file path (shared by all accounts)
+fn esp_stats_path() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.data_dir();
+
+    fs::create_dir_all(dir).context("Failed to create data directory")?;
+
+    Ok(dir.join("esp_stats.json"))
+}
+
+/// Replace `path` with `contents` without ever leaving it half-written
+///
+/// The data is written and synced to a temporary file next to `path`, which
+/// is then renamed over it, so a crash mid-write keeps the previous version.
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file =
+        fs::File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+    file.write_all(contents.as_bytes())
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+
+    fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
 /// Sanitize email for filename
-fn sanitize_email(email: &str) -> String {
+pub(crate) fn sanitize_email(email: &str) -> String {
     email.replace('@', "_at_").replace('.', "_")
 }
 
@@ -35,7 +117,7 @@ pub fn save_account(account: &EmailAccount) -> Result<()> {
     let path = account_path(&account.email)?;
     let json = serde_json::to_string_pretty(account).context("Failed to serialize account")?;
 
-    fs::write(&path, json).context("Failed to write account file")?;
+    write_atomic(&path, &json).context("Failed to write account file")?;
 
     Ok(())
 }
@@ -86,3 +168,307 @@ pub fn remove_account(email: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Load the sender allowlist for an account
+///
+/// Entries are exact addresses or `@domain.com` wildcards.
+pub fn load_allowlist(email: &str) -> Result<Vec<String>> {
+    let path = allowlist_path(email)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read allowlist file")?;
+
+    let entries = serde_json::from_str(&json).context("Failed to deserialize allowlist")?;
+
+    Ok(entries)
+}
+
+/// Load a blocklist from a JSON or CSV file
+///
+/// A JSON file holds an array of entries. Any other file is read as CSV: the
+/// first column of each line, skipping blank lines and `#` comments. Entries
+/// are addresses or `@domain.com` wildcards; a bare domain is read as a
+/// wildcard.
+pub fn load_blocklist(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read blocklist {}", path.display()))?;
+
+    parse_blocklist(&contents)
+        .with_context(|| format!("Failed to parse blocklist {}", path.display()))
+}
+
+fn parse_blocklist(contents: &str) -> Result<Vec<String>> {
+    let entries: Vec<String> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str(contents).context("Expected a JSON array of strings")?
+    } else {
+        contents
+            .lines()
+            .map(|line| line.split(',').next().unwrap_or_default())
+            .map(|field| field.trim().trim_matches('"').to_string())
+            .filter(|field| !field.is_empty() && !field.starts_with('#'))
+            .collect()
+    };
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| entry.trim().to_lowercase())
+        // Skips CSV header cells such as `email`, which can't be a domain
+        .filter(|entry| entry.contains('.'))
+        .map(|entry| {
+            if entry.contains('@') {
+                entry
+            } else {
+                format!("@{}", entry)
+            }
+        })
+        .collect())
+}
+
+/// Add an entry to the sender allowlist for an account
+pub fn add_allowlist_entry(email: &str, entry: &str) -> Result<()> {
+    let entry = entry.trim().to_lowercase();
+    let mut entries = load_allowlist(email)?;
+
+    if !entries.contains(&entry) {
+        entries.push(entry);
+        save_allowlist(email, &entries)?;
+    }
+
+    Ok(())
+}
+
+/// Remove an entry from the sender allowlist for an account
+pub fn remove_allowlist_entry(email: &str, entry: &str) -> Result<()> {
+    let entry = entry.trim().to_lowercase();
+    let mut entries = load_allowlist(email)?;
+
+    entries.retain(|e| *e != entry);
+
+    save_allowlist(email, &entries)
+}
+
+/// Save the sender allowlist for an account
+fn save_allowlist(email: &str, entries: &[String]) -> Result<()> {
+    let path = allowlist_path(email)?;
+    let json = serde_json::to_string_pretty(entries).context("Failed to serialize allowlist")?;
+
+    write_atomic(&path, &json).context("Failed to write allowlist file")?;
+
+    Ok(())
+}
+
+/// Load the cleanup log for an account (oldest run first)
+pub fn load_cleanup_log(email: &str) -> Result<Vec<CleanupRecord>> {
+    let path = cleanup_log_path(email)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read cleanup log")?;
+
+    let records = serde_json::from_str(&json).context("Failed to deserialize cleanup log")?;
+
+    Ok(records)
+}
+
+/// Append an operation to the cleanup run that started at `started_at`
+///
+/// A new run is created if the last recorded run has a different start time.
+pub fn record_cleanup_operation(
+    email: &str,
+    started_at: chrono::DateTime<chrono::Utc>,
+    operation: CleanupOperation,
+) -> Result<()> {
+    let mut records = load_cleanup_log(email)?;
+
+    match records.last_mut() {
+        Some(record) if record.started_at == started_at => record.operations.push(operation),
+        _ => records.push(CleanupRecord {
+            started_at,
+            operations: vec![operation],
+        }),
+    }
+
+    save_cleanup_log(email, &records)
+}
+
+/// Remove and return the most recent cleanup run
+pub fn pop_last_cleanup(email: &str) -> Result<Option<CleanupRecord>> {
+    let mut records = load_cleanup_log(email)?;
+    let last = records.pop();
+
+    if last.is_some() {
+        save_cleanup_log(email, &records)?;
+    }
+
+    Ok(last)
+}
+
+/// Save the cleanup log for an account
+fn save_cleanup_log(email: &str, records: &[CleanupRecord]) -> Result<()> {
+    let path = cleanup_log_path(email)?;
+    let json = serde_json::to_string_pretty(records).context("Failed to serialize cleanup log")?;
+
+    write_atomic(&path, &json).context("Failed to write cleanup log")?;
+
+    Ok(())
+}
+
+/// Append a cleanup report to the account's `reports.jsonl`
+pub fn append_report(email: &str, report: &CleanupReport) -> Result<()> {
+    let path = reports_path(email)?;
+    let line = serde_json::to_string(report).context("Failed to serialize report")?;
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open reports file")?;
+
+    writeln!(file, "{}", line).context("Failed to write report")?;
+
+    Ok(())
+}
+
+/// Load all cleanup reports for an account (oldest first)
+///
+/// Lines that fail to parse are skipped with a warning.
+pub fn load_reports(email: &str) -> Result<Vec<CleanupReport>> {
+    let path = reports_path(email)?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path).context("Failed to read reports file")?;
+
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                tracing::warn!(error = %e, "skipping malformed report");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Save the progress of the account's running cleanup
+pub fn save_cleanup_progress(email: &str, progress: &CleanupProgress) -> Result<()> {
+    let path = cleanup_progress_path(email)?;
+    let json = serde_json::to_string(progress).context("Failed to serialize cleanup progress")?;
+
+    write_atomic(&path, &json).context("Failed to write cleanup progress")?;
+
+    Ok(())
+}
+
+/// Load the progress of an interrupted cleanup, if any
+///
+/// A corrupt journal is treated as absent, with a warning, since the run it
+/// describes can no longer be resumed.
+pub fn load_cleanup_progress(email: &str) -> Result<Option<CleanupProgress>> {
+    let path = cleanup_progress_path(email)?;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read cleanup progress")?;
+
+    match serde_json::from_str(&json) {
+        Ok(progress) => Ok(Some(progress)),
+        Err(e) => {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "cleanup progress is corrupt, ignoring it"
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Forget the account's cleanup progress once the run is over
+pub fn clear_cleanup_progress(email: &str) -> Result<()> {
+    let path = cleanup_progress_path(email)?;
+
+    if path.exists() {
+        fs::remove_file(&path).context("Failed to remove cleanup progress")?;
+    }
+
+    Ok(())
+}
+
+/// Load the local per-ESP unsubscribe statistics
+pub fn load_esp_stats() -> Result<EspStats> {
+    let path = esp_stats_path()?;
+
+    if !path.exists() {
+        return Ok(EspStats::default());
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read ESP stats")?;
+
+    serde_json::from_str(&json).context("Failed to parse ESP stats")
+}
+
+/// Add the unsubscribe outcomes of a cleanup run to the ESP statistics
+///
+/// Only written locally; nothing is ever reported over the network.
+pub fn record_esp_results(results: &[CleanupResult]) -> Result<()> {
+    if !results
+        .iter()
+        .any(|r| r.unsubscribe_success.is_some() && r.unsubscribe_host.is_some())
+    {
+        return Ok(());
+    }
+
+    let mut stats = load_esp_stats()?;
+    stats.record_results(results);
+
+    let json = serde_json::to_string_pretty(&stats).context("Failed to serialize ESP stats")?;
+    write_atomic(&esp_stats_path()?, &json).context("Failed to write ESP stats")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("unsubmail-json-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("progress.json");
+
+        write_atomic(&path, "{\"old\": true}").unwrap();
+        write_atomic(&path, "{\"new\": true}").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"new\": true}");
+        assert!(!dir.join("progress.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_blocklist_csv_and_json() {
+        let csv = "# spammy senders\nemail,note\nDeals@Shop.com,too many\n\n\"@spam.example\"\nnews.example\n";
+        assert_eq!(
+            parse_blocklist(csv).unwrap(),
+            vec!["deals@shop.com", "@spam.example", "@news.example"]
+        );
+
+        let json = r#"["promo@shop.com", "@Spam.example"]"#;
+        assert_eq!(
+            parse_blocklist(json).unwrap(),
+            vec!["promo@shop.com", "@spam.example"]
+        );
+    }
+}
@@ -1,14 +1,30 @@
-//! Secure token storage using confy
+//! Secure token storage in the OS keyring
+//!
+//! Each account's [`OAuth2Token`] is serialized as JSON into its own keyring
+//! entry under the `unsubmail` service (Keychain on macOS, Credential Manager
+//! on Windows, Secret Service on Linux). Since keyrings can't be enumerated,
+//! the list of stored emails is kept in a separate index entry. The index is
+//! only a hint: listing checks each account's own entry, so a stale index can
+//! never hand one account's token to another.
 
 use crate::domain::models::OAuth2Token;
 use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use fs2::FileExt;
+use keyring::Entry;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::Once;
+
+const SERVICE: &str = "unsubmail";
+const INDEX_USER: &str = "__unsubmail_accounts__";
 
 const APP_NAME: &str = "unsubmail";
 const CONFIG_NAME: &str = "tokens";
 
-/// Token storage configuration
+/// Legacy plaintext token store written by previous versions via confy
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct TokenStore {
     tokens: HashMap<String, OAuth2Token>,
@@ -16,40 +32,315 @@ struct TokenStore {
 
 /// Store OAuth2 token for an email
 pub fn store_token(email: &str, token: OAuth2Token) -> Result<()> {
-    let mut store: TokenStore =
-        confy::load(APP_NAME, CONFIG_NAME).context("Failed to load token store")?;
-
-    store.tokens.insert(email.to_string(), token);
+    migrate_legacy_store();
+    write_token(email, &token)?;
 
-    confy::store(APP_NAME, CONFIG_NAME, store).context("Failed to save token store")?;
-
-    Ok(())
+    update_index(|emails| {
+        if !emails.iter().any(|e| e == email) {
+            emails.push(email.to_string());
+        }
+    })
 }
 
 /// Get OAuth2 token for an email
 pub fn get_token(email: &str) -> Result<Option<OAuth2Token>> {
-    let store: TokenStore =
-        confy::load(APP_NAME, CONFIG_NAME).context("Failed to load token store")?;
+    migrate_legacy_store();
 
-    Ok(store.tokens.get(email).cloned())
+    match entry(email)?.get_password() {
+        Ok(json) => {
+            let token = serde_json::from_str(&json).context("Failed to deserialize token")?;
+            Ok(Some(token))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).context("Failed to read token from keyring"),
+    }
 }
 
 /// Delete token for an email
 pub fn delete_token(email: &str) -> Result<()> {
-    let mut store: TokenStore =
-        confy::load(APP_NAME, CONFIG_NAME).context("Failed to load token store")?;
+    migrate_legacy_store();
 
-    store.tokens.remove(email);
+    match entry(email)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e).context("Failed to delete token from keyring"),
+    }
 
-    confy::store(APP_NAME, CONFIG_NAME, store).context("Failed to save token store")?;
-
-    Ok(())
+    update_index(|emails| emails.retain(|e| e != email))
 }
 
 /// List all emails with stored tokens
+///
+/// Index entries whose token entry has disappeared are skipped.
 pub fn list_token_emails() -> Result<Vec<String>> {
-    let store: TokenStore =
-        confy::load(APP_NAME, CONFIG_NAME).context("Failed to load token store")?;
+    migrate_legacy_store();
+
+    let mut emails = Vec::new();
+    for email in read_index()? {
+        match entry(&email)?.get_password() {
+            Ok(_) => emails.push(email),
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e).context("Failed to read token from keyring"),
+        }
+    }
+
+    Ok(emails)
+}
+
+fn entry(user: &str) -> Result<Entry> {
+    Entry::new(SERVICE, user).context("Failed to open keyring entry")
+}
+
+fn write_token(email: &str, token: &OAuth2Token) -> Result<()> {
+    let json = serde_json::to_string(token).context("Failed to serialize token")?;
+
+    entry(email)?
+        .set_password(&json)
+        .context("Failed to store token in keyring")
+}
+
+fn read_index() -> Result<Vec<String>> {
+    match entry(INDEX_USER)?.get_password() {
+        Ok(json) => serde_json::from_str(&json).context("Failed to deserialize account index"),
+        Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+        Err(e) => Err(e).context("Failed to read account index from keyring"),
+    }
+}
+
+/// Read-modify-write the account index
+///
+/// Holds an exclusive lock on `accounts.lock` in the config directory, so
+/// concurrent stores for different accounts, from this or another process,
+/// don't drop each other from the index.
+fn update_index(modify: impl FnOnce(&mut Vec<String>)) -> Result<()> {
+    let _lock = lock_index()?;
+
+    let mut emails = read_index()?;
+    let before = emails.clone();
+    modify(&mut emails);
+
+    if emails == before {
+        return Ok(());
+    }
+    write_index(&emails)
+}
+
+/// Open the index lock file and wait for an exclusive lock, released on drop
+fn lock_index() -> Result<File> {
+    let path = index_lock_path()?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    file.lock_exclusive()
+        .context("Failed to lock account index")?;
+    Ok(file)
+}
+
+fn index_lock_path() -> Result<PathBuf> {
+    // Tests use an in-memory keyring and must not touch the real config dir
+    let dir = if cfg!(test) {
+        std::env::temp_dir().join("unsubmail-test")
+    } else {
+        ProjectDirs::from("com", "unsubmail", "unsubmail")
+            .context("Failed to get project directories")?
+            .config_dir()
+            .to_path_buf()
+    };
+
+    fs::create_dir_all(&dir).context("Failed to create config directory")?;
+    Ok(dir.join("accounts.lock"))
+}
+
+fn write_index(emails: &[String]) -> Result<()> {
+    let json = serde_json::to_string(emails).context("Failed to serialize account index")?;
+
+    entry(INDEX_USER)?
+        .set_password(&json)
+        .context("Failed to store account index in keyring")
+}
+
+/// Move tokens from the legacy plaintext confy file into the keyring
+///
+/// Runs at most once per process. The file is only deleted after every token
+/// has been written to the keyring.
+fn migrate_legacy_store() {
+    static MIGRATION: Once = Once::new();
+
+    // Tests use an in-memory keyring and must not consume the real legacy file
+    if cfg!(test) {
+        return;
+    }
+
+    MIGRATION.call_once(|| {
+        if let Err(e) = try_migrate_legacy_store() {
+            tracing::warn!(error = %e, "failed to migrate legacy token store");
+        }
+    });
+}
+
+fn try_migrate_legacy_store() -> Result<()> {
+    let path = confy::get_configuration_file_path(APP_NAME, CONFIG_NAME)
+        .context("Failed to locate legacy token store")?;
+
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let store: TokenStore = confy::load_path(&path).context("Failed to load legacy token store")?;
+
+    for (email, token) in &store.tokens {
+        write_token(email, token)?;
+    }
+    update_index(|emails| {
+        for email in store.tokens.keys() {
+            if !emails.contains(email) {
+                emails.push(email.clone());
+            }
+        }
+    })?;
+
+    std::fs::remove_file(&path).context("Failed to delete legacy token store")?;
+
+    tracing::info!(
+        count = store.tokens.len(),
+        "migrated tokens from plaintext store to OS keyring"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+    use std::any::Any;
+    use std::sync::{Mutex, OnceLock};
+
+    type Store = Mutex<HashMap<(String, String), Vec<u8>>>;
+
+    /// Keyring entries shared by every credential, like a real OS store
+    fn store() -> &'static Store {
+        static STORE: OnceLock<Store> = OnceLock::new();
+        STORE.get_or_init(Store::default)
+    }
+
+    #[derive(Debug)]
+    struct MemoryCredential {
+        key: (String, String),
+    }
+
+    impl CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            store()
+                .lock()
+                .unwrap()
+                .insert(self.key.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            store()
+                .lock()
+                .unwrap()
+                .get(&self.key)
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            store()
+                .lock()
+                .unwrap()
+                .remove(&self.key)
+                .map(|_| ())
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[derive(Debug)]
+    struct MemoryBuilder;
+
+    impl CredentialBuilderApi for MemoryBuilder {
+        fn build(
+            &self,
+            _target: Option<&str>,
+            service: &str,
+            user: &str,
+        ) -> keyring::Result<Box<Credential>> {
+            Ok(Box::new(MemoryCredential {
+                key: (service.to_string(), user.to_string()),
+            }))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn token(access: &str) -> OAuth2Token {
+        OAuth2Token {
+            access_token: access.to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: chrono::Utc::now(),
+            scope: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_delete_token_forgets_credentials() {
+        keyring::set_default_credential_builder(Box::new(MemoryBuilder));
+        let email = "forget-me@example.com";
+        let token = token("ya29.access");
+
+        store_token(email, token).unwrap();
+        assert!(get_token(email).unwrap().is_some());
+        assert!(list_token_emails().unwrap().contains(&email.to_string()));
+
+        delete_token(email).unwrap();
+
+        assert!(get_token(email).unwrap().is_none());
+        assert!(!list_token_emails().unwrap().contains(&email.to_string()));
+
+        // Deleting again is not an error
+        delete_token(email).unwrap();
+    }
+
+    #[test]
+    fn test_storing_second_account_keeps_first() {
+        keyring::set_default_credential_builder(Box::new(MemoryBuilder));
+        let first = "first-account@example.com";
+        let second = "second-account@example.com";
+
+        store_token(first, token("ya29.first")).unwrap();
+        store_token(second, token("ya29.second")).unwrap();
+
+        assert_eq!(
+            get_token(first).unwrap().unwrap().access_token,
+            "ya29.first"
+        );
+        assert_eq!(
+            get_token(second).unwrap().unwrap().access_token,
+            "ya29.second"
+        );
+
+        let emails = list_token_emails().unwrap();
+        assert!(emails.contains(&first.to_string()));
+        assert!(emails.contains(&second.to_string()));
+
+        // A token removed behind the index's back is no longer listed
+        entry(second).unwrap().delete_credential().unwrap();
+        let emails = list_token_emails().unwrap();
+        assert!(emails.contains(&first.to_string()));
+        assert!(!emails.contains(&second.to_string()));
 
-    Ok(store.tokens.keys().cloned().collect())
+        delete_token(first).unwrap();
+        delete_token(second).unwrap();
+    }
 }
@@ -1,4 +1,7 @@
 //! Storage layer
 
+pub mod config;
+pub mod header_cache;
 pub mod json_store;
 pub mod keyring;
+pub mod scan_cache;
@@ -0,0 +1,137 @@
+//! Cache of analyzed scan results
+
+use super::json_store::sanitize_email;
+use crate::domain::models::{SenderGrouping, SenderInfo};
+use crate::infrastructure::imap::connection::DEFAULT_MAILBOX;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Default maximum age of a reusable scan
+pub const DEFAULT_TTL: Duration = Duration::hours(1);
+
+/// Analyzed senders from a previous scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedScan {
+    /// When the scan finished
+    pub scanned_at: DateTime<Utc>,
+
+    /// Number of messages whose headers were scanned
+    pub messages_scanned: usize,
+
+    /// Whether senders were grouped by address or by domain
+    #[serde(default)]
+    pub grouping: SenderGrouping,
+
+    /// Mailbox that was scanned
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+
+    /// Approximate mailbox size when the scan stopped at its time budget
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_of: Option<usize>,
+
+    /// Analyzed senders
+    pub senders: Vec<SenderInfo>,
+}
+
+fn default_mailbox() -> String {
+    DEFAULT_MAILBOX.to_string()
+}
+
+impl CachedScan {
+    /// Age of the scan relative to `now`
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        now - self.scanned_at
+    }
+
+    /// Whether the scan is younger than `ttl`
+    pub fn is_fresh(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        self.age(now) < ttl
+    }
+}
+
+/// Cache TTL from `UNSUBMAIL_SCAN_CACHE_TTL` (minutes), or [`DEFAULT_TTL`]
+pub fn ttl_from_env() -> Duration {
+    std::env::var("UNSUBMAIL_SCAN_CACHE_TTL")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(Duration::minutes)
+        .unwrap_or(DEFAULT_TTL)
+}
+
+/// Get cache file path for an account
+fn cache_path(email: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "unsubmail", "unsubmail")
+        .context("Failed to get project directories")?;
+
+    let dir = proj_dirs.cache_dir().join("scans");
+
+    fs::create_dir_all(&dir).context("Failed to create scan cache directory")?;
+
+    Ok(dir.join(format!("{}.json", sanitize_email(email))))
+}
+
+/// Save scan results for an account
+pub fn save_scan(email: &str, scan: &CachedScan) -> Result<()> {
+    let path = cache_path(email)?;
+    let json = serde_json::to_string(scan).context("Failed to serialize scan cache")?;
+
+    fs::write(&path, json).context("Failed to write scan cache")?;
+
+    Ok(())
+}
+
+/// Load scan results for an account if younger than `ttl`
+pub fn load_scan(email: &str, ttl: Duration) -> Result<Option<CachedScan>> {
+    let path = cache_path(email)?;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&path).context("Failed to read scan cache")?;
+
+    // An unreadable cache (e.g. from an older version) is just a cache miss
+    let Ok(scan) = serde_json::from_str::<CachedScan>(&json) else {
+        return Ok(None);
+    };
+
+    Ok(scan.is_fresh(ttl, Utc::now()).then_some(scan))
+}
+
+/// Drop cached scan results for an account
+pub fn invalidate(email: &str) -> Result<()> {
+    let path = cache_path(email)?;
+
+    if path.exists() {
+        fs::remove_file(&path).context("Failed to remove scan cache")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_fresh() {
+        let now = Utc::now();
+        let scan = CachedScan {
+            scanned_at: now - Duration::minutes(12),
+            messages_scanned: 100,
+            grouping: SenderGrouping::Address,
+            mailbox: DEFAULT_MAILBOX.to_string(),
+            partial_of: None,
+            senders: vec![],
+        };
+
+        assert_eq!(scan.age(now).num_minutes(), 12);
+        assert!(scan.is_fresh(Duration::hours(1), now));
+        assert!(!scan.is_fresh(Duration::minutes(10), now));
+    }
+}
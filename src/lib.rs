@@ -45,12 +45,13 @@
 //! # Example
 //!
 //! ```no_run
+//! use unsubmail::application::workflow::SignInMethod;
 //! use unsubmail::cli::interactive;
 //!
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
-//!     // Run interactive mode
-//!     interactive::run_interactive().await?;
+//!     // Run interactive mode with defaults from the config file
+//!     interactive::run_interactive(None, None, None, None, SignInMethod::detect()).await?;
 //!     Ok(())
 //! }
 //! ```
@@ -1,4 +1,6 @@
 use anyhow::Result;
+use clap::Parser;
+use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::{fmt, EnvFilter};
 use unsubmail::cli;
 
@@ -9,11 +11,28 @@ async fn main() -> Result<()> {
         .or_else(|_| dotenvy::dotenv())
         .ok(); // Ignore if no .env file exists
 
-    // Initialize logging
+    // Initialize logging on stderr, so stdout only carries results; closing a
+    // scan or cleanup span logs how long it took
     fmt()
         .with_env_filter(EnvFilter::from_default_env().add_directive("unsubmail=info".parse()?))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
         .init();
 
-    // Always run interactive mode
-    cli::interactive::run_interactive().await
+    // Run a subcommand, or fall back to interactive mode
+    let args = cli::args::Cli::parse();
+    let sign_in = args.sign_in_method();
+    match args.command {
+        Some(command) => cli::args::run(command).await,
+        None => {
+            cli::interactive::run_interactive(
+                args.min_display_score,
+                args.max_delete,
+                args.blocklist,
+                args.time_budget,
+                sign_in,
+            )
+            .await
+        }
+    }
 }
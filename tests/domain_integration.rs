@@ -4,10 +4,25 @@
 
 use unsubmail::domain::analysis::{
     analyze_sender, calculate_heuristic_score, detect_one_click, parse_list_unsubscribe,
+    BulkSignals, ScoringConfig, UnsubscribeHeaders,
 };
 use unsubmail::domain::models::UnsubscribeMethod;
 use unsubmail::domain::planner::{plan_action, plan_actions};
 
+/// Score with the default weights and no List-Id, header or subject signals
+fn default_score(email: &str, has_unsubscribe: bool, message_count: usize) -> f32 {
+    calculate_heuristic_score(
+        &ScoringConfig::default(),
+        email,
+        has_unsubscribe,
+        false,
+        BulkSignals::default(),
+        message_count,
+        &[],
+    )
+    .total
+}
+
 #[test]
 fn test_parse_list_unsubscribe_with_multiple_urls() {
     let header = "<https://example.com/unsub?id=123>, <mailto:unsub@example.com>";
@@ -40,7 +55,7 @@ fn test_detect_one_click_negative() {
 #[test]
 fn test_heuristic_score_newsletter_with_unsubscribe() {
     // Newsletter with List-Unsubscribe and high message count
-    let score = calculate_heuristic_score("newsletter@example.com", true, 35);
+    let score = default_score("newsletter@example.com", true, 35);
 
     // Should get: 0.5 (unsubscribe) + 0.3 (pattern) + 0.2 (>10) + 0.3 (>30) = 1.3
     assert!(score > 1.0, "Expected score > 1.0, got {}", score);
@@ -49,7 +64,7 @@ fn test_heuristic_score_newsletter_with_unsubscribe() {
 #[test]
 fn test_heuristic_score_personal_email_capped() {
     // Personal email with high message count but no List-Unsubscribe
-    let score = calculate_heuristic_score("john.doe@example.com", false, 100);
+    let score = default_score("john.doe@example.com", false, 100);
 
     // Should be capped at 0.5 without List-Unsubscribe header
     assert_eq!(score, 0.5, "Personal email should be capped at 0.5");
@@ -57,7 +72,7 @@ fn test_heuristic_score_personal_email_capped() {
 
 #[test]
 fn test_heuristic_score_low_volume_personal() {
-    let score = calculate_heuristic_score("jane@example.com", false, 3);
+    let score = default_score("jane@example.com", false, 3);
 
     // Low volume, no patterns, no unsubscribe = 0.0
     assert_eq!(score, 0.0);
@@ -70,8 +85,10 @@ fn test_analyze_sender_with_one_click() {
         Some("Example News".to_string()),
         25,
         vec![1, 2, 3],
-        Some("<https://example.com/unsub>".to_string()),
-        Some("List-Unsubscribe=One-Click".to_string()),
+        &[UnsubscribeHeaders::new(
+            Some("<https://example.com/unsub>".to_string()),
+            Some("List-Unsubscribe=One-Click".to_string()),
+        )],
         vec!["Subject 1".to_string(), "Subject 2".to_string()],
     );
 
@@ -88,8 +105,10 @@ fn test_analyze_sender_with_http_link() {
         None,
         10,
         vec![1, 2],
-        Some("<https://example.com/unsubscribe>".to_string()),
-        None, // No one-click
+        &[UnsubscribeHeaders::new(
+            Some("<https://example.com/unsubscribe>".to_string()),
+            None, // No one-click
+        )],
         vec![],
     );
 
@@ -108,8 +127,10 @@ fn test_analyze_sender_mailto_only() {
         None,
         5,
         vec![1],
-        Some("<mailto:unsub@example.com>".to_string()),
-        None,
+        &[UnsubscribeHeaders::new(
+            Some("<mailto:unsub@example.com>".to_string()),
+            None,
+        )],
         vec![],
     );
 
@@ -128,8 +149,10 @@ fn test_plan_action_for_one_click() {
         None,
         10,
         vec![1, 2],
-        Some("<https://example.com/unsub>".to_string()),
-        Some("List-Unsubscribe=One-Click".to_string()),
+        &[UnsubscribeHeaders::new(
+            Some("<https://example.com/unsub>".to_string()),
+            Some("List-Unsubscribe=One-Click".to_string()),
+        )],
         vec![],
     );
 
@@ -148,8 +171,7 @@ fn test_plan_action_for_no_unsubscribe() {
         None,
         5,
         vec![1, 2],
-        None,
-        None,
+        &[UnsubscribeHeaders::new(None, None)],
         vec![],
     );
 
@@ -168,8 +190,10 @@ fn test_plan_actions_multiple_senders() {
         None,
         10,
         vec![1],
-        Some("<https://example.com/unsub>".to_string()),
-        Some("List-Unsubscribe=One-Click".to_string()),
+        &[UnsubscribeHeaders::new(
+            Some("<https://example.com/unsub>".to_string()),
+            Some("List-Unsubscribe=One-Click".to_string()),
+        )],
         vec![],
     );
 
@@ -178,8 +202,7 @@ fn test_plan_actions_multiple_senders() {
         None,
         5,
         vec![2],
-        None,
-        None,
+        &[UnsubscribeHeaders::new(None, None)],
         vec![],
     );
 
@@ -214,7 +237,7 @@ fn test_email_pattern_matching() {
     ];
 
     for (email, should_match) in patterns {
-        let score = calculate_heuristic_score(email, false, 5);
+        let score = default_score(email, false, 5);
         if should_match {
             assert!(
                 score >= 0.3,
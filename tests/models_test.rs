@@ -2,9 +2,11 @@
 //!
 //! Tests model behavior and validation logic.
 
-use chrono::Utc;
+use chrono::{TimeZone, Utc};
 use unsubmail::domain::models::{
-    ActionType, CleanupResult, EmailAccount, OAuth2Token, UnsubscribeMethod,
+    AccessScope, ActionType, CleanupProgress, CleanupReport, CleanupResult, CleanupSummary,
+    EmailAccount, EspStats, InboxSummary, OAuth2Token, ResolutionStep, SenderInfo,
+    UnsubscribeMethod, UnsubscribeOutcome, UnsubscribeReport,
 };
 
 #[test]
@@ -12,11 +14,34 @@ fn test_email_account_creation() {
     let account = EmailAccount {
         email: "test@gmail.com".to_string(),
         added_at: Utc::now(),
+        protected: false,
     };
 
     assert_eq!(account.email, "test@gmail.com");
 }
 
+#[test]
+fn test_email_account_protected_defaults_off() {
+    let json = r#"{"email":"test@gmail.com","added_at":"2024-01-01T00:00:00Z"}"#;
+    let account: EmailAccount = serde_json::from_str(json).unwrap();
+
+    assert!(!account.protected);
+}
+
+#[test]
+fn test_email_account_confirms_typed_address() {
+    let account = EmailAccount {
+        email: "me@gmail.com".to_string(),
+        added_at: Utc::now(),
+        protected: true,
+    };
+
+    assert!(account.confirms("me@gmail.com"));
+    assert!(account.confirms("  ME@Gmail.com\n"));
+    assert!(!account.confirms("me@gmail"));
+    assert!(!account.confirms(""));
+}
+
 #[test]
 fn test_oauth2_token_expired() {
     let past = Utc::now() - chrono::Duration::hours(2);
@@ -24,6 +49,7 @@ fn test_oauth2_token_expired() {
         access_token: "token123".to_string(),
         refresh_token: "refresh123".to_string(),
         expires_at: past,
+        scope: AccessScope::Full,
     };
 
     assert!(token.is_expired());
@@ -36,11 +62,36 @@ fn test_oauth2_token_not_expired() {
         access_token: "token123".to_string(),
         refresh_token: "refresh123".to_string(),
         expires_at: future,
+        scope: AccessScope::Full,
     };
 
     assert!(!token.is_expired());
 }
 
+#[test]
+fn test_access_scope() {
+    assert!(AccessScope::Full.covers(AccessScope::ReadOnly));
+    assert!(AccessScope::ReadOnly.covers(AccessScope::ReadOnly));
+    assert!(!AccessScope::ReadOnly.covers(AccessScope::Full));
+    assert!(AccessScope::FullWithFilters.covers(AccessScope::Full));
+    assert!(!AccessScope::Full.covers(AccessScope::FullWithFilters));
+    assert!(AccessScope::FullWithFilters.allows_changes());
+    assert!(AccessScope::FullWithFilters
+        .url()
+        .contains("https://www.googleapis.com/auth/gmail.settings.basic"));
+    assert_eq!(
+        AccessScope::ReadOnly.url(),
+        "https://www.googleapis.com/auth/gmail.readonly"
+    );
+
+    // Tokens saved before the scope was stored were granted full access
+    let token: OAuth2Token = serde_json::from_str(
+        r#"{"access_token":"a","refresh_token":"r","expires_at":"2025-01-01T00:00:00Z"}"#,
+    )
+    .unwrap();
+    assert_eq!(token.scope, AccessScope::Full);
+}
+
 #[test]
 fn test_unsubscribe_method_is_one_click() {
     let one_click = UnsubscribeMethod::OneClick {
@@ -75,6 +126,16 @@ fn test_unsubscribe_method_is_available() {
     assert!(!none.is_available());
 }
 
+#[test]
+fn test_action_type_display() {
+    assert_eq!(
+        ActionType::UnsubscribeAndDelete.to_string(),
+        "Unsubscribe + delete"
+    );
+    assert_eq!(ActionType::SpamAndDelete.to_string(), "Move to spam");
+    assert_eq!(ActionType::DeleteOnly.to_string(), "Delete");
+}
+
 #[test]
 fn test_cleanup_result_success() {
     let result = CleanupResult::success(
@@ -103,6 +164,194 @@ fn test_cleanup_result_failure() {
     assert_eq!(result.error, Some("Network timeout".to_string()));
 }
 
+#[test]
+fn test_cleanup_result_keeps_first_resolution() {
+    let mut result = CleanupResult::success(
+        "news@example.com".to_string(),
+        ActionType::SpamAndDelete,
+        0,
+        Some(false),
+    );
+    assert_eq!(result.resolved_by, None);
+
+    // One-click failed, the fallback page worked, then messages were deleted
+    result.resolve(ResolutionStep::ManualLink);
+    result.resolve(ResolutionStep::Delete);
+    assert_eq!(result.resolved_by, Some(ResolutionStep::ManualLink));
+
+    let json = serde_json::to_string(&result).unwrap();
+    assert!(json.contains("\"resolved_by\":\"manual-link\""));
+}
+
+#[test]
+fn test_cleanup_summary() {
+    let results = vec![
+        CleanupResult::success(
+            "a@example.com".to_string(),
+            ActionType::UnsubscribeAndDelete,
+            400,
+            Some(true),
+        ),
+        CleanupResult::success(
+            "b@example.com".to_string(),
+            ActionType::UnsubscribeAndDelete,
+            12,
+            Some(false),
+        ),
+        CleanupResult::failure(
+            "c@example.com".to_string(),
+            ActionType::SpamAndDelete,
+            "Network timeout".to_string(),
+        ),
+    ];
+
+    let summary = CleanupSummary::from_results(&results);
+
+    assert_eq!(summary.senders, 3);
+    assert_eq!(summary.messages_deleted, 412);
+    assert_eq!(summary.unsubscribes_succeeded, 1);
+    assert_eq!(summary.unsubscribes_failed, 1);
+    assert_eq!(summary.errors, 1);
+    assert_eq!(
+        summary.to_string(),
+        "Deleted 412 messages across 3 senders, 1 unsubscribe succeeded, 1 failed (1 sender with errors)"
+    );
+}
+
+#[test]
+fn test_inbox_summary_newsletter_share() {
+    let senders: Vec<SenderInfo> = [("news@shop.com", 62, 0.9), ("friend@example.com", 30, 0.1)]
+        .into_iter()
+        .map(|(email, count, score)| SenderInfo {
+            email: email.to_string(),
+            message_count: count,
+            message_uids: (1..=count as u32).collect(),
+            heuristic_score: score,
+            ..SenderInfo::default()
+        })
+        .collect();
+
+    let summary = InboxSummary::from_senders(100, &senders, 0.6);
+    assert_eq!(summary.total_messages, 100);
+    assert_eq!(summary.senders, 2);
+    assert_eq!(summary.newsletter_senders, 1);
+    assert_eq!(summary.newsletter_messages, 62);
+    assert_eq!(summary.newsletter_percent(), 62);
+
+    // An unknown mailbox size falls back to the messages attributed to senders
+    let summary = InboxSummary::from_senders(0, &senders, 0.6);
+    assert_eq!(summary.total_messages, 92);
+    assert_eq!(summary.newsletter_percent(), 67);
+    assert_eq!(summary.partial_of, None);
+
+    // A scan cut short keeps its percentages relative to what was scanned
+    let summary = InboxSummary::from_senders(100, &senders, 0.6).partial(30_000);
+    assert_eq!(summary.total_messages, 100);
+    assert_eq!(summary.partial_of, Some(30_000));
+    assert_eq!(summary.newsletter_percent(), 62);
+
+    assert_eq!(
+        InboxSummary::from_senders(0, &[], 0.6).newsletter_percent(),
+        0
+    );
+}
+
+#[test]
+fn test_unsubscribe_report_categories() {
+    let one_click = UnsubscribeMethod::OneClick {
+        url: "https://esp.example.com/u".to_string(),
+    };
+    let senders: Vec<SenderInfo> = [
+        ("ok@example.com", one_click.clone()),
+        ("failed@example.com", one_click.clone()),
+        (
+            "manual@example.com",
+            UnsubscribeMethod::HttpLink {
+                url: "https://example.com/u".to_string(),
+            },
+        ),
+        ("none@example.com", UnsubscribeMethod::None),
+        ("skipped@example.com", one_click),
+    ]
+    .into_iter()
+    .map(|(email, method)| SenderInfo {
+        email: email.to_string(),
+        message_count: 1,
+        message_uids: vec![1],
+        unsubscribe_method: method,
+        heuristic_score: 1.0,
+        ..SenderInfo::default()
+    })
+    .collect();
+    let result = |email: &str, unsubscribed| {
+        CleanupResult::success(email.to_string(), ActionType::DeleteOnly, 1, unsubscribed)
+    };
+    let results = vec![
+        result("ok@example.com", Some(true)),
+        result("failed@example.com", Some(false)),
+        result("manual@example.com", None),
+        result("none@example.com", None),
+        CleanupResult::failure(
+            "skipped@example.com".to_string(),
+            ActionType::UnsubscribeAndDelete,
+            "Deletion limit reached".to_string(),
+        ),
+    ];
+
+    let report = UnsubscribeReport::from_results(&results, &senders);
+
+    assert_eq!(report.count(UnsubscribeOutcome::Unsubscribed), 1);
+    assert_eq!(report.count(UnsubscribeOutcome::Failed), 1);
+    assert_eq!(report.count(UnsubscribeOutcome::NoMethod), 2);
+    assert_eq!(report.count(UnsubscribeOutcome::Skipped), 1);
+    assert_eq!(
+        report
+            .senders_with(UnsubscribeOutcome::NoMethod)
+            .collect::<Vec<_>>(),
+        vec!["manual@example.com", "none@example.com"]
+    );
+    assert_eq!(
+        report.to_string(),
+        "1 unsubscribed, 1 unsubscribe failed, 2 no unsubscribe method, 1 skipped"
+    );
+}
+
+#[test]
+fn test_cleanup_report() {
+    let senders: Vec<SenderInfo> = [("a@example.com", 40), ("b@example.com", 10)]
+        .into_iter()
+        .map(|(email, count)| SenderInfo {
+            email: email.to_string(),
+            message_count: count,
+            heuristic_score: 1.0,
+            ..SenderInfo::default()
+        })
+        .collect();
+    let results = vec![
+        CleanupResult::success(
+            "a@example.com".to_string(),
+            ActionType::UnsubscribeAndDelete,
+            40,
+            Some(true),
+        ),
+        CleanupResult::success("b@example.com".to_string(), ActionType::DeleteOnly, 0, None),
+    ];
+
+    let report = CleanupReport::new("me@gmail.com", Utc::now(), &senders, 2, &results);
+
+    assert_eq!(report.messages_scanned, 50);
+    assert_eq!(report.unique_senders, 2);
+    assert_eq!(report.newsletters, 2);
+    assert_eq!(report.senders_cleaned, 1);
+    assert_eq!(report.messages_deleted, 40);
+    assert_eq!(report.unsubscribes_attempted, 1);
+    assert_eq!(report.unsubscribes_succeeded, 1);
+
+    let json = serde_json::to_string(&report).unwrap();
+    let parsed: CleanupReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+}
+
 #[test]
 fn test_unsubscribe_method_variants() {
     // Test OneClick variant
@@ -142,3 +391,135 @@ fn test_unsubscribe_method_variants() {
     let none = UnsubscribeMethod::None;
     assert!(matches!(none, UnsubscribeMethod::None));
 }
+
+#[test]
+fn test_last_message_date_follows_message_dates() {
+    let old = Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap();
+    let new = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    let mut sender = SenderInfo {
+        email: "news@example.com".to_string(),
+        message_count: 3,
+        message_uids: vec![1, 2, 3],
+        heuristic_score: 1.0,
+        ..SenderInfo::default()
+    };
+
+    sender.set_message_dates(vec![Some(old), None, Some(new)]);
+    assert_eq!(sender.last_message_date, Some(new));
+
+    // Only the old message is kept, so the newest date moves back
+    sender.retain_older_than(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    assert_eq!(sender.last_message_date, Some(old));
+}
+
+#[test]
+fn test_esp_stats_per_host() {
+    let host = |h: &str| Some(h.to_string());
+    let results = vec![
+        CleanupResult::success(
+            "a@x.com".into(),
+            ActionType::UnsubscribeAndDelete,
+            3,
+            Some(true),
+        )
+        .with_unsubscribe_host(host("list.sendgrid.net")),
+        CleanupResult::success(
+            "b@x.com".into(),
+            ActionType::UnsubscribeAndDelete,
+            1,
+            Some(false),
+        )
+        .with_unsubscribe_host(host("list.sendgrid.net")),
+        CleanupResult::success(
+            "c@y.com".into(),
+            ActionType::UnsubscribeAndDelete,
+            2,
+            Some(true),
+        )
+        .with_unsubscribe_host(host("mailchimp.com")),
+        // No unsubscribe attempted: the host is not recorded
+        CleanupResult::success("d@z.com".into(), ActionType::DeleteOnly, 5, None)
+            .with_unsubscribe_host(host("ignored.com")),
+    ];
+
+    let mut stats = EspStats::default();
+    stats.record_results(&results);
+    stats.record_results(&results[2..3]);
+
+    let hosts = stats.by_attempts();
+    assert_eq!(hosts.len(), 2);
+    assert_eq!(hosts[0].0, "list.sendgrid.net");
+    assert_eq!(hosts[0].1.attempts, 2);
+    assert_eq!(hosts[0].1.success_rate(), 0.5);
+    assert_eq!(hosts[1].0, "mailchimp.com");
+    assert_eq!(hosts[1].1.successes, 2);
+    assert_eq!(hosts[1].1.success_rate(), 1.0);
+}
+
+#[test]
+fn test_split_keep_latest() {
+    let day = |d: u32| Some(Utc.with_ymd_and_hms(2024, 3, d, 9, 0, 0).unwrap());
+    let mut sender = SenderInfo {
+        email: "news@example.com".to_string(),
+        message_count: 6,
+        message_uids: vec![10, 11, 12, 13, 14, 15],
+        heuristic_score: 1.0,
+        has_list_id: true,
+        ..SenderInfo::default()
+    };
+    // UID 12 arrived late but is the newest issue; 13 and 14 share a date;
+    // 15 has no Date header
+    sender.set_message_dates(vec![day(1), day(3), day(9), day(5), day(5), None]);
+
+    let (kept, older) = sender.split_keep_latest(2);
+    assert_eq!(kept, vec![12, 14, 15]);
+    assert_eq!(older, vec![10, 11, 13]);
+
+    let (kept, older) = sender.split_keep_latest(10);
+    assert_eq!(kept, sender.message_uids);
+    assert!(older.is_empty());
+
+    let (kept, older) = sender.split_keep_latest(0);
+    assert_eq!(kept, vec![15]);
+    assert_eq!(older, vec![10, 11, 12, 13, 14]);
+}
+
+#[test]
+fn test_cleanup_progress_resume_point() {
+    let senders: Vec<SenderInfo> = ["a@example.com", "b@example.com", "c@example.com"]
+        .into_iter()
+        .map(|email| SenderInfo {
+            email: email.to_string(),
+            message_count: 1,
+            message_uids: vec![1],
+            heuristic_score: 1.0,
+            ..SenderInfo::default()
+        })
+        .collect();
+    let done =
+        |email: &str| CleanupResult::success(email.to_string(), ActionType::DeleteOnly, 1, None);
+
+    let mut progress = CleanupProgress::new(Utc::now(), "INBOX", senders);
+    assert_eq!(progress.resume_point(), 0);
+    assert_eq!(progress.pending().len(), 3);
+
+    progress.complete(done("a@example.com"));
+    assert_eq!(progress.resume_point(), 1);
+    assert_eq!(progress.pending()[0].email, "b@example.com");
+
+    // Survives a round trip through the journal file
+    let json = serde_json::to_string(&progress).unwrap();
+    let mut progress: CleanupProgress = serde_json::from_str(&json).unwrap();
+    assert_eq!(progress.resume_point(), 1);
+
+    // A result that doesn't match the next sender doesn't advance it
+    progress.results.push(done("c@example.com"));
+    assert_eq!(progress.resume_point(), 1);
+    progress.complete(done("b@example.com"));
+    assert_eq!(progress.results.len(), 2);
+    assert!(!progress.is_complete());
+
+    progress.complete(done("c@example.com"));
+    assert!(progress.is_complete());
+    assert!(progress.pending().is_empty());
+}
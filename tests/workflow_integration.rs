@@ -0,0 +1,201 @@
+//! Integration tests for the scan analysis pipeline
+//!
+//! Canned headers are fed through grouping and analysis, the part of
+//! `workflow::scan_inbox` that runs after the IMAP fetch.
+
+use chrono::{TimeZone, Utc};
+use unsubmail::application::workflow::{analyze_headers_grouped, SenderAccumulator};
+use unsubmail::domain::models::{SenderGrouping, SenderInfo, UnsubscribeMethod};
+use unsubmail::infrastructure::imap::fetch::MessageHeader;
+
+fn header(uid: u32, from: &str, subject: &str, list_unsubscribe: Option<&str>) -> MessageHeader {
+    MessageHeader {
+        uid,
+        from: from.to_string(),
+        subject: subject.to_string(),
+        list_unsubscribe: list_unsubscribe.map(str::to_string),
+        list_unsubscribe_post: list_unsubscribe.map(|_| "List-Unsubscribe=One-Click".to_string()),
+        list_id: None,
+        date: None,
+        precedence: None,
+        auto_submitted: None,
+        seen: None,
+    }
+}
+
+fn canned_headers() -> Vec<MessageHeader> {
+    let unsubscribe = Some("<https://retailer.com/unsub?id=1>");
+    vec![
+        header(
+            1,
+            "Retailer <news@retailer.com>",
+            "50% off today",
+            unsubscribe,
+        ),
+        header(
+            2,
+            "Retailer <news@retailer.com>",
+            "Last chance",
+            unsubscribe,
+        ),
+        header(
+            3,
+            "Retailer Deals <deals@retailer.com>",
+            "Flash sale",
+            unsubscribe,
+        ),
+        header(4, "Alice <alice@example.com>", "Lunch?", None),
+    ]
+}
+
+fn find<'a>(senders: &'a [SenderInfo], email: &str) -> &'a SenderInfo {
+    senders
+        .iter()
+        .find(|s| s.email == email)
+        .unwrap_or_else(|| panic!("{} not found", email))
+}
+
+#[test]
+fn test_analyze_headers_by_address() {
+    let senders = analyze_headers_grouped(canned_headers(), SenderGrouping::Address);
+    assert_eq!(senders.len(), 3);
+
+    let news = find(&senders, "news@retailer.com");
+    assert_eq!(news.message_count, 2);
+    assert_eq!(news.display_name.as_deref(), Some("Retailer"));
+    assert!(matches!(
+        news.unsubscribe_method,
+        UnsubscribeMethod::OneClick { .. }
+    ));
+
+    let alice = find(&senders, "alice@example.com");
+    assert_eq!(alice.unsubscribe_method, UnsubscribeMethod::None);
+    assert!(news.heuristic_score > alice.heuristic_score);
+}
+
+#[test]
+fn test_analyze_headers_by_domain() {
+    let senders = analyze_headers_grouped(canned_headers(), SenderGrouping::Domain);
+    assert_eq!(senders.len(), 2);
+
+    let retailer = find(&senders, "retailer.com");
+    let mut uids = retailer.message_uids.clone();
+    uids.sort_unstable();
+    assert_eq!(uids, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_last_message_date_is_newest() {
+    let newest = Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap();
+    let mut headers = canned_headers();
+    headers[0].date = Some(newest);
+    headers[1].date = Some(Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap());
+    headers[2].date = Some(Utc.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap());
+
+    let senders = analyze_headers_grouped(headers.clone(), SenderGrouping::Address);
+    assert_eq!(
+        find(&senders, "news@retailer.com").last_message_date,
+        Some(newest)
+    );
+    assert_eq!(find(&senders, "alice@example.com").last_message_date, None);
+
+    let senders = analyze_headers_grouped(headers, SenderGrouping::Domain);
+    assert_eq!(
+        find(&senders, "retailer.com").last_message_date,
+        Some(newest)
+    );
+}
+
+#[test]
+fn test_unread_ratio_from_seen_flags() {
+    let mut headers = canned_headers();
+    headers[0].seen = Some(false);
+    headers[1].seen = Some(true);
+    headers[2].seen = Some(false);
+    // headers[3] has unknown read state and counts as read
+
+    let senders = analyze_headers_grouped(headers.clone(), SenderGrouping::Address);
+    let news = find(&senders, "news@retailer.com");
+    assert_eq!(news.unread_count, 1);
+    assert_eq!(news.unread_ratio(), 0.5);
+
+    let senders = analyze_headers_grouped(headers, SenderGrouping::Domain);
+    let retailer = find(&senders, "retailer.com");
+    assert_eq!(retailer.unread_count, 2);
+    assert!((retailer.unread_ratio() - 2.0 / 3.0).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_unsubscribe_dropped_on_recent_messages() {
+    let unsubscribe = Some("<https://retailer.com/unsub?id=1>");
+    let dated = |uid: u32, day: u32, list_unsubscribe: Option<&str>| MessageHeader {
+        date: Some(Utc.with_ymd_and_hms(2024, 3, day, 8, 0, 0).unwrap()),
+        ..header(
+            uid,
+            "Retailer <news@retailer.com>",
+            "Update",
+            list_unsubscribe,
+        )
+    };
+
+    let senders = analyze_headers_grouped(
+        vec![
+            dated(1, 1, unsubscribe),
+            dated(2, 2, unsubscribe),
+            dated(3, 10, None),
+            dated(4, 11, None),
+        ],
+        SenderGrouping::Address,
+    );
+    let news = find(&senders, "news@retailer.com");
+    assert!(news.unsubscribe_dropped);
+    // The older messages' link is still offered
+    assert!(news.unsubscribe_method.is_one_click());
+
+    let senders = analyze_headers_grouped(canned_headers(), SenderGrouping::Address);
+    assert!(!find(&senders, "news@retailer.com").unsubscribe_dropped);
+}
+
+#[test]
+fn test_one_click_found_on_any_message() {
+    let one_click = header(
+        2,
+        "Shop <news@shop.com>",
+        "Sale",
+        Some("<https://shop.com/unsub>"),
+    );
+    let mut plain = header(1, "Shop <news@shop.com>", "Receipt", None);
+    plain.list_unsubscribe_post = None;
+
+    // Whichever message the grouping puts first, one-click is detected
+    for headers in [
+        vec![plain.clone(), one_click.clone()],
+        vec![one_click.clone(), plain.clone()],
+    ] {
+        let senders = analyze_headers_grouped(headers, SenderGrouping::Address);
+        assert!(find(&senders, "news@shop.com")
+            .unsubscribe_method
+            .is_one_click());
+    }
+}
+
+#[test]
+fn test_accumulator_updates_counts_in_place() {
+    let headers = canned_headers();
+    let mut accumulator = SenderAccumulator::new("alice@example.com", SenderGrouping::Address);
+
+    let first = accumulator.add(&[headers[0].clone(), headers[2].clone()]);
+    assert_eq!(first.len(), 2);
+    assert_eq!(find(&first, "news@retailer.com").message_count, 1);
+
+    // Only the touched sender is reported, with its running total
+    let second = accumulator.add(&[headers[1].clone(), headers[3].clone()]);
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].email, "news@retailer.com");
+    assert_eq!(second[0].message_count, 2);
+    assert_eq!(second[0].message_uids, vec![1, 2]);
+
+    let senders = accumulator.into_senders();
+    assert_eq!(senders.len(), 2);
+    assert_eq!(find(&senders, "deals@retailer.com").message_count, 1);
+}